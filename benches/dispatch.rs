@@ -0,0 +1,219 @@
+//! Compares dispatching every instruction with executing pre-decoded instructions from a cache, and with executing
+//! translated basic blocks.
+//!
+//! Run with `cargo bench`.
+
+use std::time::{Duration, Instant};
+
+use arviss::blocks::BlockCpu;
+use arviss::decoding::Reg;
+use arviss::icache::{CachedCpu, DecodeFn};
+use arviss::platforms::basic::*;
+use arviss::{Address, DispatchRv32i, DispatchRv32ic, MemoryResult};
+
+const HELLO_WORLD_RV32I: &[u8] = include_bytes!("../images/hello_world.rv32i");
+const HELLO_WORLD_RV32IC: &[u8] = include_bytes!("../images/hello_world.rv32ic");
+
+// How many times to run the hello world images.
+const HELLO_WORLD_RUNS: u32 = 1000;
+
+// A CPU-bound guest that hashes a counter with some arithmetic and a round trip through memory, 2M times.
+#[rustfmt::skip]
+const CPU_BOUND: &[u32] = &[
+    0x00000513, // 0x00: li   a0, 0
+    0x002005b7, // 0x04: lui  a1, 0x200
+    0x00005137, // 0x08: lui  sp, 0x5
+    0x00551293, // 0x0c: slli t0, a0, 5
+    0x00550533, // 0x10: add  a0, a0, t0
+    0x05a54513, // 0x14: xori a0, a0, 0x5a
+    0x00a12023, // 0x18: sw   a0, 0(sp)
+    0x00012303, // 0x1c: lw   t1, 0(sp)
+    0x00650533, // 0x20: add  a0, a0, t1
+    0xfff58593, // 0x24: addi a1, a1, -1
+    0xfe0592e3, // 0x28: bnez a1, 0x0c
+    0x00100073, // 0x2c: ebreak
+];
+
+/// Basic memory whose TTY discards its output so that the hello world images can run quietly.
+#[derive(Default)]
+struct QuietMem(BasicMem);
+
+impl Memory for QuietMem {
+    fn read8(&self, address: Address) -> MemoryResult<u8> {
+        self.0.read8(address)
+    }
+
+    fn read16(&self, address: Address) -> MemoryResult<u16> {
+        self.0.read16(address)
+    }
+
+    fn read32(&self, address: Address) -> MemoryResult<u32> {
+        self.0.read32(address)
+    }
+
+    fn write8(&mut self, address: Address, byte: u8) -> MemoryResult<()> {
+        match address {
+            0x8001 => Ok(()),
+            _ => self.0.write8(address, byte),
+        }
+    }
+
+    fn write16(&mut self, address: Address, half_word: u16) -> MemoryResult<()> {
+        self.0.write16(address, half_word)
+    }
+
+    fn write32(&mut self, address: Address, word: u32) -> MemoryResult<()> {
+        self.0.write32(address, word)
+    }
+}
+
+impl Load for QuietMem {
+    fn write_bytes(&mut self, start: Address, bytes: &[u8]) -> MemoryResult<()> {
+        self.0.write_bytes(start, bytes)
+    }
+}
+
+type Cpu = Rv32iCpu<QuietMem>;
+
+fn load(image: &[u8]) -> Cpu {
+    let mut cpu = Cpu::new();
+    cpu.write_bytes(0, image)
+        .expect("Failed to initialize memory.");
+    cpu
+}
+
+fn cpu_bound() -> Cpu {
+    let image: Vec<u8> = CPU_BOUND.iter().flat_map(|ins| ins.to_le_bytes()).collect();
+    load(&image)
+}
+
+// Runs a CPU until it traps by dispatching every instruction, returning the number of instructions executed.
+fn run_dispatched(cpu: &mut Cpu, dispatch: fn(&mut Cpu, u32)) -> u64 {
+    let mut count = 0;
+    while !cpu.is_trapped() {
+        let ins = cpu.fetch().unwrap();
+        dispatch(cpu, ins);
+        count += 1;
+    }
+    count
+}
+
+// Runs a CPU until it traps by executing instructions from the cache, returning the number of instructions executed.
+fn run_cached(cpu: &mut CachedCpu<Cpu>) -> u64 {
+    let mut count = 0;
+    while !cpu.is_trapped() {
+        cpu.step();
+        count += 1;
+    }
+    count
+}
+
+fn bench_blocks(
+    name: &str,
+    runs: u32,
+    make: impl Fn() -> Cpu,
+    decode: DecodeFn<BlockCpu<Cpu>>,
+) -> u32 {
+    let mut count = 0;
+    let mut result = 0;
+    let start = Instant::now();
+    for _ in 0..runs {
+        let mut cpu = BlockCpu::new(make(), decode);
+        count += cpu.run();
+        result = cpu.rx(Reg::new(10));
+    }
+    report(name, count, start.elapsed());
+    result
+}
+
+fn report(name: &str, count: u64, elapsed: Duration) {
+    let mips = count as f64 / elapsed.as_secs_f64() / 1_000_000.0;
+    println!(
+        "{:<32} {:>12} instructions {:>10.2?} {:>8.1} MIPS",
+        name, count, elapsed, mips
+    );
+}
+
+fn bench_dispatched(
+    name: &str,
+    runs: u32,
+    make: impl Fn() -> Cpu,
+    dispatch: fn(&mut Cpu, u32),
+) -> u32 {
+    let mut count = 0;
+    let mut result = 0;
+    let start = Instant::now();
+    for _ in 0..runs {
+        let mut cpu = make();
+        count += run_dispatched(&mut cpu, dispatch);
+        result = cpu.rx(Reg::new(10));
+    }
+    report(name, count, start.elapsed());
+    result
+}
+
+fn bench_cached(
+    name: &str,
+    runs: u32,
+    make: impl Fn() -> Cpu,
+    decode: DecodeFn<CachedCpu<Cpu>>,
+) -> u32 {
+    let mut count = 0;
+    let mut result = 0;
+    let start = Instant::now();
+    for _ in 0..runs {
+        let mut cpu = CachedCpu::new(make(), decode);
+        count += run_cached(&mut cpu);
+        result = cpu.rx(Reg::new(10));
+    }
+    report(name, count, start.elapsed());
+    result
+}
+
+fn main() {
+    let rv32i = || load(HELLO_WORLD_RV32I);
+    let rv32ic = || load(HELLO_WORLD_RV32IC);
+
+    println!("hello_world.rv32i x {}", HELLO_WORLD_RUNS);
+    bench_dispatched("  DispatchRv32i", HELLO_WORLD_RUNS, rv32i, |cpu, ins| {
+        DispatchRv32i::dispatch(cpu, ins)
+    });
+    bench_cached(
+        "  CachedCpu (Rv32i)",
+        HELLO_WORLD_RUNS,
+        rv32i,
+        DispatchRv32i::dispatch,
+    );
+    bench_blocks(
+        "  BlockCpu (Rv32i)",
+        HELLO_WORLD_RUNS,
+        rv32i,
+        DispatchRv32i::dispatch,
+    );
+
+    println!("hello_world.rv32ic x {}", HELLO_WORLD_RUNS);
+    bench_dispatched("  DispatchRv32ic", HELLO_WORLD_RUNS, rv32ic, |cpu, ins| {
+        DispatchRv32ic::dispatch(cpu, ins)
+    });
+    bench_cached(
+        "  CachedCpu (Rv32ic)",
+        HELLO_WORLD_RUNS,
+        rv32ic,
+        DispatchRv32ic::dispatch,
+    );
+    bench_blocks(
+        "  BlockCpu (Rv32ic)",
+        HELLO_WORLD_RUNS,
+        rv32ic,
+        DispatchRv32ic::dispatch,
+    );
+
+    println!("cpu-bound guest");
+    let dispatched = bench_dispatched("  DispatchRv32i", 1, cpu_bound, |cpu, ins| {
+        DispatchRv32i::dispatch(cpu, ins)
+    });
+    let cached = bench_cached("  CachedCpu (Rv32i)", 1, cpu_bound, DispatchRv32i::dispatch);
+    let blocks = bench_blocks("  BlockCpu (Rv32i)", 1, cpu_bound, DispatchRv32i::dispatch);
+    assert_eq!(dispatched, cached);
+    assert_eq!(dispatched, blocks);
+}
@@ -13,7 +13,7 @@ pub fn main() {
 
     // Disassemble the image, one instruction at a time, taking into account that compact
     // instructions are only 2 bytes.
-    let mut disassembler = Disassembler {};
+    let mut disassembler = Disassembler::new();
     let mut index: usize = 0;
     let image = buffer.as_slice();
     println!("addr     instr    code");
@@ -22,6 +22,7 @@ pub fn main() {
             let word = u32::from_le_bytes(*slice);
             let is_compact = (word & 3) != 3;
             let word = if is_compact { word & 0xffff } else { word };
+            disassembler.set_pc(index as u32);
             let result = disassembler.dispatch(word);
             if is_compact {
                 // Compact instructions are 2 bytes each.
@@ -0,0 +1,54 @@
+use std::env;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::net::TcpListener;
+
+use arviss::DispatchRv32ic;
+
+use arviss::elf;
+use arviss::gdbstub::GdbStub;
+use arviss::platforms::basic::*;
+
+pub fn main() -> io::Result<()> {
+    let args = env::args().collect::<Vec<_>>();
+    let (filename, port) = match args.len() {
+        2 => (&args[1], 1234),
+        3 => match args[2].parse::<u16>() {
+            Ok(port) => (&args[1], port),
+            Err(_) => usage(&args[0]),
+        },
+        _ => usage(&args[0]),
+    };
+
+    // Load the image into a buffer.
+    let mut f = File::open(filename)?;
+    let mut buffer = Vec::new();
+    f.read_to_end(&mut buffer)?;
+
+    // Create a simulator and copy the image from the buffer into simulator memory.
+    let mut cpu = Rv32iCpu::<BasicMem>::new();
+    if buffer.starts_with(b"\x7fELF") {
+        elf::load(&mut cpu, buffer.as_slice())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    } else {
+        cpu.write_bytes(0, buffer.as_slice())
+            .expect("Failed to initialize memory.");
+    }
+
+    // Wait for GDB to attach, then let it drive the simulator until it detaches.
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    eprintln!("Waiting for GDB on port {}", port);
+    let (mut stream, address) = listener.accept()?;
+    eprintln!("GDB attached from {}", address);
+    let mut stub = GdbStub::new(cpu);
+    stub.serve(&mut stream, |cpu, ins| cpu.dispatch(ins))?;
+    eprintln!("GDB detached");
+
+    Ok(())
+}
+
+fn usage(program: &str) -> ! {
+    eprintln!("\nUsage:\n\t{} <filename> [port]", program);
+    std::process::exit(2);
+}
@@ -3,6 +3,7 @@ use std::io::prelude::*;
 
 use arviss::DispatchRv32i;
 
+use arviss::executor::run;
 use arviss::platforms::basic::*;
 
 pub fn main() {
@@ -19,8 +20,5 @@ pub fn main() {
 
     // Execute the image.
     let mut cpu = Rv32iCpu::<BasicMem>::with_mem(mem);
-    while !cpu.is_trapped() {
-        let instruction = cpu.fetch().expect("Failed to fetch instruction.");
-        cpu.dispatch(instruction);
-    }
+    run(&mut cpu, u64::MAX, |cpu, ins| cpu.dispatch(ins));
 }
@@ -6,6 +6,8 @@ use std::io::prelude::*;
 use arviss::DispatchRv32i;
 
 use arviss::disassembler::Disassembler;
+use arviss::elf;
+use arviss::executor::{Executor, StopReason};
 use arviss::platforms::basic::*;
 
 pub fn main() -> io::Result<()> {
@@ -24,34 +26,51 @@ pub fn main() -> io::Result<()> {
     let mut buffer = Vec::new();
     f.read_to_end(&mut buffer)?;
 
-    // Create a simulator and copy the image from the buffer into simulator memory.
+    // Create a simulator and copy the image from the buffer into simulator memory. ELF files are loaded at the addresses
+    // that they specify, and anything else is treated as a flat binary that's loaded at address 0.
     let mut cpu = Rv32iCpu::<BasicMem>::new();
-    cpu.write_bytes(0, buffer.as_slice())
-        .expect("Failed to initialize memory.");
+    cpu.set_alignment(Alignment::RV32I);
+    let symbols = if buffer.starts_with(b"\x7fELF") {
+        let info = elf::load(&mut cpu, buffer.as_slice())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        info.symbols
+    } else {
+        cpu.write_bytes(0, buffer.as_slice())
+            .expect("Failed to initialize memory.");
+        Default::default()
+    };
 
-    // Run until we can run no more.
-    let mut disassembler = Disassembler {};
+    // Run until we can run no more, one instruction at a time if we're disassembling.
+    let mut disassembler = Disassembler::with_symbols(symbols);
     if disassemble {
         println!("pc       (pc)     Code");
     }
-    while !cpu.is_trapped() {
-        // Fetch.
-        let ins = cpu.fetch().unwrap();
+    let limit = if disassemble { 1 } else { u64::MAX };
+    let mut executor = Executor::new();
+    let reason = loop {
+        // Decode and dispatch.
+        let reason = executor.run(&mut cpu, limit, |cpu, ins| cpu.dispatch(ins));
 
-        // Disassemble if the user asked for it.
-        if disassemble {
+        // Disassemble the instruction that was executed if the user asked for it.
+        if let (true, Ok(ins)) = (disassemble, cpu.fetch32(cpu.pc())) {
+            let ins = if (ins & 3) == 3 { ins } else { ins & 0xffff };
+            if let Some(label) = disassembler.label(cpu.pc()) {
+                println!("{}", label);
+            }
+            disassembler.set_pc(cpu.pc());
             let result = disassembler.dispatch(ins);
             println!("{:08x} {:08x} {}", cpu.pc(), ins, result);
         }
 
-        // Decode and dispatch.
-        cpu.dispatch(ins);
-    }
+        if reason != StopReason::Limit {
+            break reason;
+        }
+    };
 
-    match cpu.trap_cause() {
-        Some(TrapCause::Breakpoint) => {}
-        Some(cause) => println!("{:?} at 0x{:08x}", cause, cpu.pc()),
-        None => {}
+    match (reason, cpu.trap()) {
+        (StopReason::Trap(TrapCause::Breakpoint(_)), _) => {}
+        (StopReason::Trap(_), Some(trap)) => println!("{}", trap),
+        (reason, _) => println!("{:?} at 0x{:08x}", reason, cpu.pc()),
     }
 
     Ok(())
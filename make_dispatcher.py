@@ -80,6 +80,20 @@ rem     rd rs1 rs2 31..25=1 14..12=6 6..2=0x0C 1..0=3
 remu    rd rs1 rs2 31..25=1 14..12=7 6..2=0x0C 1..0=3
 """
 
+rv32a = """\
+lr.w      rd rs1 24..20=0 aq rl 31..29=0 28..27=2 14..12=2 6..2=0x0B 1..0=3
+sc.w      rd rs1 rs2      aq rl 31..29=0 28..27=3 14..12=2 6..2=0x0B 1..0=3
+amoswap.w rd rs1 rs2      aq rl 31..29=0 28..27=1 14..12=2 6..2=0x0B 1..0=3
+amoadd.w  rd rs1 rs2      aq rl 31..29=0 28..27=0 14..12=2 6..2=0x0B 1..0=3
+amoxor.w  rd rs1 rs2      aq rl 31..29=1 28..27=0 14..12=2 6..2=0x0B 1..0=3
+amoand.w  rd rs1 rs2      aq rl 31..29=3 28..27=0 14..12=2 6..2=0x0B 1..0=3
+amoor.w   rd rs1 rs2      aq rl 31..29=2 28..27=0 14..12=2 6..2=0x0B 1..0=3
+amomin.w  rd rs1 rs2      aq rl 31..29=4 28..27=0 14..12=2 6..2=0x0B 1..0=3
+amomax.w  rd rs1 rs2      aq rl 31..29=5 28..27=0 14..12=2 6..2=0x0B 1..0=3
+amominu.w rd rs1 rs2      aq rl 31..29=6 28..27=0 14..12=2 6..2=0x0B 1..0=3
+amomaxu.w rd rs1 rs2      aq rl 31..29=7 28..27=0 14..12=2 6..2=0x0B 1..0=3
+"""
+
 rv32c = """\
 # quadrant 0
 c.addi4spn rd_p c_nzuimm10              1..0=0 15..13=0
@@ -153,9 +167,73 @@ fnmsub.s  rd rs1 rs2 rs3 rm 26..25=0 6..2=0x12 1..0=3
 fnmadd.s  rd rs1 rs2 rs3 rm 26..25=0 6..2=0x13 1..0=3
 """
 
+rv32fc = """\
+c.flw rd_p rs1_p c_uimm7lo c_uimm7hi     1..0=0 15..13=3
+c.fsw rs1_p rs2_p c_uimm7lo c_uimm7hi    1..0=0 15..13=7
+c.flwsp rd c_uimm8sphi c_uimm8splo       1..0=2 15..13=3
+c.fswsp c_rs2 c_uimm8sp_s                1..0=2 15..13=7
+"""
+
+rv32d = """\
+fadd.d    rd rs1 rs2      31..27=0x00 rm       26..25=1 6..2=0x14 1..0=3
+fsub.d    rd rs1 rs2      31..27=0x01 rm       26..25=1 6..2=0x14 1..0=3
+fmul.d    rd rs1 rs2      31..27=0x02 rm       26..25=1 6..2=0x14 1..0=3
+fdiv.d    rd rs1 rs2      31..27=0x03 rm       26..25=1 6..2=0x14 1..0=3
+fsgnj.d   rd rs1 rs2      31..27=0x04 14..12=0 26..25=1 6..2=0x14 1..0=3
+fsgnjn.d  rd rs1 rs2      31..27=0x04 14..12=1 26..25=1 6..2=0x14 1..0=3
+fsgnjx.d  rd rs1 rs2      31..27=0x04 14..12=2 26..25=1 6..2=0x14 1..0=3
+fmin.d    rd rs1 rs2      31..27=0x05 14..12=0 26..25=1 6..2=0x14 1..0=3
+fmax.d    rd rs1 rs2      31..27=0x05 14..12=1 26..25=1 6..2=0x14 1..0=3
+fcvt.s.d  rd rs1 24..20=1 31..27=0x08 rm       26..25=0 6..2=0x14 1..0=3
+fcvt.d.s  rd rs1 24..20=0 31..27=0x08 rm       26..25=1 6..2=0x14 1..0=3
+fsqrt.d   rd rs1 24..20=0 31..27=0x0B rm       26..25=1 6..2=0x14 1..0=3
+
+fle.d     rd rs1 rs2      31..27=0x14 14..12=0 26..25=1 6..2=0x14 1..0=3
+flt.d     rd rs1 rs2      31..27=0x14 14..12=1 26..25=1 6..2=0x14 1..0=3
+feq.d     rd rs1 rs2      31..27=0x14 14..12=2 26..25=1 6..2=0x14 1..0=3
+
+fcvt.w.d  rd rs1 24..20=0 31..27=0x18 rm       26..25=1 6..2=0x14 1..0=3
+fcvt.wu.d rd rs1 24..20=1 31..27=0x18 rm       26..25=1 6..2=0x14 1..0=3
+fclass.d  rd rs1 24..20=0 31..27=0x1C 14..12=1 26..25=1 6..2=0x14 1..0=3
+
+fcvt.d.w  rd rs1 24..20=0 31..27=0x1A rm       26..25=1 6..2=0x14 1..0=3
+fcvt.d.wu rd rs1 24..20=1 31..27=0x1A rm       26..25=1 6..2=0x14 1..0=3
+
+fld       rd rs1 imm12 14..12=3 6..2=0x01 1..0=3
+
+fsd       imm12hi rs1 rs2 imm12lo 14..12=3 6..2=0x09 1..0=3
+
+fmadd.d   rd rs1 rs2 rs3 rm 26..25=1 6..2=0x10 1..0=3
+fmsub.d   rd rs1 rs2 rs3 rm 26..25=1 6..2=0x11 1..0=3
+fnmsub.d  rd rs1 rs2 rs3 rm 26..25=1 6..2=0x12 1..0=3
+fnmadd.d  rd rs1 rs2 rs3 rm 26..25=1 6..2=0x13 1..0=3
+"""
+
+rv32dc = """\
+c.fld rd_p rs1_p c_uimm8lo c_uimm8hi     1..0=0 15..13=1
+c.fsd rs1_p rs2_p c_uimm8lo c_uimm8hi    1..0=0 15..13=5
+c.fldsp rd c_uimm9sphi c_uimm9splo       1..0=2 15..13=1
+c.fsdsp c_rs2 c_uimm9sp_s                1..0=2 15..13=5
+"""
+
+zicsr = """\
+csrrw     rd rs1 csr 14..12=1 6..2=0x1C 1..0=3
+csrrs     rd rs1 csr 14..12=2 6..2=0x1C 1..0=3
+csrrc     rd rs1 csr 14..12=3 6..2=0x1C 1..0=3
+csrrwi    rd zimm csr 14..12=5 6..2=0x1C 1..0=3
+csrrsi    rd zimm csr 14..12=6 6..2=0x1C 1..0=3
+csrrci    rd zimm csr 14..12=7 6..2=0x1C 1..0=3
+"""
+
+zifencei = """\
+fence.i     imm12                       rs1 14..12=1 rd 6..2=0x03 1..0=3
+"""
+
 system = """\
 sret      11..7=0 19..15=0 31..20=0x102 14..12=0 6..2=0x1C 1..0=3
 mret      11..7=0 19..15=0 31..20=0x302 14..12=0 6..2=0x1C 1..0=3
+wfi       11..7=0 19..15=0 31..20=0x105 14..12=0 6..2=0x1C 1..0=3
+sfence.vma 11..7=0 rs1 rs2 31..25=0x09 14..12=0 6..2=0x1C 1..0=3
 """
 
 # A lovely piece of music by Vivaldi. Don't try to decode it. Just listen and enjoy.
@@ -202,8 +280,41 @@ lut = {
     "rd rs1 rs2 rs3 rm": "(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm())",
     "rd rs1 rs2 rm": "(c.rd(), c.rs1(), c.rs2(), c.rm())",
     "rd rs1 rm": "(c.rd(), c.rs1(), c.rm())",
+    "rd_p rs1_p c_uimm8lo c_uimm8hi": "(c.rdp(), c.rs1p(), c.c_uimm8())",  # c.fld
+    "rs1_p rs2_p c_uimm8lo c_uimm8hi": "(c.rs1p(), c.rs2p(), c.c_uimm8())",  # c.fsd
+    "rd c_uimm8sphi c_uimm8splo": "(c.rd(), c.c_uimm8sp())",  # c.flwsp
+    "rd c_uimm9sphi c_uimm9splo": "(c.rd(), c.c_uimm9sp())",  # c.fldsp
+    "c_rs2 c_uimm9sp_s": "(c.c_rs2(), c.c_uimm9sp_s())",  # c.fsdsp
+    # A-extension.
+    "rd rs1 aq rl": "(c.rd(), c.rs1(), c.aqrl())",
+    "rd rs1 rs2 aq rl": "(c.rd(), c.rs1(), c.rs2(), c.aqrl())",
+    # Zicsr extension.
+    "rd rs1 csr": "(c.rd(), c.rs1(), c.csr())",
+    "rd zimm csr": "(c.rd(), c.zimm(), c.csr())",
+    # Zifencei extension.
+    "imm12 rs1 rd": "(c.rd(), c.rs1(), c.iimmediate())",
+    # Privileged instructions.
+    "rs1 rs2": "(c.rs1(), c.rs2())",  # sfence.vma
 }
 
+# Maps each extension to the name of its instruction handler trait, and to how it appears in an ISA string.
+handlers = dict(
+    i="HandleRv32i",
+    m="HandleRv32m",
+    a="HandleRv32a",
+    f="HandleRv32f",
+    d="HandleRv32d",
+    c="HandleRv32c",
+    zicsr="HandleZicsr",
+    zifencei="HandleZifencei",
+    priv="HandlePrivileged",
+)
+
+isa_names = dict(zicsr="Zicsr", zifencei="Zifencei", priv="Priv")
+
+# The extensions that make up the general purpose "G" ISA, i.e., IMAFD_Zicsr_Zifencei.
+general = ["m", "a", "f", "d", "zicsr", "zifencei"]
+
 
 @dataclass
 class BitPattern:
@@ -345,19 +456,25 @@ def generate_bitmask_code(specs: List[Spec], extensions: str):
     """Generates a dispatcher that uses Rust match expressions based on bitmasks."""
 
     command_line = " ".join(sys.argv[0:])
-    trait_name = f"DispatchRv32{extensions}"
-
-    short_bounds = " + ".join(f"HandleRv32{x}" for x in extensions)
-    full_bounds = "\n        + ".join(f"HandleRv32{x}\n        + HandleRv32{x}<Item = U>" for x in extensions)
+    abbreviated = [x for x in extensions if x not in general] if all(x in extensions for x in general) else extensions
+    letters = "".join(x for x in abbreviated if len(x) == 1)
+    if abbreviated is not extensions:
+        letters = letters.replace("i", "g")
+    named = [isa_names[x] for x in abbreviated if len(x) > 1]
+    trait_name = f"DispatchRv32{letters}{''.join(named)}"
+    isa = "_".join([f"RV32{letters.upper()}"] + named)
+
+    short_bounds = " + ".join(handlers[x] for x in extensions)
+    full_bounds = "\n        + ".join(f"{handlers[x]}\n        + {handlers[x]}<Item = U>" for x in extensions)
     preamble = f"""\
 // This code was generated by `{command_line}`. Do not edit.
 
-/// A dispatcher for RV32{extensions.upper()} instructions.
+/// A dispatcher for {isa} instructions.
 pub trait {trait_name} {{
     type Item;
 
-    /// Decodes the input word to an RV32{extensions.upper()} instruction and dispatches it to a handler.
-    fn dispatch(&mut self, code: u32) -> <Self as Rv32i>::Item
+    /// Decodes the input word to an {isa} instruction and dispatches it to a handler.
+    fn dispatch(&mut self, code: u32) -> <Self as HandleRv32i>::Item
     where
         Self: {short_bounds};
 }}
@@ -414,15 +531,26 @@ where
 
 def parse_command_line():
     parser = argparse.ArgumentParser(description="Generate a RISC-V instruction dispatcher for the RV32I base ISA plus extensions.")
+    parser.add_argument("-a", dest="extensions", help="Enable the 'A' extension", action="append_const", const="a")
     parser.add_argument("-c", dest="extensions", help="Enable the 'C' extension", action="append_const", const="c")
+    parser.add_argument("-d", dest="extensions", help="Enable the 'D' extension (implies 'F')", action="append_const", const="d")
     parser.add_argument("-f", dest="extensions", help="Enable the 'F' extension", action="append_const", const="f")
+    parser.add_argument("-g", dest="extensions", help="Enable the 'G' extensions, i.e., IMAFD_Zicsr_Zifencei", action="append_const", const="g")
     parser.add_argument("-m", dest="extensions", help="Enable the 'M' extension", action="append_const", const="m")
+    parser.add_argument("-z", dest="extensions", help="Enable the 'Zicsr' extension", action="append_const", const="zicsr")
+    parser.add_argument("-y", dest="extensions", help="Enable the 'Zifencei' extension", action="append_const", const="zifencei")
+    parser.add_argument("-p", dest="extensions", help="Enable privileged instructions", action="append_const", const="priv")
     args = parser.parse_args()
-    args.extensions = list(set(args.extensions)) if args.extensions is not None else []
+    extensions = set(args.extensions) if args.extensions is not None else set()
+    if "g" in extensions:
+        extensions.remove("g")
+        extensions.update(general)
+    if "d" in extensions:
+        extensions.add("f")
+    args.extensions = list(extensions)
     args.extensions.append("i")
-    extension_priorities = "imafdc"
-    args.extensions.sort(key=lambda k: extension_priorities.find(k))
-    args.extensions = "".join(args.extensions)
+    extension_priorities = ["i", "m", "a", "f", "d", "c", "zicsr", "zifencei", "priv"]
+    args.extensions.sort(key=lambda k: extension_priorities.index(k))
     return args
 
 if __name__ == "__main__":
@@ -430,12 +558,22 @@ if __name__ == "__main__":
 
     dispatchers = dict(
         i=rv32i,
+        a=rv32a,
         c=rv32c,
         f=rv32f,
+        d=rv32d,
         m=rv32m,
+        zicsr=zicsr,
+        zifencei=zifencei,
+        priv=system,
     )
 
+    # Compressed floating point loads and stores are only available when the 'C' extension is also enabled.
+    compressed = dict(f=rv32fc, d=rv32dc)
+
     opcodes_to_parse = "\n".join(dispatchers[x] for x in args.extensions)
+    if "c" in args.extensions:
+        opcodes_to_parse += "".join(compressed[x] for x in args.extensions if x in compressed)
 
     specs = parse(opcodes_to_parse)
     # generate_naive_code(specs)
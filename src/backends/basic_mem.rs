@@ -2,7 +2,8 @@
 
 use crate::memory::{Address, MemoryResult};
 pub use crate::memory::{Load, Memory};
-use std::io::{self, Write};
+
+use super::tty::Console;
 
 const MEMBASE: Address = 0;
 const MEMSIZE: Address = 0x8000;
@@ -28,16 +29,29 @@ const TTY_DATA: Address = TTY_STATUS + 1;
 /// | Address | Usage | Access |
 /// |---------|-------|--------|
 /// |  0x8000 | TTY status | R/O |
-/// |  0x8001 | TTY data   | W/O |
+/// |  0x8001 | TTY data   | R/W |
+///
+/// Bit 0 of the TTY status register is always set, meaning that the TTY can transmit. Bit 1 is set when the TTY has
+/// received a byte, which can be read from the TTY data register. By default, the TTY transmits to stdout and never
+/// receives anything, but it can be given a different [`Console`] with [`BasicMem::with_console()`].
 ///
 /// Attempts to read or write outside of the address space will result in an error.
 ///
 /// Attempts to write to ROM will result in an error.
-#[derive(PartialEq, Eq)]
 pub struct BasicMem {
     mem: [u8; MEMSIZE as usize],
+    console: Console,
+}
+
+// Memory is equal if its contents are equal, regardless of where the TTY is connected.
+impl PartialEq for BasicMem {
+    fn eq(&self, other: &Self) -> bool {
+        self.mem == other.mem
+    }
 }
 
+impl Eq for BasicMem {}
+
 impl Default for BasicMem {
     fn default() -> Self {
         Self::new()
@@ -48,8 +62,14 @@ impl BasicMem {
     pub fn new() -> Self {
         BasicMem {
             mem: [0; MEMSIZE as usize],
+            console: Console::default(),
         }
     }
+
+    /// Returns this memory, but with its TTY connected to the given console.
+    pub fn with_console(self, console: Console) -> Self {
+        Self { console, ..self }
+    }
 }
 
 impl Load for BasicMem {
@@ -73,7 +93,9 @@ impl Memory for BasicMem {
         if (MEMBASE..MEMBASE + MEMSIZE).contains(&address) {
             Ok(self.mem[(address - MEMBASE) as usize])
         } else if address == TTY_STATUS {
-            Ok(1)
+            Ok(self.console.status())
+        } else if address == TTY_DATA {
+            Ok(self.console.read().unwrap_or(0))
         } else {
             Err(address)
         }
@@ -110,10 +132,7 @@ impl Memory for BasicMem {
             self.mem[addr] = byte;
             Ok(())
         } else if address == TTY_DATA {
-            // Note that this flushes, largely because not doing so causes problems when switching from interpreted code
-            // to native code, most likely because of multiple runtimes.
-            print!("{}", byte as char);
-            io::stdout().flush().unwrap();
+            self.console.write(byte);
             Ok(())
         } else {
             Err(address)
@@ -145,11 +164,44 @@ impl Memory for BasicMem {
         }
         Err(address)
     }
+
+    #[inline]
+    fn read64(&self, address: Address) -> MemoryResult<u64> {
+        if (MEMBASE..MEMBASE + MEMSIZE - 7).contains(&address) {
+            let addr = (address - MEMBASE) as usize;
+            if let Ok(slice) = &self.mem[addr..addr + 8].try_into() {
+                return Ok(u64::from_le_bytes(*slice));
+            }
+        }
+        Err(address)
+    }
+
+    #[inline]
+    fn write64(&mut self, address: Address, double_word: u64) -> MemoryResult<()> {
+        if (RAMBASE..RAMBASE + RAMSIZE - 7).contains(&address) {
+            let addr = (address - MEMBASE) as usize;
+            self.mem[addr..addr + 8].copy_from_slice(&double_word.to_le_bytes());
+            return Ok(());
+        }
+        Err(address)
+    }
+
+    #[inline]
+    fn peek8(&self, address: Address) -> MemoryResult<u8> {
+        if (MEMBASE..MEMBASE + MEMSIZE).contains(&address) {
+            Ok(self.mem[(address - MEMBASE) as usize])
+        } else {
+            Err(address)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{BasicMem, Memory, TTY_DATA};
+    use std::sync::mpsc::channel;
+
+    use super::{BasicMem, Memory, TTY_DATA, TTY_STATUS};
+    use crate::backends::tty::{CaptureBuffer, Console};
 
     #[test]
     fn test_the_tty_is_writable() {
@@ -158,4 +210,22 @@ mod tests {
             mem.write8(TTY_DATA, *c).expect("Failed to write to TTY")
         }
     }
+
+    #[test]
+    fn test_the_tty_can_be_captured_and_fed() {
+        let output = CaptureBuffer::new();
+        let (sender, receiver) = channel();
+        let console = Console::new(output.clone()).with_input(receiver);
+        let mut mem = BasicMem::new().with_console(console);
+        assert_eq!(Ok(0b01), mem.read8(TTY_STATUS));
+        assert_eq!(Ok(0), mem.read8(TTY_DATA));
+        sender.send(b'y').unwrap();
+        assert_eq!(Ok(0b11), mem.read8(TTY_STATUS));
+        assert_eq!(Ok(b'y'), mem.read8(TTY_DATA));
+        assert_eq!(Ok(0b01), mem.read8(TTY_STATUS));
+        for c in b"ok\n" {
+            mem.write8(TTY_DATA, *c).unwrap();
+        }
+        assert_eq!("ok\n", output.to_string_lossy());
+    }
 }
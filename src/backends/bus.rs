@@ -0,0 +1,620 @@
+//! A memory bus that routes accesses to memory-mapped devices.
+
+use std::cell::{Cell, RefCell};
+use std::fmt::Display;
+use std::ops::BitOr;
+use std::rc::Rc;
+
+use crate::memory::{Address, MemoryResult};
+pub use crate::memory::{Load, Memory};
+
+/// The width of a memory access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Width {
+    /// An 8-bit access.
+    Byte,
+    /// A 16-bit access.
+    HalfWord,
+    /// A 32-bit access.
+    Word,
+}
+
+impl Width {
+    /// Returns the number of bytes accessed.
+    pub fn bytes(self) -> u32 {
+        match self {
+            Width::Byte => 1,
+            Width::HalfWord => 2,
+            Width::Word => 4,
+        }
+    }
+}
+
+/// A set of access widths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Widths(u8);
+
+impl Widths {
+    /// 8-bit accesses.
+    pub const BYTE: Widths = Widths(1);
+    /// 16-bit accesses.
+    pub const HALF_WORD: Widths = Widths(2);
+    /// 32-bit accesses.
+    pub const WORD: Widths = Widths(4);
+    /// Accesses of any width.
+    pub const ALL: Widths = Widths(7);
+
+    /// Returns true if the set contains the given width.
+    pub fn contains(self, width: Width) -> bool {
+        self.0 & (width.bytes() as u8) != 0
+    }
+}
+
+impl BitOr for Widths {
+    type Output = Widths;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Widths(self.0 | rhs.0)
+    }
+}
+
+/// What a region allows the CPU to do. Regions can always be loaded with [`Load::write_bytes`] if their device supports
+/// it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// The CPU can read and write the region.
+    ReadWrite,
+    /// The CPU can read the region, but writes are errors.
+    ReadOnly,
+    /// The CPU can write the region, but reads are errors.
+    WriteOnly,
+}
+
+impl Access {
+    fn can_read(self) -> bool {
+        self != Access::WriteOnly
+    }
+
+    fn can_write(self) -> bool {
+        self != Access::ReadOnly
+    }
+}
+
+/// A range of addresses that a device is mapped to, and how it may be accessed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    base: Address,
+    size: u32,
+    access: Access,
+    widths: Widths,
+}
+
+impl Region {
+    /// Creates a read-write region of `size` bytes starting at `base` that allows accesses of any width.
+    pub fn new(base: Address, size: u32) -> Self {
+        Self {
+            base,
+            size,
+            access: Access::ReadWrite,
+            widths: Widths::ALL,
+        }
+    }
+
+    /// Returns this region, but with the given access permissions.
+    pub fn with_access(self, access: Access) -> Self {
+        Self { access, ..self }
+    }
+
+    /// Returns this region, but only allowing accesses of the given widths.
+    pub fn with_widths(self, widths: Widths) -> Self {
+        Self { widths, ..self }
+    }
+
+    /// Returns the first address in the region.
+    pub fn base(&self) -> Address {
+        self.base
+    }
+
+    /// Returns the size of the region in bytes.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Returns the region's access permissions.
+    pub fn access(&self) -> Access {
+        self.access
+    }
+
+    /// Returns the access widths that the region allows.
+    pub fn widths(&self) -> Widths {
+        self.widths
+    }
+
+    /// Returns true if the region contains the given address.
+    pub fn contains(&self, address: Address) -> bool {
+        address.wrapping_sub(self.base) < self.size
+    }
+
+    // Returns the address after the end of the region, which may be 2^32.
+    fn end(&self) -> u64 {
+        u64::from(self.base) + u64::from(self.size)
+    }
+}
+
+/// A memory-mapped device.
+///
+/// Offsets are relative to the start of the region that the device is mapped to, and the bus guarantees that every
+/// byte of an access lies within that region. Errors return the offset that caused them.
+pub trait Device {
+    /// Reads a zero-extended value of the given width from the given offset.
+    fn read(&mut self, offset: u32, width: Width) -> MemoryResult<u32>;
+
+    /// Writes the low bytes of `value`, depending on the width, to the given offset.
+    fn write(&mut self, offset: u32, width: Width, value: u32) -> MemoryResult<()>;
+
+    /// Reads a zero-extended value of the given width from the given offset without any side effects, e.g., for a
+    /// watchpoint. Devices can't be peeked unless they override this.
+    fn peek(&self, offset: u32, _width: Width) -> MemoryResult<u32> {
+        Err(offset)
+    }
+
+    /// Loads a slice of bytes into the device starting at the given offset, e.g., to initialize ROM. Devices can't be
+    /// loaded unless they override this.
+    fn load(&mut self, offset: u32, _bytes: &[u8]) -> MemoryResult<()> {
+        Err(offset)
+    }
+
+    /// Gives the device a chance to respond to events that don't come from the CPU, e.g., input from the host. It's
+    /// called whenever the bus is ticked with [`Memory::tick`].
+    fn tick(&mut self) {}
+
+    /// Returns the number of ticks until the device next does something of its own accord, e.g., until a timer
+    /// expires, or `None` if nothing is scheduled.
+    fn next_event(&self) -> Option<u64> {
+        None
+    }
+
+    /// Advances the device by the given number of ticks at once. By default, this ticks once, which suits devices that
+    /// don't keep time.
+    fn advance(&mut self, _ticks: u64) {
+        self.tick()
+    }
+}
+
+/// A level-triggered interrupt line that connects a device to an interrupt controller or to a CPU.
+///
+/// Clones share the same line, so a device can keep one clone to raise and lower the interrupt while the receiver keeps
+/// another to see whether it's raised.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct InterruptLine(Rc<Cell<bool>>);
+
+impl InterruptLine {
+    /// Creates a line that isn't raised.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Raises or lowers the line.
+    pub fn set(&self, raised: bool) {
+        self.0.set(raised);
+    }
+
+    /// Returns true if the line is raised.
+    pub fn is_raised(&self) -> bool {
+        self.0.get()
+    }
+}
+
+/// A block of zero-initialized memory, which is ROM if it's mapped read-only.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Ram {
+    bytes: Box<[u8]>,
+}
+
+impl Ram {
+    /// Creates `size` bytes of memory on the heap.
+    pub fn new(size: u32) -> Self {
+        Self {
+            bytes: vec![0; size as usize].into_boxed_slice(),
+        }
+    }
+}
+
+impl Device for Ram {
+    fn read(&mut self, offset: u32, width: Width) -> MemoryResult<u32> {
+        self.peek(offset, width)
+    }
+
+    fn peek(&self, offset: u32, width: Width) -> MemoryResult<u32> {
+        let start = offset as usize;
+        let bytes = self
+            .bytes
+            .get(start..start + width.bytes() as usize)
+            .ok_or(offset)?;
+        Ok(bytes
+            .iter()
+            .rev()
+            .fold(0, |value, &byte| (value << 8) | u32::from(byte)))
+    }
+
+    fn write(&mut self, offset: u32, width: Width, value: u32) -> MemoryResult<()> {
+        let start = offset as usize;
+        let len = width.bytes() as usize;
+        let bytes = self.bytes.get_mut(start..start + len).ok_or(offset)?;
+        bytes.copy_from_slice(&value.to_le_bytes()[..len]);
+        Ok(())
+    }
+
+    fn load(&mut self, offset: u32, bytes: &[u8]) -> MemoryResult<()> {
+        let start = offset as usize;
+        let dst = self
+            .bytes
+            .get_mut(start..start + bytes.len())
+            .ok_or(offset)?;
+        dst.copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// An error that prevents a device from being mapped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusError {
+    /// The region at the given base address is empty.
+    Empty(Address),
+    /// The region at the given base address extends past the end of the address space.
+    OutOfRange(Address),
+    /// The region overlaps the existing region at the given base address.
+    Overlaps(Address),
+}
+
+impl Display for BusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BusError::Empty(base) => write!(f, "the region at 0x{:08x} is empty", base),
+            BusError::OutOfRange(base) => {
+                write!(
+                    f,
+                    "the region at 0x{:08x} is outside the address space",
+                    base
+                )
+            }
+            BusError::Overlaps(base) => {
+                write!(f, "the region overlaps the region at 0x{:08x}", base)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BusError {}
+
+/// A device that's mapped to a region.
+struct Mapping {
+    region: Region,
+    device: RefCell<Box<dyn Device>>,
+}
+
+/// A memory bus that routes accesses to the devices that are mapped to it.
+///
+/// Accesses to unmapped addresses, accesses that aren't allowed by a region's permissions or widths, and accesses that
+/// straddle the end of a region all result in an error.
+///
+/// This example assembles the same memory map as [`BasicMem`](crate::backends::memory::basic::BasicMem), but with a TTY that discards
+/// its output.
+///
+/// ```
+/// use arviss::backends::memory::bus::*;
+/// use arviss::MemoryResult;
+///
+/// struct Tty;
+///
+/// impl Device for Tty {
+///     fn read(&mut self, offset: u32, _width: Width) -> MemoryResult<u32> {
+///         if offset == 0 { Ok(1) } else { Err(offset) }
+///     }
+///
+///     fn write(&mut self, offset: u32, _width: Width, _value: u32) -> MemoryResult<()> {
+///         if offset == 1 { Ok(()) } else { Err(offset) }
+///     }
+/// }
+///
+/// let mut bus = Bus::new();
+/// bus.map_rom(0x0000, 0x4000).unwrap();
+/// bus.map_ram(0x4000, 0x4000).unwrap();
+/// bus.map(Region::new(0x8000, 2).with_widths(Widths::BYTE), Tty).unwrap();
+///
+/// bus.write_bytes(0, &[0x13, 0x00, 0x00, 0x00]).unwrap();
+/// assert_eq!(Ok(0x00000013), bus.read32(0));
+/// assert_eq!(Err(0), bus.write32(0, 0));
+/// assert_eq!(Ok(()), bus.write8(0x8001, b'A'));
+/// assert_eq!(Err(0x8000), bus.read16(0x8000));
+/// ```
+#[derive(Default)]
+pub struct Bus {
+    mappings: Vec<Mapping>, // Sorted by base address.
+}
+
+impl Bus {
+    /// Creates a bus with nothing mapped to it.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps a device to a region. Returns an error if the region is empty, if it extends past the end of the address
+    /// space, or if it overlaps a region that's already mapped.
+    pub fn map(&mut self, region: Region, device: impl Device + 'static) -> Result<(), BusError> {
+        if region.size == 0 {
+            return Err(BusError::Empty(region.base));
+        }
+        if region.end() > 1 << 32 {
+            return Err(BusError::OutOfRange(region.base));
+        }
+        let index = self
+            .mappings
+            .partition_point(|m| m.region.base < region.base);
+        let before = index.checked_sub(1).map(|i| &self.mappings[i]);
+        if let Some(before) = before.filter(|m| m.region.end() > u64::from(region.base)) {
+            return Err(BusError::Overlaps(before.region.base));
+        }
+        if let Some(after) = self
+            .mappings
+            .get(index)
+            .filter(|m| u64::from(m.region.base) < region.end())
+        {
+            return Err(BusError::Overlaps(after.region.base));
+        }
+        self.mappings.insert(
+            index,
+            Mapping {
+                region,
+                device: RefCell::new(Box::new(device)),
+            },
+        );
+        Ok(())
+    }
+
+    /// Maps `size` bytes of read-write memory starting at `base`.
+    pub fn map_ram(&mut self, base: Address, size: u32) -> Result<(), BusError> {
+        self.map(Region::new(base, size), Ram::new(size))
+    }
+
+    /// Maps `size` bytes of read-only memory starting at `base`. Its contents can be set with [`Load::write_bytes`].
+    pub fn map_rom(&mut self, base: Address, size: u32) -> Result<(), BusError> {
+        self.map(
+            Region::new(base, size).with_access(Access::ReadOnly),
+            Ram::new(size),
+        )
+    }
+
+    /// Returns the mapped regions in address order.
+    pub fn regions(&self) -> impl Iterator<Item = &Region> {
+        self.mappings.iter().map(|m| &m.region)
+    }
+
+    // Returns the mapping that contains the given address.
+    fn find(&self, address: Address) -> Option<&Mapping> {
+        let index = self.mappings.partition_point(|m| m.region.base <= address);
+        let mapping = &self.mappings[index.checked_sub(1)?];
+        mapping.region.contains(address).then_some(mapping)
+    }
+
+    // Returns the mapping that contains every byte of the given access, if the region allows its width.
+    fn route(&self, address: Address, width: Width) -> MemoryResult<&Mapping> {
+        let mapping = self.find(address).ok_or(address)?;
+        let region = &mapping.region;
+        let offset = address - region.base;
+        if !region.widths.contains(width) || region.size - offset < width.bytes() {
+            return Err(address);
+        }
+        Ok(mapping)
+    }
+
+    fn read(&self, address: Address, width: Width) -> MemoryResult<u32> {
+        let mapping = self.route(address, width)?;
+        if !mapping.region.access.can_read() {
+            return Err(address);
+        }
+        let base = mapping.region.base;
+        mapping
+            .device
+            .borrow_mut()
+            .read(address - base, width)
+            .map_err(|offset| base.wrapping_add(offset))
+    }
+
+    fn write(&mut self, address: Address, width: Width, value: u32) -> MemoryResult<()> {
+        let mapping = self.route(address, width)?;
+        if !mapping.region.access.can_write() {
+            return Err(address);
+        }
+        let base = mapping.region.base;
+        mapping
+            .device
+            .borrow_mut()
+            .write(address - base, width, value)
+            .map_err(|offset| base.wrapping_add(offset))
+    }
+}
+
+impl Load for Bus {
+    fn write_bytes(&mut self, start: Address, bytes: &[u8]) -> MemoryResult<()> {
+        // Split the bytes between the regions that they span.
+        let mut address = start;
+        let mut bytes = bytes;
+        while !bytes.is_empty() {
+            let mapping = self.find(address).ok_or(address)?;
+            let base = mapping.region.base;
+            let offset = address - base;
+            let len = bytes.len().min((mapping.region.size - offset) as usize);
+            mapping
+                .device
+                .borrow_mut()
+                .load(offset, &bytes[..len])
+                .map_err(|offset| base.wrapping_add(offset))?;
+            address = address.wrapping_add(len as u32);
+            bytes = &bytes[len..];
+        }
+        Ok(())
+    }
+}
+
+impl Memory for Bus {
+    fn read8(&self, address: Address) -> MemoryResult<u8> {
+        self.read(address, Width::Byte).map(|value| value as u8)
+    }
+
+    fn read16(&self, address: Address) -> MemoryResult<u16> {
+        self.read(address, Width::HalfWord)
+            .map(|value| value as u16)
+    }
+
+    fn read32(&self, address: Address) -> MemoryResult<u32> {
+        self.read(address, Width::Word)
+    }
+
+    fn write8(&mut self, address: Address, byte: u8) -> MemoryResult<()> {
+        self.write(address, Width::Byte, u32::from(byte))
+    }
+
+    fn write16(&mut self, address: Address, half_word: u16) -> MemoryResult<()> {
+        self.write(address, Width::HalfWord, u32::from(half_word))
+    }
+
+    fn write32(&mut self, address: Address, word: u32) -> MemoryResult<()> {
+        self.write(address, Width::Word, word)
+    }
+
+    /// Writes a double word as two words, but only after checking that both of them are routed to writable regions.
+    fn write64(&mut self, address: Address, double_word: u64) -> MemoryResult<()> {
+        for word_address in [address, address.wrapping_add(4)] {
+            let mapping = self.route(word_address, Width::Word)?;
+            if !mapping.region.access.can_write() {
+                return Err(word_address);
+            }
+        }
+        self.write(address, Width::Word, double_word as u32)?;
+        self.write(
+            address.wrapping_add(4),
+            Width::Word,
+            (double_word >> 32) as u32,
+        )
+    }
+
+    /// Peeks at a byte if it's in a region that can be read and its device can be peeked. See [`Device::peek`].
+    fn peek8(&self, address: Address) -> MemoryResult<u8> {
+        let mapping = self.route(address, Width::Byte)?;
+        if !mapping.region.access.can_read() {
+            return Err(address);
+        }
+        let base = mapping.region.base;
+        mapping
+            .device
+            .borrow()
+            .peek(address - base, Width::Byte)
+            .map(|value| value as u8)
+            .map_err(|offset| base.wrapping_add(offset))
+    }
+
+    /// Ticks every device. See [`Device::tick`].
+    fn tick(&mut self) {
+        for mapping in &self.mappings {
+            mapping.device.borrow_mut().tick();
+        }
+    }
+
+    /// Returns the soonest event of any device. See [`Device::next_event`].
+    fn next_event(&self) -> Option<u64> {
+        self.mappings
+            .iter()
+            .filter_map(|mapping| mapping.device.borrow().next_event())
+            .min()
+    }
+
+    /// Advances every device. See [`Device::advance`].
+    fn advance(&mut self, ticks: u64) {
+        for mapping in &self.mappings {
+            mapping.device.borrow_mut().advance(ticks);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Access, Bus, BusError, Load, Memory, Region, Widths};
+
+    #[test]
+    fn test_regions_must_not_be_empty_out_of_range_or_overlapping() {
+        let mut bus = Bus::new();
+        bus.map_ram(0x1000, 0x1000).unwrap();
+        bus.map_ram(0xffff_f000, 0x1000).unwrap();
+        assert_eq!(Err(BusError::Empty(0)), bus.map_ram(0, 0));
+        assert_eq!(
+            Err(BusError::OutOfRange(0xffff_f000)),
+            bus.map_ram(0xffff_f000, 0x1001)
+        );
+        assert_eq!(Err(BusError::Overlaps(0x1000)), bus.map_ram(0x1fff, 1));
+        assert_eq!(Err(BusError::Overlaps(0x1000)), bus.map_ram(0x0800, 0x1000));
+        assert_eq!(Err(BusError::Overlaps(0x1000)), bus.map_ram(0x0000, 0x3000));
+        bus.map_ram(0x0800, 0x800).unwrap();
+        bus.map_ram(0x2000, 0x800).unwrap();
+        let bases: Vec<u32> = bus.regions().map(|r| r.base()).collect();
+        assert_eq!(vec![0x0800, 0x1000, 0x2000, 0xffff_f000], bases);
+    }
+
+    #[test]
+    fn test_accesses_are_routed_to_the_region_that_contains_them() {
+        let mut bus = Bus::new();
+        bus.map_ram(0x1000, 0x100).unwrap();
+        bus.map_ram(0x1100, 0x100).unwrap();
+        bus.write32(0x10fc, 0x44332211).unwrap();
+        bus.write32(0x1100, 0x88776655).unwrap();
+        assert_eq!(Ok(0x2211), bus.read16(0x10fc));
+        assert_eq!(Ok(0x55), bus.read8(0x1100));
+        assert_eq!(Err(0x10fe), bus.read32(0x10fe)); // Straddles two regions.
+        assert_eq!(Err(0x1200), bus.read8(0x1200)); // Unmapped.
+        assert_eq!(Err(0x0fff), bus.write8(0x0fff, 0)); // Unmapped.
+        bus.write_bytes(0x10ff, &[1, 2]).unwrap();
+        assert_eq!(Ok(1), bus.read8(0x10ff));
+        assert_eq!(Ok(2), bus.read8(0x1100));
+        assert_eq!(Err(0x1200), bus.write_bytes(0x11ff, &[1, 2]));
+    }
+
+    #[test]
+    fn test_regions_enforce_permissions_and_widths() {
+        let mut bus = Bus::new();
+        bus.map_rom(0, 0x100).unwrap();
+        bus.map(
+            Region::new(0x100, 0x100)
+                .with_access(Access::WriteOnly)
+                .with_widths(Widths::BYTE | Widths::WORD),
+            super::Ram::new(0x100),
+        )
+        .unwrap();
+        bus.write_bytes(0, &[0x13, 0, 0, 0]).unwrap();
+        assert_eq!(Ok(0x13), bus.read32(0));
+        assert_eq!(Err(0), bus.write8(0, 0));
+        assert_eq!(Ok(()), bus.write8(0x100, 0));
+        assert_eq!(Ok(()), bus.write32(0x104, 0));
+        assert_eq!(Err(0x108), bus.write16(0x108, 0));
+        assert_eq!(Err(0x100), bus.read8(0x100));
+    }
+
+    #[test]
+    fn test_only_readable_memory_can_be_peeked() {
+        use crate::backends::devices::tty::Console;
+
+        let mut bus = Bus::new();
+        bus.map_rom(0, 0x100).unwrap();
+        bus.map(
+            Region::new(0x100, 0x100).with_access(Access::WriteOnly),
+            super::Ram::new(0x100),
+        )
+        .unwrap();
+        bus.map(Region::new(0x200, 2), Console::new(std::io::sink()))
+            .unwrap();
+        bus.write_bytes(0, &[0x13]).unwrap();
+        assert_eq!(Ok(0x13), bus.peek8(0));
+        assert_eq!(Err(0x100), bus.peek8(0x100));
+        assert_eq!(Err(0x200), bus.peek8(0x200));
+        assert_eq!(Err(0x300), bus.peek8(0x300));
+    }
+}
@@ -0,0 +1,275 @@
+//! A core-local interruptor (CLINT) that provides a single hart with its machine timer and software interrupts.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use super::bus::{Device, InterruptLine, Width};
+use crate::memory::MemoryResult;
+
+/// The size of the CLINT's address space in bytes.
+pub const CLINT_SIZE: u32 = 0x10000;
+
+// Register offsets, as used by SiFive's CLINT and QEMU's `virt` machine.
+const MSIP: u32 = 0x0000;
+const MTIMECMP: u32 = 0x4000;
+const MTIMECMPH: u32 = 0x4004;
+const MTIME: u32 = 0xbff8;
+const MTIMEH: u32 = 0xbffc;
+
+/// A clock that's advanced by the host rather than by the guest.
+///
+/// Clones share the same clock, so the host can keep one clone to advance time while devices keep others to read it.
+#[derive(Debug, Default, Clone)]
+pub struct VirtualClock(Rc<Cell<u64>>);
+
+impl VirtualClock {
+    /// Creates a clock that reads zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the current time in ticks.
+    pub fn now(&self) -> u64 {
+        self.0.get()
+    }
+
+    /// Sets the current time.
+    pub fn set(&self, now: u64) {
+        self.0.set(now);
+    }
+
+    /// Advances the current time by the given number of ticks.
+    pub fn advance(&self, ticks: u64) {
+        self.0.set(self.0.get().wrapping_add(ticks));
+    }
+}
+
+/// Where a [`Clint`] gets the time from.
+#[derive(Debug, Default, Clone)]
+pub enum Timebase {
+    /// `mtime` advances by one every time the CLINT is ticked, i.e., once per instruction.
+    #[default]
+    Instructions,
+    /// `mtime` follows a virtual clock, which the host advances. The CLINT only advances it when idle time is skipped.
+    Clock(VirtualClock),
+}
+
+/// A core-local interruptor with `msip`, `mtimecmp` and `mtime` registers for a single hart.
+///
+/// The timer interrupt line is raised whenever `mtime` is greater than or equal to `mtimecmp`, and the software
+/// interrupt line is raised whenever bit 0 of `msip` is set. The registers are 32 bits wide, so the 64-bit `mtime` and
+/// `mtimecmp` are accessed as pairs of words. Only word accesses are supported.
+///
+/// ```
+/// use arviss::backends::devices::clint::*;
+/// use arviss::backends::memory::bus::*;
+///
+/// let timer = InterruptLine::new();
+/// let mut bus = Bus::new();
+/// let region = Region::new(0x0200_0000, CLINT_SIZE).with_widths(Widths::WORD);
+/// bus.map(region, Clint::new().with_timer_interrupt(timer.clone())).unwrap();
+/// bus.write32(0x0200_4000, 2).unwrap(); // mtimecmp
+/// bus.write32(0x0200_4004, 0).unwrap(); // mtimecmp (high)
+/// bus.tick();
+/// assert!(!timer.is_raised());
+/// bus.tick();
+/// assert!(timer.is_raised());
+/// assert_eq!(Ok(2), bus.read32(0x0200_bff8)); // mtime
+/// ```
+#[derive(Debug, Clone)]
+pub struct Clint {
+    timebase: Timebase,
+    ticks: u64,  // Ticks counted for an instruction timebase.
+    offset: u64, // Added to the timebase to give mtime, so that mtime can be written.
+    mtimecmp: u64,
+    msip: bool,
+    software: InterruptLine,
+    timer: InterruptLine,
+}
+
+impl Default for Clint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clint {
+    /// Creates a CLINT whose `mtime` advances once per instruction, with no interrupt lines connected.
+    pub fn new() -> Self {
+        Self {
+            timebase: Timebase::Instructions,
+            ticks: 0,
+            offset: 0,
+            mtimecmp: u64::MAX,
+            msip: false,
+            software: InterruptLine::new(),
+            timer: InterruptLine::new(),
+        }
+    }
+
+    /// Returns this CLINT, but with `mtime` following the given virtual clock.
+    pub fn with_clock(self, clock: VirtualClock) -> Self {
+        Self {
+            timebase: Timebase::Clock(clock),
+            ..self
+        }
+    }
+
+    /// Returns this CLINT, but driving the given line when a software interrupt is pending.
+    pub fn with_software_interrupt(self, software: InterruptLine) -> Self {
+        let clint = Self { software, ..self };
+        clint.update_interrupts();
+        clint
+    }
+
+    /// Returns this CLINT, but driving the given line when a timer interrupt is pending.
+    pub fn with_timer_interrupt(self, timer: InterruptLine) -> Self {
+        let clint = Self { timer, ..self };
+        clint.update_interrupts();
+        clint
+    }
+
+    /// Returns the current value of `mtime`.
+    pub fn mtime(&self) -> u64 {
+        self.timebase().wrapping_add(self.offset)
+    }
+
+    /// Returns the current value of `mtimecmp`.
+    pub fn mtimecmp(&self) -> u64 {
+        self.mtimecmp
+    }
+
+    fn timebase(&self) -> u64 {
+        match &self.timebase {
+            Timebase::Instructions => self.ticks,
+            Timebase::Clock(clock) => clock.now(),
+        }
+    }
+
+    fn set_mtime(&mut self, mtime: u64) {
+        self.offset = mtime.wrapping_sub(self.timebase());
+    }
+
+    fn update_interrupts(&self) {
+        self.software.set(self.msip);
+        self.timer.set(self.mtime() >= self.mtimecmp);
+    }
+}
+
+// Replaces the low or the high word of a 64-bit register.
+fn with_word(register: u64, high: bool, word: u32) -> u64 {
+    if high {
+        (register & 0xffff_ffff) | (u64::from(word) << 32)
+    } else {
+        (register & !0xffff_ffff) | u64::from(word)
+    }
+}
+
+impl Device for Clint {
+    fn read(&mut self, offset: u32, width: Width) -> MemoryResult<u32> {
+        if width != Width::Word {
+            return Err(offset);
+        }
+        self.update_interrupts();
+        match offset {
+            MSIP => Ok(u32::from(self.msip)),
+            MTIMECMP => Ok(self.mtimecmp as u32),
+            MTIMECMPH => Ok((self.mtimecmp >> 32) as u32),
+            MTIME => Ok(self.mtime() as u32),
+            MTIMEH => Ok((self.mtime() >> 32) as u32),
+            _ => Err(offset),
+        }
+    }
+
+    fn write(&mut self, offset: u32, width: Width, value: u32) -> MemoryResult<()> {
+        if width != Width::Word {
+            return Err(offset);
+        }
+        match offset {
+            MSIP => self.msip = value & 1 != 0,
+            MTIMECMP | MTIMECMPH => {
+                self.mtimecmp = with_word(self.mtimecmp, offset == MTIMECMPH, value)
+            }
+            MTIME | MTIMEH => self.set_mtime(with_word(self.mtime(), offset == MTIMEH, value)),
+            _ => return Err(offset),
+        }
+        self.update_interrupts();
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        if let Timebase::Instructions = self.timebase {
+            self.ticks = self.ticks.wrapping_add(1);
+        }
+        self.update_interrupts();
+    }
+
+    fn next_event(&self) -> Option<u64> {
+        // The timer interrupt is the only thing that's scheduled, and there's nothing to wait for once it's pending.
+        let mtime = self.mtime();
+        (mtime < self.mtimecmp).then(|| self.mtimecmp - mtime)
+    }
+
+    // Skipping ahead moves a virtual clock too, because it's the only way that time can pass while the hart is idle.
+    fn advance(&mut self, ticks: u64) {
+        match &self.timebase {
+            Timebase::Instructions => self.ticks = self.ticks.wrapping_add(ticks),
+            Timebase::Clock(clock) => clock.advance(ticks),
+        }
+        self.update_interrupts();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clint, VirtualClock, CLINT_SIZE};
+    use crate::backends::bus::{Bus, InterruptLine, Memory, Region, Widths};
+
+    const BASE: u32 = 0x0200_0000;
+
+    fn bus_with(clint: Clint) -> Bus {
+        let mut bus = Bus::new();
+        let region = Region::new(BASE, CLINT_SIZE).with_widths(Widths::WORD);
+        bus.map(region, clint).unwrap();
+        bus
+    }
+
+    #[test]
+    fn test_msip_drives_the_software_interrupt() {
+        let software = InterruptLine::new();
+        let mut bus = bus_with(Clint::new().with_software_interrupt(software.clone()));
+        bus.write32(BASE, 0xffff_ffff).unwrap();
+        assert!(software.is_raised());
+        assert_eq!(Ok(1), bus.read32(BASE));
+        bus.write32(BASE, 0).unwrap();
+        assert!(!software.is_raised());
+        assert_eq!(Err(BASE), bus.read8(BASE));
+    }
+
+    #[test]
+    fn test_mtime_can_follow_a_virtual_clock_and_be_written() {
+        let clock = VirtualClock::new();
+        let timer = InterruptLine::new();
+        let clint = Clint::new()
+            .with_clock(clock.clone())
+            .with_timer_interrupt(timer.clone());
+        let mut bus = bus_with(clint);
+        bus.write32(BASE + 0x4004, 1).unwrap(); // mtimecmp = 0x1_0000_0000
+        bus.write32(BASE + 0x4000, 0).unwrap();
+        clock.set(0xffff_fff0);
+        bus.tick();
+        assert_eq!(Ok(0xffff_fff0), bus.read32(BASE + 0xbff8));
+        assert!(!timer.is_raised());
+        clock.advance(0x10);
+        bus.tick();
+        assert!(timer.is_raised());
+        assert_eq!(Ok(1), bus.read32(BASE + 0xbffc));
+
+        // Writing mtime moves it relative to the clock.
+        bus.write32(BASE + 0xbffc, 0).unwrap();
+        assert!(!timer.is_raised());
+        assert_eq!(Ok(0), bus.read32(BASE + 0xbff8));
+        clock.advance(5);
+        assert_eq!(Ok(5), bus.read32(BASE + 0xbff8));
+    }
+}
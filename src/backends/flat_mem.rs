@@ -0,0 +1,398 @@
+//! Memory with a ROM, a RAM and a TTY whose sizes and addresses are chosen at construction time.
+
+use crate::memory::{Address, MemoryResult};
+pub use crate::memory::{Load, Memory};
+
+use super::tty::Console;
+
+/// The layout of a [`FlatMem`].
+///
+/// The TTY has a status register at `tty_base` and a data register at `tty_base + 1`, just like
+/// [`BasicMem`](crate::backends::memory::basic::BasicMem)'s. It transmits to stdout unless it's given a different
+/// [`Console`] with [`FlatMem::with_console()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    /// The address of the first byte of ROM.
+    pub rom_base: Address,
+    /// The size of ROM in bytes, which may be zero.
+    pub rom_size: u32,
+    /// The address of the first byte of RAM.
+    pub ram_base: Address,
+    /// The size of RAM in bytes.
+    pub ram_size: u32,
+    /// The address of the TTY, if there is one.
+    pub tty_base: Option<Address>,
+}
+
+impl Layout {
+    /// The same layout as `BasicMem`, i.e., 16K of ROM at 0x0000, 16K of RAM at 0x4000, and a TTY at 0x8000.
+    pub const BASIC: Layout = Layout {
+        rom_base: 0x0000,
+        rom_size: 0x4000,
+        ram_base: 0x4000,
+        ram_size: 0x4000,
+        tty_base: Some(0x8000),
+    };
+
+    /// The memory map shared by QEMU's `virt` machine and Spike, i.e., a boot ROM at 0x1000 and 128M of RAM at
+    /// 0x8000_0000. There's no TTY because both use a UART at 0x1000_0000 instead.
+    pub const QEMU_VIRT: Layout = Layout {
+        rom_base: 0x0000_1000,
+        rom_size: 0xf000,
+        ram_base: 0x8000_0000,
+        ram_size: 0x0800_0000,
+        tty_base: None,
+    };
+
+    /// Returns this layout, but with the given amount of RAM.
+    pub fn with_ram_size(self, ram_size: u32) -> Self {
+        Self { ram_size, ..self }
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout::BASIC
+    }
+}
+
+/// Memory with a ROM, a RAM and an optional TTY, laid out as described by a [`Layout`].
+///
+/// ROM and RAM are allocated on the heap, so they can be as large as the address space allows. As with `BasicMem`,
+/// attempts to write to ROM result in an error, but it can be initialized with [`Load::write_bytes`]. Attempts to read
+/// or write anything that isn't mapped also result in an error.
+///
+/// ```
+/// use arviss::backends::memory::flat::*;
+///
+/// let mut mem = FlatMem::new(Layout::QEMU_VIRT);
+/// mem.write_bytes(0x8000_0000, &0x00100073u32.to_le_bytes()).unwrap(); // ebreak
+/// assert_eq!(Ok(0x00100073), mem.read32(0x8000_0000));
+/// assert_eq!(Err(0x1000), mem.write32(0x1000, 0)); // ROM
+/// ```
+pub struct FlatMem {
+    layout: Layout,
+    rom: Box<[u8]>,
+    ram: Box<[u8]>,
+    console: Console,
+}
+
+// Memory is equal if its layout and contents are equal, regardless of where the TTY is connected.
+impl PartialEq for FlatMem {
+    fn eq(&self, other: &Self) -> bool {
+        self.layout == other.layout && self.rom == other.rom && self.ram == other.ram
+    }
+}
+
+impl Eq for FlatMem {}
+
+impl Default for FlatMem {
+    fn default() -> Self {
+        Self::new(Layout::default())
+    }
+}
+
+impl FlatMem {
+    /// Creates memory with the given layout.
+    ///
+    /// # Panics
+    ///
+    /// Panics if ROM, RAM or the TTY extend past the end of the address space, or if any of them overlap.
+    pub fn new(layout: Layout) -> Self {
+        let areas = [
+            (layout.rom_base, layout.rom_size),
+            (layout.ram_base, layout.ram_size),
+            layout.tty_base.map_or((0, 0), |base| (base, 2)),
+        ];
+        for (i, &(base, size)) in areas.iter().enumerate() {
+            let end = u64::from(base) + u64::from(size);
+            assert!(
+                end <= 1 << 32,
+                "0x{:08x} is outside the address space",
+                base
+            );
+            for &(other_base, other_size) in &areas[i + 1..] {
+                let other_end = u64::from(other_base) + u64::from(other_size);
+                let overlaps = size != 0
+                    && other_size != 0
+                    && u64::from(base) < other_end
+                    && u64::from(other_base) < end;
+                assert!(!overlaps, "0x{:08x} overlaps 0x{:08x}", base, other_base);
+            }
+        }
+        Self {
+            layout,
+            rom: vec![0; layout.rom_size as usize].into_boxed_slice(),
+            ram: vec![0; layout.ram_size as usize].into_boxed_slice(),
+            console: Console::default(),
+        }
+    }
+
+    /// Returns this memory, but with its TTY connected to the given console.
+    pub fn with_console(self, console: Console) -> Self {
+        Self { console, ..self }
+    }
+
+    /// Returns the layout.
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    // Returns true if the address is the TTY's data register.
+    #[inline]
+    fn is_tty_data(&self, address: Address) -> bool {
+        self.layout.tty_base.map(|base| base.wrapping_add(1)) == Some(address)
+    }
+
+    // Returns the offset of `len` bytes at `address` within RAM, if they're all in RAM.
+    #[inline]
+    fn ram_offset(&self, address: Address, len: u32) -> Option<usize> {
+        let offset = address.wrapping_sub(self.layout.ram_base);
+        (offset < self.layout.ram_size && self.layout.ram_size - offset >= len)
+            .then_some(offset as usize)
+    }
+
+    // Returns the offset of `len` bytes at `address` within ROM, if they're all in ROM.
+    #[inline]
+    fn rom_offset(&self, address: Address, len: u32) -> Option<usize> {
+        let offset = address.wrapping_sub(self.layout.rom_base);
+        (offset < self.layout.rom_size && self.layout.rom_size - offset >= len)
+            .then_some(offset as usize)
+    }
+
+    // Returns `N` bytes from RAM or ROM.
+    #[inline]
+    fn read_bytes<const N: usize>(&self, address: Address) -> MemoryResult<[u8; N]> {
+        let bytes = if let Some(offset) = self.ram_offset(address, N as u32) {
+            &self.ram[offset..offset + N]
+        } else if let Some(offset) = self.rom_offset(address, N as u32) {
+            &self.rom[offset..offset + N]
+        } else {
+            return Err(address);
+        };
+        bytes.try_into().map_err(|_| address)
+    }
+
+    // Writes bytes to RAM.
+    #[inline]
+    fn write_bytes_to_ram(&mut self, address: Address, bytes: &[u8]) -> MemoryResult<()> {
+        let offset = self
+            .ram_offset(address, bytes.len() as u32)
+            .ok_or(address)?;
+        self.ram[offset..offset + bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+impl Load for FlatMem {
+    fn write_bytes(&mut self, start: Address, bytes: &[u8]) -> MemoryResult<()> {
+        let len = u32::try_from(bytes.len()).map_err(|_| start)?;
+        if let Some(offset) = self.ram_offset(start, len) {
+            self.ram[offset..offset + bytes.len()].copy_from_slice(bytes);
+            Ok(())
+        } else if let Some(offset) = self.rom_offset(start, len) {
+            self.rom[offset..offset + bytes.len()].copy_from_slice(bytes);
+            Ok(())
+        } else {
+            Err(start)
+        }
+    }
+}
+
+impl Memory for FlatMem {
+    #[inline]
+    fn read8(&self, address: Address) -> MemoryResult<u8> {
+        match self.read_bytes::<1>(address) {
+            Ok(bytes) => Ok(bytes[0]),
+            Err(_) if self.layout.tty_base == Some(address) => Ok(self.console.status()),
+            Err(_) if self.is_tty_data(address) => Ok(self.console.read().unwrap_or(0)),
+            Err(address) => Err(address),
+        }
+    }
+
+    #[inline]
+    fn read16(&self, address: Address) -> MemoryResult<u16> {
+        self.read_bytes(address).map(u16::from_le_bytes)
+    }
+
+    #[inline]
+    fn read32(&self, address: Address) -> MemoryResult<u32> {
+        self.read_bytes(address).map(u32::from_le_bytes)
+    }
+
+    #[inline]
+    fn write8(&mut self, address: Address, byte: u8) -> MemoryResult<()> {
+        if self.is_tty_data(address) {
+            self.console.write(byte);
+            return Ok(());
+        }
+        self.write_bytes_to_ram(address, &[byte])
+    }
+
+    #[inline]
+    fn write16(&mut self, address: Address, half_word: u16) -> MemoryResult<()> {
+        self.write_bytes_to_ram(address, &half_word.to_le_bytes())
+    }
+
+    #[inline]
+    fn write32(&mut self, address: Address, word: u32) -> MemoryResult<()> {
+        self.write_bytes_to_ram(address, &word.to_le_bytes())
+    }
+
+    #[inline]
+    fn read64(&self, address: Address) -> MemoryResult<u64> {
+        self.read_bytes(address).map(u64::from_le_bytes)
+    }
+
+    #[inline]
+    fn write64(&mut self, address: Address, double_word: u64) -> MemoryResult<()> {
+        self.write_bytes_to_ram(address, &double_word.to_le_bytes())
+    }
+
+    #[inline]
+    fn peek8(&self, address: Address) -> MemoryResult<u8> {
+        self.read_bytes::<1>(address).map(|bytes| bytes[0])
+    }
+}
+
+/// A [`FlatMem`] whose default layout is [`Layout::QEMU_VIRT`] rather than [`Layout::BASIC`], so that CPUs created
+/// with `new()` get memory laid out like QEMU's `virt` machine.
+///
+/// ```
+/// use arviss::backends::memory::flat::*;
+///
+/// let mem = VirtMem::new();
+/// assert_eq!(&Layout::QEMU_VIRT, mem.layout());
+/// let mem = VirtMem::from(FlatMem::new(Layout::QEMU_VIRT.with_ram_size(0x1000)));
+/// assert_eq!(Err(0x8000_1000), mem.read32(0x8000_1000));
+/// ```
+#[derive(PartialEq, Eq)]
+pub struct VirtMem(FlatMem);
+
+impl Default for VirtMem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<FlatMem> for VirtMem {
+    fn from(mem: FlatMem) -> Self {
+        Self(mem)
+    }
+}
+
+impl VirtMem {
+    /// Creates memory with the [`Layout::QEMU_VIRT`] layout.
+    pub fn new() -> Self {
+        Self(FlatMem::new(Layout::QEMU_VIRT))
+    }
+
+    /// Returns the layout.
+    pub fn layout(&self) -> &Layout {
+        self.0.layout()
+    }
+
+    /// Consumes this memory, returning the underlying [`FlatMem`].
+    pub fn into_inner(self) -> FlatMem {
+        self.0
+    }
+}
+
+impl Load for VirtMem {
+    fn write_bytes(&mut self, start: Address, bytes: &[u8]) -> MemoryResult<()> {
+        self.0.write_bytes(start, bytes)
+    }
+}
+
+impl Memory for VirtMem {
+    #[inline]
+    fn read8(&self, address: Address) -> MemoryResult<u8> {
+        self.0.read8(address)
+    }
+
+    #[inline]
+    fn read16(&self, address: Address) -> MemoryResult<u16> {
+        self.0.read16(address)
+    }
+
+    #[inline]
+    fn read32(&self, address: Address) -> MemoryResult<u32> {
+        self.0.read32(address)
+    }
+
+    #[inline]
+    fn write8(&mut self, address: Address, byte: u8) -> MemoryResult<()> {
+        self.0.write8(address, byte)
+    }
+
+    #[inline]
+    fn write16(&mut self, address: Address, half_word: u16) -> MemoryResult<()> {
+        self.0.write16(address, half_word)
+    }
+
+    #[inline]
+    fn write32(&mut self, address: Address, word: u32) -> MemoryResult<()> {
+        self.0.write32(address, word)
+    }
+
+    #[inline]
+    fn read64(&self, address: Address) -> MemoryResult<u64> {
+        self.0.read64(address)
+    }
+
+    #[inline]
+    fn write64(&mut self, address: Address, double_word: u64) -> MemoryResult<()> {
+        self.0.write64(address, double_word)
+    }
+
+    #[inline]
+    fn peek8(&self, address: Address) -> MemoryResult<u8> {
+        self.0.peek8(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FlatMem, Layout, Load, Memory};
+    use crate::backends::tty::{CaptureBuffer, Console};
+
+    #[test]
+    fn test_rom_is_read_only_but_loadable() {
+        let mut mem = FlatMem::new(Layout::QEMU_VIRT.with_ram_size(0x1000));
+        mem.write_bytes(0x1000, &[0x13, 0x00, 0x00, 0x00]).unwrap();
+        assert_eq!(Ok(0x00000013), mem.read32(0x1000));
+        assert_eq!(Err(0x1000), mem.write8(0x1000, 0));
+        assert_eq!(Err(0xfffe), mem.read32(0xfffe)); // Straddles the end of ROM.
+    }
+
+    #[test]
+    fn test_ram_is_where_the_layout_says() {
+        let mut mem = FlatMem::new(Layout::QEMU_VIRT.with_ram_size(0x1000));
+        mem.write32(0x8000_0ffc, 0x44332211).unwrap();
+        assert_eq!(Ok(0x4433), mem.read16(0x8000_0ffe));
+        assert_eq!(Err(0x8000_0ffe), mem.write32(0x8000_0ffe, 0));
+        assert_eq!(Err(0x8000_1000), mem.read8(0x8000_1000));
+        assert_eq!(Err(0x7fff_ffff), mem.read8(0x7fff_ffff));
+        assert_eq!(Err(0x8000_0fff), mem.write_bytes(0x8000_0fff, &[1, 2]));
+    }
+
+    #[test]
+    fn test_the_basic_layout_has_a_tty() {
+        let output = CaptureBuffer::new();
+        let mut mem = FlatMem::new(Layout::BASIC).with_console(Console::new(output.clone()));
+        assert_eq!(Ok(1), mem.read8(0x8000));
+        assert_eq!(Ok(0), mem.read8(0x8001));
+        assert_eq!(Ok(()), mem.write8(0x8001, b'\n'));
+        assert_eq!(Err(0x8000), mem.write8(0x8000, 0));
+        assert_eq!(b"\n", output.contents().as_slice());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_overlapping_layouts_are_rejected() {
+        FlatMem::new(Layout {
+            ram_base: 0x2000,
+            ..Layout::BASIC
+        });
+    }
+}
@@ -1,22 +1,92 @@
 //! Implementations of CPU and memory back ends.
 
 mod basic_mem;
+mod bus;
+mod clint;
+mod flat_mem;
+mod plic;
 mod rv32icpu;
+mod rv32ifcpu;
+mod rv32ifdcpu;
+mod rv32iprivcpu;
+mod rv32izicsrcpu;
+mod sparse_mem;
+mod tty;
+mod uart;
 
 /// Memory back ends.
 pub mod memory {
     use super::basic_mem;
+    use super::bus as bus_mem;
+    use super::flat_mem;
+    use super::sparse_mem;
     /// A back end for the "basic" memory layout.
     pub mod basic {
         pub use super::basic_mem::*;
     }
+    /// A back end that routes accesses to memory-mapped devices.
+    pub mod bus {
+        pub use super::bus_mem::*;
+    }
+    /// A back end for ROM and RAM with a configurable layout.
+    pub mod flat {
+        pub use super::flat_mem::*;
+    }
+    /// A back end for sparse memory that covers the whole address space.
+    pub mod sparse {
+        pub use super::sparse_mem::*;
+    }
 }
 
 /// CPU back ends.
 pub mod cpus {
     use super::rv32icpu;
+    use super::rv32ifcpu;
+    use super::rv32ifdcpu;
+    use super::rv32iprivcpu;
+    use super::rv32izicsrcpu;
     /// A back end for an integer only RV32I CPU.
     pub mod rv32i {
         pub use super::rv32icpu::*;
     }
+    /// A back end for an RV32IF CPU with single-precision floating point.
+    pub mod rv32if {
+        pub use super::rv32ifcpu::*;
+    }
+    /// A back end for an RV32IFD CPU with single and double-precision floating point.
+    pub mod rv32ifd {
+        pub use super::rv32ifdcpu::*;
+    }
+    /// A back end for an RV32I CPU with machine, supervisor and user privilege levels, and Sv32 virtual memory.
+    pub mod rv32ipriv {
+        pub use super::rv32iprivcpu::*;
+    }
+    /// A back end for an integer only RV32I CPU with machine-level control and status registers.
+    pub mod rv32izicsr {
+        pub use super::rv32izicsrcpu::*;
+    }
+}
+
+/// Device models that can be mapped to a [`memory::bus::Bus`].
+pub mod devices {
+    use super::clint as clint_device;
+    use super::plic as plic_device;
+    use super::tty as tty_device;
+    use super::uart as uart_device;
+    /// A core-local interruptor (CLINT) with the machine timer and software interrupts.
+    pub mod clint {
+        pub use super::clint_device::*;
+    }
+    /// A platform-level interrupt controller (PLIC) that routes device interrupts to harts.
+    pub mod plic {
+        pub use super::plic_device::*;
+    }
+    /// A simple console, and helpers for connecting guest consoles to the host.
+    pub mod tty {
+        pub use super::tty_device::*;
+    }
+    /// An NS16550A-compatible UART.
+    pub mod uart {
+        pub use super::uart_device::*;
+    }
 }
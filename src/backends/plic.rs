@@ -0,0 +1,338 @@
+//! A platform-level interrupt controller (PLIC) that routes device interrupts to harts.
+
+use super::bus::{Device, InterruptLine, Width};
+use crate::memory::MemoryResult;
+
+/// The size of the PLIC's address space in bytes.
+pub const PLIC_SIZE: u32 = 0x0400_0000;
+
+/// The largest number of interrupt sources that a PLIC can have. Sources are numbered from 1, because 0 means "no
+/// interrupt".
+pub const MAX_SOURCES: u32 = 1023;
+
+/// The largest number of contexts that a PLIC can have.
+pub const MAX_CONTEXTS: u32 = 15872;
+
+/// The highest priority that a source can have. Sources with priority 0 never interrupt.
+pub const MAX_PRIORITY: u32 = 7;
+
+// Register offsets, as described by the RISC-V PLIC specification.
+const PRIORITY_BASE: u32 = 0x0000;
+const PENDING_BASE: u32 = 0x1000;
+const ENABLE_BASE: u32 = 0x2000;
+const ENABLE_STRIDE: u32 = 0x80;
+const CONTEXT_BASE: u32 = 0x20_0000;
+const CONTEXT_STRIDE: u32 = 0x1000;
+const THRESHOLD: u32 = 0;
+const CLAIM_COMPLETE: u32 = 4;
+
+/// A target for interrupts, e.g., a hart's M-mode or S-mode.
+#[derive(Debug, Clone)]
+struct Context {
+    enables: Vec<u32>, // One bit per source.
+    threshold: u32,
+    line: InterruptLine,
+}
+
+impl Context {
+    // Returns true if the source is enabled for this context.
+    fn is_enabled(&self, source: usize) -> bool {
+        self.enables[source / 32] & (1 << (source % 32)) != 0
+    }
+}
+
+/// A platform-level interrupt controller with a configurable number of sources and contexts.
+///
+/// Each source is a level-triggered [`InterruptLine`] that a device raises to request an interrupt. Each context also
+/// has an interrupt line, which the PLIC raises whenever the context has an enabled source that's pending with a
+/// priority above the context's threshold. Typically, context 0 is connected to a hart's machine external interrupt,
+/// and context 1 to its supervisor external interrupt.
+///
+/// A hart claims an interrupt by reading the context's claim register, which returns the highest priority source and
+/// stops it from being pending until the hart writes the same source to the complete register. If the source's line is
+/// still raised at that point then it becomes pending again.
+///
+/// The registers are laid out as described by the RISC-V PLIC specification, so the PLIC is compatible with the one
+/// in QEMU's `virt` machine. Only word accesses are supported.
+///
+/// ```
+/// use arviss::backends::devices::plic::*;
+/// use arviss::backends::memory::bus::*;
+///
+/// let plic = Plic::new(32, 1);
+/// let uart = plic.source(10); // Give this to the UART.
+/// let hart = plic.context(0); // Connect this to the hart.
+/// let mut bus = Bus::new();
+/// let region = Region::new(0x0c00_0000, PLIC_SIZE).with_widths(Widths::WORD);
+/// bus.map(region, plic).unwrap();
+/// bus.write32(0x0c00_0028, 1).unwrap(); // Source 10 has priority 1.
+/// bus.write32(0x0c00_2000, 1 << 10).unwrap(); // Context 0 enables source 10.
+///
+/// uart.set(true);
+/// bus.tick();
+/// assert!(hart.is_raised());
+/// assert_eq!(Ok(10), bus.read32(0x0c20_0004)); // Claim.
+/// assert!(!hart.is_raised());
+/// uart.set(false);
+/// bus.write32(0x0c20_0004, 10).unwrap(); // Complete.
+/// ```
+#[derive(Debug, Clone)]
+pub struct Plic {
+    sources: Vec<InterruptLine>, // Indexed by source, so source 0 is never raised.
+    priorities: Vec<u32>,
+    pending: Vec<bool>,
+    claimed: Vec<bool>,
+    contexts: Vec<Context>,
+}
+
+impl Plic {
+    /// Creates a PLIC with the given number of sources, numbered from 1, and the given number of contexts, numbered
+    /// from 0. Every source starts with priority 0 and disabled, and every context starts with threshold 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than [`MAX_SOURCES`] sources or more than [`MAX_CONTEXTS`] contexts.
+    pub fn new(sources: u32, contexts: u32) -> Self {
+        assert!(sources <= MAX_SOURCES, "too many sources: {}", sources);
+        assert!(contexts <= MAX_CONTEXTS, "too many contexts: {}", contexts);
+        let count = sources as usize + 1;
+        Self {
+            sources: (0..count).map(|_| InterruptLine::new()).collect(),
+            priorities: vec![0; count],
+            pending: vec![false; count],
+            claimed: vec![false; count],
+            contexts: (0..contexts)
+                .map(|_| Context {
+                    enables: vec![0; count.div_ceil(32)],
+                    threshold: 0,
+                    line: InterruptLine::new(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the interrupt line for the given source, which a device raises to request an interrupt and lowers when
+    /// it no longer needs one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the source doesn't exist.
+    pub fn source(&self, source: u32) -> InterruptLine {
+        assert!(
+            source != 0 && (source as usize) < self.sources.len(),
+            "no such source: {}",
+            source
+        );
+        self.sources[source as usize].clone()
+    }
+
+    /// Returns the interrupt line for the given context, which is raised when the context has an interrupt to claim.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the context doesn't exist.
+    pub fn context(&self, context: u32) -> InterruptLine {
+        self.contexts[context as usize].line.clone()
+    }
+
+    // Returns the source that the context would claim, or 0 if there isn't one.
+    fn best(&self, context: &Context) -> u32 {
+        let mut best = 0;
+        let mut best_priority = context.threshold;
+        for source in 1..self.sources.len() {
+            let priority = self.priorities[source];
+            if self.pending[source] && priority > best_priority && context.is_enabled(source) {
+                best = source as u32;
+                best_priority = priority;
+            }
+        }
+        best
+    }
+
+    // Samples the sources, then raises or lowers the line for each context.
+    fn update(&mut self) {
+        for source in 1..self.sources.len() {
+            self.pending[source] = self.sources[source].is_raised() && !self.claimed[source];
+        }
+        for context in &self.contexts {
+            context.line.set(self.best(context) != 0);
+        }
+    }
+
+    fn claim(&mut self, context: usize) -> u32 {
+        let source = self.best(&self.contexts[context]);
+        if source != 0 {
+            self.pending[source as usize] = false;
+            self.claimed[source as usize] = true;
+        }
+        source
+    }
+
+    fn complete(&mut self, context: usize, source: u32) {
+        // Completions for sources that aren't enabled for the context are ignored.
+        let source = source as usize;
+        if source < self.sources.len() && self.contexts[context].is_enabled(source) {
+            self.claimed[source] = false;
+        }
+    }
+
+    // Returns the context and register for an offset in the per-context area.
+    fn context_register(&self, offset: u32) -> Option<(usize, u32)> {
+        let context = ((offset - CONTEXT_BASE) / CONTEXT_STRIDE) as usize;
+        (context < self.contexts.len()).then_some((context, offset % CONTEXT_STRIDE))
+    }
+
+    // Returns the context and word for an offset in the enable area.
+    fn enable_word(&self, offset: u32) -> Option<(usize, usize)> {
+        let context = ((offset - ENABLE_BASE) / ENABLE_STRIDE) as usize;
+        let word = ((offset % ENABLE_STRIDE) / 4) as usize;
+        (context < self.contexts.len() && word < self.contexts[context].enables.len())
+            .then_some((context, word))
+    }
+}
+
+impl Device for Plic {
+    fn read(&mut self, offset: u32, width: Width) -> MemoryResult<u32> {
+        if width != Width::Word {
+            return Err(offset);
+        }
+        self.update();
+        let value = match offset {
+            PRIORITY_BASE..PENDING_BASE => {
+                let source = ((offset - PRIORITY_BASE) / 4) as usize;
+                self.priorities.get(source).copied().unwrap_or(0)
+            }
+            PENDING_BASE..ENABLE_BASE => {
+                let first = ((offset - PENDING_BASE) / 4 * 32) as usize;
+                (0..32)
+                    .filter(|bit| self.pending.get(first + bit) == Some(&true))
+                    .fold(0, |word, bit| word | (1 << bit))
+            }
+            ENABLE_BASE..CONTEXT_BASE => match self.enable_word(offset) {
+                Some((context, word)) => self.contexts[context].enables[word],
+                None => 0,
+            },
+            _ => match self.context_register(offset) {
+                Some((context, THRESHOLD)) => self.contexts[context].threshold,
+                Some((context, CLAIM_COMPLETE)) => {
+                    let source = self.claim(context);
+                    self.update();
+                    source
+                }
+                _ => 0,
+            },
+        };
+        Ok(value)
+    }
+
+    fn write(&mut self, offset: u32, width: Width, value: u32) -> MemoryResult<()> {
+        if width != Width::Word {
+            return Err(offset);
+        }
+        match offset {
+            PRIORITY_BASE..PENDING_BASE => {
+                // Source 0 doesn't exist, so its priority is hardwired to zero.
+                let source = ((offset - PRIORITY_BASE) / 4) as usize;
+                if source != 0 && source < self.priorities.len() {
+                    self.priorities[source] = value.min(MAX_PRIORITY);
+                }
+            }
+            PENDING_BASE..ENABLE_BASE => {} // Pending bits are read-only.
+            ENABLE_BASE..CONTEXT_BASE => {
+                if let Some((context, word)) = self.enable_word(offset) {
+                    // Bits for sources that don't exist, including source 0, are hardwired to zero.
+                    let first = word * 32;
+                    let valid = (0..32)
+                        .filter(|bit| (1..self.sources.len()).contains(&(first + bit)))
+                        .fold(0, |mask, bit| mask | (1 << bit));
+                    self.contexts[context].enables[word] = value & valid;
+                }
+            }
+            _ => match self.context_register(offset) {
+                Some((context, THRESHOLD)) => {
+                    self.contexts[context].threshold = value.min(MAX_PRIORITY)
+                }
+                Some((context, CLAIM_COMPLETE)) => self.complete(context, value),
+                _ => {}
+            },
+        }
+        self.update();
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        self.update();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Plic, PLIC_SIZE};
+    use crate::backends::bus::{Bus, Memory, Region, Widths};
+
+    const BASE: u32 = 0x0c00_0000;
+    const CLAIM: u32 = BASE + 0x20_0004;
+
+    fn bus_with(plic: Plic) -> Bus {
+        let mut bus = Bus::new();
+        let region = Region::new(BASE, PLIC_SIZE).with_widths(Widths::WORD);
+        bus.map(region, plic).unwrap();
+        bus
+    }
+
+    #[test]
+    fn test_the_highest_priority_source_is_claimed_first() {
+        let plic = Plic::new(40, 1);
+        let low = plic.source(3);
+        let high = plic.source(33);
+        let hart = plic.context(0);
+        let mut bus = bus_with(plic);
+        bus.write32(BASE + 4 * 3, 1).unwrap();
+        bus.write32(BASE + 4 * 33, 5).unwrap();
+        bus.write32(BASE + 0x2000, 1 << 3).unwrap();
+        bus.write32(BASE + 0x2004, 1 << 1).unwrap(); // Source 33.
+
+        low.set(true);
+        high.set(true);
+        bus.tick();
+        assert_eq!(Ok(1 << 3), bus.read32(BASE + 0x1000));
+        assert_eq!(Ok(1 << 1), bus.read32(BASE + 0x1004));
+        assert!(hart.is_raised());
+        assert_eq!(Ok(33), bus.read32(CLAIM));
+        assert_eq!(Ok(3), bus.read32(CLAIM));
+        assert_eq!(Ok(0), bus.read32(CLAIM));
+        assert!(!hart.is_raised());
+
+        // A source that's still raised when it's completed becomes pending again.
+        high.set(false);
+        bus.write32(CLAIM, 33).unwrap();
+        bus.write32(CLAIM, 3).unwrap();
+        assert!(hart.is_raised());
+        assert_eq!(Ok(3), bus.read32(CLAIM));
+    }
+
+    #[test]
+    fn test_thresholds_and_enables_are_per_context() {
+        let plic = Plic::new(8, 2);
+        let source = plic.source(1);
+        let machine = plic.context(0);
+        let supervisor = plic.context(1);
+        let mut bus = bus_with(plic);
+        bus.write32(BASE + 4, 9).unwrap();
+        assert_eq!(Ok(7), bus.read32(BASE + 4)); // The highest priority.
+        bus.write32(BASE + 0x2000, 0xffff_ffff).unwrap();
+        bus.write32(BASE + 0x2080, 0xffff_ffff).unwrap();
+        assert_eq!(Ok(0x1fe), bus.read32(BASE + 0x2000)); // Sources 1 to 8.
+        bus.write32(BASE + 0x20_1000, 7).unwrap(); // Context 1's threshold.
+
+        source.set(true);
+        bus.tick();
+        assert!(machine.is_raised());
+        assert!(!supervisor.is_raised());
+        bus.write32(BASE + 0x20_1000, 6).unwrap();
+        assert!(supervisor.is_raised());
+        assert_eq!(Ok(1), bus.read32(BASE + 0x20_1004));
+        assert!(!machine.is_raised());
+        assert_eq!(Err(BASE + 4), bus.read8(BASE + 4));
+    }
+}
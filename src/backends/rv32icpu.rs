@@ -7,26 +7,32 @@ use crate::{
     reg::Reg,
 };
 
-pub use crate::cpu::{Fetch, XRegisters};
-pub use crate::trap::{Trap, TrapCause};
+pub use crate::cpu::{Alignment, Fetch, Reservation, XRegisters};
+pub use crate::trap::{Trap, TrapCause, TrapRecord};
 
 /// The current trap state of the CPU.
 #[derive(Default, Clone, Copy, PartialEq, Eq)]
 pub struct TrapState {
-    cause: Option<TrapCause>,
+    trap: Option<TrapRecord>,
 }
 
 /// A basic RV32I CPU with integer registers but no floating point.
+///
+/// The CPU starts with the [`Alignment::RV32IC`] policy so that it can run compressed instructions. To make jumps and
+/// taken branches to addresses that aren't a multiple of 4 trap, as they do without the 'C' extension, call
+/// [`set_alignment`](Rv32iCpu::set_alignment) with [`Alignment::RV32I`].
 #[derive(Default, PartialEq, Eq)]
 pub struct Rv32iCpu<M>
 where
     M: Memory,
 {
-    pc: u32,               // The program counter.
-    next_pc: u32,          // The program counter for the next instruction.
-    xreg: [u32; 32],       // Regular registers, x0-x31.
-    mem: M,                // Memory.
-    trap_state: TrapState, // The current trap state.
+    pc: u32,                      // The program counter.
+    next_pc: u32,                 // The program counter for the next instruction.
+    xreg: [u32; 32],              // Regular registers, x0-x31.
+    mem: M,                       // Memory.
+    trap_state: TrapState,        // The current trap state.
+    reservation: Option<Address>, // The reservation set for LR / SC.
+    alignment: Alignment,         // The alignment policy.
 }
 
 impl<M> Display for Rv32iCpu<M>
@@ -62,8 +68,15 @@ where
             xreg: Default::default(),
             mem,
             trap_state: Default::default(),
+            reservation: None,
+            alignment: Alignment::RV32IC,
         }
     }
+
+    /// Sets the alignment policy, which determines what happens when an instruction uses a misaligned address.
+    pub fn set_alignment(&mut self, alignment: Alignment) {
+        self.alignment = alignment;
+    }
 }
 
 impl<M> Fetch for Rv32iCpu<M>
@@ -86,6 +99,10 @@ where
     fn fetch32(&self, address: Address) -> MemoryResult<u32> {
         self.read32(address)
     }
+
+    fn alignment(&self) -> Alignment {
+        self.alignment
+    }
 }
 
 impl<M> Memory for Rv32iCpu<M>
@@ -115,6 +132,30 @@ where
     fn write32(&mut self, address: Address, value: u32) -> MemoryResult<()> {
         self.mem.write32(address, value)
     }
+
+    fn read64(&self, address: Address) -> MemoryResult<u64> {
+        self.mem.read64(address)
+    }
+
+    fn write64(&mut self, address: Address, value: u64) -> MemoryResult<()> {
+        self.mem.write64(address, value)
+    }
+
+    fn peek8(&self, address: Address) -> MemoryResult<u8> {
+        self.mem.peek8(address)
+    }
+
+    fn tick(&mut self) {
+        self.mem.tick()
+    }
+
+    fn next_event(&self) -> Option<u64> {
+        self.mem.next_event()
+    }
+
+    fn advance(&mut self, ticks: u64) {
+        self.mem.advance(ticks)
+    }
 }
 
 impl<M> Load for Rv32iCpu<M>
@@ -142,19 +183,135 @@ where
     }
 }
 
+impl<M> Reservation for Rv32iCpu<M>
+where
+    M: Memory,
+{
+    fn reserve(&mut self, address: Address) {
+        self.reservation = Some(address & !3);
+    }
+
+    fn take_reservation(&mut self, address: Address) -> bool {
+        self.reservation.take() == Some(address & !3)
+    }
+}
+
 impl<M> Trap for Rv32iCpu<M>
 where
     M: Memory,
 {
-    fn trap_cause(&self) -> Option<TrapCause> {
-        self.trap_state.cause
+    fn trap(&self) -> Option<TrapRecord> {
+        self.trap_state.trap
     }
 
     fn clear_trap(&mut self) {
-        self.trap_state.cause = None
+        self.trap_state.trap = None
     }
 
     fn handle_trap(&mut self, cause: TrapCause) {
-        self.trap_state.cause = Some(cause);
+        self.trap_state.trap = Some(TrapRecord { cause, pc: self.pc });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Alignment, Fetch, Rv32iCpu, Trap, TrapCause, XRegisters};
+    use crate::{backends::memory::basic::BasicMem, memory::Memory, reg::Reg, DispatchRv32ima};
+
+    #[test]
+    fn test_sc_w_succeeds_only_while_the_reservation_is_held() {
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+        cpu.write32(0x4000, 5).unwrap();
+        cpu.wx(Reg::new(11), 0x4000);
+        cpu.wx(Reg::new(13), 7);
+        cpu.dispatch(0x1005a52f); // lr.w a0, (a1)
+        assert_eq!(5, cpu.rx(Reg::new(10)));
+        cpu.dispatch(0x18d5a62f); // sc.w a2, a3, (a1)
+        assert_eq!(0, cpu.rx(Reg::new(12)));
+        assert_eq!(Ok(7), cpu.read32(0x4000));
+        cpu.wx(Reg::new(13), 9);
+        cpu.dispatch(0x18d5a62f); // sc.w a2, a3, (a1)
+        assert_eq!(1, cpu.rx(Reg::new(12)));
+        assert_eq!(Ok(7), cpu.read32(0x4000));
+        assert!(!cpu.is_trapped());
+    }
+
+    #[test]
+    fn test_amos_return_the_old_value_and_update_memory() {
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+        cpu.write32(0x4000, 5).unwrap();
+        cpu.wx(Reg::new(11), 0x4000);
+        cpu.wx(Reg::new(13), 7);
+        cpu.dispatch(0x00d5a72f); // amoadd.w a4, a3, (a1)
+        assert_eq!(5, cpu.rx(Reg::new(14)));
+        assert_eq!(Ok(12), cpu.read32(0x4000));
+        cpu.dispatch(0xa0d5a72f); // amomax.w a4, a3, (a1)
+        assert_eq!(12, cpu.rx(Reg::new(14)));
+        assert_eq!(Ok(12), cpu.read32(0x4000));
+        assert!(!cpu.is_trapped());
+    }
+
+    #[test]
+    fn test_misaligned_atomics_trap() {
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+        cpu.wx(Reg::new(11), 0x4002);
+        cpu.dispatch(0x1005a52f); // lr.w a0, (a1)
+        assert_eq!(
+            Some(TrapCause::LoadAddressMisaligned(0x4002)),
+            cpu.trap_cause()
+        );
+        cpu.clear_trap();
+        cpu.dispatch(0x00d5a72f); // amoadd.w a4, a3, (a1)
+        assert_eq!(
+            Some(TrapCause::StoreAddressMisaligned(0x4002)),
+            cpu.trap_cause()
+        );
+    }
+
+    #[test]
+    fn test_misaligned_jumps_trap_only_without_compressed_instructions() {
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+        cpu.wx(Reg::new(11), 0x100);
+        cpu.dispatch(0x00258567); // jalr a0, 2(a1)
+        assert!(!cpu.is_trapped());
+        assert_eq!(0x102, cpu.transfer());
+
+        cpu.set_alignment(Alignment::RV32I);
+        cpu.dispatch(0x004000ef); // jal ra, 4
+        assert_eq!(
+            Some(TrapCause::InstructionAddressMisaligned(0x106)),
+            cpu.trap_cause()
+        );
+        assert_eq!(0, cpu.rx(Reg::RA));
+    }
+
+    #[test]
+    fn test_misaligned_loads_and_stores_trap_or_are_emulated() {
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+        cpu.write32(0x4000, 0x44332211).unwrap();
+        cpu.write32(0x4004, 0x88776655).unwrap();
+        cpu.wx(Reg::new(11), 0x4000);
+        cpu.dispatch(0x0015a503); // lw a0, 1(a1)
+        assert_eq!(
+            Some(TrapCause::LoadAddressMisaligned(0x4001)),
+            cpu.trap_cause()
+        );
+        assert_eq!(0, cpu.rx(Reg::new(10)));
+        cpu.clear_trap();
+        cpu.dispatch(0x00a5a0a3); // sw a0, 1(a1)
+        assert_eq!(
+            Some(TrapCause::StoreAddressMisaligned(0x4001)),
+            cpu.trap_cause()
+        );
+        cpu.clear_trap();
+
+        cpu.set_alignment(Alignment::RV32I.emulating_misaligned_data());
+        cpu.dispatch(0x0015a503); // lw a0, 1(a1)
+        assert_eq!(0x55443322, cpu.rx(Reg::new(10)));
+        cpu.wx(Reg::new(10), 0xaabbccdd);
+        cpu.dispatch(0x00a5a0a3); // sw a0, 1(a1)
+        assert_eq!(Ok(0xbbccdd11), cpu.read32(0x4000));
+        assert_eq!(Ok(0x887766aa), cpu.read32(0x4004));
+        assert!(!cpu.is_trapped());
     }
 }
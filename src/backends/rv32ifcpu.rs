@@ -0,0 +1,491 @@
+//! An RV32I CPU with integer registers and single-precision floating point registers.
+
+use std::fmt::Display;
+
+use crate::{
+    csr,
+    memory::{Address, Load, Memory, MemoryResult},
+    reg::Reg,
+};
+
+pub use crate::cpu::{Alignment, CsrResult, Csrs, FRegisters, Fetch, Reservation, XRegisters};
+pub use crate::trap::{Trap, TrapCause, TrapRecord};
+
+// MXL = 1 (32-bit) and the 'I' and 'F' extensions.
+const MISA_VALUE: u32 = (1 << 30) | (1 << 8) | (1 << 5);
+
+// Bits of mstatus.
+const MSTATUS_FS_SHIFT: u32 = 13;
+const MSTATUS_FS: u32 = 3 << MSTATUS_FS_SHIFT;
+const MSTATUS_SD: u32 = 1 << 31;
+
+// Values of mstatus.FS.
+const FS_OFF: u32 = 0;
+const FS_INITIAL: u32 = 1;
+const FS_DIRTY: u32 = 3;
+
+// Fields of fcsr.
+const FFLAGS_MASK: u32 = 0x1f;
+const FRM_SHIFT: u32 = 5;
+const FRM_MASK: u32 = 0x7;
+
+/// An RV32I CPU with integer registers, single-precision floating point registers, and the `fcsr` floating point
+/// control and status register.
+///
+/// The floating point unit's state is tracked by `mstatus.FS`. It starts as Initial so that floating point instructions
+/// can be used straight away, and it becomes Dirty whenever a floating point register or `fcsr` is modified. When the
+/// guest sets it to Off, floating point instructions and accesses to `fflags`, `frm` and `fcsr` are illegal.
+///
+/// Traps aren't handled by the guest. Instead, the cause of the most recent trap is recorded so that the host can see
+/// it with [`Trap::trap_cause`].
+///
+/// The CPU starts with the [`Alignment::RV32IC`] policy so that it can run compressed instructions. To make jumps and
+/// taken branches to addresses that aren't a multiple of 4 trap, as they do without the 'C' extension, call
+/// [`set_alignment`](Rv32ifCpu::set_alignment) with [`Alignment::RV32I`].
+#[derive(Default, PartialEq)]
+pub struct Rv32ifCpu<M>
+where
+    M: Memory,
+{
+    pc: u32,                      // The program counter.
+    next_pc: u32,                 // The program counter for the next instruction.
+    xreg: [u32; 32],              // Regular registers, x0-x31.
+    freg: [f32; 32],              // Floating point registers, f0-f31.
+    fflags: u32,                  // Accrued floating point exception flags.
+    frm: u32,                     // The dynamic rounding mode.
+    fs: u32,                      // The floating point unit state from mstatus.FS.
+    mem: M,                       // Memory.
+    trap: Option<TrapRecord>,     // The current trap state.
+    reservation: Option<Address>, // The reservation set for LR / SC.
+    alignment: Alignment,         // The alignment policy.
+}
+
+impl<M> Display for Rv32ifCpu<M>
+where
+    M: Memory,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "pc: {:08x} next_pc {:08x}\n",
+            self.pc, self.next_pc
+        ))?;
+        f.write_fmt(format_args!("xregs: {:?}\n", self.xreg))?;
+        f.write_fmt(format_args!("fregs: {:?}\n", self.freg))?;
+        f.write_fmt(format_args!(
+            "fcsr: {:08x} mstatus: {:08x}\n",
+            self.fcsr(),
+            self.mstatus()
+        ))
+    }
+}
+
+impl<M> Rv32ifCpu<M>
+where
+    M: Memory,
+{
+    /// Creates a new CPU with default memory.
+    pub fn new() -> Self
+    where
+        M: Default,
+    {
+        Self::with_mem(Default::default())
+    }
+
+    /// Creates a new CPU with caller-supplied memory.
+    pub fn with_mem(mem: M) -> Self {
+        Self {
+            pc: 0,
+            next_pc: 0,
+            xreg: Default::default(),
+            freg: Default::default(),
+            fflags: 0,
+            frm: 0,
+            fs: FS_INITIAL,
+            mem,
+            trap: None,
+            reservation: None,
+            alignment: Alignment::RV32IC,
+        }
+    }
+
+    /// Sets the alignment policy, which determines what happens when an instruction uses a misaligned address.
+    pub fn set_alignment(&mut self, alignment: Alignment) {
+        self.alignment = alignment;
+    }
+
+    fn mstatus(&self) -> u32 {
+        let sd = if self.fs == FS_DIRTY { MSTATUS_SD } else { 0 };
+        sd | (self.fs << MSTATUS_FS_SHIFT)
+    }
+
+    fn fcsr(&self) -> u32 {
+        (self.frm << FRM_SHIFT) | self.fflags
+    }
+}
+
+impl<M> Fetch for Rv32ifCpu<M>
+where
+    M: Memory,
+{
+    fn pc(&self) -> Address {
+        self.pc
+    }
+
+    fn transfer(&mut self) -> Address {
+        self.pc = self.next_pc;
+        self.pc
+    }
+
+    fn set_next_pc(&mut self, address: Address) {
+        self.next_pc = address;
+    }
+
+    fn fetch32(&self, address: Address) -> MemoryResult<u32> {
+        self.read32(address)
+    }
+
+    fn alignment(&self) -> Alignment {
+        self.alignment
+    }
+}
+
+impl<M> Memory for Rv32ifCpu<M>
+where
+    M: Memory,
+{
+    fn read8(&self, address: Address) -> MemoryResult<u8> {
+        self.mem.read8(address)
+    }
+
+    fn read16(&self, address: Address) -> MemoryResult<u16> {
+        self.mem.read16(address)
+    }
+
+    fn read32(&self, address: Address) -> MemoryResult<u32> {
+        self.mem.read32(address)
+    }
+
+    fn write8(&mut self, address: Address, value: u8) -> MemoryResult<()> {
+        self.mem.write8(address, value)
+    }
+
+    fn write16(&mut self, address: Address, value: u16) -> MemoryResult<()> {
+        self.mem.write16(address, value)
+    }
+
+    fn write32(&mut self, address: Address, value: u32) -> MemoryResult<()> {
+        self.mem.write32(address, value)
+    }
+
+    fn read64(&self, address: Address) -> MemoryResult<u64> {
+        self.mem.read64(address)
+    }
+
+    fn write64(&mut self, address: Address, value: u64) -> MemoryResult<()> {
+        self.mem.write64(address, value)
+    }
+
+    fn peek8(&self, address: Address) -> MemoryResult<u8> {
+        self.mem.peek8(address)
+    }
+
+    fn tick(&mut self) {
+        self.mem.tick()
+    }
+
+    fn next_event(&self) -> Option<u64> {
+        self.mem.next_event()
+    }
+
+    fn advance(&mut self, ticks: u64) {
+        self.mem.advance(ticks)
+    }
+}
+
+impl<M> Load for Rv32ifCpu<M>
+where
+    M: Memory + Load,
+{
+    fn write_bytes(&mut self, start: crate::Address, bytes: &[u8]) -> crate::MemoryResult<()> {
+        self.mem.write_bytes(start, bytes)
+    }
+}
+
+impl<M> XRegisters for Rv32ifCpu<M>
+where
+    M: Memory,
+{
+    fn rx(&self, reg: Reg) -> u32 {
+        let index: usize = Into::into(reg);
+        self.xreg[index]
+    }
+
+    fn wx(&mut self, reg: Reg, val: u32) {
+        let index: usize = Into::into(reg);
+        self.xreg[index] = val;
+        self.xreg[0] = 0;
+    }
+}
+
+impl<M> FRegisters for Rv32ifCpu<M>
+where
+    M: Memory,
+{
+    fn rf(&self, reg: Reg) -> f32 {
+        let index: usize = Into::into(reg);
+        self.freg[index]
+    }
+
+    fn rf_bits(&self, reg: Reg) -> u32 {
+        let index: usize = Into::into(reg);
+        self.freg[index].to_bits()
+    }
+
+    fn wf(&mut self, reg: Reg, val: f32) {
+        let index: usize = Into::into(reg);
+        self.freg[index] = val;
+        self.fs = FS_DIRTY;
+    }
+
+    fn frm(&self) -> u32 {
+        self.frm
+    }
+
+    fn accrue_fflags(&mut self, flags: u32) {
+        if flags != 0 {
+            self.fflags |= flags & FFLAGS_MASK;
+            self.fs = FS_DIRTY;
+        }
+    }
+
+    fn is_fp_enabled(&self) -> bool {
+        self.fs != FS_OFF
+    }
+}
+
+impl<M> Csrs for Rv32ifCpu<M>
+where
+    M: Memory,
+{
+    fn rcsr(&self, csr: u32) -> CsrResult<u32> {
+        match csr {
+            csr::FFLAGS | csr::FRM | csr::FCSR if !self.is_fp_enabled() => Err(csr),
+            csr::FFLAGS => Ok(self.fflags),
+            csr::FRM => Ok(self.frm),
+            csr::FCSR => Ok(self.fcsr()),
+            csr::MSTATUS => Ok(self.mstatus()),
+            csr::MISA => Ok(MISA_VALUE),
+            _ => Err(csr),
+        }
+    }
+
+    fn wcsr(&mut self, csr: u32, val: u32) -> CsrResult<()> {
+        match csr {
+            csr::FFLAGS | csr::FRM | csr::FCSR if !self.is_fp_enabled() => return Err(csr),
+            csr::FFLAGS => self.fflags = val & FFLAGS_MASK,
+            csr::FRM => self.frm = val & FRM_MASK,
+            csr::FCSR => {
+                self.fflags = val & FFLAGS_MASK;
+                self.frm = (val >> FRM_SHIFT) & FRM_MASK;
+            }
+            csr::MSTATUS => {
+                // Only FS is writable. SD is derived from it.
+                self.fs = (val & MSTATUS_FS) >> MSTATUS_FS_SHIFT;
+                return Ok(());
+            }
+            csr::MISA => return Ok(()), // WARL, and we only have one legal value.
+            _ => return Err(csr),
+        }
+        // Writing to the floating point CSRs modifies the floating point state.
+        self.fs = FS_DIRTY;
+        Ok(())
+    }
+}
+
+impl<M> Reservation for Rv32ifCpu<M>
+where
+    M: Memory,
+{
+    fn reserve(&mut self, address: Address) {
+        self.reservation = Some(address & !3);
+    }
+
+    fn take_reservation(&mut self, address: Address) -> bool {
+        self.reservation.take() == Some(address & !3)
+    }
+}
+
+impl<M> Trap for Rv32ifCpu<M>
+where
+    M: Memory,
+{
+    fn trap(&self) -> Option<TrapRecord> {
+        self.trap
+    }
+
+    fn clear_trap(&mut self) {
+        self.trap = None
+    }
+
+    fn handle_trap(&mut self, cause: TrapCause) {
+        self.trap = Some(TrapRecord { cause, pc: self.pc });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{csr, Csrs, FRegisters, Fetch, Load, Rv32ifCpu, Trap, TrapCause, XRegisters};
+    use crate::{
+        backends::memory::basic::BasicMem,
+        reg::Reg,
+        softfloat::{DYN, RDN, RMM, RNE, RUP},
+        DispatchRv32imfc, HandleRv32f,
+    };
+
+    const HALF_ULP: u32 = 0x3380_0000; // 2^-24, half of the gap between 1.0 and the next single.
+
+    fn cpu_with_operands() -> Rv32ifCpu<BasicMem> {
+        let mut cpu = Rv32ifCpu::<BasicMem>::new();
+        cpu.wf(Reg::new(1), 1.0);
+        cpu.wf(Reg::new(2), f32::from_bits(HALF_ULP));
+        cpu
+    }
+
+    #[test]
+    fn test_handlers_round_with_a_static_rounding_mode() {
+        let mut cpu = cpu_with_operands();
+        cpu.wcsr(csr::FRM, RUP).unwrap();
+
+        // 1.0 + 2^-24 is a tie, so each rounding mode picks a different neighbour, regardless of frm.
+        cpu.fadd_s(Reg::new(3), Reg::new(1), Reg::new(2), RNE);
+        assert_eq!(0x3f80_0000, cpu.rf(Reg::new(3)).to_bits());
+        cpu.fadd_s(Reg::new(3), Reg::new(1), Reg::new(2), RMM);
+        assert_eq!(0x3f80_0001, cpu.rf(Reg::new(3)).to_bits());
+        cpu.fsub_s(Reg::new(3), Reg::new(1), Reg::new(2), RDN);
+        assert_eq!(0x3f7f_ffff, cpu.rf(Reg::new(3)).to_bits());
+
+        assert_eq!(Ok(0x01), cpu.rcsr(csr::FFLAGS)); // NX.
+        assert_eq!(Ok(RUP), cpu.rcsr(csr::FRM));
+        assert!(!cpu.is_trapped());
+    }
+
+    #[test]
+    fn test_handlers_round_with_the_dynamic_rounding_mode() {
+        let mut cpu = cpu_with_operands();
+        cpu.wcsr(csr::FRM, RUP).unwrap();
+        cpu.fadd_s(Reg::new(3), Reg::new(1), Reg::new(2), DYN);
+        assert_eq!(0x3f80_0001, cpu.rf(Reg::new(3)).to_bits());
+
+        cpu.wcsr(csr::FRM, RDN).unwrap();
+        cpu.fadd_s(Reg::new(3), Reg::new(1), Reg::new(2), DYN);
+        assert_eq!(0x3f80_0000, cpu.rf(Reg::new(3)).to_bits());
+        assert_eq!(Ok(0x01), cpu.rcsr(csr::FFLAGS)); // NX.
+        assert!(!cpu.is_trapped());
+    }
+
+    #[test]
+    fn test_handlers_accrue_exception_flags() {
+        let mut cpu = cpu_with_operands();
+        cpu.fdiv_s(Reg::new(3), Reg::new(1), Reg::new(0), RNE); // 1.0 / 0.0
+        assert_eq!(f32::INFINITY, cpu.rf(Reg::new(3)));
+        assert_eq!(Ok(0x08), cpu.rcsr(csr::FFLAGS)); // DZ.
+
+        cpu.wf(Reg::new(4), -1.0);
+        cpu.fsqrt_s(Reg::new(3), Reg::new(4), RNE);
+        assert_eq!(0x7fc0_0000, cpu.rf(Reg::new(3)).to_bits()); // The canonical NaN.
+        assert_eq!(Ok(0x18), cpu.rcsr(csr::FFLAGS)); // NV, DZ.
+
+        cpu.fmadd_s(Reg::new(3), Reg::new(1), Reg::new(1), Reg::new(2), RNE); // 1.0 * 1.0 + 2^-24
+        assert_eq!(1.0, cpu.rf(Reg::new(3)));
+        assert_eq!(Ok(0x19), cpu.rcsr(csr::FFLAGS)); // NV, DZ, NX.
+        assert!(!cpu.is_trapped());
+    }
+
+    #[test]
+    fn test_handlers_with_reserved_rounding_modes_trap() {
+        let mut cpu = cpu_with_operands();
+        cpu.wf(Reg::new(3), 42.0);
+
+        // A reserved static rounding mode.
+        cpu.fadd_s(Reg::new(3), Reg::new(1), Reg::new(2), 0b101);
+        assert_eq!(
+            Some(TrapCause::IllegalInstruction(0x0020d1d3)), // fadd.s f3, f1, f2, 5
+            cpu.trap_cause()
+        );
+        cpu.clear_trap();
+        cpu.fmadd_s(Reg::new(3), Reg::new(1), Reg::new(2), Reg::new(4), 0b110);
+        assert_eq!(
+            Some(TrapCause::IllegalInstruction(0x2020e1c3)), // fmadd.s f3, f1, f2, f4, 6
+            cpu.trap_cause()
+        );
+
+        // A reserved dynamic rounding mode.
+        cpu.clear_trap();
+        cpu.wcsr(csr::FRM, 0b101).unwrap();
+        cpu.fadd_s(Reg::new(3), Reg::new(1), Reg::new(2), DYN);
+        assert_eq!(
+            Some(TrapCause::IllegalInstruction(0x0020f1d3)), // fadd.s f3, f1, f2, dyn
+            cpu.trap_cause()
+        );
+
+        // Nothing was written and no flags were accrued.
+        assert_eq!(42.0, cpu.rf(Reg::new(3)));
+        assert_eq!(Ok(0), cpu.rcsr(csr::FFLAGS));
+    }
+
+    #[test]
+    fn test_floating_point_instructions_use_the_dynamic_rounding_mode_and_accrue_flags() {
+        let mut cpu = Rv32ifCpu::<BasicMem>::new();
+        assert_eq!(Ok(0x2000), cpu.rcsr(csr::MSTATUS)); // FS = Initial.
+        cpu.wf(Reg::new(1), 1.0);
+        cpu.wf(Reg::new(2), 3.0);
+        cpu.dispatch(0x1820f1d3); // fdiv.s f3, f1, f2, dyn
+        assert_eq!(0x3eaa_aaab, cpu.rf(Reg::new(3)).to_bits());
+        cpu.wcsr(csr::FRM, 1).unwrap(); // Round towards zero.
+        cpu.dispatch(0x1820f1d3); // fdiv.s f3, f1, f2, dyn
+        assert_eq!(0x3eaa_aaaa, cpu.rf(Reg::new(3)).to_bits());
+        assert_eq!(Ok(0x21), cpu.rcsr(csr::FCSR)); // frm = RTZ, fflags = NX.
+        assert_eq!(Ok(0x8000_6000), cpu.rcsr(csr::MSTATUS)); // SD = 1, FS = Dirty.
+        assert!(!cpu.is_trapped());
+    }
+
+    #[test]
+    fn test_reserved_rounding_modes_are_illegal() {
+        let mut cpu = Rv32ifCpu::<BasicMem>::new();
+        cpu.dispatch(0x0020d1d3); // fadd.s f3, f1, f2, 5
+        assert_eq!(
+            Some(TrapCause::IllegalInstruction(0x0020d1d3)),
+            cpu.trap_cause()
+        );
+    }
+
+    #[test]
+    fn test_floating_point_instructions_are_illegal_when_fs_is_off() {
+        let mut cpu = Rv32ifCpu::<BasicMem>::new();
+        cpu.write_bytes(0, &0x0020f1d3u32.to_le_bytes()).unwrap(); // fadd.s f3, f1, f2, dyn
+        cpu.write_bytes(4, &0x0000_6004u32.to_le_bytes()).unwrap(); // c.flw f9, 0(s0)
+        cpu.wcsr(csr::MSTATUS, 0).unwrap();
+        assert_eq!(Err(csr::FCSR), cpu.rcsr(csr::FCSR));
+
+        let ins = cpu.fetch().unwrap();
+        cpu.dispatch(ins);
+        assert_eq!(
+            Some(TrapCause::IllegalInstruction(0x0020f1d3)),
+            cpu.trap_cause()
+        );
+
+        cpu.clear_trap();
+        let ins = cpu.fetch().unwrap();
+        cpu.dispatch(ins);
+        assert_eq!(
+            Some(TrapCause::IllegalInstruction(0x6004)),
+            cpu.trap_cause()
+        );
+
+        // Integer instructions are unaffected.
+        cpu.clear_trap();
+        cpu.dispatch(0x00100513); // addi a0, zero, 1
+        assert_eq!(1, cpu.rx(Reg::new(10)));
+        assert!(!cpu.is_trapped());
+    }
+}
@@ -0,0 +1,481 @@
+//! An RV32I CPU with integer registers and double-precision floating point registers.
+
+use std::fmt::Display;
+
+use crate::{
+    csr,
+    memory::{Address, Load, Memory, MemoryResult},
+    reg::Reg,
+};
+
+pub use crate::cpu::{
+    nan_box, nan_unbox, Alignment, CsrResult, Csrs, DRegisters, FRegisters, Fetch, Reservation,
+    XRegisters,
+};
+pub use crate::trap::{Trap, TrapCause, TrapRecord};
+
+// MXL = 1 (32-bit) and the 'I', 'F' and 'D' extensions.
+const MISA_VALUE: u32 = (1 << 30) | (1 << 8) | (1 << 5) | (1 << 3);
+
+// Bits of mstatus.
+const MSTATUS_FS_SHIFT: u32 = 13;
+const MSTATUS_FS: u32 = 3 << MSTATUS_FS_SHIFT;
+const MSTATUS_SD: u32 = 1 << 31;
+
+// Values of mstatus.FS.
+const FS_OFF: u32 = 0;
+const FS_INITIAL: u32 = 1;
+const FS_DIRTY: u32 = 3;
+
+// Fields of fcsr.
+const FFLAGS_MASK: u32 = 0x1f;
+const FRM_SHIFT: u32 = 5;
+const FRM_MASK: u32 = 0x7;
+
+/// An RV32I CPU with integer registers, double-precision floating point registers, and the `fcsr` floating point
+/// control and status register.
+///
+/// There's one set of 64-bit floating point registers, which serves both the 'F' and the 'D' extensions. Single-precision
+/// values are NaN-boxed when they're written, and reading a single-precision value from a register that doesn't hold a
+/// correctly NaN-boxed value gives the canonical NaN.
+///
+/// The floating point unit's state is tracked by `mstatus.FS`. It starts as Initial so that floating point instructions
+/// can be used straight away, and it becomes Dirty whenever a floating point register or `fcsr` is modified. When the
+/// guest sets it to Off, floating point instructions and accesses to `fflags`, `frm` and `fcsr` are illegal.
+///
+/// Traps aren't handled by the guest. Instead, the cause of the most recent trap is recorded so that the host can see
+/// it with [`Trap::trap_cause`].
+///
+/// The CPU starts with the [`Alignment::RV32IC`] policy so that it can run compressed instructions. To make jumps and
+/// taken branches to addresses that aren't a multiple of 4 trap, as they do without the 'C' extension, call
+/// [`set_alignment`](Rv32ifdCpu::set_alignment) with [`Alignment::RV32I`].
+#[derive(Default, PartialEq)]
+pub struct Rv32ifdCpu<M>
+where
+    M: Memory,
+{
+    pc: u32,                      // The program counter.
+    next_pc: u32,                 // The program counter for the next instruction.
+    xreg: [u32; 32],              // Regular registers, x0-x31.
+    freg: [u64; 32],              // Floating point registers, f0-f31, as raw bits.
+    fflags: u32,                  // Accrued floating point exception flags.
+    frm: u32,                     // The dynamic rounding mode.
+    fs: u32,                      // The floating point unit state from mstatus.FS.
+    mem: M,                       // Memory.
+    trap: Option<TrapRecord>,     // The current trap state.
+    reservation: Option<Address>, // The reservation set for LR / SC.
+    alignment: Alignment,         // The alignment policy.
+}
+
+impl<M> Display for Rv32ifdCpu<M>
+where
+    M: Memory,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "pc: {:08x} next_pc {:08x}\n",
+            self.pc, self.next_pc
+        ))?;
+        f.write_fmt(format_args!("xregs: {:?}\n", self.xreg))?;
+        f.write_fmt(format_args!("fregs: {:016x?}\n", self.freg))?;
+        f.write_fmt(format_args!(
+            "fcsr: {:08x} mstatus: {:08x}\n",
+            self.fcsr(),
+            self.mstatus()
+        ))
+    }
+}
+
+impl<M> Rv32ifdCpu<M>
+where
+    M: Memory,
+{
+    /// Creates a new CPU with default memory.
+    pub fn new() -> Self
+    where
+        M: Default,
+    {
+        Self::with_mem(Default::default())
+    }
+
+    /// Creates a new CPU with caller-supplied memory.
+    pub fn with_mem(mem: M) -> Self {
+        Self {
+            pc: 0,
+            next_pc: 0,
+            xreg: Default::default(),
+            freg: Default::default(),
+            fflags: 0,
+            frm: 0,
+            fs: FS_INITIAL,
+            mem,
+            trap: None,
+            reservation: None,
+            alignment: Alignment::RV32IC,
+        }
+    }
+
+    /// Sets the alignment policy, which determines what happens when an instruction uses a misaligned address.
+    pub fn set_alignment(&mut self, alignment: Alignment) {
+        self.alignment = alignment;
+    }
+
+    fn mstatus(&self) -> u32 {
+        let sd = if self.fs == FS_DIRTY { MSTATUS_SD } else { 0 };
+        sd | (self.fs << MSTATUS_FS_SHIFT)
+    }
+
+    fn fcsr(&self) -> u32 {
+        (self.frm << FRM_SHIFT) | self.fflags
+    }
+}
+
+impl<M> Fetch for Rv32ifdCpu<M>
+where
+    M: Memory,
+{
+    fn pc(&self) -> Address {
+        self.pc
+    }
+
+    fn transfer(&mut self) -> Address {
+        self.pc = self.next_pc;
+        self.pc
+    }
+
+    fn set_next_pc(&mut self, address: Address) {
+        self.next_pc = address;
+    }
+
+    fn fetch32(&self, address: Address) -> MemoryResult<u32> {
+        self.read32(address)
+    }
+
+    fn alignment(&self) -> Alignment {
+        self.alignment
+    }
+}
+
+impl<M> Memory for Rv32ifdCpu<M>
+where
+    M: Memory,
+{
+    fn read8(&self, address: Address) -> MemoryResult<u8> {
+        self.mem.read8(address)
+    }
+
+    fn read16(&self, address: Address) -> MemoryResult<u16> {
+        self.mem.read16(address)
+    }
+
+    fn read32(&self, address: Address) -> MemoryResult<u32> {
+        self.mem.read32(address)
+    }
+
+    fn write8(&mut self, address: Address, value: u8) -> MemoryResult<()> {
+        self.mem.write8(address, value)
+    }
+
+    fn write16(&mut self, address: Address, value: u16) -> MemoryResult<()> {
+        self.mem.write16(address, value)
+    }
+
+    fn write32(&mut self, address: Address, value: u32) -> MemoryResult<()> {
+        self.mem.write32(address, value)
+    }
+
+    fn read64(&self, address: Address) -> MemoryResult<u64> {
+        self.mem.read64(address)
+    }
+
+    fn write64(&mut self, address: Address, value: u64) -> MemoryResult<()> {
+        self.mem.write64(address, value)
+    }
+
+    fn peek8(&self, address: Address) -> MemoryResult<u8> {
+        self.mem.peek8(address)
+    }
+
+    fn tick(&mut self) {
+        self.mem.tick()
+    }
+
+    fn next_event(&self) -> Option<u64> {
+        self.mem.next_event()
+    }
+
+    fn advance(&mut self, ticks: u64) {
+        self.mem.advance(ticks)
+    }
+}
+
+impl<M> Load for Rv32ifdCpu<M>
+where
+    M: Memory + Load,
+{
+    fn write_bytes(&mut self, start: crate::Address, bytes: &[u8]) -> crate::MemoryResult<()> {
+        self.mem.write_bytes(start, bytes)
+    }
+}
+
+impl<M> XRegisters for Rv32ifdCpu<M>
+where
+    M: Memory,
+{
+    fn rx(&self, reg: Reg) -> u32 {
+        let index: usize = Into::into(reg);
+        self.xreg[index]
+    }
+
+    fn wx(&mut self, reg: Reg, val: u32) {
+        let index: usize = Into::into(reg);
+        self.xreg[index] = val;
+        self.xreg[0] = 0;
+    }
+}
+
+impl<M> FRegisters for Rv32ifdCpu<M>
+where
+    M: Memory,
+{
+    fn rf(&self, reg: Reg) -> f32 {
+        let index: usize = Into::into(reg);
+        nan_unbox(self.freg[index])
+    }
+
+    fn rf_bits(&self, reg: Reg) -> u32 {
+        let index: usize = Into::into(reg);
+        self.freg[index] as u32
+    }
+
+    fn wf(&mut self, reg: Reg, val: f32) {
+        let index: usize = Into::into(reg);
+        self.freg[index] = nan_box(val);
+        self.fs = FS_DIRTY;
+    }
+
+    fn frm(&self) -> u32 {
+        self.frm
+    }
+
+    fn accrue_fflags(&mut self, flags: u32) {
+        if flags != 0 {
+            self.fflags |= flags & FFLAGS_MASK;
+            self.fs = FS_DIRTY;
+        }
+    }
+
+    fn is_fp_enabled(&self) -> bool {
+        self.fs != FS_OFF
+    }
+}
+
+impl<M> DRegisters for Rv32ifdCpu<M>
+where
+    M: Memory,
+{
+    fn rfd(&self, reg: Reg) -> f64 {
+        let index: usize = Into::into(reg);
+        f64::from_bits(self.freg[index])
+    }
+
+    fn wfd(&mut self, reg: Reg, val: f64) {
+        let index: usize = Into::into(reg);
+        self.freg[index] = val.to_bits();
+        self.fs = FS_DIRTY;
+    }
+}
+
+impl<M> Csrs for Rv32ifdCpu<M>
+where
+    M: Memory,
+{
+    fn rcsr(&self, csr: u32) -> CsrResult<u32> {
+        match csr {
+            csr::FFLAGS | csr::FRM | csr::FCSR if !self.is_fp_enabled() => Err(csr),
+            csr::FFLAGS => Ok(self.fflags),
+            csr::FRM => Ok(self.frm),
+            csr::FCSR => Ok(self.fcsr()),
+            csr::MSTATUS => Ok(self.mstatus()),
+            csr::MISA => Ok(MISA_VALUE),
+            _ => Err(csr),
+        }
+    }
+
+    fn wcsr(&mut self, csr: u32, val: u32) -> CsrResult<()> {
+        match csr {
+            csr::FFLAGS | csr::FRM | csr::FCSR if !self.is_fp_enabled() => return Err(csr),
+            csr::FFLAGS => self.fflags = val & FFLAGS_MASK,
+            csr::FRM => self.frm = val & FRM_MASK,
+            csr::FCSR => {
+                self.fflags = val & FFLAGS_MASK;
+                self.frm = (val >> FRM_SHIFT) & FRM_MASK;
+            }
+            csr::MSTATUS => {
+                // Only FS is writable. SD is derived from it.
+                self.fs = (val & MSTATUS_FS) >> MSTATUS_FS_SHIFT;
+                return Ok(());
+            }
+            csr::MISA => return Ok(()), // WARL, and we only have one legal value.
+            _ => return Err(csr),
+        }
+        // Writing to the floating point CSRs modifies the floating point state.
+        self.fs = FS_DIRTY;
+        Ok(())
+    }
+}
+
+impl<M> Reservation for Rv32ifdCpu<M>
+where
+    M: Memory,
+{
+    fn reserve(&mut self, address: Address) {
+        self.reservation = Some(address & !3);
+    }
+
+    fn take_reservation(&mut self, address: Address) -> bool {
+        self.reservation.take() == Some(address & !3)
+    }
+}
+
+impl<M> Trap for Rv32ifdCpu<M>
+where
+    M: Memory,
+{
+    fn trap(&self) -> Option<TrapRecord> {
+        self.trap
+    }
+
+    fn clear_trap(&mut self) {
+        self.trap = None
+    }
+
+    fn handle_trap(&mut self, cause: TrapCause) {
+        self.trap = Some(TrapRecord { cause, pc: self.pc });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        csr, Csrs, DRegisters, FRegisters, Memory, Rv32ifdCpu, Trap, TrapCause, XRegisters,
+    };
+    use crate::{
+        backends::memory::{basic::BasicMem, bus::Bus},
+        reg::Reg,
+        DispatchRv32imfdc,
+    };
+
+    #[test]
+    fn test_fld_and_fsd_transfer_all_64_bits() {
+        let mut cpu = Rv32ifdCpu::<BasicMem>::new();
+        cpu.write64(0x4000, 0x4009_21fb_5444_2d18).unwrap(); // pi
+        cpu.wx(Reg::new(10), 0x4000);
+        cpu.dispatch(0x00053087); // fld f1, 0(a0)
+        assert_eq!(std::f64::consts::PI, cpu.rfd(Reg::new(1)));
+        cpu.dispatch(0x00153427); // fsd f1, 8(a0)
+        assert_eq!(Ok(0x5444_2d18), cpu.read32(0x4008));
+        assert_eq!(Ok(0x4009_21fb), cpu.read32(0x400c));
+        assert!(!cpu.is_trapped());
+    }
+
+    #[test]
+    fn test_double_precision_arithmetic_conversion_and_comparison() {
+        let mut cpu = Rv32ifdCpu::<BasicMem>::new();
+        cpu.wfd(Reg::new(1), 1.5);
+        cpu.wfd(Reg::new(2), 2.25);
+        cpu.dispatch(0x0220f1d3); // fadd.d f3, f1, f2, dyn
+        assert_eq!(3.75, cpu.rfd(Reg::new(3)));
+
+        cpu.dispatch(0xa220a2d3); // feq.d t0, f1, f2
+        assert_eq!(0, cpu.rx(Reg::new(5)));
+        cpu.dispatch(0xa210a2d3); // feq.d t0, f1, f1
+        assert_eq!(1, cpu.rx(Reg::new(5)));
+
+        cpu.wfd(Reg::new(1), -2.75);
+        cpu.dispatch(0xc20092d3); // fcvt.w.d t0, f1, rtz
+        assert_eq!(-2i32 as u32, cpu.rx(Reg::new(5)));
+        assert_eq!(Ok(0x01), cpu.rcsr(csr::FFLAGS)); // NX.
+
+        cpu.wfd(Reg::new(1), 0.1);
+        cpu.dispatch(0x4010f1d3); // fcvt.s.d f3, f1, dyn
+        assert_eq!(0.1f32, cpu.rf(Reg::new(3)));
+        cpu.dispatch(0x420181d3); // fcvt.d.s f3, f3
+        assert_eq!(f64::from(0.1f32), cpu.rfd(Reg::new(3)));
+        assert!(!cpu.is_trapped());
+    }
+
+    #[test]
+    fn test_single_precision_values_are_nan_boxed() {
+        let mut cpu = Rv32ifdCpu::<BasicMem>::new();
+        cpu.write32(0x4000, 1.0f32.to_bits()).unwrap();
+        cpu.wx(Reg::new(10), 0x4000);
+        cpu.dispatch(0x00052087); // flw f1, 0(a0)
+        assert_eq!(0xffff_ffff_3f80_0000, cpu.rfd(Reg::new(1)).to_bits());
+
+        // A double isn't a valid single, so it reads as the canonical NaN.
+        cpu.wfd(Reg::new(1), 1.0);
+        cpu.wf(Reg::new(2), 1.0);
+        cpu.dispatch(0x0020f1d3); // fadd.s f3, f1, f2, dyn
+        assert_eq!(0x7fc0_0000, cpu.rf(Reg::new(3)).to_bits());
+    }
+
+    #[test]
+    fn test_fsw_and_fmv_x_w_move_the_low_word_without_nan_unboxing() {
+        let mut cpu = Rv32ifdCpu::<BasicMem>::new();
+        cpu.write64(0x4000, 1.5f64.to_bits()).unwrap(); // 0x3ff8_0000_0000_0000
+        cpu.write32(0x4008, 0xffff_ffff).unwrap();
+        cpu.write32(0x4010, 0xffff_ffff).unwrap();
+        cpu.wx(Reg::new(10), 0x4000);
+        cpu.wx(Reg::new(2), 0x4010);
+        cpu.dispatch(0x00053087); // fld f1, 0(a0)
+        cpu.dispatch(0x00152427); // fsw f1, 8(a0)
+        assert_eq!(Ok(0), cpu.read32(0x4008));
+        cpu.dispatch(0xe006); // c.fswsp f1, 0(sp)
+        assert_eq!(Ok(0), cpu.read32(0x4010));
+        cpu.wx(Reg::new(5), 0xffff_ffff);
+        cpu.dispatch(0xe00082d3); // fmv.x.w t0, f1
+        assert_eq!(0, cpu.rx(Reg::new(5)));
+        assert!(!cpu.is_trapped());
+    }
+
+    #[test]
+    fn test_fsd_that_faults_on_its_high_word_writes_nothing() {
+        let mut bus = Bus::new();
+        bus.map_ram(0, 0x1004).unwrap();
+        let mut cpu = Rv32ifdCpu::with_mem(bus);
+        cpu.wfd(Reg::new(1), -1.0);
+        cpu.wx(Reg::new(10), 0x0ff8);
+        cpu.dispatch(0x00153427); // fsd f1, 8(a0)
+        assert_eq!(Some(TrapCause::StoreAccessFault(0x1004)), cpu.trap_cause());
+        assert_eq!(Ok(0), cpu.read32(0x1000));
+    }
+
+    #[test]
+    fn test_illegal_instructions_report_their_own_bits_when_fs_is_off() {
+        let mut cpu = Rv32ifdCpu::<BasicMem>::new();
+        cpu.wcsr(csr::MSTATUS, 0).unwrap();
+        #[rustfmt::skip]
+        let instructions = [
+            0xffc52087, // flw      f1, -4(a0)
+            0x00213c27, // fsd      f2, 24(sp)
+            0x2220a1d3, // fsgnjx.d f3, f1, f2
+            0xe0008553, // fmv.x.w  a0, f1
+            0xa020a553, // feq.s    a0, f1, f2
+            0x6004,     // c.flw    f9, 0(s0)
+            0xe0e4,     // c.fsw    f9, 68(s1)
+            0x2404,     // c.fld    f9, 8(s0)
+            0x60ae,     // c.flwsp  f1, 200(sp)
+            0xe312,     // c.fswsp  f4, 132(sp)
+            0x31e2,     // c.fldsp  f3, 56(sp)
+            0xa60a,     // c.fsdsp  f2, 264(sp)
+        ];
+
+        // The instructions aren't in memory, so the bits must come from the instructions' operands.
+        for ins in instructions {
+            cpu.dispatch(ins);
+            assert_eq!(Some(TrapCause::IllegalInstruction(ins)), cpu.trap_cause());
+            cpu.clear_trap();
+        }
+    }
+}
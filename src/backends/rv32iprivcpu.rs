@@ -0,0 +1,899 @@
+//! An RV32I CPU with machine, supervisor and user privilege levels, traps that can be delegated to S-mode, and Sv32
+//! virtual memory.
+
+use std::fmt::Display;
+
+pub use super::bus::InterruptLine;
+use crate::{
+    csr,
+    memory::{Address, Load, Memory, MemoryResult},
+    mmu::{Access, Context, Sv32},
+    reg::Reg,
+};
+
+pub use crate::cpu::{Alignment, CsrResult, Csrs, Fetch, Privilege, Reservation, XRegisters};
+pub use crate::trap::{Trap, TrapCause, TrapRecord};
+
+// MXL = 1 (32-bit) and the 'I', 'S' and 'U' extensions.
+const MISA_VALUE: u32 = (1 << 30) | (1 << 8) | (1 << 18) | (1 << 20);
+
+// Bits of mstatus.
+const MSTATUS_SIE: u32 = 1 << 1;
+const MSTATUS_MIE: u32 = 1 << 3;
+const MSTATUS_SPIE: u32 = 1 << 5;
+const MSTATUS_MPIE: u32 = 1 << 7;
+const MSTATUS_SPP: u32 = 1 << 8;
+const MSTATUS_MPP_SHIFT: u32 = 11;
+const MSTATUS_MPP: u32 = 3 << MSTATUS_MPP_SHIFT;
+const MSTATUS_MPRV: u32 = 1 << 17;
+const MSTATUS_SUM: u32 = 1 << 18;
+const MSTATUS_MXR: u32 = 1 << 19;
+const MSTATUS_TVM: u32 = 1 << 20;
+const MSTATUS_TW: u32 = 1 << 21;
+const MSTATUS_TSR: u32 = 1 << 22;
+
+// The writable bits of mstatus.
+const MSTATUS_MASK: u32 = MSTATUS_SIE
+    | MSTATUS_MIE
+    | MSTATUS_SPIE
+    | MSTATUS_MPIE
+    | MSTATUS_SPP
+    | MSTATUS_MPP
+    | MSTATUS_MPRV
+    | MSTATUS_SUM
+    | MSTATUS_MXR
+    | MSTATUS_TVM
+    | MSTATUS_TW
+    | MSTATUS_TSR;
+
+// The bits of mstatus that are visible in sstatus.
+const SSTATUS_MASK: u32 = MSTATUS_SIE | MSTATUS_SPIE | MSTATUS_SPP | MSTATUS_SUM | MSTATUS_MXR;
+
+// Bits of mtvec and stvec.
+const TVEC_MODE: u32 = 0b11;
+const TVEC_VECTORED: u32 = 1;
+
+// Supervisor and machine software, timer and external interrupt bits of mie and mip.
+const SI_MASK: u32 = (1 << 1) | (1 << 5) | (1 << 9);
+const MI_MASK: u32 = (1 << 3) | (1 << 7) | (1 << 11);
+
+// The supervisor-level bits of mip that M-mode can write, e.g., so that an SBI can forward timer interrupts.
+const MIP_WRITABLE: u32 = SI_MASK;
+
+// The supervisor-level bit of sip that S-mode can write, i.e., SSIP.
+const SIP_WRITABLE: u32 = 1 << 1;
+
+// The exceptions that can be delegated, i.e., all of them except an ecall from M-mode.
+const MEDELEG_MASK: u32 = 0xb3ff;
+
+// Interrupts in the order that they're taken when more than one is pending for the same privilege level.
+const INTERRUPT_PRIORITY: [TrapCause; 6] = [
+    TrapCause::MachineExternalInterrupt,
+    TrapCause::MachineSoftwareInterrupt,
+    TrapCause::MachineTimerInterrupt,
+    TrapCause::SupervisorExternalInterrupt,
+    TrapCause::SupervisorSoftwareInterrupt,
+    TrapCause::SupervisorTimerInterrupt,
+];
+
+// The encodings of instructions that can be illegal at the current privilege level.
+const MRET: u32 = 0x30200073;
+const SRET: u32 = 0x10200073;
+const WFI: u32 = 0x10500073;
+const SFENCE_VMA: u32 = 0x12000073;
+
+/// The machine-level and supervisor-level CSRs. The supervisor-level views of `mstatus`, `mie` and `mip` are
+/// calculated from the machine-level registers.
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+struct PrivilegedCsrs {
+    mstatus: u32,
+    medeleg: u32,
+    mideleg: u32,
+    mie: u32,
+    mip: u32,
+    mtvec: u32,
+    mcounteren: u32,
+    mscratch: u32,
+    mepc: u32,
+    mcause: u32,
+    mtval: u32,
+    stvec: u32,
+    scounteren: u32,
+    sscratch: u32,
+    sepc: u32,
+    scause: u32,
+    stval: u32,
+    satp: u32,
+}
+
+/// An RV32I CPU with machine, supervisor and user privilege levels, but no floating point.
+///
+/// The CPU starts in M-mode. Traps are taken in M-mode unless they happen in S-mode or U-mode and have been delegated
+/// to S-mode with `medeleg` or `mideleg`, in which case the CPU writes `sepc`, `scause` and `stval`, saves
+/// `sstatus.SIE` in `sstatus.SPIE` and the previous privilege level in `sstatus.SPP`, and continues from `stvec`. The
+/// handler returns with `sret`. Traps taken in M-mode behave as they do on an
+/// [`Rv32iZicsrCpu`](crate::backends::cpus::rv32izicsr::Rv32iZicsrCpu), except that the previous privilege level is
+/// saved in `mstatus.MPP`.
+///
+/// CSRs can only be accessed from a privilege level at least as high as the one encoded in their address, so, e.g.,
+/// reading `mstatus` from S-mode is an illegal instruction. `mret` is only legal in M-mode, and `sret` in S-mode or
+/// above. `mstatus.TSR`, `mstatus.TW` and `mstatus.TVM` make `sret`, `wfi` and `satp` illegal in S-mode, respectively,
+/// and `wfi` is always illegal in U-mode.
+///
+/// Interrupts are driven by interrupt lines connected with [`Rv32iPrivCpu::connect_interrupt`], and by the
+/// supervisor-level bits of `mip` that M-mode software writes. An M-mode interrupt is taken if the CPU is below M-mode,
+/// or is in M-mode with `mstatus.MIE` set. An interrupt that's delegated to S-mode is taken if the CPU is in U-mode, or
+/// is in S-mode with `sstatus.SIE` set.
+///
+/// When `satp` selects Sv32, loads, stores and instruction fetches below M-mode are translated by an
+/// [`Sv32`](crate::mmu::Sv32) MMU, which honours `mstatus.SUM`, `mstatus.MXR` and `mstatus.MPRV`. Translations are
+/// cached until they're flushed with `sfence.vma`, and translation failures raise page faults with the virtual address
+/// in `stval` or `mtval`. Addresses aren't translated by [`Load`], so images are loaded at physical addresses.
+///
+/// The CPU starts with the [`Alignment::RV32IC`] policy so that it can run compressed instructions. To make jumps and
+/// taken branches to addresses that aren't a multiple of 4 trap, as they do without the 'C' extension, call
+/// [`set_alignment`](Rv32iPrivCpu::set_alignment) with [`Alignment::RV32I`].
+///
+/// ```
+/// use arviss::backends::cpus::rv32ipriv::*;
+/// use arviss::backends::memory::basic::BasicMem;
+/// use arviss::DispatchRv32iZicsrPriv;
+///
+/// let mut cpu = Rv32iPrivCpu::<BasicMem>::new();
+/// cpu.wcsr(arviss::csr::MEPC, 0x100).unwrap(); // MPP is U-mode, so mret drops to U-mode at 0x100.
+/// cpu.dispatch(0x30200073); // mret
+/// assert_eq!(Privilege::User, cpu.privilege());
+/// cpu.dispatch(0x00000073); // ecall
+/// assert_eq!(Some(TrapCause::EnvironmentCallFromUMode), cpu.trap_cause());
+/// assert_eq!(Privilege::Machine, cpu.privilege());
+/// ```
+#[derive(Default, PartialEq, Eq)]
+pub struct Rv32iPrivCpu<M>
+where
+    M: Memory,
+{
+    pc: u32,                          // The program counter.
+    next_pc: u32,                     // The program counter for the next instruction.
+    xreg: [u32; 32],                  // Regular registers, x0-x31.
+    privilege: Privilege,             // The current privilege level.
+    csrs: PrivilegedCsrs,             // Control and status registers.
+    mem: M,                           // Memory.
+    trap: Option<TrapRecord>,         // The current trap state.
+    reservation: Option<Address>,     // The reservation set for LR / SC.
+    alignment: Alignment,             // The alignment policy.
+    lines: Vec<(u32, InterruptLine)>, // Interrupt lines and the mip bits that they drive.
+    waiting: bool,                    // True after a wfi, until the CPU resumes.
+    mmu: Sv32,                        // Address translation.
+}
+
+impl<M> Display for Rv32iPrivCpu<M>
+where
+    M: Memory,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "pc: {:08x} next_pc {:08x} privilege: {:?}\n",
+            self.pc, self.next_pc, self.privilege
+        ))?;
+        f.write_fmt(format_args!("xregs: {:?}\n", self.xreg))?;
+        f.write_fmt(format_args!(
+            "mstatus: {:08x} mcause: {:08x} mepc: {:08x} mtval: {:08x}\n",
+            self.csrs.mstatus, self.csrs.mcause, self.csrs.mepc, self.csrs.mtval
+        ))?;
+        f.write_fmt(format_args!(
+            "scause: {:08x} sepc: {:08x} stval: {:08x}\n",
+            self.csrs.scause, self.csrs.sepc, self.csrs.stval
+        ))
+    }
+}
+
+impl<M> Rv32iPrivCpu<M>
+where
+    M: Memory,
+{
+    /// Creates a new CPU with default memory.
+    pub fn new() -> Self
+    where
+        M: Default,
+    {
+        Self::with_mem(Default::default())
+    }
+
+    /// Creates a new CPU with caller-supplied memory.
+    pub fn with_mem(mem: M) -> Self {
+        Self {
+            pc: 0,
+            next_pc: 0,
+            xreg: Default::default(),
+            privilege: Privilege::Machine,
+            csrs: Default::default(),
+            mem,
+            trap: None,
+            reservation: None,
+            alignment: Alignment::RV32IC,
+            lines: Vec::new(),
+            waiting: false,
+            mmu: Sv32::new(),
+        }
+    }
+
+    /// Sets the alignment policy, which determines what happens when an instruction uses a misaligned address.
+    pub fn set_alignment(&mut self, alignment: Alignment) {
+        self.alignment = alignment;
+    }
+
+    /// Connects an interrupt line to the pending bit in `mip` for the given interrupt, e.g., a CLINT's timer line to
+    /// [`TrapCause::MachineTimerInterrupt`], or a PLIC context to [`TrapCause::SupervisorExternalInterrupt`]. More
+    /// than one line can drive the same bit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cause isn't an interrupt.
+    pub fn connect_interrupt(&mut self, cause: TrapCause, line: InterruptLine) {
+        assert!(cause.is_interrupt(), "{} is not an interrupt", cause);
+        self.lines.push((1 << cause.code(), line));
+    }
+
+    fn mip(&self) -> u32 {
+        self.lines
+            .iter()
+            .filter(|(_, line)| line.is_raised())
+            .fold(self.csrs.mip, |mip, (mask, _)| mip | mask)
+    }
+
+    // Returns the highest priority interrupt in `interrupts`.
+    fn highest_priority(interrupts: u32) -> Option<TrapCause> {
+        INTERRUPT_PRIORITY
+            .into_iter()
+            .find(|cause| interrupts & (1 << cause.code()) != 0)
+    }
+
+    // Returns the address of the trap handler given by a trap vector base address register.
+    fn vector(tvec: u32, cause: TrapCause) -> Address {
+        let base = tvec & !TVEC_MODE;
+        if (tvec & TVEC_MODE) == TVEC_VECTORED && cause.is_interrupt() {
+            base.wrapping_add(4 * cause.code())
+        } else {
+            base
+        }
+    }
+
+    // Returns true if the CSR can't be accessed at the current privilege level.
+    fn is_inaccessible(&self, csr: u32) -> bool {
+        csr::privilege(csr) > self.privilege.bits()
+            || (csr == csr::SATP
+                && self.privilege == Privilege::Supervisor
+                && self.csrs.mstatus & MSTATUS_TVM != 0)
+    }
+
+    // Returns the state that translation depends on. Loads and stores in M-mode use mstatus.MPP if mstatus.MPRV is set.
+    fn context(&self, access: Access) -> Context {
+        let mstatus = self.csrs.mstatus;
+        let privilege = if access != Access::Fetch && mstatus & MSTATUS_MPRV != 0 {
+            Privilege::from_bits(mstatus >> MSTATUS_MPP_SHIFT)
+        } else {
+            self.privilege
+        };
+        Context {
+            satp: self.csrs.satp,
+            privilege,
+            sum: mstatus & MSTATUS_SUM != 0,
+            mxr: mstatus & MSTATUS_MXR != 0,
+        }
+    }
+
+    // Translates a virtual address to a physical address.
+    fn translate(&self, address: Address, access: Access) -> MemoryResult<Address> {
+        self.mmu
+            .translate(&self.mem, &self.context(access), address, access)
+    }
+
+    // Fetches the half word at the given virtual address.
+    fn fetch16(&self, address: Address) -> MemoryResult<u32> {
+        let physical = self.translate(address, Access::Fetch)?;
+        self.mem
+            .read16(physical)
+            .map(u32::from)
+            .map_err(|_| address)
+    }
+}
+
+impl<M> Fetch for Rv32iPrivCpu<M>
+where
+    M: Memory,
+{
+    fn pc(&self) -> Address {
+        self.pc
+    }
+
+    fn transfer(&mut self) -> Address {
+        self.waiting = false;
+        self.pc = self.next_pc;
+        self.pc
+    }
+
+    fn set_next_pc(&mut self, address: Address) {
+        self.next_pc = address;
+    }
+
+    fn fetch32(&self, address: Address) -> MemoryResult<u32> {
+        // A fetch that crosses into another page only needs that page if the instruction isn't compressed.
+        if address & 0xfff == 0xffe {
+            let low = self.fetch16(address)?;
+            if low & 0b11 != 0b11 {
+                return Ok(low);
+            }
+            let high = self.fetch16(address.wrapping_add(2))?;
+            return Ok((high << 16) | low);
+        }
+        let physical = self.translate(address, Access::Fetch)?;
+        self.mem.read32(physical).map_err(|_| address)
+    }
+
+    fn alignment(&self) -> Alignment {
+        self.alignment
+    }
+}
+
+impl<M> Memory for Rv32iPrivCpu<M>
+where
+    M: Memory,
+{
+    fn read8(&self, address: Address) -> MemoryResult<u8> {
+        let physical = self.translate(address, Access::Load)?;
+        self.mem.read8(physical).map_err(|_| address)
+    }
+
+    fn read16(&self, address: Address) -> MemoryResult<u16> {
+        let physical = self.translate(address, Access::Load)?;
+        self.mem.read16(physical).map_err(|_| address)
+    }
+
+    fn read32(&self, address: Address) -> MemoryResult<u32> {
+        let physical = self.translate(address, Access::Load)?;
+        self.mem.read32(physical).map_err(|_| address)
+    }
+
+    fn write8(&mut self, address: Address, value: u8) -> MemoryResult<()> {
+        let physical = self.translate(address, Access::Store)?;
+        self.mem.write8(physical, value).map_err(|_| address)
+    }
+
+    fn write16(&mut self, address: Address, value: u16) -> MemoryResult<()> {
+        let physical = self.translate(address, Access::Store)?;
+        self.mem.write16(physical, value).map_err(|_| address)
+    }
+
+    fn write32(&mut self, address: Address, value: u32) -> MemoryResult<()> {
+        let physical = self.translate(address, Access::Store)?;
+        self.mem.write32(physical, value).map_err(|_| address)
+    }
+
+    fn read64(&self, address: Address) -> MemoryResult<u64> {
+        let physical = self.translate(address, Access::Load)?;
+        self.mem.read64(physical).map_err(|_| address)
+    }
+
+    fn write64(&mut self, address: Address, value: u64) -> MemoryResult<()> {
+        let physical = self.translate(address, Access::Store)?;
+        self.mem.write64(physical, value).map_err(|_| address)
+    }
+
+    fn peek8(&self, address: Address) -> MemoryResult<u8> {
+        let physical = self.translate(address, Access::Load)?;
+        self.mem.peek8(physical).map_err(|_| address)
+    }
+
+    fn tick(&mut self) {
+        self.mem.tick()
+    }
+
+    fn next_event(&self) -> Option<u64> {
+        self.mem.next_event()
+    }
+
+    fn advance(&mut self, ticks: u64) {
+        self.mem.advance(ticks)
+    }
+}
+
+impl<M> Load for Rv32iPrivCpu<M>
+where
+    M: Memory + Load,
+{
+    fn write_bytes(&mut self, start: crate::Address, bytes: &[u8]) -> crate::MemoryResult<()> {
+        self.mem.write_bytes(start, bytes)
+    }
+}
+
+impl<M> XRegisters for Rv32iPrivCpu<M>
+where
+    M: Memory,
+{
+    fn rx(&self, reg: Reg) -> u32 {
+        let index: usize = Into::into(reg);
+        self.xreg[index]
+    }
+
+    fn wx(&mut self, reg: Reg, val: u32) {
+        let index: usize = Into::into(reg);
+        self.xreg[index] = val;
+        self.xreg[0] = 0;
+    }
+}
+
+impl<M> Csrs for Rv32iPrivCpu<M>
+where
+    M: Memory,
+{
+    fn rcsr(&self, csr: u32) -> CsrResult<u32> {
+        if self.is_inaccessible(csr) {
+            return Err(csr);
+        }
+        match csr {
+            csr::SSTATUS => Ok(self.csrs.mstatus & SSTATUS_MASK),
+            csr::SIE => Ok(self.csrs.mie & self.csrs.mideleg),
+            csr::STVEC => Ok(self.csrs.stvec),
+            csr::SCOUNTEREN => Ok(self.csrs.scounteren),
+            csr::SSCRATCH => Ok(self.csrs.sscratch),
+            csr::SEPC => Ok(self.csrs.sepc),
+            csr::SCAUSE => Ok(self.csrs.scause),
+            csr::STVAL => Ok(self.csrs.stval),
+            csr::SIP => Ok(self.mip() & self.csrs.mideleg),
+            csr::SATP => Ok(self.csrs.satp),
+            csr::MVENDORID | csr::MARCHID | csr::MIMPID | csr::MHARTID => Ok(0),
+            csr::MSTATUS => Ok(self.csrs.mstatus),
+            csr::MSTATUSH => Ok(0),
+            csr::MISA => Ok(MISA_VALUE),
+            csr::MEDELEG => Ok(self.csrs.medeleg),
+            csr::MIDELEG => Ok(self.csrs.mideleg),
+            csr::MIE => Ok(self.csrs.mie),
+            csr::MIP => Ok(self.mip()),
+            csr::MTVEC => Ok(self.csrs.mtvec),
+            csr::MCOUNTEREN => Ok(self.csrs.mcounteren),
+            csr::MSCRATCH => Ok(self.csrs.mscratch),
+            csr::MEPC => Ok(self.csrs.mepc),
+            csr::MCAUSE => Ok(self.csrs.mcause),
+            csr::MTVAL => Ok(self.csrs.mtval),
+            _ => Err(csr),
+        }
+    }
+
+    fn wcsr(&mut self, csr: u32, val: u32) -> CsrResult<()> {
+        if csr::is_read_only(csr) || self.is_inaccessible(csr) {
+            return Err(csr);
+        }
+        let csrs = &mut self.csrs;
+        match csr {
+            csr::SSTATUS => csrs.mstatus = (csrs.mstatus & !SSTATUS_MASK) | (val & SSTATUS_MASK),
+            csr::SIE => {
+                csrs.mie = (csrs.mie & !csrs.mideleg) | (val & csrs.mideleg);
+            }
+            csr::STVEC => csrs.stvec = val & !0b10, // Only direct (0) and vectored (1) modes are legal.
+            csr::SCOUNTEREN => csrs.scounteren = val,
+            csr::SSCRATCH => csrs.sscratch = val,
+            csr::SEPC => csrs.sepc = val & !1,
+            csr::SCAUSE => csrs.scause = val,
+            csr::STVAL => csrs.stval = val,
+            csr::SIP => {
+                let writable = SIP_WRITABLE & csrs.mideleg;
+                csrs.mip = (csrs.mip & !writable) | (val & writable);
+            }
+            csr::SATP => csrs.satp = val,
+            csr::MSTATUS => {
+                // MPP is WARL, so writing the reserved encoding leaves it unchanged.
+                let mut val = val & MSTATUS_MASK;
+                if (val & MSTATUS_MPP) >> MSTATUS_MPP_SHIFT == 2 {
+                    val = (val & !MSTATUS_MPP) | (csrs.mstatus & MSTATUS_MPP);
+                }
+                csrs.mstatus = val;
+            }
+            csr::MSTATUSH | csr::MISA => {} // WARL, and we only have one legal value.
+            csr::MEDELEG => csrs.medeleg = val & MEDELEG_MASK,
+            csr::MIDELEG => csrs.mideleg = val & SI_MASK,
+            csr::MIE => csrs.mie = val & (SI_MASK | MI_MASK),
+            csr::MIP => csrs.mip = (csrs.mip & !MIP_WRITABLE) | (val & MIP_WRITABLE),
+            csr::MTVEC => csrs.mtvec = val & !0b10,
+            csr::MCOUNTEREN => csrs.mcounteren = val,
+            csr::MSCRATCH => csrs.mscratch = val,
+            csr::MEPC => csrs.mepc = val & !1,
+            csr::MCAUSE => csrs.mcause = val,
+            csr::MTVAL => csrs.mtval = val,
+            _ => return Err(csr),
+        }
+        Ok(())
+    }
+}
+
+impl<M> Reservation for Rv32iPrivCpu<M>
+where
+    M: Memory,
+{
+    fn reserve(&mut self, address: Address) {
+        self.reservation = Some(address & !3);
+    }
+
+    fn take_reservation(&mut self, address: Address) -> bool {
+        self.reservation.take() == Some(address & !3)
+    }
+}
+
+impl<M> Trap for Rv32iPrivCpu<M>
+where
+    M: Memory,
+{
+    fn trap(&self) -> Option<TrapRecord> {
+        self.trap
+    }
+
+    fn clear_trap(&mut self) {
+        self.trap = None
+    }
+
+    fn vectors_traps(&self) -> bool {
+        true
+    }
+
+    fn handle_trap(&mut self, cause: TrapCause) {
+        // Memory accesses fail with access faults, but they're page faults if the MMU says so.
+        let cause = self.mmu.page_fault(cause);
+
+        // Interrupts are taken between instructions, so they return to the next instruction rather than this one.
+        let epc = if cause.is_interrupt() {
+            self.next_pc
+        } else {
+            self.pc
+        };
+
+        // Traps are delegated to S-mode if they're marked as such, unless they happen in M-mode.
+        let delegation = if cause.is_interrupt() {
+            self.csrs.mideleg
+        } else {
+            self.csrs.medeleg
+        };
+        let delegated =
+            self.privilege != Privilege::Machine && delegation & (1 << cause.code()) != 0;
+
+        let csrs = &mut self.csrs;
+        if delegated {
+            csrs.sepc = epc;
+            csrs.scause = cause.mcause();
+            csrs.stval = cause.tval();
+
+            // Disable interrupts, remembering whether they were enabled, and the previous privilege level.
+            let sie = csrs.mstatus & MSTATUS_SIE;
+            let spp = if self.privilege == Privilege::Supervisor {
+                MSTATUS_SPP
+            } else {
+                0
+            };
+            csrs.mstatus =
+                (csrs.mstatus & !(MSTATUS_SIE | MSTATUS_SPIE | MSTATUS_SPP)) | (sie << 4) | spp;
+            self.privilege = Privilege::Supervisor;
+            self.next_pc = Self::vector(csrs.stvec, cause);
+        } else {
+            csrs.mepc = epc;
+            csrs.mcause = cause.mcause();
+            csrs.mtval = cause.tval();
+
+            // Disable interrupts, remembering whether they were enabled, and the previous privilege level.
+            let mie = csrs.mstatus & MSTATUS_MIE;
+            let mpp = self.privilege.bits() << MSTATUS_MPP_SHIFT;
+            csrs.mstatus =
+                (csrs.mstatus & !(MSTATUS_MIE | MSTATUS_MPIE | MSTATUS_MPP)) | (mie << 4) | mpp;
+            self.privilege = Privilege::Machine;
+            self.next_pc = Self::vector(csrs.mtvec, cause);
+        }
+
+        self.trap = Some(TrapRecord { cause, pc: epc });
+    }
+
+    fn privilege(&self) -> Privilege {
+        self.privilege
+    }
+
+    fn pending_interrupt(&self) -> Option<TrapCause> {
+        let pending = self.mip() & self.csrs.mie;
+
+        // Interrupts for M-mode come first. They're enabled below M-mode, or in M-mode if mstatus.MIE is set.
+        let machine_enabled =
+            self.privilege < Privilege::Machine || self.csrs.mstatus & MSTATUS_MIE != 0;
+        let machine = pending & !self.csrs.mideleg;
+        if machine_enabled && machine != 0 {
+            return Self::highest_priority(machine);
+        }
+
+        // Interrupts for S-mode are enabled in U-mode, or in S-mode if sstatus.SIE is set.
+        let supervisor_enabled = self.privilege < Privilege::Supervisor
+            || (self.privilege == Privilege::Supervisor && self.csrs.mstatus & MSTATUS_SIE != 0);
+        let supervisor = pending & self.csrs.mideleg;
+        if supervisor_enabled && supervisor != 0 {
+            return Self::highest_priority(supervisor);
+        }
+        None
+    }
+
+    fn handle_mret(&mut self) {
+        if self.privilege != Privilege::Machine {
+            return self.handle_trap(TrapCause::IllegalInstruction(MRET));
+        }
+
+        // Restore the interrupt enable bit and the privilege level, then return to the instruction in mepc.
+        let csrs = &mut self.csrs;
+        let mpie = csrs.mstatus & MSTATUS_MPIE;
+        self.privilege = Privilege::from_bits(csrs.mstatus >> MSTATUS_MPP_SHIFT);
+        csrs.mstatus = (csrs.mstatus & !(MSTATUS_MIE | MSTATUS_MPP)) | MSTATUS_MPIE | (mpie >> 4);
+        if self.privilege != Privilege::Machine {
+            csrs.mstatus &= !MSTATUS_MPRV;
+        }
+        self.next_pc = csrs.mepc;
+    }
+
+    fn handle_sret(&mut self) {
+        let tsr = self.csrs.mstatus & MSTATUS_TSR != 0;
+        if self.privilege == Privilege::User || (self.privilege == Privilege::Supervisor && tsr) {
+            return self.handle_trap(TrapCause::IllegalInstruction(SRET));
+        }
+
+        // Restore the interrupt enable bit and the privilege level, then return to the instruction in sepc.
+        let csrs = &mut self.csrs;
+        let spie = csrs.mstatus & MSTATUS_SPIE;
+        self.privilege = if csrs.mstatus & MSTATUS_SPP != 0 {
+            Privilege::Supervisor
+        } else {
+            Privilege::User
+        };
+        csrs.mstatus = (csrs.mstatus & !(MSTATUS_SIE | MSTATUS_SPP | MSTATUS_MPRV))
+            | MSTATUS_SPIE
+            | (spie >> 4);
+        self.next_pc = csrs.sepc;
+    }
+
+    fn handle_wfi(&mut self) {
+        let tw = self.csrs.mstatus & MSTATUS_TW != 0;
+        if self.privilege == Privilege::User || (self.privilege == Privilege::Supervisor && tw) {
+            return self.handle_trap(TrapCause::IllegalInstruction(WFI));
+        }
+        self.waiting = true;
+    }
+
+    fn handle_sfence_vma(&mut self, rs1: Reg, rs2: Reg) {
+        let tvm = self.csrs.mstatus & MSTATUS_TVM != 0;
+        if self.privilege == Privilege::User || (self.privilege == Privilege::Supervisor && tvm) {
+            let code = SFENCE_VMA | (u32::from(rs2) << 20) | (u32::from(rs1) << 15);
+            return self.handle_trap(TrapCause::IllegalInstruction(code));
+        }
+
+        // x0 selects every address or every address space, rather than the value of x0.
+        let address = (rs1 != Reg::ZERO).then(|| self.rx(rs1));
+        let asid = (rs2 != Reg::ZERO).then(|| self.rx(rs2));
+        self.mmu.flush(address, asid);
+    }
+
+    fn is_waiting(&self) -> bool {
+        self.waiting && self.mip() & self.csrs.mie == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        csr, Csrs, Fetch, InterruptLine, Privilege, Rv32iPrivCpu, Trap, TrapCause, XRegisters,
+    };
+    use crate::{
+        backends::memory::basic::BasicMem, reg::Reg, test_support::program, DispatchRv32iZicsrPriv,
+    };
+
+    fn step(cpu: &mut Rv32iPrivCpu<BasicMem>) {
+        let ins = cpu.fetch().unwrap();
+        cpu.dispatch(ins);
+    }
+
+    // Returns a CPU that's about to run in the given mode at 0x100, with M-mode traps at 0x200 and S-mode traps at
+    // 0x300.
+    fn cpu_in(privilege: Privilege) -> Rv32iPrivCpu<BasicMem> {
+        let mut cpu = Rv32iPrivCpu::<BasicMem>::new();
+        cpu.wcsr(csr::MTVEC, 0x200).unwrap();
+        cpu.wcsr(csr::STVEC, 0x300).unwrap();
+        cpu.wcsr(csr::MEPC, 0x100).unwrap();
+        cpu.wcsr(csr::MSTATUS, privilege.bits() << 11).unwrap();
+        cpu.handle_mret();
+        assert_eq!(privilege, cpu.privilege());
+        cpu
+    }
+
+    #[test]
+    fn test_ecalls_report_the_mode_they_come_from() {
+        for (privilege, cause) in [
+            (Privilege::User, TrapCause::EnvironmentCallFromUMode),
+            (Privilege::Supervisor, TrapCause::EnvironmentCallFromSMode),
+            (Privilege::Machine, TrapCause::EnvironmentCallFromMMode),
+        ] {
+            let mut cpu = cpu_in(privilege);
+            program(&mut cpu, 0x100, &[0x00000073]); // ecall
+            step(&mut cpu);
+            assert_eq!(Some(cause), cpu.trap_cause());
+            assert_eq!(
+                Ok(privilege.bits() << 11),
+                cpu.rcsr(csr::MSTATUS).map(|s| s & (3 << 11))
+            );
+            assert_eq!(0x200, cpu.transfer());
+        }
+    }
+
+    #[test]
+    fn test_delegated_traps_are_taken_in_s_mode_and_return_with_sret() {
+        let mut cpu = cpu_in(Privilege::Machine);
+        cpu.wcsr(csr::MEDELEG, 1 << 8).unwrap(); // Delegate ecalls from U-mode.
+        cpu.handle_mret(); // Drop to U-mode, because mret set MPP to U-mode.
+        assert_eq!(Privilege::User, cpu.privilege());
+        #[rustfmt::skip]
+        program(&mut cpu, 0x100, &[
+            0x00000073, // 0x100: ecall
+            0x30200073, // 0x104: mret
+        ]);
+        #[rustfmt::skip]
+        program(&mut cpu, 0x300, &[
+            0x14102573, // 0x300: csrr  a0, sepc
+            0x00450513, // 0x304: addi  a0, a0, 4
+            0x14151073, // 0x308: csrw  sepc, a0
+            0x10200073, // 0x30c: sret
+        ]);
+
+        step(&mut cpu);
+        assert_eq!(Some(TrapCause::EnvironmentCallFromUMode), cpu.trap_cause());
+        assert_eq!(Privilege::Supervisor, cpu.privilege());
+        assert_eq!(Ok(8), cpu.rcsr(csr::SCAUSE));
+        assert_eq!(Ok(0x100), cpu.rcsr(csr::SEPC));
+        cpu.clear_trap();
+        for _ in 0..4 {
+            step(&mut cpu);
+        }
+        assert!(!cpu.is_trapped());
+        assert_eq!(Privilege::User, cpu.privilege());
+        assert_eq!(0x104, cpu.transfer());
+
+        // Traps that aren't delegated go to M-mode, even from U-mode.
+        step(&mut cpu);
+        assert_eq!(
+            Some(TrapCause::IllegalInstruction(0x30200073)),
+            cpu.trap_cause()
+        );
+        assert_eq!(Privilege::Machine, cpu.privilege());
+        assert_eq!(Ok(0x104), cpu.rcsr(csr::MEPC));
+        assert_eq!(Ok(2), cpu.rcsr(csr::MCAUSE));
+        assert_eq!(Ok(8), cpu.rcsr(csr::SCAUSE));
+        assert_eq!(0x200, cpu.transfer());
+    }
+
+    #[test]
+    fn test_csrs_and_instructions_are_checked_against_the_privilege_level() {
+        let mut cpu = cpu_in(Privilege::Supervisor);
+        cpu.wx(Reg::new(10), 0x1234);
+        #[rustfmt::skip]
+        program(&mut cpu, 0x100, &[
+            0x14051073, // 0x100: csrw  sscratch, a0
+            0x300025f3, // 0x104: csrr  a1, mstatus
+        ]);
+        step(&mut cpu);
+        assert!(!cpu.is_trapped());
+        step(&mut cpu);
+        assert_eq!(
+            Some(TrapCause::IllegalInstruction(0x300025f3)),
+            cpu.trap_cause()
+        );
+        assert_eq!(Ok(0x1234), cpu.rcsr(csr::SSCRATCH));
+
+        // mret is illegal below M-mode, and sret and wfi are illegal in U-mode.
+        let mut cpu = cpu_in(Privilege::Supervisor);
+        cpu.handle_mret();
+        assert_eq!(
+            Some(TrapCause::IllegalInstruction(0x30200073)),
+            cpu.trap_cause()
+        );
+        let mut cpu = cpu_in(Privilege::User);
+        cpu.handle_sret();
+        assert_eq!(
+            Some(TrapCause::IllegalInstruction(0x10200073)),
+            cpu.trap_cause()
+        );
+        let mut cpu = cpu_in(Privilege::User);
+        cpu.handle_wfi();
+        assert_eq!(
+            Some(TrapCause::IllegalInstruction(0x10500073)),
+            cpu.trap_cause()
+        );
+    }
+
+    #[test]
+    fn test_delegated_interrupts_are_taken_in_s_mode() {
+        let mut cpu = Rv32iPrivCpu::<BasicMem>::new();
+        let timer = InterruptLine::new();
+        cpu.connect_interrupt(TrapCause::MachineTimerInterrupt, timer.clone());
+        cpu.wcsr(csr::MIDELEG, 0xffff_ffff).unwrap();
+        assert_eq!(Ok(0x222), cpu.rcsr(csr::MIDELEG));
+        cpu.wcsr(csr::MIE, 0x0a0).unwrap(); // MTIE and STIE.
+        cpu.wcsr(csr::MIP, 0x020).unwrap(); // STIP, e.g., from an SBI.
+        assert_eq!(Ok(0x020), cpu.rcsr(csr::SIP));
+        assert_eq!(Ok(0x020), cpu.rcsr(csr::SIE));
+
+        // S-mode interrupts are never taken in M-mode.
+        assert_eq!(None, cpu.pending_interrupt());
+
+        // In S-mode, they need sstatus.SIE, but M-mode interrupts are always enabled.
+        cpu.wcsr(csr::MSTATUS, 1 << 11).unwrap();
+        cpu.handle_mret();
+        assert_eq!(None, cpu.pending_interrupt());
+        timer.set(true);
+        assert_eq!(
+            Some(TrapCause::MachineTimerInterrupt),
+            cpu.pending_interrupt()
+        );
+        timer.set(false);
+        cpu.wcsr(csr::SSTATUS, 1 << 1).unwrap();
+        assert_eq!(
+            Some(TrapCause::SupervisorTimerInterrupt),
+            cpu.take_interrupt()
+        );
+        assert_eq!(Privilege::Supervisor, cpu.privilege());
+        assert_eq!(Ok(0x8000_0005), cpu.rcsr(csr::SCAUSE));
+        assert_eq!(Ok(1 << 8 | 1 << 5), cpu.rcsr(csr::SSTATUS)); // SPP = S, SPIE = 1, SIE = 0.
+    }
+
+    #[test]
+    fn test_loads_and_stores_are_translated_and_page_faults_report_the_virtual_address() {
+        let mut cpu = cpu_in(Privilege::Supervisor);
+        #[rustfmt::skip]
+        program(&mut cpu, 0x1000, &[
+            0x00000801, // 0x1000: VA 0x0000_0000 -> the table at 0x2000.
+        ]);
+        #[rustfmt::skip]
+        program(&mut cpu, 0x2000, &[
+            0x0000004b, // 0x2000: VA 0x0000 -> PA 0x0000, R X A.
+            0, 0, 0, 0,
+            0x000018c7, // 0x2014: VA 0x5000 -> PA 0x6000, R W A D.
+        ]);
+        program(&mut cpu, 0x6000, &[0x1234]);
+        #[rustfmt::skip]
+        program(&mut cpu, 0x100, &[
+            0x00005537, // 0x100: lui   a0, 0x5
+            0x00052583, // 0x104: lw    a1, 0(a0)
+            0x00b02023, // 0x108: sw    a1, 0(zero)
+            0x00007537, // 0x10c: lui   a0, 0x7
+            0x00052583, // 0x110: lw    a1, 0(a0)
+        ]);
+        cpu.wcsr(csr::SATP, 0x8000_0001).unwrap();
+
+        // The store goes to a read-only page.
+        for _ in 0..3 {
+            step(&mut cpu);
+        }
+        assert_eq!(0x1234, cpu.rx(Reg::new(11)));
+        assert_eq!(Some(TrapCause::StorePageFault(0)), cpu.trap_cause());
+        assert_eq!(Ok(15), cpu.rcsr(csr::MCAUSE));
+
+        // The load goes to an unmapped page, and it's delegated to S-mode.
+        let mut cpu = cpu_in(Privilege::Machine);
+        cpu.wcsr(csr::MEDELEG, 1 << 13).unwrap();
+        cpu.wcsr(csr::MSTATUS, 1 << 11).unwrap();
+        cpu.handle_mret();
+        program(&mut cpu, 0x1000, &[0x00000801]);
+        program(&mut cpu, 0x2000, &[0x0000004b]);
+        program(&mut cpu, 0x100, &[0x00007537, 0x00052583]);
+        cpu.wcsr(csr::SATP, 0x8000_0001).unwrap();
+        step(&mut cpu);
+        step(&mut cpu);
+        assert_eq!(Some(TrapCause::LoadPageFault(0x7000)), cpu.trap_cause());
+        assert_eq!(Ok(13), cpu.rcsr(csr::SCAUSE));
+        assert_eq!(Ok(0x7000), cpu.rcsr(csr::STVAL));
+        assert_eq!(Ok(0x104), cpu.rcsr(csr::SEPC));
+
+        // U-mode can't flush the TLB.
+        let mut cpu = cpu_in(Privilege::User);
+        cpu.handle_sfence_vma(Reg::new(10), Reg::ZERO);
+        assert_eq!(
+            Some(TrapCause::IllegalInstruction(0x12050073)),
+            cpu.trap_cause()
+        );
+    }
+}
@@ -0,0 +1,524 @@
+//! An RV32I CPU with integer registers, machine-level control and status registers, and machine-mode traps.
+
+use std::fmt::Display;
+
+pub use super::bus::InterruptLine;
+use crate::{
+    csr,
+    memory::{Address, Load, Memory, MemoryResult},
+    reg::Reg,
+};
+
+pub use crate::cpu::{Alignment, CsrResult, Csrs, Fetch, Reservation, XRegisters};
+pub use crate::trap::{Trap, TrapCause, TrapRecord};
+
+// MXL = 1 (32-bit) and the 'I' extension.
+const MISA_VALUE: u32 = (1 << 30) | (1 << 8);
+
+// Bits of mstatus.
+const MSTATUS_MIE: u32 = 1 << 3;
+const MSTATUS_MPIE: u32 = 1 << 7;
+const MSTATUS_MPP: u32 = 3 << 11;
+
+// Bits of mtvec.
+const MTVEC_MODE: u32 = 0b11;
+const MTVEC_VECTORED: u32 = 1;
+
+// The writable bits of mstatus. MPP is hardwired to M-mode because that's the only privilege level.
+const MSTATUS_MASK: u32 = MSTATUS_MIE | MSTATUS_MPIE;
+
+// Machine software, timer and external interrupt bits of mie and mip.
+const MI_MASK: u32 = (1 << 3) | (1 << 7) | (1 << 11);
+
+// Machine-level interrupts in the order that they're taken when more than one is pending.
+const MI_PRIORITY: [TrapCause; 3] = [
+    TrapCause::MachineExternalInterrupt,
+    TrapCause::MachineSoftwareInterrupt,
+    TrapCause::MachineTimerInterrupt,
+];
+
+/// The machine-level CSRs.
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+struct MachineCsrs {
+    mstatus: u32,
+    mie: u32,
+    mip: u32,
+    mtvec: u32,
+    mscratch: u32,
+    mepc: u32,
+    mcause: u32,
+    mtval: u32,
+}
+
+/// An RV32I CPU with integer registers and machine-level control and status registers, but no floating point.
+///
+/// Traps are taken in machine mode as described by the privileged architecture. When a trap is taken the CPU writes
+/// `mepc`, `mcause` and `mtval`, saves `mstatus.MIE` in `mstatus.MPIE` then clears it, and continues from the trap
+/// handler given by `mtvec`, which may be in direct or vectored mode. The handler returns with `mret`.
+///
+/// The cause of the most recent trap is also recorded so that the host can see it with [`Trap::trap_cause`]. Because
+/// the CPU is already at the trap handler, calling [`Trap::clear_trap`] and continuing lets the guest handle the trap.
+///
+/// The machine-level interrupt pending bits of `mip` are driven by devices through interrupt lines that are connected
+/// with [`Rv32iZicsrCpu::connect_interrupt`]. An interrupt is taken between instructions when it's pending, enabled
+/// in `mie`, and `mstatus.MIE` is set. After a `wfi`, the CPU waits until an interrupt is pending and enabled in `mie`,
+/// whether or not `mstatus.MIE` is set.
+///
+/// The CPU starts with the [`Alignment::RV32IC`] policy so that it can run compressed instructions. To make jumps and
+/// taken branches to addresses that aren't a multiple of 4 trap, as they do without the 'C' extension, call
+/// [`set_alignment`](Rv32iZicsrCpu::set_alignment) with [`Alignment::RV32I`].
+#[derive(Default, PartialEq, Eq)]
+pub struct Rv32iZicsrCpu<M>
+where
+    M: Memory,
+{
+    pc: u32,                          // The program counter.
+    next_pc: u32,                     // The program counter for the next instruction.
+    xreg: [u32; 32],                  // Regular registers, x0-x31.
+    csrs: MachineCsrs,                // Control and status registers.
+    mem: M,                           // Memory.
+    trap: Option<TrapRecord>,         // The current trap state.
+    reservation: Option<Address>,     // The reservation set for LR / SC.
+    alignment: Alignment,             // The alignment policy.
+    lines: Vec<(u32, InterruptLine)>, // Interrupt lines and the mip bits that they drive.
+    waiting: bool,                    // True after a wfi, until the CPU resumes.
+}
+
+impl<M> Display for Rv32iZicsrCpu<M>
+where
+    M: Memory,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "pc: {:08x} next_pc {:08x}\n",
+            self.pc, self.next_pc
+        ))?;
+        f.write_fmt(format_args!("xregs: {:?}\n", self.xreg))?;
+        f.write_fmt(format_args!(
+            "mstatus: {:08x} mcause: {:08x} mepc: {:08x} mtval: {:08x}\n",
+            self.mstatus(),
+            self.csrs.mcause,
+            self.csrs.mepc,
+            self.csrs.mtval
+        ))
+    }
+}
+
+impl<M> Rv32iZicsrCpu<M>
+where
+    M: Memory,
+{
+    /// Creates a new CPU with default memory.
+    pub fn new() -> Self
+    where
+        M: Default,
+    {
+        Self::with_mem(Default::default())
+    }
+
+    /// Creates a new CPU with caller-supplied memory.
+    pub fn with_mem(mem: M) -> Self {
+        Self {
+            pc: 0,
+            next_pc: 0,
+            xreg: Default::default(),
+            csrs: Default::default(),
+            mem,
+            trap: None,
+            reservation: None,
+            alignment: Alignment::RV32IC,
+            lines: Vec::new(),
+            waiting: false,
+        }
+    }
+
+    /// Sets the alignment policy, which determines what happens when an instruction uses a misaligned address.
+    pub fn set_alignment(&mut self, alignment: Alignment) {
+        self.alignment = alignment;
+    }
+
+    /// Connects an interrupt line to the pending bit in `mip` for the given interrupt, e.g., a CLINT's timer line to
+    /// [`TrapCause::MachineTimerInterrupt`]. More than one line can drive the same bit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cause isn't a machine-level interrupt.
+    pub fn connect_interrupt(&mut self, cause: TrapCause, line: InterruptLine) {
+        let mask = 1 << cause.code();
+        assert!(
+            cause.is_interrupt() && mask & MI_MASK != 0,
+            "{} is not a machine-level interrupt",
+            cause
+        );
+        self.lines.push((mask, line));
+    }
+
+    fn mstatus(&self) -> u32 {
+        self.csrs.mstatus | MSTATUS_MPP
+    }
+
+    fn mip(&self) -> u32 {
+        self.lines
+            .iter()
+            .filter(|(_, line)| line.is_raised())
+            .fold(self.csrs.mip, |mip, (mask, _)| mip | mask)
+    }
+}
+
+impl<M> Fetch for Rv32iZicsrCpu<M>
+where
+    M: Memory,
+{
+    fn pc(&self) -> Address {
+        self.pc
+    }
+
+    fn transfer(&mut self) -> Address {
+        self.waiting = false;
+        self.pc = self.next_pc;
+        self.pc
+    }
+
+    fn set_next_pc(&mut self, address: Address) {
+        self.next_pc = address;
+    }
+
+    fn fetch32(&self, address: Address) -> MemoryResult<u32> {
+        self.read32(address)
+    }
+
+    fn alignment(&self) -> Alignment {
+        self.alignment
+    }
+}
+
+impl<M> Memory for Rv32iZicsrCpu<M>
+where
+    M: Memory,
+{
+    fn read8(&self, address: Address) -> MemoryResult<u8> {
+        self.mem.read8(address)
+    }
+
+    fn read16(&self, address: Address) -> MemoryResult<u16> {
+        self.mem.read16(address)
+    }
+
+    fn read32(&self, address: Address) -> MemoryResult<u32> {
+        self.mem.read32(address)
+    }
+
+    fn write8(&mut self, address: Address, value: u8) -> MemoryResult<()> {
+        self.mem.write8(address, value)
+    }
+
+    fn write16(&mut self, address: Address, value: u16) -> MemoryResult<()> {
+        self.mem.write16(address, value)
+    }
+
+    fn write32(&mut self, address: Address, value: u32) -> MemoryResult<()> {
+        self.mem.write32(address, value)
+    }
+
+    fn read64(&self, address: Address) -> MemoryResult<u64> {
+        self.mem.read64(address)
+    }
+
+    fn write64(&mut self, address: Address, value: u64) -> MemoryResult<()> {
+        self.mem.write64(address, value)
+    }
+
+    fn peek8(&self, address: Address) -> MemoryResult<u8> {
+        self.mem.peek8(address)
+    }
+
+    fn tick(&mut self) {
+        self.mem.tick()
+    }
+
+    fn next_event(&self) -> Option<u64> {
+        self.mem.next_event()
+    }
+
+    fn advance(&mut self, ticks: u64) {
+        self.mem.advance(ticks)
+    }
+}
+
+impl<M> Load for Rv32iZicsrCpu<M>
+where
+    M: Memory + Load,
+{
+    fn write_bytes(&mut self, start: crate::Address, bytes: &[u8]) -> crate::MemoryResult<()> {
+        self.mem.write_bytes(start, bytes)
+    }
+}
+
+impl<M> XRegisters for Rv32iZicsrCpu<M>
+where
+    M: Memory,
+{
+    fn rx(&self, reg: Reg) -> u32 {
+        let index: usize = Into::into(reg);
+        self.xreg[index]
+    }
+
+    fn wx(&mut self, reg: Reg, val: u32) {
+        let index: usize = Into::into(reg);
+        self.xreg[index] = val;
+        self.xreg[0] = 0;
+    }
+}
+
+impl<M> Csrs for Rv32iZicsrCpu<M>
+where
+    M: Memory,
+{
+    fn rcsr(&self, csr: u32) -> CsrResult<u32> {
+        match csr {
+            csr::MVENDORID | csr::MARCHID | csr::MIMPID | csr::MHARTID => Ok(0),
+            csr::MSTATUS => Ok(self.mstatus()),
+            csr::MSTATUSH => Ok(0),
+            csr::MISA => Ok(MISA_VALUE),
+            csr::MIE => Ok(self.csrs.mie),
+            csr::MIP => Ok(self.mip()),
+            csr::MTVEC => Ok(self.csrs.mtvec),
+            csr::MSCRATCH => Ok(self.csrs.mscratch),
+            csr::MEPC => Ok(self.csrs.mepc),
+            csr::MCAUSE => Ok(self.csrs.mcause),
+            csr::MTVAL => Ok(self.csrs.mtval),
+            _ => Err(csr),
+        }
+    }
+
+    fn wcsr(&mut self, csr: u32, val: u32) -> CsrResult<()> {
+        if csr::is_read_only(csr) {
+            return Err(csr);
+        }
+        match csr {
+            csr::MSTATUS => self.csrs.mstatus = val & MSTATUS_MASK,
+            csr::MSTATUSH | csr::MISA => {} // WARL, and we only have one legal value.
+            csr::MIE => self.csrs.mie = val & MI_MASK,
+            csr::MIP => {} // Machine-level interrupt pending bits are read-only, because devices drive them.
+            csr::MTVEC => self.csrs.mtvec = val & !0b10, // Only direct (0) and vectored (1) modes are legal.
+            csr::MSCRATCH => self.csrs.mscratch = val,
+            csr::MEPC => self.csrs.mepc = val & !1,
+            csr::MCAUSE => self.csrs.mcause = val,
+            csr::MTVAL => self.csrs.mtval = val,
+            _ => return Err(csr),
+        }
+        Ok(())
+    }
+}
+
+impl<M> Reservation for Rv32iZicsrCpu<M>
+where
+    M: Memory,
+{
+    fn reserve(&mut self, address: Address) {
+        self.reservation = Some(address & !3);
+    }
+
+    fn take_reservation(&mut self, address: Address) -> bool {
+        self.reservation.take() == Some(address & !3)
+    }
+}
+
+impl<M> Trap for Rv32iZicsrCpu<M>
+where
+    M: Memory,
+{
+    fn trap(&self) -> Option<TrapRecord> {
+        self.trap
+    }
+
+    fn clear_trap(&mut self) {
+        self.trap = None
+    }
+
+    fn vectors_traps(&self) -> bool {
+        true
+    }
+
+    fn handle_trap(&mut self, cause: TrapCause) {
+        // Interrupts are taken between instructions, so they return to the next instruction rather than this one.
+        self.csrs.mepc = if cause.is_interrupt() {
+            self.next_pc
+        } else {
+            self.pc
+        };
+        self.csrs.mcause = cause.mcause();
+        self.csrs.mtval = cause.tval();
+
+        // Disable interrupts, remembering whether they were enabled.
+        let mie = self.csrs.mstatus & MSTATUS_MIE;
+        self.csrs.mstatus = (self.csrs.mstatus & !(MSTATUS_MIE | MSTATUS_MPIE)) | (mie << 4);
+
+        // Jump to the trap handler. In vectored mode, interrupts go to an offset from the base.
+        let mtvec = self.csrs.mtvec;
+        let base = mtvec & !MTVEC_MODE;
+        self.next_pc = if (mtvec & MTVEC_MODE) == MTVEC_VECTORED && cause.is_interrupt() {
+            base.wrapping_add(4 * cause.code())
+        } else {
+            base
+        };
+
+        self.trap = Some(TrapRecord {
+            cause,
+            pc: self.csrs.mepc,
+        });
+    }
+
+    fn pending_interrupt(&self) -> Option<TrapCause> {
+        if self.csrs.mstatus & MSTATUS_MIE == 0 {
+            return None;
+        }
+        let pending = self.mip() & self.csrs.mie;
+        MI_PRIORITY
+            .into_iter()
+            .find(|cause| pending & (1 << cause.code()) != 0)
+    }
+
+    fn handle_wfi(&mut self) {
+        self.waiting = true;
+    }
+
+    fn is_waiting(&self) -> bool {
+        self.waiting && self.mip() & self.csrs.mie == 0
+    }
+
+    fn handle_mret(&mut self) {
+        // Restore the interrupt enable bit, then return to the instruction in mepc.
+        let mpie = self.csrs.mstatus & MSTATUS_MPIE;
+        self.csrs.mstatus = (self.csrs.mstatus & !MSTATUS_MIE) | MSTATUS_MPIE | (mpie >> 4);
+        self.next_pc = self.csrs.mepc;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        csr, Csrs, Fetch, InterruptLine, Load, Rv32iZicsrCpu, Trap, TrapCause, XRegisters,
+    };
+    use crate::{backends::memory::basic::BasicMem, reg::Reg};
+
+    #[test]
+    fn test_csr_instructions_read_and_write_csrs() {
+        use crate::DispatchRv32iZicsr;
+
+        let mut cpu = Rv32iZicsrCpu::<BasicMem>::new();
+        cpu.wx(Reg::new(10), 0x1234_5678);
+        cpu.dispatch(0x34051073); // csrrw zero, mscratch, a0
+        cpu.dispatch(0x340025f3); // csrrs a1, mscratch, zero
+        assert_eq!(Ok(0x1234_5678), cpu.rcsr(csr::MSCRATCH));
+        assert_eq!(0x1234_5678, cpu.rx(Reg::new(11)));
+        cpu.dispatch(0x3407f673); // csrrci a2, mscratch, 15
+        assert_eq!(Ok(0x1234_5670), cpu.rcsr(csr::MSCRATCH));
+        assert_eq!(0x1234_5678, cpu.rx(Reg::new(12)));
+        assert!(!cpu.is_trapped());
+    }
+
+    #[test]
+    fn test_writing_a_read_only_csr_is_illegal() {
+        use crate::DispatchRv32iZicsr;
+
+        let mut cpu = Rv32iZicsrCpu::<BasicMem>::new();
+        cpu.dispatch(0xf1402573); // csrrs a0, mhartid, zero
+        assert!(!cpu.is_trapped());
+        cpu.dispatch(0xf1451073); // csrrw zero, mhartid, a0
+        assert_eq!(
+            Some(TrapCause::IllegalInstruction(0xf1451073)),
+            cpu.trap_cause()
+        );
+    }
+
+    #[test]
+    fn test_accessing_a_missing_csr_is_illegal() {
+        use crate::DispatchRv32iZicsr;
+
+        let mut cpu = Rv32iZicsrCpu::<BasicMem>::new();
+        cpu.dispatch(0x00302573); // csrrs a0, fcsr, zero
+        assert_eq!(
+            Some(TrapCause::IllegalInstruction(0x00302573)),
+            cpu.trap_cause()
+        );
+    }
+
+    #[test]
+    fn test_traps_are_taken_in_machine_mode_and_return_with_mret() {
+        use crate::DispatchRv32iZicsrPriv;
+
+        let mut cpu = Rv32iZicsrCpu::<BasicMem>::new();
+        #[rustfmt::skip]
+        let program: [u32; 8] = [
+            0x00000073, // 0x00: ecall
+            0x00100073, // 0x04: ebreak
+            0x00000013, // 0x08: nop
+            0x00000013, // 0x0c: nop
+            0x34102573, // 0x10: csrrs a0, mepc, zero   <- the trap handler
+            0x00450513, // 0x14: addi a0, a0, 4
+            0x34151073, // 0x18: csrrw zero, mepc, a0
+            0x30200073, // 0x1c: mret
+        ];
+        for (i, ins) in program.iter().enumerate() {
+            cpu.write_bytes(4 * i as u32, &ins.to_le_bytes()).unwrap();
+        }
+        cpu.wcsr(csr::MTVEC, 0x10).unwrap();
+        cpu.wcsr(csr::MSTATUS, 1 << 3).unwrap();
+
+        // Take the trap.
+        let ins = cpu.fetch().unwrap();
+        cpu.dispatch(ins);
+        assert_eq!(Some(TrapCause::EnvironmentCallFromMMode), cpu.trap_cause());
+        assert_eq!(Ok(0), cpu.rcsr(csr::MEPC));
+        assert_eq!(Ok(11), cpu.rcsr(csr::MCAUSE));
+        assert_eq!(Ok(0x1880), cpu.rcsr(csr::MSTATUS)); // MPP = M, MPIE = 1, MIE = 0.
+
+        // Let the guest handle it.
+        cpu.clear_trap();
+        while !cpu.is_trapped() {
+            let ins = cpu.fetch().unwrap();
+            cpu.dispatch(ins);
+        }
+        assert_eq!(Some(TrapCause::Breakpoint(4)), cpu.trap_cause());
+        assert_eq!(Ok(4), cpu.rcsr(csr::MEPC));
+        assert_eq!(Ok(4), cpu.rcsr(csr::MTVAL));
+        assert_eq!(4, cpu.pc());
+    }
+
+    #[test]
+    fn test_interrupts_are_vectored() {
+        let mut cpu = Rv32iZicsrCpu::<BasicMem>::new();
+        cpu.wcsr(csr::MTVEC, 0x100 | 1).unwrap();
+        cpu.handle_trap(TrapCause::IllegalInstruction(0));
+        assert_eq!(0x100, cpu.transfer());
+        cpu.handle_trap(TrapCause::MachineTimerInterrupt);
+        assert_eq!(0x11c, cpu.transfer());
+        assert_eq!(Ok(0x8000_0007), cpu.rcsr(csr::MCAUSE));
+    }
+
+    #[test]
+    fn test_interrupt_lines_drive_mip_and_are_taken_by_priority() {
+        let mut cpu = Rv32iZicsrCpu::<BasicMem>::new();
+        let timer = InterruptLine::new();
+        let external = InterruptLine::new();
+        cpu.connect_interrupt(TrapCause::MachineTimerInterrupt, timer.clone());
+        cpu.connect_interrupt(TrapCause::MachineExternalInterrupt, external.clone());
+        timer.set(true);
+        external.set(true);
+        assert_eq!(Ok(0x880), cpu.rcsr(csr::MIP));
+
+        // Nothing is taken until the interrupts are enabled in both mie and mstatus.
+        cpu.wcsr(csr::MIE, 0x880).unwrap();
+        assert_eq!(None, cpu.pending_interrupt());
+        cpu.wcsr(csr::MSTATUS, 1 << 3).unwrap();
+        assert_eq!(
+            Some(TrapCause::MachineExternalInterrupt),
+            cpu.pending_interrupt()
+        );
+        external.set(false);
+        assert_eq!(Some(TrapCause::MachineTimerInterrupt), cpu.take_interrupt());
+        assert!(!cpu.is_trapped());
+        assert_eq!(None, cpu.pending_interrupt()); // Taking it cleared mstatus.MIE.
+    }
+}
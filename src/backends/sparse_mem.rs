@@ -0,0 +1,233 @@
+//! Sparse memory that covers the whole address space but only allocates the pages that are written.
+
+use std::collections::BTreeMap;
+
+use crate::memory::{Address, MemoryResult};
+pub use crate::memory::{Load, Memory};
+
+/// The size of a page in bytes.
+pub const PAGE_SIZE: u32 = 4096;
+
+const PAGE_SHIFT: u32 = 12;
+const PAGE_MASK: u32 = PAGE_SIZE - 1;
+
+type Page = Box<[u8; PAGE_SIZE as usize]>;
+
+/// What happens when a page that hasn't been allocated is read.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Unallocated {
+    /// The page reads as zero, as though it had always been there.
+    #[default]
+    Zero,
+    /// The read results in an error.
+    Fault,
+}
+
+/// Memory that covers the whole 32-bit address space, allocating pages of [`PAGE_SIZE`] bytes the first time that
+/// they're written.
+///
+/// Every address is writable, so there's no ROM and no I/O. Reads from pages that haven't been allocated either return
+/// zero or fail, depending on how the memory was created.
+///
+/// ```
+/// use arviss::backends::memory::sparse::*;
+///
+/// let mut mem = SparseMem::new();
+/// mem.write32(0xdead_beec, 0x12345678).unwrap();
+/// assert_eq!(Ok(0x12345678), mem.read32(0xdead_beec));
+/// assert_eq!(Ok(0), mem.read32(0x8000_0000));
+/// assert_eq!(PAGE_SIZE as usize, mem.resident_bytes());
+/// ```
+#[derive(Default, Clone, PartialEq, Eq)]
+pub struct SparseMem {
+    pages: BTreeMap<u32, Page>, // Keyed by page number.
+    unallocated: Unallocated,
+}
+
+impl SparseMem {
+    /// Creates memory with no pages allocated, where unallocated pages read as zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates memory with no pages allocated, where reading unallocated pages has the given behaviour.
+    pub fn with_unallocated(unallocated: Unallocated) -> Self {
+        Self {
+            pages: BTreeMap::new(),
+            unallocated,
+        }
+    }
+
+    /// Returns the number of pages that have been allocated.
+    pub fn resident_pages(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Returns the number of bytes in the pages that have been allocated.
+    pub fn resident_bytes(&self) -> usize {
+        self.pages.len() * PAGE_SIZE as usize
+    }
+
+    /// Returns the contents of the page that contains the given address, if it has been allocated.
+    pub fn page(&self, address: Address) -> Option<&[u8]> {
+        self.pages
+            .get(&(address >> PAGE_SHIFT))
+            .map(|page| page.as_slice())
+    }
+
+    /// Returns the base address and contents of each allocated page, in address order.
+    pub fn pages(&self) -> impl Iterator<Item = (Address, &[u8])> {
+        self.pages
+            .iter()
+            .map(|(&number, page)| (number << PAGE_SHIFT, page.as_slice()))
+    }
+
+    /// Frees every page.
+    pub fn clear(&mut self) {
+        self.pages.clear();
+    }
+
+    // Returns the page that contains the given address, allocating it if necessary.
+    fn page_mut(&mut self, address: Address) -> &mut Page {
+        self.pages
+            .entry(address >> PAGE_SHIFT)
+            .or_insert_with(|| Box::new([0; PAGE_SIZE as usize]))
+    }
+
+    // Reads `N` bytes, which may span pages.
+    #[inline]
+    fn read_bytes<const N: usize>(&self, address: Address) -> MemoryResult<[u8; N]> {
+        let mut bytes = [0; N];
+        let offset = (address & PAGE_MASK) as usize;
+        if offset + N <= PAGE_SIZE as usize {
+            match self.pages.get(&(address >> PAGE_SHIFT)) {
+                Some(page) => bytes.copy_from_slice(&page[offset..offset + N]),
+                None if self.unallocated == Unallocated::Zero => {}
+                None => return Err(address),
+            }
+        } else {
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                *byte = self.read_bytes::<1>(address.wrapping_add(i as u32))?[0];
+            }
+        }
+        Ok(bytes)
+    }
+
+    // Writes bytes, which may span pages.
+    fn write_bytes_to_pages(&mut self, address: Address, bytes: &[u8]) {
+        let mut address = address;
+        let mut bytes = bytes;
+        while !bytes.is_empty() {
+            let offset = (address & PAGE_MASK) as usize;
+            let len = bytes.len().min(PAGE_SIZE as usize - offset);
+            self.page_mut(address)[offset..offset + len].copy_from_slice(&bytes[..len]);
+            address = address.wrapping_add(len as u32);
+            bytes = &bytes[len..];
+        }
+    }
+}
+
+impl Load for SparseMem {
+    fn write_bytes(&mut self, start: Address, bytes: &[u8]) -> MemoryResult<()> {
+        if bytes.len() as u64 > 1 << 32 {
+            return Err(start);
+        }
+        self.write_bytes_to_pages(start, bytes);
+        Ok(())
+    }
+}
+
+impl Memory for SparseMem {
+    #[inline]
+    fn read8(&self, address: Address) -> MemoryResult<u8> {
+        self.read_bytes::<1>(address).map(|bytes| bytes[0])
+    }
+
+    #[inline]
+    fn read16(&self, address: Address) -> MemoryResult<u16> {
+        self.read_bytes(address).map(u16::from_le_bytes)
+    }
+
+    #[inline]
+    fn read32(&self, address: Address) -> MemoryResult<u32> {
+        self.read_bytes(address).map(u32::from_le_bytes)
+    }
+
+    #[inline]
+    fn write8(&mut self, address: Address, byte: u8) -> MemoryResult<()> {
+        self.write_bytes_to_pages(address, &[byte]);
+        Ok(())
+    }
+
+    #[inline]
+    fn write16(&mut self, address: Address, half_word: u16) -> MemoryResult<()> {
+        self.write_bytes_to_pages(address, &half_word.to_le_bytes());
+        Ok(())
+    }
+
+    #[inline]
+    fn write32(&mut self, address: Address, word: u32) -> MemoryResult<()> {
+        self.write_bytes_to_pages(address, &word.to_le_bytes());
+        Ok(())
+    }
+
+    #[inline]
+    fn read64(&self, address: Address) -> MemoryResult<u64> {
+        self.read_bytes(address).map(u64::from_le_bytes)
+    }
+
+    #[inline]
+    fn write64(&mut self, address: Address, double_word: u64) -> MemoryResult<()> {
+        self.write_bytes_to_pages(address, &double_word.to_le_bytes());
+        Ok(())
+    }
+
+    #[inline]
+    fn peek8(&self, address: Address) -> MemoryResult<u8> {
+        self.read8(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Load, Memory, SparseMem, Unallocated, PAGE_SIZE};
+
+    #[test]
+    fn test_pages_are_allocated_on_first_write() {
+        let mut mem = SparseMem::new();
+        assert_eq!(Ok(0), mem.read32(0x1234));
+        assert_eq!(0, mem.resident_pages());
+        mem.write8(0x1234, 1).unwrap();
+        mem.write8(0x1235, 2).unwrap();
+        assert_eq!(1, mem.resident_pages());
+        mem.write32(0x2ffe, 0x44332211).unwrap(); // Spans two pages.
+        assert_eq!(3, mem.resident_pages());
+        assert_eq!(Ok(0x4433), mem.read16(0x3000));
+        assert_eq!(Ok(0x44332211), mem.read32(0x2ffe));
+        let bases: Vec<u32> = mem.pages().map(|(base, _)| base).collect();
+        assert_eq!(vec![0x1000, 0x2000, 0x3000], bases);
+        assert_eq!(
+            Some(&[1, 2][..]),
+            mem.page(0x1000).map(|p| &p[0x234..0x236])
+        );
+    }
+
+    #[test]
+    fn test_unallocated_pages_can_fault() {
+        let mut mem = SparseMem::with_unallocated(Unallocated::Fault);
+        assert_eq!(Err(0x1000), mem.read8(0x1000));
+        mem.write8(0x0fff, 0xff).unwrap();
+        assert_eq!(Ok(0xff), mem.read8(0x0fff));
+        assert_eq!(Err(0x1000), mem.read16(0x0fff));
+    }
+
+    #[test]
+    fn test_the_whole_address_space_is_loadable() {
+        let mut mem = SparseMem::new();
+        mem.write_bytes(0xffff_fffe, &[1, 2, 3, 4]).unwrap(); // Wraps around.
+        assert_eq!(Ok(0x04030201), mem.read32(0xffff_fffe));
+        assert_eq!(2 * PAGE_SIZE as usize, mem.resident_bytes());
+        mem.clear();
+        assert_eq!(0, mem.resident_pages());
+    }
+}
@@ -0,0 +1,265 @@
+//! A simple console, and helpers for connecting guest consoles to the host.
+
+use std::cell::Cell;
+use std::fmt::Debug;
+use std::io::{self, BufReader, Read, Write};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+
+use super::bus::{Device, Width};
+use crate::memory::MemoryResult;
+
+/// The bit of the status register that's set when the console can transmit. It's always set.
+pub const STATUS_TX_READY: u8 = 1 << 0;
+
+/// The bit of the status register that's set when the console has received a byte.
+pub const STATUS_RX_READY: u8 = 1 << 1;
+
+/// The offset of the console's status register.
+pub const TTY_STATUS: u32 = 0;
+
+/// The offset of the console's data register.
+pub const TTY_DATA: u32 = 1;
+
+/// Returns a channel that receives the bytes read from the given reader, which is read on a separate thread so that
+/// it's never waited for. The channel disconnects when the reader reaches the end of its input or fails.
+pub fn spawn_reader(reader: impl Read + Send + 'static) -> Receiver<u8> {
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        for byte in BufReader::new(reader).bytes() {
+            match byte {
+                Ok(byte) if sender.send(byte).is_ok() => {}
+                _ => break,
+            }
+        }
+    });
+    receiver
+}
+
+/// A writer that captures everything that's written to it, e.g., so that tests can check a guest's output.
+///
+/// Clones share the same buffer, so one clone can be given to a device while another is kept to look at the output,
+/// even from another thread.
+#[derive(Clone, Default)]
+pub struct CaptureBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl CaptureBuffer {
+    /// Creates an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of everything that has been written.
+    pub fn contents(&self) -> Vec<u8> {
+        self.lock().clone()
+    }
+
+    /// Returns everything that has been written as a string, replacing anything that isn't UTF-8.
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.lock()).into_owned()
+    }
+
+    /// Removes and returns everything that has been written.
+    pub fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut self.lock())
+    }
+
+    // Locks the buffer. A writer that panicked can't have left it in an inconsistent state, so poisoning is ignored.
+    fn lock(&self) -> MutexGuard<'_, Vec<u8>> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Debug for CaptureBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CaptureBuffer")
+            .field(&self.to_string_lossy())
+            .finish()
+    }
+}
+
+impl Write for CaptureBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.lock().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A console that transmits to a host writer and receives from a host channel.
+///
+/// As a [`Device`], it has a status register at [`TTY_STATUS`] and a data register at [`TTY_DATA`], just like the TTY
+/// in [`BasicMem`](crate::backends::memory::basic::BasicMem). Writing the data register transmits a byte. Reading it
+/// returns the next byte that was received, or zero if there isn't one.
+///
+/// ```
+/// use std::sync::mpsc::channel;
+/// use arviss::backends::devices::tty::*;
+///
+/// let output = CaptureBuffer::new();
+/// let (sender, receiver) = channel();
+/// let mut console = Console::new(output.clone()).with_input(receiver);
+/// console.write(b'A');
+/// sender.send(b'B').unwrap();
+/// assert_eq!(STATUS_TX_READY | STATUS_RX_READY, console.status());
+/// assert_eq!(Some(b'B'), console.read());
+/// assert_eq!("A", output.to_string_lossy());
+/// ```
+pub struct Console {
+    output: Box<dyn Write + Send>,
+    input: Option<Receiver<u8>>,
+    pending: Cell<Option<u8>>, // A byte that has been received but not read.
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Self::stdout()
+    }
+}
+
+impl Console {
+    /// Creates a console that transmits to stdout and has no input.
+    pub fn stdout() -> Self {
+        Self::new(io::stdout())
+    }
+
+    /// Creates a console that transmits to the given writer and has no input.
+    pub fn new(output: impl Write + Send + 'static) -> Self {
+        Self {
+            output: Box::new(output),
+            input: None,
+            pending: Cell::new(None),
+        }
+    }
+
+    /// Returns this console, but receiving the bytes that the host sends to the given channel.
+    pub fn with_input(self, input: Receiver<u8>) -> Self {
+        Self {
+            input: Some(input),
+            ..self
+        }
+    }
+
+    /// Returns this console, but receiving the bytes that are read from the given reader. See [`spawn_reader`].
+    pub fn with_reader(self, reader: impl Read + Send + 'static) -> Self {
+        self.with_input(spawn_reader(reader))
+    }
+
+    /// Transmits a byte. Note that this flushes, largely because not doing so causes problems when switching from
+    /// interpreted code to native code, most likely because of multiple runtimes.
+    pub fn write(&mut self, byte: u8) {
+        // The guest can't do anything about errors, so they're ignored.
+        let _ = self
+            .output
+            .write_all(&[byte])
+            .and_then(|_| self.output.flush());
+    }
+
+    /// Returns the next byte that was received, if there is one.
+    pub fn read(&self) -> Option<u8> {
+        self.peek();
+        self.pending.take()
+    }
+
+    /// Returns the value of the status register.
+    pub fn status(&self) -> u8 {
+        if self.peek().is_some() {
+            STATUS_TX_READY | STATUS_RX_READY
+        } else {
+            STATUS_TX_READY
+        }
+    }
+
+    // Returns the next byte that was received without consuming it.
+    fn peek(&self) -> Option<u8> {
+        if self.pending.get().is_none() {
+            let byte = self.input.as_ref().and_then(|input| input.try_recv().ok());
+            self.pending.set(byte);
+        }
+        self.pending.get()
+    }
+}
+
+impl Device for Console {
+    fn read(&mut self, offset: u32, width: Width) -> MemoryResult<u32> {
+        match (offset, width) {
+            (TTY_STATUS, Width::Byte) => Ok(u32::from(self.status())),
+            (TTY_DATA, Width::Byte) => Ok(u32::from(Console::read(self).unwrap_or(0))),
+            _ => Err(offset),
+        }
+    }
+
+    fn write(&mut self, offset: u32, width: Width, value: u32) -> MemoryResult<()> {
+        match (offset, width) {
+            (TTY_DATA, Width::Byte) => {
+                Console::write(self, value as u8);
+                Ok(())
+            }
+            _ => Err(offset),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{spawn_reader, CaptureBuffer, Console, STATUS_RX_READY, STATUS_TX_READY};
+    use crate::backends::{
+        basic_mem::BasicMem,
+        bus::{Bus, Memory, Region, Widths},
+    };
+
+    #[test]
+    fn test_the_console_reads_from_a_reader() {
+        let console = Console::new(CaptureBuffer::new()).with_reader(&b"hi"[..]);
+        let mut received = Vec::new();
+        while received.len() < 2 {
+            if let Some(byte) = console.read() {
+                received.push(byte);
+            }
+        }
+        assert_eq!(b"hi", received.as_slice());
+        assert_eq!(None, console.read());
+        assert_eq!(STATUS_TX_READY, console.status());
+    }
+
+    #[test]
+    fn test_memory_with_a_console_can_be_sent_to_another_thread() {
+        use crate::platforms::{basic::BasicCpu, virt::FlatMem};
+
+        let output = CaptureBuffer::new();
+        let mut cpu =
+            BasicCpu::with_mem(BasicMem::new().with_console(Console::new(output.clone())));
+        cpu = std::thread::spawn(move || {
+            cpu.write8(0x8001, b'!').unwrap();
+            cpu
+        })
+        .join()
+        .unwrap();
+        cpu.write8(0x8001, b'?').unwrap();
+        assert_eq!("!?", output.to_string_lossy());
+
+        fn assert_send<T: Send>() {}
+        assert_send::<FlatMem>();
+    }
+
+    #[test]
+    fn test_the_console_can_be_mapped_to_a_bus() {
+        let output = CaptureBuffer::new();
+        let console = Console::new(output.clone()).with_input(spawn_reader(&b"x"[..]));
+        let mut bus = Bus::new();
+        let region = Region::new(0x2000, 2).with_widths(Widths::BYTE);
+        bus.map(region, console).unwrap();
+        bus.write8(0x2001, b'!').unwrap();
+        while bus.read8(0x2000) != Ok(STATUS_TX_READY | STATUS_RX_READY) {}
+        assert_eq!(Ok(b'x'), bus.read8(0x2001));
+        assert_eq!(Ok(0), bus.read8(0x2001));
+        assert_eq!(b"!", output.take().as_slice());
+        assert!(output.contents().is_empty());
+    }
+}
@@ -0,0 +1,376 @@
+//! A model of the NS16550A UART, as found on QEMU's `virt` machine and on many real boards.
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::mpsc::Receiver;
+
+use super::bus::{Device, InterruptLine, Width};
+use crate::memory::MemoryResult;
+
+/// The number of bytes occupied by the UART's registers.
+pub const UART_SIZE: u32 = 8;
+
+// Register offsets.
+const RBR_THR_DLL: u32 = 0; // Receive buffer (read), transmit holding (write), divisor latch low (DLAB).
+const IER_DLM: u32 = 1; // Interrupt enable, divisor latch high (DLAB).
+const IIR_FCR: u32 = 2; // Interrupt identification (read), FIFO control (write).
+const LCR: u32 = 3; // Line control.
+const MCR: u32 = 4; // Modem control.
+const LSR: u32 = 5; // Line status.
+const MSR: u32 = 6; // Modem status.
+const SCR: u32 = 7; // Scratch.
+
+// Bits of IER.
+const IER_ERBFI: u8 = 1 << 0; // Received data available.
+const IER_ETBEI: u8 = 1 << 1; // Transmit holding register empty.
+const IER_MASK: u8 = 0x0f;
+
+// Values of IIR.
+const IIR_NONE: u8 = 0x01;
+const IIR_THRE: u8 = 0x02;
+const IIR_RDA: u8 = 0x04;
+const IIR_FIFO: u8 = 0xc0;
+
+// Bits of FCR.
+const FCR_ENABLE: u8 = 1 << 0;
+const FCR_CLEAR_RX: u8 = 1 << 1;
+
+// Bits of LCR.
+const LCR_DLAB: u8 = 1 << 7;
+
+// Bits of MCR.
+const MCR_LOOP: u8 = 1 << 4;
+
+// Bits of LSR.
+const LSR_DR: u8 = 1 << 0; // Data ready.
+const LSR_THRE: u8 = 1 << 5; // Transmit holding register empty.
+const LSR_TEMT: u8 = 1 << 6; // Transmitter empty.
+
+// Bits of MSR. Outside loopback mode, the modem is always connected and ready.
+const MSR_CONNECTED: u8 = 0xb0; // DCD, DSR and CTS.
+
+// The receive FIFO's size when FIFOs are enabled. Without them there's a single holding register.
+const FIFO_SIZE: usize = 16;
+
+/// An NS16550A UART.
+///
+/// Transmitted bytes are written to the host immediately, so the transmitter is always empty. Received bytes come from
+/// a channel that the host can send to at any time, and they're moved into the receive FIFO whenever the guest reads
+/// the UART's status or when the UART is ticked. The received data available interrupt is raised whenever the FIFO
+/// isn't empty, whatever its trigger level.
+///
+/// The UART's registers are one byte apart, and it only supports byte accesses.
+///
+/// ```
+/// use arviss::backends::devices::uart::*;
+/// use arviss::backends::memory::bus::*;
+///
+/// let mut bus = Bus::new();
+/// let uart = Uart::new(std::io::sink());
+/// bus.map(Region::new(0x1000_0000, UART_SIZE).with_widths(Widths::BYTE), uart).unwrap();
+/// bus.write8(0x1000_0000, b'A').unwrap(); // THR
+/// assert_eq!(Ok(0x60), bus.read8(0x1000_0005)); // LSR: the transmitter is empty.
+/// ```
+pub struct Uart {
+    output: Box<dyn Write + Send>,
+    input: Option<Receiver<u8>>,
+    interrupt: Option<InterruptLine>,
+    rx: VecDeque<u8>,
+    thre_pending: bool, // True if the transmit holding register empty interrupt is pending.
+    ier: u8,
+    fcr: u8,
+    lcr: u8,
+    mcr: u8,
+    scr: u8,
+    dll: u8,
+    dlm: u8,
+}
+
+impl Uart {
+    /// Creates a UART that transmits to the given writer and has no input.
+    pub fn new(output: impl Write + Send + 'static) -> Self {
+        Self {
+            output: Box::new(output),
+            input: None,
+            interrupt: None,
+            rx: VecDeque::with_capacity(FIFO_SIZE),
+            thre_pending: false,
+            ier: 0,
+            fcr: 0,
+            lcr: 0,
+            mcr: 0,
+            scr: 0,
+            dll: 0,
+            dlm: 0,
+        }
+    }
+
+    /// Returns this UART, but receiving the bytes that the host sends to the given channel. To receive from a reader
+    /// such as stdin, use [`spawn_reader`](crate::backends::devices::tty::spawn_reader).
+    pub fn with_input(self, input: Receiver<u8>) -> Self {
+        Self {
+            input: Some(input),
+            ..self
+        }
+    }
+
+    /// Returns this UART, but raising the given line when it has an interrupt pending.
+    pub fn with_interrupt(self, interrupt: InterruptLine) -> Self {
+        Self {
+            interrupt: Some(interrupt),
+            ..self
+        }
+    }
+
+    // Returns the number of bytes that the receiver can hold.
+    fn rx_capacity(&self) -> usize {
+        if self.fcr & FCR_ENABLE != 0 {
+            FIFO_SIZE
+        } else {
+            1
+        }
+    }
+
+    // Moves input from the host into the receiver, as long as there's room for it.
+    fn poll_input(&mut self) {
+        if let Some(input) = &self.input {
+            while self.rx.len() < self.rx_capacity() {
+                match input.try_recv() {
+                    Ok(byte) => self.rx.push_back(byte),
+                    Err(_) => break,
+                }
+            }
+        }
+    }
+
+    // Returns the interrupt identification, i.e., the highest priority pending interrupt.
+    fn iir(&self) -> u8 {
+        let id = if self.ier & IER_ERBFI != 0 && !self.rx.is_empty() {
+            IIR_RDA
+        } else if self.ier & IER_ETBEI != 0 && self.thre_pending {
+            IIR_THRE
+        } else {
+            IIR_NONE
+        };
+        if self.fcr & FCR_ENABLE != 0 {
+            id | IIR_FIFO
+        } else {
+            id
+        }
+    }
+
+    fn lsr(&self) -> u8 {
+        let dr = if self.rx.is_empty() { 0 } else { LSR_DR };
+        dr | LSR_THRE | LSR_TEMT
+    }
+
+    fn msr(&self) -> u8 {
+        if self.mcr & MCR_LOOP != 0 {
+            // In loopback mode, RTS, DTR, OUT1 and OUT2 are connected to CTS, DSR, RI and DCD respectively.
+            let mcr = self.mcr;
+            ((mcr & 0b0010) << 3) | ((mcr & 0b0001) << 5) | ((mcr & 0b1100) << 4)
+        } else {
+            MSR_CONNECTED
+        }
+    }
+
+    fn transmit(&mut self, byte: u8) {
+        if self.mcr & MCR_LOOP != 0 {
+            if self.rx.len() < self.rx_capacity() {
+                self.rx.push_back(byte);
+            }
+        } else {
+            // Flush, so that the host sees the output straight away. The guest can't do anything about errors.
+            let _ = self
+                .output
+                .write_all(&[byte])
+                .and_then(|_| self.output.flush());
+        }
+        self.thre_pending = true;
+    }
+
+    // Raises or lowers the interrupt line to reflect the UART's state.
+    fn update_interrupt(&self) {
+        if let Some(interrupt) = &self.interrupt {
+            interrupt.set(self.iir() & IIR_NONE == 0);
+        }
+    }
+
+    fn read_register(&mut self, offset: u32) -> u8 {
+        let dlab = self.lcr & LCR_DLAB != 0;
+        match offset {
+            RBR_THR_DLL if dlab => self.dll,
+            RBR_THR_DLL => {
+                let byte = self.rx.pop_front().unwrap_or(0);
+                self.poll_input();
+                byte
+            }
+            IER_DLM if dlab => self.dlm,
+            IER_DLM => self.ier,
+            IIR_FCR => {
+                self.poll_input();
+                let iir = self.iir();
+                if iir & !IIR_FIFO == IIR_THRE {
+                    // Reading IIR acknowledges the transmit holding register empty interrupt.
+                    self.thre_pending = false;
+                }
+                iir
+            }
+            LCR => self.lcr,
+            MCR => self.mcr,
+            LSR => {
+                self.poll_input();
+                self.lsr()
+            }
+            MSR => self.msr(),
+            SCR => self.scr,
+            _ => 0,
+        }
+    }
+
+    fn write_register(&mut self, offset: u32, value: u8) {
+        let dlab = self.lcr & LCR_DLAB != 0;
+        match offset {
+            RBR_THR_DLL if dlab => self.dll = value,
+            RBR_THR_DLL => self.transmit(value),
+            IER_DLM if dlab => self.dlm = value,
+            IER_DLM => {
+                // Enabling the transmit holding register empty interrupt raises it, because the register is empty.
+                if value & IER_ETBEI != 0 && self.ier & IER_ETBEI == 0 {
+                    self.thre_pending = true;
+                }
+                self.ier = value & IER_MASK;
+            }
+            IIR_FCR => {
+                if value & FCR_CLEAR_RX != 0 {
+                    self.rx.clear();
+                }
+                self.fcr = value;
+                self.rx.truncate(self.rx_capacity());
+                self.poll_input();
+            }
+            LCR => self.lcr = value,
+            MCR => self.mcr = value & 0x1f,
+            SCR => self.scr = value,
+            _ => {}
+        }
+    }
+}
+
+impl Device for Uart {
+    fn read(&mut self, offset: u32, width: Width) -> MemoryResult<u32> {
+        if width != Width::Byte || offset >= UART_SIZE {
+            return Err(offset);
+        }
+        let value = self.read_register(offset);
+        self.update_interrupt();
+        Ok(u32::from(value))
+    }
+
+    fn write(&mut self, offset: u32, width: Width, value: u32) -> MemoryResult<()> {
+        if width != Width::Byte || offset >= UART_SIZE {
+            return Err(offset);
+        }
+        self.write_register(offset, value as u8);
+        self.update_interrupt();
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        self.poll_input();
+        self.update_interrupt();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::sync::mpsc::channel;
+
+    use super::{Uart, UART_SIZE};
+    use crate::backends::bus::{Bus, InterruptLine, Memory, Region, Widths};
+    use crate::backends::tty::CaptureBuffer;
+
+    const BASE: u32 = 0x1000_0000;
+
+    fn bus_with(uart: Uart) -> Bus {
+        let mut bus = Bus::new();
+        let region = Region::new(BASE, UART_SIZE).with_widths(Widths::BYTE);
+        bus.map(region, uart).unwrap();
+        bus
+    }
+
+    #[test]
+    fn test_the_uart_transmits_and_receives() {
+        let output = CaptureBuffer::new();
+        let (sender, receiver) = channel();
+        let mut bus = bus_with(Uart::new(output.clone()).with_input(receiver));
+        for &byte in b"Hi!" {
+            bus.write8(BASE, byte).unwrap();
+        }
+        assert_eq!("Hi!", output.to_string_lossy());
+
+        assert_eq!(Ok(0x60), bus.read8(BASE + 5)); // Nothing received.
+        sender.send(b'x').unwrap();
+        sender.send(b'y').unwrap();
+        assert_eq!(Ok(0x61), bus.read8(BASE + 5)); // Data ready.
+        assert_eq!(Ok(b'x'), bus.read8(BASE));
+        assert_eq!(Ok(b'y'), bus.read8(BASE));
+        assert_eq!(Ok(0x60), bus.read8(BASE + 5));
+        assert_eq!(Err(BASE), bus.read32(BASE));
+    }
+
+    #[test]
+    fn test_the_divisor_latch_and_scratch_registers_hold_values() {
+        let mut bus = bus_with(Uart::new(io::sink()));
+        bus.write8(BASE + 3, 0x83).unwrap(); // LCR: DLAB, 8 bits.
+        bus.write8(BASE, 0x01).unwrap(); // DLL
+        bus.write8(BASE + 1, 0x02).unwrap(); // DLM
+        bus.write8(BASE + 3, 0x03).unwrap(); // LCR: 8 bits.
+        bus.write8(BASE + 7, 0x5a).unwrap(); // SCR
+        assert_eq!(Ok(0), bus.read8(BASE + 1)); // IER
+        assert_eq!(Ok(0x5a), bus.read8(BASE + 7));
+        bus.write8(BASE + 3, 0x83).unwrap();
+        assert_eq!(Ok(0x01), bus.read8(BASE));
+        assert_eq!(Ok(0x02), bus.read8(BASE + 1));
+    }
+
+    #[test]
+    fn test_interrupts_are_raised_for_received_data_and_an_empty_transmitter() {
+        let (sender, receiver) = channel();
+        let interrupt = InterruptLine::new();
+        let uart = Uart::new(io::sink())
+            .with_input(receiver)
+            .with_interrupt(interrupt.clone());
+        let mut bus = bus_with(uart);
+        bus.write8(BASE + 2, 0x07).unwrap(); // FCR: enable and clear the FIFOs.
+
+        // Enabling the THRE interrupt raises it straight away, and reading IIR acknowledges it.
+        bus.write8(BASE + 1, 0x02).unwrap();
+        assert!(interrupt.is_raised());
+        assert_eq!(Ok(0xc2), bus.read8(BASE + 2));
+        assert!(!interrupt.is_raised());
+        assert_eq!(Ok(0xc1), bus.read8(BASE + 2));
+
+        // Received data raises an interrupt when the UART is ticked, until the data is read.
+        bus.write8(BASE + 1, 0x01).unwrap();
+        sender.send(b'z').unwrap();
+        assert!(!interrupt.is_raised());
+        bus.tick();
+        assert!(interrupt.is_raised());
+        assert_eq!(Ok(0xc4), bus.read8(BASE + 2));
+        assert_eq!(Ok(b'z'), bus.read8(BASE));
+        assert!(!interrupt.is_raised());
+    }
+
+    #[test]
+    fn test_loopback_mode_receives_what_is_transmitted() {
+        let output = CaptureBuffer::new();
+        let mut bus = bus_with(Uart::new(output.clone()));
+        bus.write8(BASE + 4, 0x1b).unwrap(); // MCR: loopback, OUT2, RTS, DTR.
+        assert_eq!(Ok(0xb0), bus.read8(BASE + 6)); // MSR: DCD, CTS, DSR.
+        bus.write8(BASE, 0xa5).unwrap();
+        assert_eq!(Ok(0xa5), bus.read8(BASE));
+        assert!(output.contents().is_empty());
+    }
+}
@@ -0,0 +1,729 @@
+//! Basic-block translation with threaded-code execution.
+//!
+//! A [`BlockCpu`] discovers basic blocks, i.e., straight-line runs of instructions that end with a control transfer,
+//! and translates each of them into a vector of [`Decoded`] instructions whose operands are already bound. It then
+//! executes whole blocks at a time, following the links between blocks rather than looking up the next block, so the
+//! per-instruction cost is little more than an indirect call.
+//!
+//! ```
+//! use arviss::blocks::BlockCpu;
+//! use arviss::platforms::basic::*;
+//! use arviss::DispatchRv32ic;
+//!
+//! let mut cpu = BlockCpu::new(Rv32iCpu::<BasicMem>::new(), |decoder, ins| decoder.dispatch(ins));
+//! cpu.write_bytes(0, &0x00100073u32.to_le_bytes()).unwrap(); // ebreak
+//! cpu.run();
+//! assert_eq!(Some(TrapCause::Breakpoint(0)), cpu.trap_cause());
+//! ```
+
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{
+    cpu::{
+        Alignment, CsrResult, Csrs, DRegisters, FRegisters, Fetch, Privilege, Reservation,
+        XRegisters,
+    },
+    csr,
+    icache::{DecodeFn, Decoded, Decoder},
+    memory::{Address, Load, Memory, MemoryResult},
+    reg::Reg,
+    trap::{Trap, TrapCause, TrapRecord},
+};
+
+// The maximum number of instructions in a block.
+const MAX_BLOCK_LEN: usize = 64;
+
+// Code is tracked in pages of this many bytes, with one bit per byte.
+const PAGE_SHIFT: u32 = 12;
+const PAGE_SIZE: usize = 1 << PAGE_SHIFT;
+
+/// Returns true if an instruction ends a basic block, i.e., if it may transfer control somewhere other than the next
+/// instruction. System instructions also end blocks because they can change the state that later instructions depend
+/// on, as can fences.
+fn ends_block(ins: u32) -> bool {
+    if (ins & 0b11) == 0b11 {
+        // Branches, jal, jalr, system instructions and fences.
+        return matches!(ins & 0x7f, 0x63 | 0x67 | 0x6f | 0x73 | 0x0f);
+    }
+    let funct3 = (ins >> 13) & 0b111;
+    match ins & 0b11 {
+        // c.jal, c.j, c.beqz and c.bnez.
+        0b01 => matches!(funct3, 0b001 | 0b101 | 0b110 | 0b111),
+        // c.jr, c.jalr and c.ebreak, which are the only instructions in their group with rs2 = 0.
+        0b10 => funct3 == 0b100 && (ins >> 2) & 0x1f == 0,
+        _ => false,
+    }
+}
+
+/// A translated instruction.
+struct Op<T> {
+    pc: Address,         // The address of the instruction.
+    len: u32,            // The length of the instruction in bytes.
+    decoded: Decoded<T>, // The decoded instruction.
+}
+
+impl<T> Clone for Op<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Op<T> {}
+
+/// A translated basic block.
+struct Block<T> {
+    ops: Rc<[Op<T>]>,                     // The block's instructions.
+    links: [Option<(Address, usize)>; 2], // The blocks that this block has exited to, by address.
+}
+
+/// A CPU that translates basic blocks into threaded code and executes them.
+///
+/// Executing a block has exactly the same effect on the CPU's architectural state as fetching and dispatching each of
+/// its instructions in turn. If an instruction traps then execution stops at that instruction, just as it would for the
+/// interpreter. If it transfers control elsewhere, e.g., because the wrapped CPU handles the trap, then execution
+/// continues from the new program counter.
+///
+/// Writes to memory made through the `BlockCpu`, including the guest's own stores, discard every translation when they
+/// overlap translated code. If the write came from the block that's executing then the block stops after the write
+/// and execution resumes from a fresh translation, so self-modifying code works without the guest needing to execute
+/// `fence.i`. Writes made directly to the wrapped CPU bypass this, so call [`BlockCpu::flush`] after making them.
+///
+/// Blocks are translated from the address space that was current when they were translated, so every translation is
+/// also discarded by `sfence.vma`, by writes to `satp`, and whenever a trap, `mret` or `sret` changes the privilege
+/// level.
+pub struct BlockCpu<T> {
+    cpu: T,
+    decode: DecodeFn<BlockCpu<T>>,
+    blocks: Vec<Block<BlockCpu<T>>>,
+    index: HashMap<Address, usize>,
+    code: HashMap<Address, Box<[u64]>>,
+    code_range: (Address, Address),
+    last: Option<usize>,
+    flushed: bool,
+}
+
+impl<T> BlockCpu<T> {
+    /// Wraps a CPU with a translator that uses `decode` to decode instructions.
+    pub fn new(cpu: T, decode: DecodeFn<Self>) -> Self {
+        Self {
+            cpu,
+            decode,
+            blocks: Vec::new(),
+            index: HashMap::new(),
+            code: HashMap::new(),
+            code_range: (Address::MAX, 0),
+            last: None,
+            flushed: false,
+        }
+    }
+
+    /// Returns the wrapped CPU.
+    pub fn cpu(&self) -> &T {
+        &self.cpu
+    }
+
+    /// Returns the wrapped CPU. Call [`BlockCpu::flush`] after using it to modify code.
+    pub fn cpu_mut(&mut self) -> &mut T {
+        &mut self.cpu
+    }
+
+    /// Consumes the translator, returning the wrapped CPU.
+    pub fn into_inner(self) -> T {
+        self.cpu
+    }
+
+    /// Discards every translated block.
+    pub fn flush(&mut self) {
+        self.blocks.clear();
+        self.index.clear();
+        self.code.clear();
+        self.code_range = (Address::MAX, 0);
+        self.last = None;
+        self.flushed = true;
+    }
+
+    /// Executes the basic block at the next instruction, translating it first if necessary. Returns the number of
+    /// instructions executed. If the first instruction can't be fetched then the CPU takes an instruction access fault
+    /// and nothing is executed.
+    ///
+    /// Execution stops early if an instruction traps, if it transfers control out of the block, if it modifies
+    /// translated code, or if an interrupt is taken. Like [`Executor::run`](crate::executor::Executor::run), this takes
+    /// any pending interrupt before each instruction and ticks memory after it. If the CPU is waiting for an interrupt
+    /// then it ticks memory once without executing anything.
+    pub fn step_block(&mut self) -> u64
+    where
+        T: Fetch + Trap + Memory,
+    {
+        self.take_interrupt();
+        if self.cpu.is_waiting() {
+            self.cpu.tick();
+            return 0;
+        }
+        let mut pc = self.cpu.transfer();
+        let block = match self.find_block(pc) {
+            Ok(block) => block,
+            Err(address) => {
+                self.handle_trap(TrapCause::InstructionAccessFault(address));
+                self.cpu.tick();
+                return 0;
+            }
+        };
+        self.last = Some(block);
+        self.flushed = false;
+        let ops = Rc::clone(&self.blocks[block].ops);
+        let mut count = 0;
+        for (i, op) in ops.iter().enumerate() {
+            if i > 0 {
+                if self.take_interrupt().is_some() {
+                    // Control went to the trap handler.
+                    break;
+                }
+                pc = self.cpu.transfer();
+                if pc != op.pc {
+                    // Control left the block.
+                    break;
+                }
+            }
+            self.cpu.set_next_pc(pc.wrapping_add(op.len));
+            op.decoded.execute(self);
+            self.cpu.tick();
+            count += 1;
+            if self.cpu.is_trapped() || self.flushed {
+                break;
+            }
+        }
+        count
+    }
+
+    /// Executes blocks until the CPU traps. Returns the number of instructions executed. A CPU that's waiting for an
+    /// interrupt keeps ticking memory until one arrives.
+    pub fn run(&mut self) -> u64
+    where
+        T: Fetch + Trap + Memory,
+    {
+        let mut count = 0;
+        while !self.cpu.is_trapped() {
+            count += self.step_block();
+        }
+        count
+    }
+
+    // Finds the block that starts at the given address, translating it if there isn't one, and links it to the block
+    // that was executed before it.
+    fn find_block(&mut self, pc: Address) -> MemoryResult<usize>
+    where
+        T: Fetch,
+    {
+        // Follow a link from the previous block if there is one.
+        let last = self.last.filter(|&last| last < self.blocks.len());
+        if let Some(last) = last {
+            for (address, block) in self.blocks[last].links.iter().flatten() {
+                if *address == pc {
+                    return Ok(*block);
+                }
+            }
+        }
+
+        let block = match self.index.get(&pc) {
+            Some(&block) => block,
+            None => self.translate(pc)?,
+        };
+
+        // Link the previous block to this one, replacing its oldest link if it already has two.
+        if let Some(last) = last {
+            let links = &mut self.blocks[last].links;
+            links[1] = links[0];
+            links[0] = Some((pc, block));
+        }
+        Ok(block)
+    }
+
+    // Translates the basic block that starts at the given address.
+    fn translate(&mut self, start: Address) -> MemoryResult<usize>
+    where
+        T: Fetch,
+    {
+        let mut ops = Vec::new();
+        let mut pc = start;
+        while ops.len() < MAX_BLOCK_LEN {
+            let ins = match self.cpu.fetch32(pc) {
+                Ok(ins) => ins,
+                Err(address) if ops.is_empty() => return Err(address),
+                // End the block here, leaving the error to be reported if we ever get to this instruction.
+                Err(_) => break,
+            };
+            let (ins, len) = if (ins & 0b11) == 0b11 {
+                (ins, 4)
+            } else {
+                (ins & 0xffff, 2)
+            };
+            let decoded = (self.decode)(&mut Decoder::new(), ins);
+            ops.push(Op { pc, len, decoded });
+            pc = pc.wrapping_add(len);
+            if ends_block(ins) {
+                break;
+            }
+        }
+        self.mark_code(start, pc.wrapping_sub(start));
+        let block = self.blocks.len();
+        self.blocks.push(Block {
+            ops: ops.into(),
+            links: [None, None],
+        });
+        self.index.insert(start, block);
+        Ok(block)
+    }
+
+    // Records that the given range of addresses contains translated code.
+    fn mark_code(&mut self, start: Address, len: u32) {
+        for address in (0..len).map(|i| start.wrapping_add(i)) {
+            let page = self
+                .code
+                .entry(address >> PAGE_SHIFT)
+                .or_insert_with(|| vec![0; PAGE_SIZE / 64].into_boxed_slice());
+            let offset = address as usize % PAGE_SIZE;
+            page[offset / 64] |= 1 << (offset % 64);
+        }
+        let (lo, hi) = self.code_range;
+        self.code_range = (lo.min(start), hi.max(start.wrapping_add(len)));
+    }
+
+    // Runs `f` on the wrapped CPU, then discards every translation if it changed the privilege level, because fetches are
+    // translated and checked differently at each level.
+    fn flush_on_privilege_change(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Trap,
+    {
+        let privilege = self.cpu.privilege();
+        f(&mut self.cpu);
+        if self.cpu.privilege() != privilege {
+            self.flush();
+        }
+    }
+
+    // Discards every translation if the given range of addresses overlaps translated code.
+    fn invalidate(&mut self, start: Address, len: u32) {
+        let (lo, hi) = self.code_range;
+        if start >= hi || start.saturating_add(len) <= lo {
+            return;
+        }
+        let overlaps = (0..len).map(|i| start.wrapping_add(i)).any(|address| {
+            self.code.get(&(address >> PAGE_SHIFT)).is_some_and(|page| {
+                let offset = address as usize % PAGE_SIZE;
+                page[offset / 64] & (1 << (offset % 64)) != 0
+            })
+        });
+        if overlaps {
+            self.flush();
+        }
+    }
+}
+
+impl<T> Fetch for BlockCpu<T>
+where
+    T: Fetch,
+{
+    fn pc(&self) -> Address {
+        self.cpu.pc()
+    }
+
+    fn transfer(&mut self) -> Address {
+        self.cpu.transfer()
+    }
+
+    fn set_next_pc(&mut self, address: Address) {
+        self.cpu.set_next_pc(address)
+    }
+
+    fn fetch32(&self, address: Address) -> MemoryResult<u32> {
+        self.cpu.fetch32(address)
+    }
+
+    fn alignment(&self) -> Alignment {
+        self.cpu.alignment()
+    }
+}
+
+impl<T> Memory for BlockCpu<T>
+where
+    T: Memory,
+{
+    fn read8(&self, address: Address) -> MemoryResult<u8> {
+        self.cpu.read8(address)
+    }
+
+    fn read16(&self, address: Address) -> MemoryResult<u16> {
+        self.cpu.read16(address)
+    }
+
+    fn read32(&self, address: Address) -> MemoryResult<u32> {
+        self.cpu.read32(address)
+    }
+
+    fn write8(&mut self, address: Address, value: u8) -> MemoryResult<()> {
+        self.cpu.write8(address, value)?;
+        self.invalidate(address, 1);
+        Ok(())
+    }
+
+    fn write16(&mut self, address: Address, value: u16) -> MemoryResult<()> {
+        self.cpu.write16(address, value)?;
+        self.invalidate(address, 2);
+        Ok(())
+    }
+
+    fn write32(&mut self, address: Address, value: u32) -> MemoryResult<()> {
+        self.cpu.write32(address, value)?;
+        self.invalidate(address, 4);
+        Ok(())
+    }
+
+    fn read64(&self, address: Address) -> MemoryResult<u64> {
+        self.cpu.read64(address)
+    }
+
+    fn write64(&mut self, address: Address, value: u64) -> MemoryResult<()> {
+        self.cpu.write64(address, value)?;
+        self.invalidate(address, 8);
+        Ok(())
+    }
+
+    fn peek8(&self, address: Address) -> MemoryResult<u8> {
+        self.cpu.peek8(address)
+    }
+
+    fn tick(&mut self) {
+        self.cpu.tick()
+    }
+
+    fn next_event(&self) -> Option<u64> {
+        self.cpu.next_event()
+    }
+
+    fn advance(&mut self, ticks: u64) {
+        self.cpu.advance(ticks)
+    }
+}
+
+impl<T> Load for BlockCpu<T>
+where
+    T: Load,
+{
+    fn write_bytes(&mut self, start: Address, bytes: &[u8]) -> MemoryResult<()> {
+        self.cpu.write_bytes(start, bytes)?;
+        self.invalidate(start, bytes.len().min(u32::MAX as usize) as u32);
+        Ok(())
+    }
+}
+
+impl<T> XRegisters for BlockCpu<T>
+where
+    T: XRegisters,
+{
+    fn rx(&self, reg: Reg) -> u32 {
+        self.cpu.rx(reg)
+    }
+
+    fn wx(&mut self, reg: Reg, val: u32) {
+        self.cpu.wx(reg, val)
+    }
+}
+
+impl<T> FRegisters for BlockCpu<T>
+where
+    T: FRegisters,
+{
+    fn rf(&self, reg: Reg) -> f32 {
+        self.cpu.rf(reg)
+    }
+
+    fn rf_bits(&self, reg: Reg) -> u32 {
+        self.cpu.rf_bits(reg)
+    }
+
+    fn wf(&mut self, reg: Reg, val: f32) {
+        self.cpu.wf(reg, val)
+    }
+
+    fn frm(&self) -> u32 {
+        self.cpu.frm()
+    }
+
+    fn accrue_fflags(&mut self, flags: u32) {
+        self.cpu.accrue_fflags(flags)
+    }
+
+    fn is_fp_enabled(&self) -> bool {
+        self.cpu.is_fp_enabled()
+    }
+}
+
+impl<T> DRegisters for BlockCpu<T>
+where
+    T: DRegisters,
+{
+    fn rfd(&self, reg: Reg) -> f64 {
+        self.cpu.rfd(reg)
+    }
+
+    fn wfd(&mut self, reg: Reg, val: f64) {
+        self.cpu.wfd(reg, val)
+    }
+}
+
+impl<T> Csrs for BlockCpu<T>
+where
+    T: Csrs,
+{
+    fn rcsr(&self, csr: u32) -> CsrResult<u32> {
+        self.cpu.rcsr(csr)
+    }
+
+    fn wcsr(&mut self, csr: u32, val: u32) -> CsrResult<()> {
+        self.cpu.wcsr(csr, val)?;
+        if csr == csr::SATP {
+            // Fetches are translated through the new address space from now on.
+            self.flush();
+        }
+        Ok(())
+    }
+}
+
+impl<T> Reservation for BlockCpu<T>
+where
+    T: Reservation,
+{
+    fn reserve(&mut self, address: Address) {
+        self.cpu.reserve(address)
+    }
+
+    fn take_reservation(&mut self, address: Address) -> bool {
+        self.cpu.take_reservation(address)
+    }
+}
+
+impl<T> Trap for BlockCpu<T>
+where
+    T: Trap,
+{
+    fn trap(&self) -> Option<TrapRecord> {
+        self.cpu.trap()
+    }
+
+    fn clear_trap(&mut self) {
+        self.cpu.clear_trap()
+    }
+
+    fn handle_trap(&mut self, cause: TrapCause) {
+        self.flush_on_privilege_change(|cpu| cpu.handle_trap(cause))
+    }
+
+    fn privilege(&self) -> Privilege {
+        self.cpu.privilege()
+    }
+
+    fn vectors_traps(&self) -> bool {
+        self.cpu.vectors_traps()
+    }
+
+    fn handle_ecall(&mut self) {
+        self.flush_on_privilege_change(|cpu| cpu.handle_ecall())
+    }
+
+    fn handle_ebreak(&mut self, address: Address) {
+        self.flush_on_privilege_change(|cpu| cpu.handle_ebreak(address))
+    }
+
+    fn pending_interrupt(&self) -> Option<TrapCause> {
+        self.cpu.pending_interrupt()
+    }
+
+    fn handle_mret(&mut self) {
+        self.flush_on_privilege_change(|cpu| cpu.handle_mret())
+    }
+
+    fn handle_sret(&mut self) {
+        self.flush_on_privilege_change(|cpu| cpu.handle_sret())
+    }
+
+    fn handle_wfi(&mut self) {
+        self.cpu.handle_wfi()
+    }
+
+    fn handle_sfence_vma(&mut self, rs1: Reg, rs2: Reg) {
+        self.cpu.handle_sfence_vma(rs1, rs2);
+        self.flush();
+    }
+
+    fn is_waiting(&self) -> bool {
+        self.cpu.is_waiting()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BlockCpu;
+    use crate::{platforms::basic::*, reg::Reg, test_support::program, DispatchRv32ic};
+
+    // Runs a program with the interpreter and with translated blocks, and checks that they end in the same state.
+    fn run_both(address: u32, code: &[u32]) -> Rv32iCpu<BasicMem> {
+        let mut interpreted = Rv32iCpu::<BasicMem>::new();
+        program(&mut interpreted, address, code);
+        interpreted.set_next_pc(address);
+        while !interpreted.is_trapped() {
+            let ins = interpreted.fetch().unwrap();
+            interpreted.dispatch(ins);
+        }
+
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+        program(&mut cpu, address, code);
+        cpu.set_next_pc(address);
+        let mut translated = BlockCpu::new(cpu, |decoder, ins| decoder.dispatch(ins));
+        translated.run();
+        let translated = translated.into_inner();
+
+        assert!(interpreted == translated);
+        translated
+    }
+
+    #[test]
+    fn test_translated_blocks_give_the_same_state_as_the_interpreter() {
+        #[rustfmt::skip]
+        let cpu = run_both(0, &[
+            0x000055b7, // 0x00: lui  a1, 0x5
+            0x00a00613, // 0x04: li   a2, 10
+            0x00c5a023, // 0x08: sw   a2, 0(a1)
+            0x014000ef, // 0x0c: jal  0x20
+            0xfff60613, // 0x10: addi a2, a2, -1
+            0xfe061ae3, // 0x14: bnez a2, 0x08
+            0x00100073, // 0x18: ebreak
+            0x00000013, // 0x1c: nop
+            0x0005a683, // 0x20: lw   a3, 0(a1)
+            0x00d50533, // 0x24: add  a0, a0, a3
+            0x00008067, // 0x28: ret
+        ]);
+        assert_eq!(55, cpu.rx(Reg::new(10)));
+    }
+
+    #[test]
+    fn test_blocks_stop_at_the_instruction_that_traps() {
+        #[rustfmt::skip]
+        let cpu = run_both(0, &[
+            0x00100513, // 0x00: li a0, 1
+            0x000105b7, // 0x04: lui a1, 0x10
+            0x0005a583, // 0x08: lw a1, 0(a1)
+            0x00200513, // 0x0c: li a0, 2
+        ]);
+        assert_eq!(Some(TrapCause::LoadAccessFault(0x10000)), cpu.trap_cause());
+        assert_eq!(1, cpu.rx(Reg::new(10)));
+        assert_eq!(8, cpu.pc());
+    }
+
+    #[test]
+    fn test_self_modifying_code_is_retranslated() {
+        // Overwrite the instruction after the store, which is in the same block, with "li a0, 2".
+        #[rustfmt::skip]
+        let cpu = run_both(0x4000, &[
+            0x00004637, // 0x4000: lui  a2, 0x4
+            0x002006b7, // 0x4004: lui  a3, 0x200
+            0x51368693, // 0x4008: addi a3, a3, 0x513
+            0x00d62823, // 0x400c: sw   a3, 16(a2)
+            0x00100513, // 0x4010: li   a0, 1
+            0x00100073, // 0x4014: ebreak
+        ]);
+        assert_eq!(2, cpu.rx(Reg::new(10)));
+    }
+
+    #[test]
+    fn test_changing_address_space_or_privilege_level_discards_translations() {
+        use crate::{backends::cpus::rv32ipriv::Rv32iPrivCpu, csr, Csrs};
+
+        let mut cpu = BlockCpu::new(Rv32iPrivCpu::<BasicMem>::new(), |decoder, ins| {
+            decoder.dispatch(ins)
+        });
+        program(&mut cpu, 0x1000, &[0x00000801]); // VA 0x0000_0000 -> the table at 0x2000.
+        program(&mut cpu, 0x200c, &[0x0000144b]); // VA 0x3000 -> PA 0x5000, R X A.
+        program(&mut cpu, 0x3000, &[0x00100513, 0x0000006f]); // addi a0, zero, 1; j .
+        program(&mut cpu, 0x5000, &[0x00200513, 0x0000006f]); // addi a0, zero, 2; j .
+        let run_at = |cpu: &mut BlockCpu<Rv32iPrivCpu<BasicMem>>, pc| {
+            cpu.set_next_pc(pc);
+            cpu.step_block();
+            cpu.rx(Reg::new(10))
+        };
+
+        // Drop to S-mode, with M-mode traps going to 0x3000.
+        cpu.wcsr(csr::MTVEC, 0x3000).unwrap();
+        cpu.wcsr(csr::MSTATUS, 1 << 11).unwrap();
+        cpu.handle_mret();
+        assert_eq!(1, run_at(&mut cpu, 0x3000));
+        cpu.wcsr(csr::SATP, 0x8000_0001).unwrap();
+        assert_eq!(2, run_at(&mut cpu, 0x3000));
+
+        // Remap VA 0x3000 -> PA 0x3000 behind the cache's back, then tell it with sfence.vma.
+        cpu.cpu_mut()
+            .write_bytes(0x200c, &0x00000c4bu32.to_le_bytes())
+            .unwrap();
+        cpu.handle_sfence_vma(Reg::ZERO, Reg::ZERO);
+        assert_eq!(1, run_at(&mut cpu, 0x3000));
+
+        // Map it back, then trap to M-mode, which doesn't translate addresses.
+        cpu.cpu_mut()
+            .write_bytes(0x200c, &0x0000144bu32.to_le_bytes())
+            .unwrap();
+        cpu.handle_sfence_vma(Reg::ZERO, Reg::ZERO);
+        assert_eq!(2, run_at(&mut cpu, 0x3000));
+        cpu.handle_trap(TrapCause::Breakpoint(0x3000));
+        cpu.clear_trap();
+        assert_eq!(1, run_at(&mut cpu, 0x3000));
+    }
+
+    #[test]
+    fn test_interrupts_are_taken_between_instructions_and_memory_is_ticked() {
+        use crate::{test_support::timer_cpu, DispatchRv32iZicsrPriv};
+
+        let code = [0x0000006f]; // 0x14: j 0x14
+        let mut cpu = BlockCpu::new(timer_cpu(20, &code), |decoder, ins| {
+            DispatchRv32iZicsrPriv::dispatch(decoder, ins)
+        });
+        let executed = cpu.run();
+        assert_eq!(Some(TrapCause::Breakpoint(0x108)), cpu.trap_cause());
+        assert_eq!(23, executed);
+        assert_eq!(0x8000_0007, cpu.rx(Reg::new(10))); // mcause
+        assert_eq!(0x14, cpu.rx(Reg::new(11))); // mepc
+
+        // A CPU that's waiting for an interrupt executes nothing, but memory keeps time.
+        #[rustfmt::skip]
+        let code = [
+            0x10500073, // 0x14: wfi
+            0xffdff06f, // 0x18: j 0x14
+        ];
+        let mut cpu = BlockCpu::new(timer_cpu(100, &code), |decoder, ins| {
+            DispatchRv32iZicsrPriv::dispatch(decoder, ins)
+        });
+        let executed = cpu.run();
+        assert_eq!(Some(TrapCause::Breakpoint(0x108)), cpu.trap_cause());
+        assert_eq!(9, executed);
+        assert_eq!(0x8000_0007, cpu.rx(Reg::new(10))); // mcause
+        assert_eq!(0x18, cpu.rx(Reg::new(11))); // mepc
+        assert_eq!(Ok(103), cpu.read32(0x0200_bff8)); // mtime
+    }
+
+    #[test]
+    fn test_fetch_errors_are_instruction_access_faults() {
+        use crate::{backends::cpus::rv32izicsr::Rv32iZicsrCpu, csr, Csrs, DispatchRv32iZicsrPriv};
+
+        let mut cpu = BlockCpu::new(Rv32iZicsrCpu::<BasicMem>::new(), |decoder, ins| {
+            DispatchRv32iZicsrPriv::dispatch(decoder, ins)
+        });
+        cpu.wcsr(csr::MTVEC, 0x100).unwrap();
+        cpu.set_next_pc(0x9000);
+        assert_eq!(0, cpu.step_block());
+        assert_eq!(
+            Some(TrapCause::InstructionAccessFault(0x9000)),
+            cpu.trap_cause()
+        );
+        assert_eq!(Ok(1), cpu.rcsr(csr::MCAUSE));
+        assert_eq!(Ok(0x9000), cpu.rcsr(csr::MEPC));
+        assert_eq!(0x100, cpu.transfer());
+    }
+}
@@ -41,6 +41,108 @@ pub trait Fetch {
 
     /// Fetches a 32-bit word from memory.
     fn fetch32(&self, address: Address) -> MemoryResult<u32>;
+
+    /// Returns the CPU's alignment policy, which determines what happens when an instruction uses a misaligned
+    /// address.
+    fn alignment(&self) -> Alignment {
+        Alignment::default()
+    }
+}
+
+/// The alignment that a CPU requires of instruction addresses, i.e., IALIGN.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InstructionAlignment {
+    /// Instructions are 4-byte aligned, as they are on CPUs without the 'C' extension. Jumps and taken branches to
+    /// addresses that aren't a multiple of 4 raise an instruction-address-misaligned exception.
+    Word,
+    /// Instructions are 2-byte aligned, as they are on CPUs with the 'C' extension. Jumps and branches can't encode an
+    /// odd target, so they never raise an instruction-address-misaligned exception.
+    #[default]
+    HalfWord,
+}
+
+impl InstructionAlignment {
+    /// Returns true if the given address is a valid target for a jump or a branch.
+    #[inline]
+    pub fn is_aligned(self, address: Address) -> bool {
+        match self {
+            InstructionAlignment::Word => address & 3 == 0,
+            InstructionAlignment::HalfWord => address & 1 == 0,
+        }
+    }
+}
+
+/// What a CPU does when a load or a store isn't naturally aligned.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MisalignedAccess {
+    /// Raise a load-address-misaligned or a store-address-misaligned exception.
+    #[default]
+    Trap,
+    /// Perform the access one byte at a time, as some cores do in hardware. A misaligned store that faults part way
+    /// through may have written some of its bytes.
+    Emulate,
+}
+
+/// A CPU's alignment policy.
+///
+/// Atomic memory operations always trap if they're misaligned, whatever the policy.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Alignment {
+    /// The alignment required of jump and branch targets.
+    pub instructions: InstructionAlignment,
+    /// What happens on a misaligned load or store.
+    pub data: MisalignedAccess,
+}
+
+impl Alignment {
+    /// The alignment policy required by the spec for a CPU without the 'C' extension.
+    pub const RV32I: Alignment = Alignment {
+        instructions: InstructionAlignment::Word,
+        data: MisalignedAccess::Trap,
+    };
+
+    /// The alignment policy required by the spec for a CPU with the 'C' extension.
+    pub const RV32IC: Alignment = Alignment {
+        instructions: InstructionAlignment::HalfWord,
+        data: MisalignedAccess::Trap,
+    };
+
+    /// Returns this policy, but with misaligned loads and stores emulated rather than trapped.
+    pub fn emulating_misaligned_data(self) -> Self {
+        Self {
+            data: MisalignedAccess::Emulate,
+            ..self
+        }
+    }
+}
+
+/// A privilege level, i.e., the mode that a hart is running in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Privilege {
+    /// User mode, for applications.
+    User = 0,
+    /// Supervisor mode, for operating systems.
+    Supervisor = 1,
+    /// Machine mode, which has full access to the hart. Harts start in machine mode.
+    #[default]
+    Machine = 3,
+}
+
+impl Privilege {
+    /// Returns the privilege level encoded as it is in fields such as `mstatus.MPP`. The reserved encoding, 2, is
+    /// treated as user mode.
+    pub fn from_bits(bits: u32) -> Self {
+        match bits & 3 {
+            3 => Privilege::Machine,
+            1 => Privilege::Supervisor,
+            _ => Privilege::User,
+        }
+    }
+
+    /// Returns the encoding of the privilege level.
+    pub fn bits(self) -> u32 {
+        self as u32
+    }
 }
 
 /// Provides access to the base RV32I integer registers.
@@ -52,11 +154,94 @@ pub trait XRegisters {
     fn wx(&mut self, reg: Reg, val: u32);
 }
 
+/// Tracks the reservation set used by the load-reserved and store-conditional instructions of the 'A' extension.
+pub trait Reservation {
+    /// Registers a reservation on the word at the given address, replacing any existing reservation.
+    fn reserve(&mut self, address: Address);
+
+    /// Returns true if there is a reservation on the word at the given address. Any reservation is invalidated.
+    fn take_reservation(&mut self, address: Address) -> bool;
+}
+
 /// Provides access to the single-precision floating point registers used by the 'F' extension.
 pub trait FRegisters {
     /// Returns the value in the given floating point register.
     fn rf(&self, reg: Reg) -> f32;
 
+    /// Returns the low 32 bits of the given floating point register as they are, without NaN-unboxing them. This is
+    /// for instructions such as `fsw` and `fmv.x.w` that move bit patterns rather than single-precision values.
+    fn rf_bits(&self, reg: Reg) -> u32;
+
     /// Writes a value to the given floating point register.
     fn wf(&mut self, reg: Reg, val: f32);
+
+    /// Returns the dynamic rounding mode, i.e., the `frm` field of `fcsr`.
+    fn frm(&self) -> u32;
+
+    /// Accrues floating point exception flags, i.e., ORs them into the `fflags` field of `fcsr`.
+    fn accrue_fflags(&mut self, flags: u32);
+
+    /// Returns true if floating point instructions are enabled, e.g., because `mstatus.FS` is not Off. Floating point
+    /// instructions are illegal when they are disabled.
+    fn is_fp_enabled(&self) -> bool;
+}
+
+/// Provides access to the double-precision floating point registers used by the 'D' extension.
+///
+/// The 'D' extension widens the floating point registers to 64 bits. A CPU that implements both [`FRegisters`] and
+/// `DRegisters` must NaN-box single-precision values, e.g., by storing them with [`nan_box`] and retrieving them with
+/// [`nan_unbox`].
+pub trait DRegisters {
+    /// Returns the value in the given floating point register.
+    fn rfd(&self, reg: Reg) -> f64;
+
+    /// Writes a value to the given floating point register.
+    fn wfd(&mut self, reg: Reg, val: f64);
+}
+
+/// NaN-boxes a single-precision value so that it can be stored in a 64-bit floating point register.
+#[inline]
+pub fn nan_box(val: f32) -> u64 {
+    0xffff_ffff_0000_0000 | u64::from(val.to_bits())
+}
+
+/// Retrieves a single-precision value from a 64-bit floating point register. Values that aren't correctly NaN-boxed are
+/// treated as the canonical NaN.
+#[inline]
+pub fn nan_unbox(bits: u64) -> f32 {
+    if bits >> 32 == 0xffff_ffff {
+        f32::from_bits(bits as u32)
+    } else {
+        f32::from_bits(0x7fc0_0000)
+    }
+}
+
+/// The result of a CSR access. The error variant returns the CSR that caused the error.
+pub type CsrResult<T> = Result<T, u32>;
+
+/// Provides access to the control and status registers used by the 'Zicsr' extension.
+pub trait Csrs {
+    /// Returns the value in the given CSR, or an error if the CSR doesn't exist or can't be read.
+    fn rcsr(&self, csr: u32) -> CsrResult<u32>;
+
+    /// Writes a value to the given CSR, or returns an error if the CSR doesn't exist or can't be written.
+    fn wcsr(&mut self, csr: u32, val: u32) -> CsrResult<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{nan_box, nan_unbox};
+
+    #[test]
+    fn test_single_precision_values_are_nan_boxed() {
+        assert_eq!(0xffff_ffff_3f80_0000, nan_box(1.0));
+        assert_eq!(1.0, nan_unbox(nan_box(1.0)));
+        assert_eq!(0x8000_0000, nan_unbox(nan_box(-0.0)).to_bits());
+    }
+
+    #[test]
+    fn test_values_that_are_not_nan_boxed_are_the_canonical_nan() {
+        assert_eq!(0x7fc0_0000, nan_unbox(1.0f64.to_bits()).to_bits());
+        assert_eq!(0x7fc0_0000, nan_unbox(0x7fff_ffff_3f80_0000).to_bits());
+    }
 }
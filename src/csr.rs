@@ -0,0 +1,112 @@
+//! Control and status registers.
+
+// User-level floating point CSRs.
+pub const FFLAGS: u32 = 0x001;
+pub const FRM: u32 = 0x002;
+pub const FCSR: u32 = 0x003;
+
+// User-level counters and timers.
+pub const CYCLE: u32 = 0xc00;
+pub const TIME: u32 = 0xc01;
+pub const INSTRET: u32 = 0xc02;
+pub const CYCLEH: u32 = 0xc80;
+pub const TIMEH: u32 = 0xc81;
+pub const INSTRETH: u32 = 0xc82;
+
+// Supervisor-level CSRs.
+pub const SSTATUS: u32 = 0x100;
+pub const SIE: u32 = 0x104;
+pub const STVEC: u32 = 0x105;
+pub const SCOUNTEREN: u32 = 0x106;
+pub const SSCRATCH: u32 = 0x140;
+pub const SEPC: u32 = 0x141;
+pub const SCAUSE: u32 = 0x142;
+pub const STVAL: u32 = 0x143;
+pub const SIP: u32 = 0x144;
+pub const SATP: u32 = 0x180;
+
+// Machine-level CSRs.
+pub const MVENDORID: u32 = 0xf11;
+pub const MARCHID: u32 = 0xf12;
+pub const MIMPID: u32 = 0xf13;
+pub const MHARTID: u32 = 0xf14;
+pub const MSTATUS: u32 = 0x300;
+pub const MISA: u32 = 0x301;
+pub const MEDELEG: u32 = 0x302;
+pub const MIDELEG: u32 = 0x303;
+pub const MIE: u32 = 0x304;
+pub const MTVEC: u32 = 0x305;
+pub const MCOUNTEREN: u32 = 0x306;
+pub const MSTATUSH: u32 = 0x310;
+pub const MSCRATCH: u32 = 0x340;
+pub const MEPC: u32 = 0x341;
+pub const MCAUSE: u32 = 0x342;
+pub const MTVAL: u32 = 0x343;
+pub const MIP: u32 = 0x344;
+pub const MCYCLE: u32 = 0xb00;
+pub const MINSTRET: u32 = 0xb02;
+pub const MCYCLEH: u32 = 0xb80;
+pub const MINSTRETH: u32 = 0xb82;
+
+const CSR_NAMES: &[(u32, &str)] = &[
+    (FFLAGS, "fflags"),
+    (FRM, "frm"),
+    (FCSR, "fcsr"),
+    (CYCLE, "cycle"),
+    (TIME, "time"),
+    (INSTRET, "instret"),
+    (CYCLEH, "cycleh"),
+    (TIMEH, "timeh"),
+    (INSTRETH, "instreth"),
+    (SSTATUS, "sstatus"),
+    (SIE, "sie"),
+    (STVEC, "stvec"),
+    (SCOUNTEREN, "scounteren"),
+    (SSCRATCH, "sscratch"),
+    (SEPC, "sepc"),
+    (SCAUSE, "scause"),
+    (STVAL, "stval"),
+    (SIP, "sip"),
+    (SATP, "satp"),
+    (MVENDORID, "mvendorid"),
+    (MARCHID, "marchid"),
+    (MIMPID, "mimpid"),
+    (MHARTID, "mhartid"),
+    (MSTATUS, "mstatus"),
+    (MISA, "misa"),
+    (MEDELEG, "medeleg"),
+    (MIDELEG, "mideleg"),
+    (MIE, "mie"),
+    (MTVEC, "mtvec"),
+    (MCOUNTEREN, "mcounteren"),
+    (MSTATUSH, "mstatush"),
+    (MSCRATCH, "mscratch"),
+    (MEPC, "mepc"),
+    (MCAUSE, "mcause"),
+    (MTVAL, "mtval"),
+    (MIP, "mip"),
+    (MCYCLE, "mcycle"),
+    (MINSTRET, "minstret"),
+    (MCYCLEH, "mcycleh"),
+    (MINSTRETH, "minstreth"),
+];
+
+/// Returns the symbolic name of a CSR, if it has one.
+pub fn name(csr: u32) -> Option<&'static str> {
+    CSR_NAMES
+        .iter()
+        .find(|(number, _)| *number == csr)
+        .map(|(_, name)| *name)
+}
+
+/// Returns true if the CSR is read-only, i.e., if the top two bits of its address are set.
+#[inline]
+pub fn is_read_only(csr: u32) -> bool {
+    (csr >> 10) & 3 == 3
+}
+
+/// Returns the lowest privilege level that can access the CSR, i.e., 0 for U-mode, 1 for S-mode and 3 for M-mode.
+#[inline]
+pub fn privilege(csr: u32) -> u32 {
+    (csr >> 8) & 3
+}
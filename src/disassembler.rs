@@ -1,24 +1,139 @@
 //! A disassembler.
 
 use crate::{
-    handlers::{HandleRv32c, HandleRv32f, HandleRv32i, HandleRv32m},
+    csr,
+    elf::SymbolTable,
+    handlers::{
+        HandlePrivileged, HandleRv32a, HandleRv32c, HandleRv32d, HandleRv32f, HandleRv32i,
+        HandleRv32m, HandleZicsr, HandleZifencei,
+    },
+    memory::Address,
     reg::Reg,
 };
 
-pub struct Disassembler;
+/// How the disassembler renders instructions.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mode {
+    /// Every instruction is shown in its canonical form, e.g., `addi zero, zero, 0`. This is useful for checking what
+    /// the decoder produced.
+    #[default]
+    Raw,
+    /// Instructions are shown as pseudo-instructions where one applies, e.g., `nop`, using the same aliasing rules as
+    /// GNU objdump. Floating point rounding modes are named, and omitted when they're dynamic.
+    Pseudo,
+}
+
+/// Disassembles instructions into assembly language.
+///
+/// By default, branch and jump targets are shown as offsets. If the disassembler is told the address of each
+/// instruction with [`Disassembler::set_pc`] then it shows them as absolute addresses instead, along with the nearest
+/// symbol if it was given a symbol table. It also annotates `auipc` + `addi` and `auipc` + `jalr` pairs with the address
+/// that they compute.
+#[derive(Default)]
+pub struct Disassembler {
+    pc: Option<Address>,                    // The address of the current instruction.
+    symbols: SymbolTable,                   // Symbols for resolving addresses.
+    auipc: Option<(Address, Reg, Address)>, // The pc, rd and result of the last auipc.
+    mode: Mode,                             // How instructions are rendered.
+}
+
+impl Disassembler {
+    /// Creates a disassembler that shows branch and jump targets as offsets.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates a disassembler that shows addresses with the nearest symbol from the given symbol table.
+    pub fn with_symbols(symbols: SymbolTable) -> Self {
+        Self {
+            symbols,
+            ..Default::default()
+        }
+    }
+
+    /// Sets how instructions are rendered.
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+
+    /// Sets the address of the next instruction to be disassembled.
+    pub fn set_pc(&mut self, pc: Address) {
+        self.pc = Some(pc);
+    }
+
+    /// Returns a label line, e.g., `00000100 <main>:`, if a symbol starts at the given address.
+    pub fn label(&self, address: Address) -> Option<String> {
+        match self.symbols.lookup(address) {
+            Some((symbol, 0)) => Some(format!("{:08x} <{}>:", address, symbol.name)),
+            _ => None,
+        }
+    }
+
+    // Formats an address, followed by the nearest symbol if there is one.
+    fn address(&self, address: Address) -> String {
+        match self.symbols.lookup(address) {
+            Some((symbol, 0)) => format!("0x{:x} <{}>", address, symbol.name),
+            Some((symbol, offset)) => format!("0x{:x} <{}+0x{:x}>", address, symbol.name, offset),
+            None => format!("0x{:x}", address),
+        }
+    }
 
-const ABI_NAMES: &[&str] = &[
+    // Formats the target of a branch or jump, which is an absolute address if the pc is known.
+    fn target(&self, offset: u32) -> String {
+        match self.pc {
+            Some(pc) => self.address(pc.wrapping_add(offset)),
+            None => format!("{}", offset as i32),
+        }
+    }
+
+    fn is_pseudo(&self) -> bool {
+        self.mode == Mode::Pseudo
+    }
+
+    // Formats the rounding mode operand of a floating point instruction.
+    fn rm(&self, rm: u32) -> String {
+        match (self.mode, rm) {
+            (Mode::Raw, _) => format!(", {}", rm),
+            (Mode::Pseudo, 7) => String::new(),
+            (Mode::Pseudo, _) => format!(", {}", RM_NAMES.get(rm as usize).unwrap_or(&"invalid")),
+        }
+    }
+
+    // Formats the rounding mode operand of a conversion that's always exact, which objdump omits when it's the default.
+    fn exact_rm(&self, rm: u32) -> String {
+        match (self.mode, rm) {
+            (Mode::Pseudo, 0 | 7) => String::new(),
+            _ => self.rm(rm),
+        }
+    }
+
+    // Formats the address computed by an instruction that follows an auipc, if it uses the auipc's result.
+    fn annotation(&self, rs1: Reg, offset: u32) -> String {
+        match (self.pc, self.auipc) {
+            (Some(pc), Some((auipc_pc, rd, result)))
+                if pc == auipc_pc.wrapping_add(4) && rd == rs1 && rd != Reg::ZERO =>
+            {
+                format!(" # {}", self.address(result.wrapping_add(offset)))
+            }
+            _ => String::new(),
+        }
+    }
+}
+
+pub(crate) const ABI_NAMES: &[&str] = &[
     "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
     "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
     "t5", "t6",
 ];
 
-const FABI_NAMES: &[&str] = &[
+pub(crate) const FABI_NAMES: &[&str] = &[
     "ft0", "ft1", "ft2", "ft3", "ft4", "ft5", "ft6", "ft7", "fs0", "fs1", "fa0", "fa1", "fa2",
     "fa3", "fa4", "fa5", "fa6", "fa7", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7", "fs8", "fs9",
     "fs10", "fs11", "ft8", "ft9", "ft10", "ft11",
 ];
 
+const RM_NAMES: &[&str] = &["rne", "rtz", "rdn", "rup", "rmm"];
+
 fn abi(reg: Reg) -> &'static str {
     let r: usize = Into::into(reg);
     match r {
@@ -35,6 +150,13 @@ fn fabi(reg: Reg) -> &'static str {
     }
 }
 
+fn csr_name(csr: u32) -> String {
+    match csr::name(csr) {
+        Some(name) => name.to_string(),
+        None => format!("0x{:03x}", csr),
+    }
+}
+
 impl HandleRv32i for Disassembler {
     type Item = String;
 
@@ -43,27 +165,49 @@ impl HandleRv32i for Disassembler {
     }
 
     fn beq(&mut self, rs1: Reg, rs2: Reg, bimm: u32) -> Self::Item {
-        format!("beq\t{}, {}, {}", abi(rs1), abi(rs2), bimm as i32)
+        if self.is_pseudo() && rs2 == Reg::ZERO {
+            return format!("beqz\t{}, {}", abi(rs1), self.target(bimm));
+        }
+        format!("beq\t{}, {}, {}", abi(rs1), abi(rs2), self.target(bimm))
     }
 
     fn bne(&mut self, rs1: Reg, rs2: Reg, bimm: u32) -> Self::Item {
-        format!("bne\t{}, {}, {}", abi(rs1), abi(rs2), bimm as i32)
+        if self.is_pseudo() && rs2 == Reg::ZERO {
+            return format!("bnez\t{}, {}", abi(rs1), self.target(bimm));
+        }
+        format!("bne\t{}, {}, {}", abi(rs1), abi(rs2), self.target(bimm))
     }
 
     fn blt(&mut self, rs1: Reg, rs2: Reg, bimm: u32) -> Self::Item {
-        format!("blt\t{}, {}, {}", abi(rs1), abi(rs2), bimm as i32)
+        if self.is_pseudo() {
+            if rs2 == Reg::ZERO {
+                return format!("bltz\t{}, {}", abi(rs1), self.target(bimm));
+            }
+            if rs1 == Reg::ZERO {
+                return format!("bgtz\t{}, {}", abi(rs2), self.target(bimm));
+            }
+        }
+        format!("blt\t{}, {}, {}", abi(rs1), abi(rs2), self.target(bimm))
     }
 
     fn bge(&mut self, rs1: Reg, rs2: Reg, bimm: u32) -> Self::Item {
-        format!("bge\t{}, {}, {}", abi(rs1), abi(rs2), bimm as i32)
+        if self.is_pseudo() {
+            if rs2 == Reg::ZERO {
+                return format!("bgez\t{}, {}", abi(rs1), self.target(bimm));
+            }
+            if rs1 == Reg::ZERO {
+                return format!("blez\t{}, {}", abi(rs2), self.target(bimm));
+            }
+        }
+        format!("bge\t{}, {}, {}", abi(rs1), abi(rs2), self.target(bimm))
     }
 
     fn bltu(&mut self, rs1: Reg, rs2: Reg, bimm: u32) -> Self::Item {
-        format!("bltu\t{}, {}, {}", abi(rs1), abi(rs2), bimm as i32)
+        format!("bltu\t{}, {}, {}", abi(rs1), abi(rs2), self.target(bimm))
     }
 
     fn bgeu(&mut self, rs1: Reg, rs2: Reg, bimm: u32) -> Self::Item {
-        format!("bgeu\t{}, {}, {}", abi(rs1), abi(rs2), bimm as i32)
+        format!("bgeu\t{}, {}, {}", abi(rs1), abi(rs2), self.target(bimm))
     }
 
     fn lb(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
@@ -87,7 +231,23 @@ impl HandleRv32i for Disassembler {
     }
 
     fn addi(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
-        format!("addi\t{}, {}, {}", abi(rd), abi(rs1), iimm as i32)
+        let annotation = self.annotation(rs1, iimm);
+        if self.is_pseudo() {
+            if rd == Reg::ZERO && rs1 == Reg::ZERO && iimm == 0 {
+                return "nop".to_string();
+            } else if rs1 == Reg::ZERO {
+                return format!("li\t{}, {}", abi(rd), iimm as i32);
+            } else if iimm == 0 {
+                return format!("mv\t{}, {}{}", abi(rd), abi(rs1), annotation);
+            }
+        }
+        format!(
+            "addi\t{}, {}, {}{}",
+            abi(rd),
+            abi(rs1),
+            iimm as i32,
+            annotation
+        )
     }
 
     fn slti(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
@@ -95,10 +255,16 @@ impl HandleRv32i for Disassembler {
     }
 
     fn sltiu(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        if self.is_pseudo() && iimm == 1 {
+            return format!("seqz\t{}, {}", abi(rd), abi(rs1));
+        }
         format!("sltiu\t{}, {}, {}", abi(rd), abi(rs1), iimm as i32)
     }
 
     fn xori(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        if self.is_pseudo() && iimm as i32 == -1 {
+            return format!("not\t{}, {}", abi(rd), abi(rs1));
+        }
         format!("xori\t{}, {}, {}", abi(rd), abi(rs1), iimm as i32)
     }
 
@@ -107,11 +273,32 @@ impl HandleRv32i for Disassembler {
     }
 
     fn andi(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        if self.is_pseudo() && iimm == 0xff {
+            return format!("zext.b\t{}, {}", abi(rd), abi(rs1));
+        }
         format!("andi\t{}, {}, {}", abi(rd), abi(rs1), iimm as i32)
     }
 
     fn jalr(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
-        format!("jalr\t{}, {}, {}", abi(rd), abi(rs1), iimm as i32)
+        let annotation = self.annotation(rs1, iimm);
+        if self.is_pseudo() {
+            let offset = iimm as i32;
+            return match (rd, offset) {
+                (Reg::ZERO, 0) if rs1 == Reg::RA => "ret".to_string(),
+                (Reg::ZERO, 0) => format!("jr\t{}{}", abi(rs1), annotation),
+                (Reg::ZERO, _) => format!("jr\t{}({}){}", offset, abi(rs1), annotation),
+                (Reg::RA, 0) => format!("jalr\t{}{}", abi(rs1), annotation),
+                (Reg::RA, _) => format!("jalr\t{}({}){}", offset, abi(rs1), annotation),
+                _ => format!("jalr\t{}, {}({}){}", abi(rd), offset, abi(rs1), annotation),
+            };
+        }
+        format!(
+            "jalr\t{}, {}, {}{}",
+            abi(rd),
+            abi(rs1),
+            iimm as i32,
+            annotation
+        )
     }
 
     fn sb(&mut self, rs1: Reg, rs2: Reg, simm: u32) -> Self::Item {
@@ -127,6 +314,9 @@ impl HandleRv32i for Disassembler {
     }
 
     fn auipc(&mut self, rd: Reg, uimm: u32) -> Self::Item {
+        if let Some(pc) = self.pc {
+            self.auipc = Some((pc, rd, pc.wrapping_add(uimm)));
+        }
         format!("auipc\t{}, {}", abi(rd), (uimm as i32) >> 12)
     }
 
@@ -135,7 +325,14 @@ impl HandleRv32i for Disassembler {
     }
 
     fn jal(&mut self, rd: Reg, jimm: u32) -> Self::Item {
-        format!("jal\t{}, {}", abi(rd), jimm as i32)
+        if self.is_pseudo() {
+            match rd {
+                Reg::ZERO => return format!("j\t{}", self.target(jimm)),
+                Reg::RA => return format!("jal\t{}", self.target(jimm)),
+                _ => {}
+            }
+        }
+        format!("jal\t{}, {}", abi(rd), self.target(jimm))
     }
 
     fn add(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
@@ -143,6 +340,9 @@ impl HandleRv32i for Disassembler {
     }
 
     fn sub(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        if self.is_pseudo() && rs1 == Reg::ZERO {
+            return format!("neg\t{}, {}", abi(rd), abi(rs2));
+        }
         format!("sub\t{}, {}, {}", abi(rd), abi(rs1), abi(rs2))
     }
 
@@ -151,10 +351,20 @@ impl HandleRv32i for Disassembler {
     }
 
     fn slt(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        if self.is_pseudo() {
+            if rs2 == Reg::ZERO {
+                return format!("sltz\t{}, {}", abi(rd), abi(rs1));
+            } else if rs1 == Reg::ZERO {
+                return format!("sgtz\t{}, {}", abi(rd), abi(rs2));
+            }
+        }
         format!("slt\t{}, {}, {}", abi(rd), abi(rs1), abi(rs2))
     }
 
     fn sltu(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        if self.is_pseudo() && rs1 == Reg::ZERO {
+            return format!("snez\t{}, {}", abi(rd), abi(rs2));
+        }
         format!("sltu\t{}, {}, {}", abi(rd), abi(rs1), abi(rs2))
     }
 
@@ -239,6 +449,124 @@ impl HandleRv32m for Disassembler {
     }
 }
 
+/// Returns the suffix that shows the ordering bits of an atomic instruction.
+fn aqrl_suffix(aqrl: u32) -> &'static str {
+    match aqrl & 3 {
+        0b00 => "",
+        0b01 => ".rl",
+        0b10 => ".aq",
+        _ => ".aqrl",
+    }
+}
+
+impl HandleRv32a for Disassembler {
+    type Item = String;
+
+    fn lr_w(&mut self, rd: Reg, rs1: Reg, aqrl: u32) -> Self::Item {
+        format!("lr.w{}\t{}, ({})", aqrl_suffix(aqrl), abi(rd), abi(rs1))
+    }
+
+    fn sc_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        format!(
+            "sc.w{}\t{}, {}, ({})",
+            aqrl_suffix(aqrl),
+            abi(rd),
+            abi(rs2),
+            abi(rs1)
+        )
+    }
+
+    fn amoswap_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        format!(
+            "amoswap.w{}\t{}, {}, ({})",
+            aqrl_suffix(aqrl),
+            abi(rd),
+            abi(rs2),
+            abi(rs1)
+        )
+    }
+
+    fn amoadd_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        format!(
+            "amoadd.w{}\t{}, {}, ({})",
+            aqrl_suffix(aqrl),
+            abi(rd),
+            abi(rs2),
+            abi(rs1)
+        )
+    }
+
+    fn amoxor_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        format!(
+            "amoxor.w{}\t{}, {}, ({})",
+            aqrl_suffix(aqrl),
+            abi(rd),
+            abi(rs2),
+            abi(rs1)
+        )
+    }
+
+    fn amoand_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        format!(
+            "amoand.w{}\t{}, {}, ({})",
+            aqrl_suffix(aqrl),
+            abi(rd),
+            abi(rs2),
+            abi(rs1)
+        )
+    }
+
+    fn amoor_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        format!(
+            "amoor.w{}\t{}, {}, ({})",
+            aqrl_suffix(aqrl),
+            abi(rd),
+            abi(rs2),
+            abi(rs1)
+        )
+    }
+
+    fn amomin_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        format!(
+            "amomin.w{}\t{}, {}, ({})",
+            aqrl_suffix(aqrl),
+            abi(rd),
+            abi(rs2),
+            abi(rs1)
+        )
+    }
+
+    fn amomax_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        format!(
+            "amomax.w{}\t{}, {}, ({})",
+            aqrl_suffix(aqrl),
+            abi(rd),
+            abi(rs2),
+            abi(rs1)
+        )
+    }
+
+    fn amominu_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        format!(
+            "amominu.w{}\t{}, {}, ({})",
+            aqrl_suffix(aqrl),
+            abi(rd),
+            abi(rs2),
+            abi(rs1)
+        )
+    }
+
+    fn amomaxu_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        format!(
+            "amomaxu.w{}\t{}, {}, ({})",
+            aqrl_suffix(aqrl),
+            abi(rd),
+            abi(rs2),
+            abi(rs1)
+        )
+    }
+}
+
 impl HandleRv32c for Disassembler {
     type Item = String;
 
@@ -278,6 +606,9 @@ impl HandleRv32c for Disassembler {
     }
 
     fn c_nop(&mut self, _imm: u32) -> Self::Item {
+        if self.is_pseudo() {
+            return "nop".to_string();
+        }
         "c.nop".to_string()
     }
 
@@ -337,6 +668,9 @@ impl HandleRv32c for Disassembler {
 
     fn c_mv(&mut self, rd: Reg, rs2n0: Reg) -> Self::Item {
         // add rd, x0, rs2
+        if self.is_pseudo() {
+            return format!("mv\t{}, {}", abi(rd), abi(rs2n0));
+        }
         self.add(rd, Reg::ZERO, rs2n0)
     }
 
@@ -380,90 +714,114 @@ impl HandleRv32f for Disassembler {
     type Item = String;
 
     fn flw(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
-        format!("flw\t{}, {}({})", fabi(rd), iimm, fabi(rs1))
+        format!("flw\t{}, {}({})", fabi(rd), iimm as i32, abi(rs1))
     }
 
     fn fsw(&mut self, rs1: Reg, rs2: Reg, simm: u32) -> Self::Item {
-        format!("fsw\t{}, {}({})", fabi(rs2), simm, fabi(rs1))
+        format!("fsw\t{}, {}({})", fabi(rs2), simm as i32, abi(rs1))
     }
 
     fn fsqrt_s(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
-        format!("fsqrt.s {}, {}, {}", fabi(rd), fabi(rs1), rm)
+        format!("fsqrt.s {}, {}{}", fabi(rd), fabi(rs1), self.rm(rm))
     }
 
     fn fcvt_w_s(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
-        format!("fcvt.w.s {}, {}, {}", abi(rd), fabi(rs1), rm)
+        format!("fcvt.w.s {}, {}{}", abi(rd), fabi(rs1), self.rm(rm))
     }
 
     fn fcvt_wu_s(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
-        format!("fcvt.wu.s {}, {}, {}", abi(rd), fabi(rs1), rm)
+        format!("fcvt.wu.s {}, {}{}", abi(rd), fabi(rs1), self.rm(rm))
     }
 
     fn fcvt_s_w(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
-        format!("fcvt.s.w {}, {}, {}", fabi(rd), abi(rs1), rm)
+        format!("fcvt.s.w {}, {}{}", fabi(rd), abi(rs1), self.rm(rm))
     }
 
     fn fcvt_s_wu(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
-        format!("fcvt.s.wu {}, {}, {}", fabi(rd), abi(rs1), rm)
+        format!("fcvt.s.wu {}, {}{}", fabi(rd), abi(rs1), self.rm(rm))
     }
 
     fn fadd_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
-        format!("fadd.s {}, {}, {}, {}", fabi(rd), fabi(rs1), fabi(rs2), rm)
+        format!(
+            "fadd.s {}, {}, {}{}",
+            fabi(rd),
+            fabi(rs1),
+            fabi(rs2),
+            self.rm(rm)
+        )
     }
 
     fn fsub_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
-        format!("fsub.s {}, {}, {}, {}", fabi(rd), fabi(rs1), fabi(rs2), rm)
+        format!(
+            "fsub.s {}, {}, {}{}",
+            fabi(rd),
+            fabi(rs1),
+            fabi(rs2),
+            self.rm(rm)
+        )
     }
 
     fn fmul_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
-        format!("fmul.s {}, {}, {}, {}", fabi(rd), fabi(rs1), fabi(rs2), rm)
+        format!(
+            "fmul.s {}, {}, {}{}",
+            fabi(rd),
+            fabi(rs1),
+            fabi(rs2),
+            self.rm(rm)
+        )
     }
 
     fn fdiv_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
-        format!("fdiv.s {}, {}, {}, {}", fabi(rd), fabi(rs1), fabi(rs2), rm)
+        format!(
+            "fdiv.s {}, {}, {}{}",
+            fabi(rd),
+            fabi(rs1),
+            fabi(rs2),
+            self.rm(rm)
+        )
     }
 
     fn fmadd_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
         format!(
-            "fmadd.s {}, {}, {}, {}, {}",
+            "fmadd.s {}, {}, {}, {}{}",
             fabi(rd),
             fabi(rs1),
             fabi(rs2),
             fabi(rs3),
-            rm
+            self.rm(rm)
         )
     }
 
     fn fmsub_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
         format!(
-            "fmsub.s {}, {}, {}, {}, {}",
+            "fmsub.s {}, {}, {}, {}{}",
             fabi(rd),
             fabi(rs1),
             fabi(rs2),
             fabi(rs3),
-            rm
+            self.rm(rm)
         )
     }
 
     fn fnmsub_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
         format!(
-            "fnmsub.s {}, {}, {}, {}, {}",
+            "fnmsub.s {}, {}, {}, {}{}",
             fabi(rd),
             fabi(rs1),
             fabi(rs2),
             fabi(rs3),
-            rm
+            self.rm(rm)
         )
     }
 
     fn fnmadd_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
         format!(
-            "fnmadd.s {}, {}, {}, {}, {}",
+            "fnmadd.s {}, {}, {}, {}{}",
             fabi(rd),
             fabi(rs1),
             fabi(rs2),
             fabi(rs3),
-            rm
+            self.rm(rm)
         )
     }
 
@@ -480,6 +838,9 @@ impl HandleRv32f for Disassembler {
     }
 
     fn fsgnj_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        if self.is_pseudo() && rs1 == rs2 {
+            return format!("fmv.s {}, {}", fabi(rd), fabi(rs1));
+        }
         format!("fsgnj.s {}, {}, {}", fabi(rd), fabi(rs1), fabi(rs2))
     }
 
@@ -492,6 +853,9 @@ impl HandleRv32f for Disassembler {
     }
 
     fn fsgnjn_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        if self.is_pseudo() && rs1 == rs2 {
+            return format!("fneg.s {}, {}", fabi(rd), fabi(rs1));
+        }
         format!("fsgnjn.s {}, {}, {}", fabi(rd), fabi(rs1), fabi(rs2))
     }
 
@@ -504,10 +868,472 @@ impl HandleRv32f for Disassembler {
     }
 
     fn fsgnjx_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        if self.is_pseudo() && rs1 == rs2 {
+            return format!("fabs.s {}, {}", fabi(rd), fabi(rs1));
+        }
         format!("fsgnjx.s {}, {}, {}", fabi(rd), fabi(rs1), fabi(rs2))
     }
 
     fn feq_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
         format!("feq.s {}, {}, {}", abi(rd), fabi(rs1), fabi(rs2))
     }
+
+    fn c_flw(&mut self, rdp: Reg, rs1p: Reg, imm: u32) -> Self::Item {
+        // flw rdp, offset[6:2](rs1p)
+        self.flw(rdp, rs1p, imm)
+    }
+
+    fn c_fsw(&mut self, rs1p: Reg, rs2p: Reg, imm: u32) -> Self::Item {
+        // fsw rs2p, offset[6:2](rs1p)
+        self.fsw(rs1p, rs2p, imm)
+    }
+
+    fn c_flwsp(&mut self, rd: Reg, imm: u32) -> Self::Item {
+        // flw rd, offset[7:2](x2)
+        self.flw(rd, Reg::SP, imm)
+    }
+
+    fn c_fswsp(&mut self, rs2: Reg, imm: u32) -> Self::Item {
+        // fsw rs2, offset[7:2](x2)
+        self.fsw(Reg::SP, rs2, imm)
+    }
+}
+
+impl HandleRv32d for Disassembler {
+    type Item = String;
+
+    fn fld(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        format!("fld\t{}, {}({})", fabi(rd), iimm as i32, abi(rs1))
+    }
+
+    fn fsd(&mut self, rs1: Reg, rs2: Reg, simm: u32) -> Self::Item {
+        format!("fsd\t{}, {}({})", fabi(rs2), simm as i32, abi(rs1))
+    }
+
+    fn fsqrt_d(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        format!("fsqrt.d {}, {}{}", fabi(rd), fabi(rs1), self.rm(rm))
+    }
+
+    fn fcvt_s_d(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        format!("fcvt.s.d {}, {}{}", fabi(rd), fabi(rs1), self.rm(rm))
+    }
+
+    fn fcvt_d_s(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        format!("fcvt.d.s {}, {}{}", fabi(rd), fabi(rs1), self.exact_rm(rm))
+    }
+
+    fn fcvt_w_d(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        format!("fcvt.w.d {}, {}{}", abi(rd), fabi(rs1), self.rm(rm))
+    }
+
+    fn fcvt_wu_d(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        format!("fcvt.wu.d {}, {}{}", abi(rd), fabi(rs1), self.rm(rm))
+    }
+
+    fn fcvt_d_w(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        format!("fcvt.d.w {}, {}{}", fabi(rd), abi(rs1), self.exact_rm(rm))
+    }
+
+    fn fcvt_d_wu(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        format!("fcvt.d.wu {}, {}{}", fabi(rd), abi(rs1), self.exact_rm(rm))
+    }
+
+    fn fadd_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        format!(
+            "fadd.d {}, {}, {}{}",
+            fabi(rd),
+            fabi(rs1),
+            fabi(rs2),
+            self.rm(rm)
+        )
+    }
+
+    fn fsub_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        format!(
+            "fsub.d {}, {}, {}{}",
+            fabi(rd),
+            fabi(rs1),
+            fabi(rs2),
+            self.rm(rm)
+        )
+    }
+
+    fn fmul_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        format!(
+            "fmul.d {}, {}, {}{}",
+            fabi(rd),
+            fabi(rs1),
+            fabi(rs2),
+            self.rm(rm)
+        )
+    }
+
+    fn fdiv_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        format!(
+            "fdiv.d {}, {}, {}{}",
+            fabi(rd),
+            fabi(rs1),
+            fabi(rs2),
+            self.rm(rm)
+        )
+    }
+
+    fn fmadd_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        format!(
+            "fmadd.d {}, {}, {}, {}{}",
+            fabi(rd),
+            fabi(rs1),
+            fabi(rs2),
+            fabi(rs3),
+            self.rm(rm)
+        )
+    }
+
+    fn fmsub_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        format!(
+            "fmsub.d {}, {}, {}, {}{}",
+            fabi(rd),
+            fabi(rs1),
+            fabi(rs2),
+            fabi(rs3),
+            self.rm(rm)
+        )
+    }
+
+    fn fnmsub_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        format!(
+            "fnmsub.d {}, {}, {}, {}{}",
+            fabi(rd),
+            fabi(rs1),
+            fabi(rs2),
+            fabi(rs3),
+            self.rm(rm)
+        )
+    }
+
+    fn fnmadd_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        format!(
+            "fnmadd.d {}, {}, {}, {}{}",
+            fabi(rd),
+            fabi(rs1),
+            fabi(rs2),
+            fabi(rs3),
+            self.rm(rm)
+        )
+    }
+
+    fn fclass_d(&mut self, rd: Reg, rs1: Reg) -> Self::Item {
+        format!("fclass.d {}, {}", abi(rd), fabi(rs1))
+    }
+
+    fn fsgnj_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        if self.is_pseudo() && rs1 == rs2 {
+            return format!("fmv.d {}, {}", fabi(rd), fabi(rs1));
+        }
+        format!("fsgnj.d {}, {}, {}", fabi(rd), fabi(rs1), fabi(rs2))
+    }
+
+    fn fsgnjn_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        if self.is_pseudo() && rs1 == rs2 {
+            return format!("fneg.d {}, {}", fabi(rd), fabi(rs1));
+        }
+        format!("fsgnjn.d {}, {}, {}", fabi(rd), fabi(rs1), fabi(rs2))
+    }
+
+    fn fsgnjx_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        if self.is_pseudo() && rs1 == rs2 {
+            return format!("fabs.d {}, {}", fabi(rd), fabi(rs1));
+        }
+        format!("fsgnjx.d {}, {}, {}", fabi(rd), fabi(rs1), fabi(rs2))
+    }
+
+    fn fmin_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        format!("fmin.d {}, {}, {}", fabi(rd), fabi(rs1), fabi(rs2))
+    }
+
+    fn fmax_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        format!("fmax.d {}, {}, {}", fabi(rd), fabi(rs1), fabi(rs2))
+    }
+
+    fn fle_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        format!("fle.d {}, {}, {}", abi(rd), fabi(rs1), fabi(rs2))
+    }
+
+    fn flt_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        format!("flt.d {}, {}, {}", abi(rd), fabi(rs1), fabi(rs2))
+    }
+
+    fn feq_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        format!("feq.d {}, {}, {}", abi(rd), fabi(rs1), fabi(rs2))
+    }
+
+    fn c_fld(&mut self, rdp: Reg, rs1p: Reg, imm: u32) -> Self::Item {
+        // fld rdp, offset[7:3](rs1p)
+        self.fld(rdp, rs1p, imm)
+    }
+
+    fn c_fsd(&mut self, rs1p: Reg, rs2p: Reg, imm: u32) -> Self::Item {
+        // fsd rs2p, offset[7:3](rs1p)
+        self.fsd(rs1p, rs2p, imm)
+    }
+
+    fn c_fldsp(&mut self, rd: Reg, imm: u32) -> Self::Item {
+        // fld rd, offset[8:3](x2)
+        self.fld(rd, Reg::SP, imm)
+    }
+
+    fn c_fsdsp(&mut self, rs2: Reg, imm: u32) -> Self::Item {
+        // fsd rs2, offset[8:3](x2)
+        self.fsd(Reg::SP, rs2, imm)
+    }
+}
+
+impl HandleZicsr for Disassembler {
+    type Item = String;
+
+    fn csrrw(&mut self, rd: Reg, rs1: Reg, csr: u32) -> Self::Item {
+        if self.is_pseudo() {
+            let alias = match csr {
+                csr::CYCLE if rd == Reg::ZERO && rs1 == Reg::ZERO => return "unimp".to_string(),
+                csr::FCSR => Some("fscsr"),
+                csr::FRM => Some("fsrm"),
+                csr::FFLAGS => Some("fsflags"),
+                _ => None,
+            };
+            match (alias, rd) {
+                (Some(alias), Reg::ZERO) => return format!("{}\t{}", alias, abi(rs1)),
+                (Some(alias), _) => return format!("{}\t{}, {}", alias, abi(rd), abi(rs1)),
+                (None, Reg::ZERO) => return format!("csrw\t{}, {}", csr_name(csr), abi(rs1)),
+                (None, _) => {}
+            }
+        }
+        format!("csrrw\t{}, {}, {}", abi(rd), csr_name(csr), abi(rs1))
+    }
+
+    fn csrrs(&mut self, rd: Reg, rs1: Reg, csr: u32) -> Self::Item {
+        if self.is_pseudo() {
+            if rs1 == Reg::ZERO {
+                let alias = match csr {
+                    csr::FCSR => "frcsr",
+                    csr::FRM => "frrm",
+                    csr::FFLAGS => "frflags",
+                    csr::CYCLE => "rdcycle",
+                    csr::TIME => "rdtime",
+                    csr::INSTRET => "rdinstret",
+                    csr::CYCLEH => "rdcycleh",
+                    csr::TIMEH => "rdtimeh",
+                    csr::INSTRETH => "rdinstreth",
+                    _ => return format!("csrr\t{}, {}", abi(rd), csr_name(csr)),
+                };
+                return format!("{}\t{}", alias, abi(rd));
+            } else if rd == Reg::ZERO {
+                return format!("csrs\t{}, {}", csr_name(csr), abi(rs1));
+            }
+        }
+        format!("csrrs\t{}, {}, {}", abi(rd), csr_name(csr), abi(rs1))
+    }
+
+    fn csrrc(&mut self, rd: Reg, rs1: Reg, csr: u32) -> Self::Item {
+        if self.is_pseudo() && rd == Reg::ZERO {
+            return format!("csrc\t{}, {}", csr_name(csr), abi(rs1));
+        }
+        format!("csrrc\t{}, {}, {}", abi(rd), csr_name(csr), abi(rs1))
+    }
+
+    fn csrrwi(&mut self, rd: Reg, zimm: u32, csr: u32) -> Self::Item {
+        if self.is_pseudo() {
+            let alias = match csr {
+                csr::FRM => Some("fsrmi"),
+                csr::FFLAGS => Some("fsflagsi"),
+                _ => None,
+            };
+            match (alias, rd) {
+                (Some(alias), Reg::ZERO) => return format!("{}\t{}", alias, zimm),
+                (Some(alias), _) => return format!("{}\t{}, {}", alias, abi(rd), zimm),
+                (None, Reg::ZERO) => return format!("csrwi\t{}, {}", csr_name(csr), zimm),
+                (None, _) => {}
+            }
+        }
+        format!("csrrwi\t{}, {}, {}", abi(rd), csr_name(csr), zimm)
+    }
+
+    fn csrrsi(&mut self, rd: Reg, zimm: u32, csr: u32) -> Self::Item {
+        if self.is_pseudo() && rd == Reg::ZERO {
+            return format!("csrsi\t{}, {}", csr_name(csr), zimm);
+        }
+        format!("csrrsi\t{}, {}, {}", abi(rd), csr_name(csr), zimm)
+    }
+
+    fn csrrci(&mut self, rd: Reg, zimm: u32, csr: u32) -> Self::Item {
+        if self.is_pseudo() && rd == Reg::ZERO {
+            return format!("csrci\t{}, {}", csr_name(csr), zimm);
+        }
+        format!("csrrci\t{}, {}, {}", abi(rd), csr_name(csr), zimm)
+    }
+}
+
+impl HandleZifencei for Disassembler {
+    type Item = String;
+
+    fn fence_i(&mut self, _rd: Reg, _rs1: Reg, _iimm: u32) -> Self::Item {
+        "fence.i".to_string()
+    }
+}
+
+impl HandlePrivileged for Disassembler {
+    type Item = String;
+
+    fn sret(&mut self) -> Self::Item {
+        "sret".to_string()
+    }
+
+    fn mret(&mut self) -> Self::Item {
+        "mret".to_string()
+    }
+
+    fn wfi(&mut self) -> Self::Item {
+        "wfi".to_string()
+    }
+
+    fn sfence_vma(&mut self, rs1: Reg, rs2: Reg) -> Self::Item {
+        match (rs1, rs2) {
+            (Reg::ZERO, Reg::ZERO) => "sfence.vma".to_string(),
+            (rs1, Reg::ZERO) => format!("sfence.vma\t{}", abi(rs1)),
+            (rs1, rs2) => format!("sfence.vma\t{}, {}", abi(rs1), abi(rs2)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Disassembler, Mode};
+    use crate::elf::{Symbol, SymbolKind, SymbolTable};
+
+    fn symbols() -> SymbolTable {
+        let symbol = |name: &str, address| Symbol {
+            name: name.to_string(),
+            address,
+            size: 0,
+            kind: SymbolKind::Func,
+        };
+        SymbolTable::new(vec![symbol("_start", 0x100), symbol("main", 0x200)])
+    }
+
+    #[test]
+    fn test_branch_targets_are_offsets_without_a_pc() {
+        use crate::DispatchRv32ic;
+
+        let mut disassembler = Disassembler::new();
+        assert_eq!("beq\ta0, a1, -12", disassembler.dispatch(0xfeb50ae3));
+        assert_eq!("jal\tra, 256", disassembler.dispatch(0x100000ef));
+    }
+
+    #[test]
+    fn test_branch_targets_are_resolved_to_symbols() {
+        use crate::DispatchRv32ic;
+
+        let mut disassembler = Disassembler::with_symbols(symbols());
+        disassembler.set_pc(0x110);
+        assert_eq!(
+            "beq\ta0, a1, 0x104 <_start+0x4>",
+            disassembler.dispatch(0xfeb50ae3)
+        );
+        disassembler.set_pc(0x100);
+        assert_eq!("jal\tra, 0x200 <main>", disassembler.dispatch(0x100000ef));
+        disassembler.set_pc(0x202);
+        assert_eq!("jal\tzero, 0x200 <main>", disassembler.dispatch(0xbffd)); // c.j
+    }
+
+    #[test]
+    fn test_labels_are_shown_at_symbols() {
+        let disassembler = Disassembler::with_symbols(symbols());
+        assert_eq!(
+            Some("00000200 <main>:".to_string()),
+            disassembler.label(0x200)
+        );
+        assert_eq!(None, disassembler.label(0x204));
+    }
+
+    #[test]
+    fn test_auipc_pairs_are_annotated() {
+        use crate::DispatchRv32ic;
+
+        let mut disassembler = Disassembler::with_symbols(symbols());
+        disassembler.set_pc(0x100);
+        assert_eq!("auipc\ta0, 0", disassembler.dispatch(0x00000517));
+        disassembler.set_pc(0x104);
+        assert_eq!(
+            "addi\ta0, a0, 256 # 0x200 <main>",
+            disassembler.dispatch(0x10050513)
+        );
+        disassembler.set_pc(0x108);
+        assert_eq!("auipc\tra, 0", disassembler.dispatch(0x00000097));
+        disassembler.set_pc(0x10c);
+        assert_eq!(
+            "jalr\tra, ra, 252 # 0x204 <main+0x4>",
+            disassembler.dispatch(0x0fc080e7)
+        );
+
+        // Only the instruction immediately after the auipc is annotated.
+        disassembler.set_pc(0x110);
+        assert_eq!("addi\ta0, a0, 256", disassembler.dispatch(0x10050513));
+    }
+
+    #[test]
+    fn test_raw_mode_shows_canonical_forms() {
+        use crate::DispatchRv32gc;
+
+        let mut disassembler = Disassembler::new();
+        assert_eq!("addi\tzero, zero, 0", disassembler.dispatch(0x00000013));
+        assert_eq!("jalr\tzero, ra, 0", disassembler.dispatch(0x00008067));
+        assert_eq!("csrrs\ta0, fcsr, zero", disassembler.dispatch(0x00302573));
+        assert_eq!("fadd.s ft0, ft1, ft2, 7", disassembler.dispatch(0x0020f053));
+    }
+
+    #[test]
+    fn test_pseudo_mode_shows_aliases() {
+        use crate::DispatchRv32gc;
+
+        let mut disassembler = Disassembler::new();
+        disassembler.set_mode(Mode::Pseudo);
+        let mut check = |expected: &str, ins: u32| {
+            assert_eq!(expected, disassembler.dispatch(ins), "{:08x}", ins);
+        };
+        check("nop", 0x00000013); // addi zero, zero, 0
+        check("nop", 0x0001); // c.nop
+        check("li\ta0, -1", 0xfff00513); // addi a0, zero, -1
+        check("mv\ts0, sp", 0x00010413); // addi s0, sp, 0
+        check("mv\ts0, sp", 0x840a); // c.mv s0, sp
+        check("not\ta0, a1", 0xfff5c513); // xori a0, a1, -1
+        check("neg\ta0, a1", 0x40b00533); // sub a0, zero, a1
+        check("seqz\ta0, a1", 0x0015b513); // sltiu a0, a1, 1
+        check("snez\ta0, a1", 0x00b03533); // sltu a0, zero, a1
+        check("sltz\ta0, a1", 0x0005a533); // slt a0, a1, zero
+        check("sgtz\ta0, a1", 0x00b02533); // slt a0, zero, a1
+        check("zext.b\ta0, a1", 0x0ff5f513); // andi a0, a1, 255
+        check("beqz\ta0, -12", 0xfe050ae3); // beq a0, zero, -12
+        check("bnez\ta0, 8", 0x00051463); // bne a0, zero, 8
+        check("blez\ta0, 8", 0x00a05463); // bge zero, a0, 8
+        check("bgtz\ta0, 8", 0x00a04463); // blt zero, a0, 8
+        check("j\t-4", 0xffdff06f); // jal zero, -4
+        check("jal\t256", 0x100000ef); // jal ra, 256
+        check("ret", 0x00008067); // jalr zero, ra, 0
+        check("ret", 0x8082); // c.jr ra
+        check("jr\ta0", 0x00050067); // jalr zero, a0, 0
+        check("jalr\ta0", 0x000500e7); // jalr ra, a0, 0
+        check("jalr\t-8(a0)", 0xff8500e7); // jalr ra, a0, -8
+        check("csrr\ta0, mstatus", 0x30002573); // csrrs a0, mstatus, zero
+        check("csrw\tmscratch, a0", 0x34051073); // csrrw zero, mscratch, a0
+        check("csrsi\tmstatus, 8", 0x30046073); // csrrsi zero, mstatus, 8
+        check("frcsr\ta0", 0x00302573); // csrrs a0, fcsr, zero
+        check("fsrmi\t1", 0x0020d073); // csrrwi zero, frm, 1
+        check("rdcycle\ta0", 0xc0002573); // csrrs a0, cycle, zero
+        check("unimp", 0xc0001073); // csrrw zero, cycle, zero
+        check("fmv.s ft0, ft1", 0x20108053); // fsgnj.s ft0, ft1, ft1
+        check("fneg.s ft0, ft1", 0x20109053); // fsgnjn.s ft0, ft1, ft1
+        check("fabs.s ft0, ft1", 0x2010a053); // fsgnjx.s ft0, ft1, ft1
+        check("fadd.s ft0, ft1, ft2", 0x0020f053); // fadd.s ft0, ft1, ft2, dyn
+        check("fadd.s ft0, ft1, ft2, rtz", 0x00209053); // fadd.s ft0, ft1, ft2, rtz
+        check("add\ta0, zero, a1", 0x00b00533); // add a0, zero, a1 isn't an alias.
+    }
 }
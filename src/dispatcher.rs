@@ -2,7 +2,10 @@
 //!
 
 use crate::{
-    handlers::{HandleRv32c, HandleRv32f, HandleRv32i, HandleRv32m},
+    handlers::{
+        HandlePrivileged, HandleRv32a, HandleRv32c, HandleRv32d, HandleRv32f, HandleRv32i,
+        HandleRv32m, HandleZicsr, HandleZifencei,
+    },
     tobits::ToBits,
 };
 
@@ -759,6 +762,1840 @@ where
             0xc002 => return self.c_swsp(c.c_rs2(), c.c_uimm8sp_s()),
             0x2001 => return self.c_jal(c.c_imm12()),
             0x0002 => return self.c_slli(c.rdrs1n0(), c.c_nzuimm6()),
+            0x6000 => return self.c_flw(c.rdp(), c.rs1p(), c.c_uimm7()),
+            0xe000 => return self.c_fsw(c.rs1p(), c.rs2p(), c.c_uimm7()),
+            0x6002 => return self.c_flwsp(c.rd(), c.c_uimm8sp()),
+            0xe002 => return self.c_fswsp(c.c_rs2(), c.c_uimm8sp_s()),
+            _ => {}
+        }
+        self.illegal(code)
+    }
+}
+
+// End of auto-generated code.
+
+// This code was generated by `make_dispatcher.py -z`. Do not edit.
+
+/// A dispatcher for RV32I_Zicsr instructions.
+pub trait DispatchRv32iZicsr {
+    type Item;
+
+    /// Decodes the input word to an RV32I_Zicsr instruction and dispatches it to a handler.
+    fn dispatch(&mut self, code: u32) -> <Self as HandleRv32i>::Item
+    where
+        Self: HandleRv32i + HandleZicsr;
+}
+
+impl<T, U> DispatchRv32iZicsr for T
+where
+    T: HandleRv32i + HandleRv32i<Item = U> + HandleZicsr + HandleZicsr<Item = U>,
+{
+    type Item = U;
+
+    fn dispatch(&mut self, code: u32) -> Self::Item {
+        #![allow(clippy::single_match)]
+
+        let c = ToBits(code);
+
+        match code {
+            0x00000073 => return self.ecall(),
+            0x00100073 => return self.ebreak(),
+            _ => {}
+        }
+        match code & 0xfe00707f {
+            0x00000033 => return self.add(c.rd(), c.rs1(), c.rs2()),
+            0x40000033 => return self.sub(c.rd(), c.rs1(), c.rs2()),
+            0x00001033 => return self.sll(c.rd(), c.rs1(), c.rs2()),
+            0x00002033 => return self.slt(c.rd(), c.rs1(), c.rs2()),
+            0x00003033 => return self.sltu(c.rd(), c.rs1(), c.rs2()),
+            0x00004033 => return self.xor(c.rd(), c.rs1(), c.rs2()),
+            0x00005033 => return self.srl(c.rd(), c.rs1(), c.rs2()),
+            0x40005033 => return self.sra(c.rd(), c.rs1(), c.rs2()),
+            0x00006033 => return self.or(c.rd(), c.rs1(), c.rs2()),
+            0x00007033 => return self.and(c.rd(), c.rs1(), c.rs2()),
+            0x00001013 => return self.slli(c.rd(), c.rs1(), c.shamtw()),
+            0x00005013 => return self.srli(c.rd(), c.rs1(), c.shamtw()),
+            0x40005013 => return self.srai(c.rd(), c.rs1(), c.shamtw()),
+            _ => {}
+        }
+        match code & 0x0000707f {
+            0x00000063 => return self.beq(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00001063 => return self.bne(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00004063 => return self.blt(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00005063 => return self.bge(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00006063 => return self.bltu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00007063 => return self.bgeu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00000067 => return self.jalr(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000013 => return self.addi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002013 => return self.slti(c.rd(), c.rs1(), c.iimmediate()),
+            0x00003013 => return self.sltiu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004013 => return self.xori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00006013 => return self.ori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00007013 => return self.andi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000003 => return self.lb(c.rd(), c.rs1(), c.iimmediate()),
+            0x00001003 => return self.lh(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002003 => return self.lw(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004003 => return self.lbu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00005003 => return self.lhu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000023 => return self.sb(c.rs1(), c.rs2(), c.simmediate()),
+            0x00001023 => return self.sh(c.rs1(), c.rs2(), c.simmediate()),
+            0x00002023 => return self.sw(c.rs1(), c.rs2(), c.simmediate()),
+            0x0000000f => return self.fence(c.fm(), c.rd(), c.rs1()),
+            0x00001073 => return self.csrrw(c.rd(), c.rs1(), c.csr()),
+            0x00002073 => return self.csrrs(c.rd(), c.rs1(), c.csr()),
+            0x00003073 => return self.csrrc(c.rd(), c.rs1(), c.csr()),
+            0x00005073 => return self.csrrwi(c.rd(), c.zimm(), c.csr()),
+            0x00006073 => return self.csrrsi(c.rd(), c.zimm(), c.csr()),
+            0x00007073 => return self.csrrci(c.rd(), c.zimm(), c.csr()),
+            _ => {}
+        }
+        match code & 0x0000007f {
+            0x0000006f => return self.jal(c.rd(), c.jimmediate()),
+            0x00000037 => return self.lui(c.rd(), c.uimmediate()),
+            0x00000017 => return self.auipc(c.rd(), c.uimmediate()),
+            _ => {}
+        }
+        self.illegal(code)
+    }
+}
+
+// End of auto-generated code.
+
+// This code was generated by `make_dispatcher.py -mcz`. Do not edit.
+
+/// A dispatcher for RV32IMC_Zicsr instructions.
+pub trait DispatchRv32imcZicsr {
+    type Item;
+
+    /// Decodes the input word to an RV32IMC_Zicsr instruction and dispatches it to a handler.
+    fn dispatch(&mut self, code: u32) -> <Self as HandleRv32i>::Item
+    where
+        Self: HandleRv32i + HandleRv32m + HandleRv32c + HandleZicsr;
+}
+
+impl<T, U> DispatchRv32imcZicsr for T
+where
+    T: HandleRv32i
+        + HandleRv32i<Item = U>
+        + HandleRv32m
+        + HandleRv32m<Item = U>
+        + HandleRv32c
+        + HandleRv32c<Item = U>
+        + HandleZicsr
+        + HandleZicsr<Item = U>,
+{
+    type Item = U;
+
+    fn dispatch(&mut self, code: u32) -> Self::Item {
+        #![allow(clippy::single_match)]
+
+        let c = ToBits(code);
+
+        match code {
+            0x00000073 => return self.ecall(),
+            0x00100073 => return self.ebreak(),
+            _ => {}
+        }
+        match code & 0xfe00707f {
+            0x00000033 => return self.add(c.rd(), c.rs1(), c.rs2()),
+            0x40000033 => return self.sub(c.rd(), c.rs1(), c.rs2()),
+            0x00001033 => return self.sll(c.rd(), c.rs1(), c.rs2()),
+            0x00002033 => return self.slt(c.rd(), c.rs1(), c.rs2()),
+            0x00003033 => return self.sltu(c.rd(), c.rs1(), c.rs2()),
+            0x00004033 => return self.xor(c.rd(), c.rs1(), c.rs2()),
+            0x00005033 => return self.srl(c.rd(), c.rs1(), c.rs2()),
+            0x40005033 => return self.sra(c.rd(), c.rs1(), c.rs2()),
+            0x00006033 => return self.or(c.rd(), c.rs1(), c.rs2()),
+            0x00007033 => return self.and(c.rd(), c.rs1(), c.rs2()),
+            0x00001013 => return self.slli(c.rd(), c.rs1(), c.shamtw()),
+            0x00005013 => return self.srli(c.rd(), c.rs1(), c.shamtw()),
+            0x40005013 => return self.srai(c.rd(), c.rs1(), c.shamtw()),
+            0x02000033 => return self.mul(c.rd(), c.rs1(), c.rs2()),
+            0x02001033 => return self.mulh(c.rd(), c.rs1(), c.rs2()),
+            0x02002033 => return self.mulhsu(c.rd(), c.rs1(), c.rs2()),
+            0x02003033 => return self.mulhu(c.rd(), c.rs1(), c.rs2()),
+            0x02004033 => return self.div(c.rd(), c.rs1(), c.rs2()),
+            0x02005033 => return self.divu(c.rd(), c.rs1(), c.rs2()),
+            0x02006033 => return self.rem(c.rd(), c.rs1(), c.rs2()),
+            0x02007033 => return self.remu(c.rd(), c.rs1(), c.rs2()),
+            _ => {}
+        }
+        match code & 0x0000ffff {
+            0x9002 => return self.c_ebreak(),
+            _ => {}
+        }
+        match code & 0x0000f07f {
+            0x8002 => return self.c_jr(c.rs1n0()),
+            0x9002 => return self.c_jalr(c.rs1n0()),
+            _ => {}
+        }
+        match code & 0x0000707f {
+            0x00000063 => return self.beq(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00001063 => return self.bne(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00004063 => return self.blt(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00005063 => return self.bge(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00006063 => return self.bltu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00007063 => return self.bgeu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00000067 => return self.jalr(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000013 => return self.addi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002013 => return self.slti(c.rd(), c.rs1(), c.iimmediate()),
+            0x00003013 => return self.sltiu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004013 => return self.xori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00006013 => return self.ori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00007013 => return self.andi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000003 => return self.lb(c.rd(), c.rs1(), c.iimmediate()),
+            0x00001003 => return self.lh(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002003 => return self.lw(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004003 => return self.lbu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00005003 => return self.lhu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000023 => return self.sb(c.rs1(), c.rs2(), c.simmediate()),
+            0x00001023 => return self.sh(c.rs1(), c.rs2(), c.simmediate()),
+            0x00002023 => return self.sw(c.rs1(), c.rs2(), c.simmediate()),
+            0x0000000f => return self.fence(c.fm(), c.rd(), c.rs1()),
+            0x00001073 => return self.csrrw(c.rd(), c.rs1(), c.csr()),
+            0x00002073 => return self.csrrs(c.rd(), c.rs1(), c.csr()),
+            0x00003073 => return self.csrrc(c.rd(), c.rs1(), c.csr()),
+            0x00005073 => return self.csrrwi(c.rd(), c.zimm(), c.csr()),
+            0x00006073 => return self.csrrsi(c.rd(), c.zimm(), c.csr()),
+            0x00007073 => return self.csrrci(c.rd(), c.zimm(), c.csr()),
+            _ => {}
+        }
+        match code & 0x0000ef83 {
+            0x0001 => return self.c_nop(c.c_nzimm6()),
+            0x6101 => return self.c_addi16sp(c.c_nzimm10()),
+            _ => {}
+        }
+        match code & 0x0000fc63 {
+            0x8c01 => return self.c_sub(c.rdrs1p(), c.rs2p()),
+            0x8c21 => return self.c_xor(c.rdrs1p(), c.rs2p()),
+            0x8c41 => return self.c_or(c.rdrs1p(), c.rs2p()),
+            0x8c61 => return self.c_and(c.rdrs1p(), c.rs2p()),
+            _ => {}
+        }
+        match code & 0x0000007f {
+            0x0000006f => return self.jal(c.rd(), c.jimmediate()),
+            0x00000037 => return self.lui(c.rd(), c.uimmediate()),
+            0x00000017 => return self.auipc(c.rd(), c.uimmediate()),
+            _ => {}
+        }
+        match code & 0x0000ec03 {
+            0x8801 => return self.c_andi(c.rdrs1p(), c.c_imm6()),
+            0x8001 => return self.c_srli(c.rdrs1p(), c.c_nzuimm6()),
+            0x8401 => return self.c_srai(c.rdrs1p(), c.c_nzuimm6()),
+            _ => {}
+        }
+        match code & 0x0000f003 {
+            0x8002 => return self.c_mv(c.rd(), c.rs2n0()),
+            0x9002 => return self.c_add(c.rdrs1(), c.rs2n0()),
+            _ => {}
+        }
+        match code & 0x0000e003 {
+            0x0000 => return self.c_addi4spn(c.rdp(), c.c_nzuimm10()),
+            0x4000 => return self.c_lw(c.rdp(), c.rs1p(), c.c_uimm7()),
+            0xc000 => return self.c_sw(c.rs1p(), c.rs2p(), c.c_uimm7()),
+            0x0001 => return self.c_addi(c.rdrs1n0(), c.c_nzimm6()),
+            0x4001 => return self.c_li(c.rd(), c.c_imm6()),
+            0x6001 => return self.c_lui(c.rdn2(), c.c_nzimm18()),
+            0xa001 => return self.c_j(c.c_imm12()),
+            0xc001 => return self.c_beqz(c.rs1p(), c.c_bimm9()),
+            0xe001 => return self.c_bnez(c.rs1p(), c.c_bimm9()),
+            0x4002 => return self.c_lwsp(c.rdn0(), c.c_uimm8sp()),
+            0xc002 => return self.c_swsp(c.c_rs2(), c.c_uimm8sp_s()),
+            0x2001 => return self.c_jal(c.c_imm12()),
+            0x0002 => return self.c_slli(c.rdrs1n0(), c.c_nzuimm6()),
+            _ => {}
+        }
+        self.illegal(code)
+    }
+}
+
+// End of auto-generated code.
+
+// This code was generated by `make_dispatcher.py -mfcz`. Do not edit.
+
+/// A dispatcher for RV32IMFC_Zicsr instructions.
+pub trait DispatchRv32imfcZicsr {
+    type Item;
+
+    /// Decodes the input word to an RV32IMFC_Zicsr instruction and dispatches it to a handler.
+    fn dispatch(&mut self, code: u32) -> <Self as HandleRv32i>::Item
+    where
+        Self: HandleRv32i + HandleRv32m + HandleRv32f + HandleRv32c + HandleZicsr;
+}
+
+impl<T, U> DispatchRv32imfcZicsr for T
+where
+    T: HandleRv32i
+        + HandleRv32i<Item = U>
+        + HandleRv32m
+        + HandleRv32m<Item = U>
+        + HandleRv32f
+        + HandleRv32f<Item = U>
+        + HandleRv32c
+        + HandleRv32c<Item = U>
+        + HandleZicsr
+        + HandleZicsr<Item = U>,
+{
+    type Item = U;
+
+    fn dispatch(&mut self, code: u32) -> Self::Item {
+        #![allow(clippy::single_match)]
+
+        let c = ToBits(code);
+
+        match code {
+            0x00000073 => return self.ecall(),
+            0x00100073 => return self.ebreak(),
+            _ => {}
+        }
+        match code & 0xfff0707f {
+            0xe0000053 => return self.fmv_x_w(c.rd(), c.rs1()),
+            0xe0001053 => return self.fclass_s(c.rd(), c.rs1()),
+            0xf0000053 => return self.fmv_w_x(c.rd(), c.rs1()),
+            _ => {}
+        }
+        match code & 0xfff0007f {
+            0x58000053 => return self.fsqrt_s(c.rd(), c.rs1(), c.rm()),
+            0xc0000053 => return self.fcvt_w_s(c.rd(), c.rs1(), c.rm()),
+            0xc0100053 => return self.fcvt_wu_s(c.rd(), c.rs1(), c.rm()),
+            0xd0000053 => return self.fcvt_s_w(c.rd(), c.rs1(), c.rm()),
+            0xd0100053 => return self.fcvt_s_wu(c.rd(), c.rs1(), c.rm()),
+            _ => {}
+        }
+        match code & 0xfe00707f {
+            0x00000033 => return self.add(c.rd(), c.rs1(), c.rs2()),
+            0x40000033 => return self.sub(c.rd(), c.rs1(), c.rs2()),
+            0x00001033 => return self.sll(c.rd(), c.rs1(), c.rs2()),
+            0x00002033 => return self.slt(c.rd(), c.rs1(), c.rs2()),
+            0x00003033 => return self.sltu(c.rd(), c.rs1(), c.rs2()),
+            0x00004033 => return self.xor(c.rd(), c.rs1(), c.rs2()),
+            0x00005033 => return self.srl(c.rd(), c.rs1(), c.rs2()),
+            0x40005033 => return self.sra(c.rd(), c.rs1(), c.rs2()),
+            0x00006033 => return self.or(c.rd(), c.rs1(), c.rs2()),
+            0x00007033 => return self.and(c.rd(), c.rs1(), c.rs2()),
+            0x00001013 => return self.slli(c.rd(), c.rs1(), c.shamtw()),
+            0x00005013 => return self.srli(c.rd(), c.rs1(), c.shamtw()),
+            0x40005013 => return self.srai(c.rd(), c.rs1(), c.shamtw()),
+            0x02000033 => return self.mul(c.rd(), c.rs1(), c.rs2()),
+            0x02001033 => return self.mulh(c.rd(), c.rs1(), c.rs2()),
+            0x02002033 => return self.mulhsu(c.rd(), c.rs1(), c.rs2()),
+            0x02003033 => return self.mulhu(c.rd(), c.rs1(), c.rs2()),
+            0x02004033 => return self.div(c.rd(), c.rs1(), c.rs2()),
+            0x02005033 => return self.divu(c.rd(), c.rs1(), c.rs2()),
+            0x02006033 => return self.rem(c.rd(), c.rs1(), c.rs2()),
+            0x02007033 => return self.remu(c.rd(), c.rs1(), c.rs2()),
+            _ => {}
+        }
+        match code & 0xfe00707f {
+            0x20000053 => return self.fsgnj_s(c.rd(), c.rs1(), c.rs2()),
+            0x20001053 => return self.fsgnjn_s(c.rd(), c.rs1(), c.rs2()),
+            0x20002053 => return self.fsgnjx_s(c.rd(), c.rs1(), c.rs2()),
+            0x28000053 => return self.fmin_s(c.rd(), c.rs1(), c.rs2()),
+            0x28001053 => return self.fmax_s(c.rd(), c.rs1(), c.rs2()),
+            0xa0000053 => return self.fle_s(c.rd(), c.rs1(), c.rs2()),
+            0xa0001053 => return self.flt_s(c.rd(), c.rs1(), c.rs2()),
+            0xa0002053 => return self.feq_s(c.rd(), c.rs1(), c.rs2()),
+            _ => {}
+        }
+        match code & 0x0000ffff {
+            0x9002 => return self.c_ebreak(),
+            _ => {}
+        }
+        match code & 0xfe00007f {
+            0x00000053 => return self.fadd_s(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x08000053 => return self.fsub_s(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x10000053 => return self.fmul_s(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x18000053 => return self.fdiv_s(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            _ => {}
+        }
+        match code & 0x0000f07f {
+            0x8002 => return self.c_jr(c.rs1n0()),
+            0x9002 => return self.c_jalr(c.rs1n0()),
+            _ => {}
+        }
+        match code & 0x0000707f {
+            0x00000063 => return self.beq(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00001063 => return self.bne(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00004063 => return self.blt(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00005063 => return self.bge(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00006063 => return self.bltu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00007063 => return self.bgeu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00000067 => return self.jalr(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000013 => return self.addi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002013 => return self.slti(c.rd(), c.rs1(), c.iimmediate()),
+            0x00003013 => return self.sltiu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004013 => return self.xori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00006013 => return self.ori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00007013 => return self.andi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000003 => return self.lb(c.rd(), c.rs1(), c.iimmediate()),
+            0x00001003 => return self.lh(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002003 => return self.lw(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004003 => return self.lbu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00005003 => return self.lhu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000023 => return self.sb(c.rs1(), c.rs2(), c.simmediate()),
+            0x00001023 => return self.sh(c.rs1(), c.rs2(), c.simmediate()),
+            0x00002023 => return self.sw(c.rs1(), c.rs2(), c.simmediate()),
+            0x0000000f => return self.fence(c.fm(), c.rd(), c.rs1()),
+            0x00002007 => return self.flw(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002027 => return self.fsw(c.rs1(), c.rs2(), c.simmediate()),
+            0x00001073 => return self.csrrw(c.rd(), c.rs1(), c.csr()),
+            0x00002073 => return self.csrrs(c.rd(), c.rs1(), c.csr()),
+            0x00003073 => return self.csrrc(c.rd(), c.rs1(), c.csr()),
+            0x00005073 => return self.csrrwi(c.rd(), c.zimm(), c.csr()),
+            0x00006073 => return self.csrrsi(c.rd(), c.zimm(), c.csr()),
+            0x00007073 => return self.csrrci(c.rd(), c.zimm(), c.csr()),
+            _ => {}
+        }
+        match code & 0x0000ef83 {
+            0x0001 => return self.c_nop(c.c_nzimm6()),
+            0x6101 => return self.c_addi16sp(c.c_nzimm10()),
+            _ => {}
+        }
+        match code & 0x0000fc63 {
+            0x8c01 => return self.c_sub(c.rdrs1p(), c.rs2p()),
+            0x8c21 => return self.c_xor(c.rdrs1p(), c.rs2p()),
+            0x8c41 => return self.c_or(c.rdrs1p(), c.rs2p()),
+            0x8c61 => return self.c_and(c.rdrs1p(), c.rs2p()),
+            _ => {}
+        }
+        match code & 0x0600007f {
+            0x00000043 => return self.fmadd_s(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x00000047 => return self.fmsub_s(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x0000004b => return self.fnmsub_s(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x0000004f => return self.fnmadd_s(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            _ => {}
+        }
+        match code & 0x0000007f {
+            0x0000006f => return self.jal(c.rd(), c.jimmediate()),
+            0x00000037 => return self.lui(c.rd(), c.uimmediate()),
+            0x00000017 => return self.auipc(c.rd(), c.uimmediate()),
+            _ => {}
+        }
+        match code & 0x0000ec03 {
+            0x8801 => return self.c_andi(c.rdrs1p(), c.c_imm6()),
+            0x8001 => return self.c_srli(c.rdrs1p(), c.c_nzuimm6()),
+            0x8401 => return self.c_srai(c.rdrs1p(), c.c_nzuimm6()),
+            _ => {}
+        }
+        match code & 0x0000f003 {
+            0x8002 => return self.c_mv(c.rd(), c.rs2n0()),
+            0x9002 => return self.c_add(c.rdrs1(), c.rs2n0()),
+            _ => {}
+        }
+        match code & 0x0000e003 {
+            0x0000 => return self.c_addi4spn(c.rdp(), c.c_nzuimm10()),
+            0x4000 => return self.c_lw(c.rdp(), c.rs1p(), c.c_uimm7()),
+            0xc000 => return self.c_sw(c.rs1p(), c.rs2p(), c.c_uimm7()),
+            0x0001 => return self.c_addi(c.rdrs1n0(), c.c_nzimm6()),
+            0x4001 => return self.c_li(c.rd(), c.c_imm6()),
+            0x6001 => return self.c_lui(c.rdn2(), c.c_nzimm18()),
+            0xa001 => return self.c_j(c.c_imm12()),
+            0xc001 => return self.c_beqz(c.rs1p(), c.c_bimm9()),
+            0xe001 => return self.c_bnez(c.rs1p(), c.c_bimm9()),
+            0x4002 => return self.c_lwsp(c.rdn0(), c.c_uimm8sp()),
+            0xc002 => return self.c_swsp(c.c_rs2(), c.c_uimm8sp_s()),
+            0x2001 => return self.c_jal(c.c_imm12()),
+            0x0002 => return self.c_slli(c.rdrs1n0(), c.c_nzuimm6()),
+            0x6000 => return self.c_flw(c.rdp(), c.rs1p(), c.c_uimm7()),
+            0xe000 => return self.c_fsw(c.rs1p(), c.rs2p(), c.c_uimm7()),
+            0x6002 => return self.c_flwsp(c.rd(), c.c_uimm8sp()),
+            0xe002 => return self.c_fswsp(c.c_rs2(), c.c_uimm8sp_s()),
+            _ => {}
+        }
+        self.illegal(code)
+    }
+}
+
+// End of auto-generated code.
+
+// This code was generated by `make_dispatcher.py -zp`. Do not edit.
+
+/// A dispatcher for RV32I_Zicsr_Priv instructions.
+pub trait DispatchRv32iZicsrPriv {
+    type Item;
+
+    /// Decodes the input word to an RV32I_Zicsr_Priv instruction and dispatches it to a handler.
+    fn dispatch(&mut self, code: u32) -> <Self as HandleRv32i>::Item
+    where
+        Self: HandleRv32i + HandleZicsr + HandlePrivileged;
+}
+
+impl<T, U> DispatchRv32iZicsrPriv for T
+where
+    T: HandleRv32i
+        + HandleRv32i<Item = U>
+        + HandleZicsr
+        + HandleZicsr<Item = U>
+        + HandlePrivileged
+        + HandlePrivileged<Item = U>,
+{
+    type Item = U;
+
+    fn dispatch(&mut self, code: u32) -> Self::Item {
+        #![allow(clippy::single_match)]
+
+        let c = ToBits(code);
+
+        match code {
+            0x00000073 => return self.ecall(),
+            0x00100073 => return self.ebreak(),
+            0x10200073 => return self.sret(),
+            0x30200073 => return self.mret(),
+            0x10500073 => return self.wfi(),
+            _ => {}
+        }
+        match code & 0xfe007fff {
+            0x12000073 => return self.sfence_vma(c.rs1(), c.rs2()),
+            _ => {}
+        }
+        match code & 0xfe00707f {
+            0x00000033 => return self.add(c.rd(), c.rs1(), c.rs2()),
+            0x40000033 => return self.sub(c.rd(), c.rs1(), c.rs2()),
+            0x00001033 => return self.sll(c.rd(), c.rs1(), c.rs2()),
+            0x00002033 => return self.slt(c.rd(), c.rs1(), c.rs2()),
+            0x00003033 => return self.sltu(c.rd(), c.rs1(), c.rs2()),
+            0x00004033 => return self.xor(c.rd(), c.rs1(), c.rs2()),
+            0x00005033 => return self.srl(c.rd(), c.rs1(), c.rs2()),
+            0x40005033 => return self.sra(c.rd(), c.rs1(), c.rs2()),
+            0x00006033 => return self.or(c.rd(), c.rs1(), c.rs2()),
+            0x00007033 => return self.and(c.rd(), c.rs1(), c.rs2()),
+            0x00001013 => return self.slli(c.rd(), c.rs1(), c.shamtw()),
+            0x00005013 => return self.srli(c.rd(), c.rs1(), c.shamtw()),
+            0x40005013 => return self.srai(c.rd(), c.rs1(), c.shamtw()),
+            _ => {}
+        }
+        match code & 0x0000707f {
+            0x00000063 => return self.beq(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00001063 => return self.bne(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00004063 => return self.blt(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00005063 => return self.bge(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00006063 => return self.bltu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00007063 => return self.bgeu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00000067 => return self.jalr(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000013 => return self.addi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002013 => return self.slti(c.rd(), c.rs1(), c.iimmediate()),
+            0x00003013 => return self.sltiu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004013 => return self.xori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00006013 => return self.ori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00007013 => return self.andi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000003 => return self.lb(c.rd(), c.rs1(), c.iimmediate()),
+            0x00001003 => return self.lh(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002003 => return self.lw(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004003 => return self.lbu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00005003 => return self.lhu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000023 => return self.sb(c.rs1(), c.rs2(), c.simmediate()),
+            0x00001023 => return self.sh(c.rs1(), c.rs2(), c.simmediate()),
+            0x00002023 => return self.sw(c.rs1(), c.rs2(), c.simmediate()),
+            0x0000000f => return self.fence(c.fm(), c.rd(), c.rs1()),
+            0x00001073 => return self.csrrw(c.rd(), c.rs1(), c.csr()),
+            0x00002073 => return self.csrrs(c.rd(), c.rs1(), c.csr()),
+            0x00003073 => return self.csrrc(c.rd(), c.rs1(), c.csr()),
+            0x00005073 => return self.csrrwi(c.rd(), c.zimm(), c.csr()),
+            0x00006073 => return self.csrrsi(c.rd(), c.zimm(), c.csr()),
+            0x00007073 => return self.csrrci(c.rd(), c.zimm(), c.csr()),
+            _ => {}
+        }
+        match code & 0x0000007f {
+            0x0000006f => return self.jal(c.rd(), c.jimmediate()),
+            0x00000037 => return self.lui(c.rd(), c.uimmediate()),
+            0x00000017 => return self.auipc(c.rd(), c.uimmediate()),
+            _ => {}
+        }
+        self.illegal(code)
+    }
+}
+
+// End of auto-generated code.
+
+// This code was generated by `make_dispatcher.py -mczp`. Do not edit.
+
+/// A dispatcher for RV32IMC_Zicsr_Priv instructions.
+pub trait DispatchRv32imcZicsrPriv {
+    type Item;
+
+    /// Decodes the input word to an RV32IMC_Zicsr_Priv instruction and dispatches it to a handler.
+    fn dispatch(&mut self, code: u32) -> <Self as HandleRv32i>::Item
+    where
+        Self: HandleRv32i + HandleRv32m + HandleRv32c + HandleZicsr + HandlePrivileged;
+}
+
+impl<T, U> DispatchRv32imcZicsrPriv for T
+where
+    T: HandleRv32i
+        + HandleRv32i<Item = U>
+        + HandleRv32m
+        + HandleRv32m<Item = U>
+        + HandleRv32c
+        + HandleRv32c<Item = U>
+        + HandleZicsr
+        + HandleZicsr<Item = U>
+        + HandlePrivileged
+        + HandlePrivileged<Item = U>,
+{
+    type Item = U;
+
+    fn dispatch(&mut self, code: u32) -> Self::Item {
+        #![allow(clippy::single_match)]
+
+        let c = ToBits(code);
+
+        match code {
+            0x00000073 => return self.ecall(),
+            0x00100073 => return self.ebreak(),
+            0x10200073 => return self.sret(),
+            0x30200073 => return self.mret(),
+            0x10500073 => return self.wfi(),
+            _ => {}
+        }
+        match code & 0xfe007fff {
+            0x12000073 => return self.sfence_vma(c.rs1(), c.rs2()),
+            _ => {}
+        }
+        match code & 0xfe00707f {
+            0x00000033 => return self.add(c.rd(), c.rs1(), c.rs2()),
+            0x40000033 => return self.sub(c.rd(), c.rs1(), c.rs2()),
+            0x00001033 => return self.sll(c.rd(), c.rs1(), c.rs2()),
+            0x00002033 => return self.slt(c.rd(), c.rs1(), c.rs2()),
+            0x00003033 => return self.sltu(c.rd(), c.rs1(), c.rs2()),
+            0x00004033 => return self.xor(c.rd(), c.rs1(), c.rs2()),
+            0x00005033 => return self.srl(c.rd(), c.rs1(), c.rs2()),
+            0x40005033 => return self.sra(c.rd(), c.rs1(), c.rs2()),
+            0x00006033 => return self.or(c.rd(), c.rs1(), c.rs2()),
+            0x00007033 => return self.and(c.rd(), c.rs1(), c.rs2()),
+            0x00001013 => return self.slli(c.rd(), c.rs1(), c.shamtw()),
+            0x00005013 => return self.srli(c.rd(), c.rs1(), c.shamtw()),
+            0x40005013 => return self.srai(c.rd(), c.rs1(), c.shamtw()),
+            0x02000033 => return self.mul(c.rd(), c.rs1(), c.rs2()),
+            0x02001033 => return self.mulh(c.rd(), c.rs1(), c.rs2()),
+            0x02002033 => return self.mulhsu(c.rd(), c.rs1(), c.rs2()),
+            0x02003033 => return self.mulhu(c.rd(), c.rs1(), c.rs2()),
+            0x02004033 => return self.div(c.rd(), c.rs1(), c.rs2()),
+            0x02005033 => return self.divu(c.rd(), c.rs1(), c.rs2()),
+            0x02006033 => return self.rem(c.rd(), c.rs1(), c.rs2()),
+            0x02007033 => return self.remu(c.rd(), c.rs1(), c.rs2()),
+            _ => {}
+        }
+        match code & 0x0000ffff {
+            0x9002 => return self.c_ebreak(),
+            _ => {}
+        }
+        match code & 0x0000f07f {
+            0x8002 => return self.c_jr(c.rs1n0()),
+            0x9002 => return self.c_jalr(c.rs1n0()),
+            _ => {}
+        }
+        match code & 0x0000707f {
+            0x00000063 => return self.beq(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00001063 => return self.bne(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00004063 => return self.blt(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00005063 => return self.bge(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00006063 => return self.bltu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00007063 => return self.bgeu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00000067 => return self.jalr(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000013 => return self.addi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002013 => return self.slti(c.rd(), c.rs1(), c.iimmediate()),
+            0x00003013 => return self.sltiu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004013 => return self.xori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00006013 => return self.ori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00007013 => return self.andi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000003 => return self.lb(c.rd(), c.rs1(), c.iimmediate()),
+            0x00001003 => return self.lh(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002003 => return self.lw(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004003 => return self.lbu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00005003 => return self.lhu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000023 => return self.sb(c.rs1(), c.rs2(), c.simmediate()),
+            0x00001023 => return self.sh(c.rs1(), c.rs2(), c.simmediate()),
+            0x00002023 => return self.sw(c.rs1(), c.rs2(), c.simmediate()),
+            0x0000000f => return self.fence(c.fm(), c.rd(), c.rs1()),
+            0x00001073 => return self.csrrw(c.rd(), c.rs1(), c.csr()),
+            0x00002073 => return self.csrrs(c.rd(), c.rs1(), c.csr()),
+            0x00003073 => return self.csrrc(c.rd(), c.rs1(), c.csr()),
+            0x00005073 => return self.csrrwi(c.rd(), c.zimm(), c.csr()),
+            0x00006073 => return self.csrrsi(c.rd(), c.zimm(), c.csr()),
+            0x00007073 => return self.csrrci(c.rd(), c.zimm(), c.csr()),
+            _ => {}
+        }
+        match code & 0x0000ef83 {
+            0x0001 => return self.c_nop(c.c_nzimm6()),
+            0x6101 => return self.c_addi16sp(c.c_nzimm10()),
+            _ => {}
+        }
+        match code & 0x0000fc63 {
+            0x8c01 => return self.c_sub(c.rdrs1p(), c.rs2p()),
+            0x8c21 => return self.c_xor(c.rdrs1p(), c.rs2p()),
+            0x8c41 => return self.c_or(c.rdrs1p(), c.rs2p()),
+            0x8c61 => return self.c_and(c.rdrs1p(), c.rs2p()),
+            _ => {}
+        }
+        match code & 0x0000007f {
+            0x0000006f => return self.jal(c.rd(), c.jimmediate()),
+            0x00000037 => return self.lui(c.rd(), c.uimmediate()),
+            0x00000017 => return self.auipc(c.rd(), c.uimmediate()),
+            _ => {}
+        }
+        match code & 0x0000ec03 {
+            0x8801 => return self.c_andi(c.rdrs1p(), c.c_imm6()),
+            0x8001 => return self.c_srli(c.rdrs1p(), c.c_nzuimm6()),
+            0x8401 => return self.c_srai(c.rdrs1p(), c.c_nzuimm6()),
+            _ => {}
+        }
+        match code & 0x0000f003 {
+            0x8002 => return self.c_mv(c.rd(), c.rs2n0()),
+            0x9002 => return self.c_add(c.rdrs1(), c.rs2n0()),
+            _ => {}
+        }
+        match code & 0x0000e003 {
+            0x0000 => return self.c_addi4spn(c.rdp(), c.c_nzuimm10()),
+            0x4000 => return self.c_lw(c.rdp(), c.rs1p(), c.c_uimm7()),
+            0xc000 => return self.c_sw(c.rs1p(), c.rs2p(), c.c_uimm7()),
+            0x0001 => return self.c_addi(c.rdrs1n0(), c.c_nzimm6()),
+            0x4001 => return self.c_li(c.rd(), c.c_imm6()),
+            0x6001 => return self.c_lui(c.rdn2(), c.c_nzimm18()),
+            0xa001 => return self.c_j(c.c_imm12()),
+            0xc001 => return self.c_beqz(c.rs1p(), c.c_bimm9()),
+            0xe001 => return self.c_bnez(c.rs1p(), c.c_bimm9()),
+            0x4002 => return self.c_lwsp(c.rdn0(), c.c_uimm8sp()),
+            0xc002 => return self.c_swsp(c.c_rs2(), c.c_uimm8sp_s()),
+            0x2001 => return self.c_jal(c.c_imm12()),
+            0x0002 => return self.c_slli(c.rdrs1n0(), c.c_nzuimm6()),
+            _ => {}
+        }
+        self.illegal(code)
+    }
+}
+
+// End of auto-generated code.
+
+// This code was generated by `make_dispatcher.py -ma`. Do not edit.
+
+/// A dispatcher for RV32IMA instructions.
+pub trait DispatchRv32ima {
+    type Item;
+
+    /// Decodes the input word to an RV32IMA instruction and dispatches it to a handler.
+    fn dispatch(&mut self, code: u32) -> <Self as HandleRv32i>::Item
+    where
+        Self: HandleRv32i + HandleRv32m + HandleRv32a;
+}
+
+impl<T, U> DispatchRv32ima for T
+where
+    T: HandleRv32i
+        + HandleRv32i<Item = U>
+        + HandleRv32m
+        + HandleRv32m<Item = U>
+        + HandleRv32a
+        + HandleRv32a<Item = U>,
+{
+    type Item = U;
+
+    fn dispatch(&mut self, code: u32) -> Self::Item {
+        #![allow(clippy::single_match)]
+
+        let c = ToBits(code);
+
+        match code {
+            0x00000073 => return self.ecall(),
+            0x00100073 => return self.ebreak(),
+            _ => {}
+        }
+        match code & 0xf9f0707f {
+            0x1000202f => return self.lr_w(c.rd(), c.rs1(), c.aqrl()),
+            _ => {}
+        }
+        match code & 0xfe00707f {
+            0x00000033 => return self.add(c.rd(), c.rs1(), c.rs2()),
+            0x40000033 => return self.sub(c.rd(), c.rs1(), c.rs2()),
+            0x00001033 => return self.sll(c.rd(), c.rs1(), c.rs2()),
+            0x00002033 => return self.slt(c.rd(), c.rs1(), c.rs2()),
+            0x00003033 => return self.sltu(c.rd(), c.rs1(), c.rs2()),
+            0x00004033 => return self.xor(c.rd(), c.rs1(), c.rs2()),
+            0x00005033 => return self.srl(c.rd(), c.rs1(), c.rs2()),
+            0x40005033 => return self.sra(c.rd(), c.rs1(), c.rs2()),
+            0x00006033 => return self.or(c.rd(), c.rs1(), c.rs2()),
+            0x00007033 => return self.and(c.rd(), c.rs1(), c.rs2()),
+            0x00001013 => return self.slli(c.rd(), c.rs1(), c.shamtw()),
+            0x00005013 => return self.srli(c.rd(), c.rs1(), c.shamtw()),
+            0x40005013 => return self.srai(c.rd(), c.rs1(), c.shamtw()),
+            0x02000033 => return self.mul(c.rd(), c.rs1(), c.rs2()),
+            0x02001033 => return self.mulh(c.rd(), c.rs1(), c.rs2()),
+            0x02002033 => return self.mulhsu(c.rd(), c.rs1(), c.rs2()),
+            0x02003033 => return self.mulhu(c.rd(), c.rs1(), c.rs2()),
+            0x02004033 => return self.div(c.rd(), c.rs1(), c.rs2()),
+            0x02005033 => return self.divu(c.rd(), c.rs1(), c.rs2()),
+            0x02006033 => return self.rem(c.rd(), c.rs1(), c.rs2()),
+            0x02007033 => return self.remu(c.rd(), c.rs1(), c.rs2()),
+            _ => {}
+        }
+        match code & 0xf800707f {
+            0x1800202f => return self.sc_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x0800202f => return self.amoswap_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x0000202f => return self.amoadd_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x2000202f => return self.amoxor_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x6000202f => return self.amoand_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x4000202f => return self.amoor_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x8000202f => return self.amomin_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0xa000202f => return self.amomax_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0xc000202f => return self.amominu_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0xe000202f => return self.amomaxu_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            _ => {}
+        }
+        match code & 0x0000707f {
+            0x00000063 => return self.beq(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00001063 => return self.bne(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00004063 => return self.blt(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00005063 => return self.bge(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00006063 => return self.bltu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00007063 => return self.bgeu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00000067 => return self.jalr(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000013 => return self.addi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002013 => return self.slti(c.rd(), c.rs1(), c.iimmediate()),
+            0x00003013 => return self.sltiu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004013 => return self.xori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00006013 => return self.ori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00007013 => return self.andi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000003 => return self.lb(c.rd(), c.rs1(), c.iimmediate()),
+            0x00001003 => return self.lh(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002003 => return self.lw(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004003 => return self.lbu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00005003 => return self.lhu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000023 => return self.sb(c.rs1(), c.rs2(), c.simmediate()),
+            0x00001023 => return self.sh(c.rs1(), c.rs2(), c.simmediate()),
+            0x00002023 => return self.sw(c.rs1(), c.rs2(), c.simmediate()),
+            0x0000000f => return self.fence(c.fm(), c.rd(), c.rs1()),
+            _ => {}
+        }
+        match code & 0x0000007f {
+            0x0000006f => return self.jal(c.rd(), c.jimmediate()),
+            0x00000037 => return self.lui(c.rd(), c.uimmediate()),
+            0x00000017 => return self.auipc(c.rd(), c.uimmediate()),
+            _ => {}
+        }
+        self.illegal(code)
+    }
+}
+
+// End of auto-generated code.
+
+// This code was generated by `make_dispatcher.py -mac`. Do not edit.
+
+/// A dispatcher for RV32IMAC instructions.
+pub trait DispatchRv32imac {
+    type Item;
+
+    /// Decodes the input word to an RV32IMAC instruction and dispatches it to a handler.
+    fn dispatch(&mut self, code: u32) -> <Self as HandleRv32i>::Item
+    where
+        Self: HandleRv32i + HandleRv32m + HandleRv32a + HandleRv32c;
+}
+
+impl<T, U> DispatchRv32imac for T
+where
+    T: HandleRv32i
+        + HandleRv32i<Item = U>
+        + HandleRv32m
+        + HandleRv32m<Item = U>
+        + HandleRv32a
+        + HandleRv32a<Item = U>
+        + HandleRv32c
+        + HandleRv32c<Item = U>,
+{
+    type Item = U;
+
+    fn dispatch(&mut self, code: u32) -> Self::Item {
+        #![allow(clippy::single_match)]
+
+        let c = ToBits(code);
+
+        match code {
+            0x00000073 => return self.ecall(),
+            0x00100073 => return self.ebreak(),
+            _ => {}
+        }
+        match code & 0xf9f0707f {
+            0x1000202f => return self.lr_w(c.rd(), c.rs1(), c.aqrl()),
+            _ => {}
+        }
+        match code & 0xfe00707f {
+            0x00000033 => return self.add(c.rd(), c.rs1(), c.rs2()),
+            0x40000033 => return self.sub(c.rd(), c.rs1(), c.rs2()),
+            0x00001033 => return self.sll(c.rd(), c.rs1(), c.rs2()),
+            0x00002033 => return self.slt(c.rd(), c.rs1(), c.rs2()),
+            0x00003033 => return self.sltu(c.rd(), c.rs1(), c.rs2()),
+            0x00004033 => return self.xor(c.rd(), c.rs1(), c.rs2()),
+            0x00005033 => return self.srl(c.rd(), c.rs1(), c.rs2()),
+            0x40005033 => return self.sra(c.rd(), c.rs1(), c.rs2()),
+            0x00006033 => return self.or(c.rd(), c.rs1(), c.rs2()),
+            0x00007033 => return self.and(c.rd(), c.rs1(), c.rs2()),
+            0x00001013 => return self.slli(c.rd(), c.rs1(), c.shamtw()),
+            0x00005013 => return self.srli(c.rd(), c.rs1(), c.shamtw()),
+            0x40005013 => return self.srai(c.rd(), c.rs1(), c.shamtw()),
+            0x02000033 => return self.mul(c.rd(), c.rs1(), c.rs2()),
+            0x02001033 => return self.mulh(c.rd(), c.rs1(), c.rs2()),
+            0x02002033 => return self.mulhsu(c.rd(), c.rs1(), c.rs2()),
+            0x02003033 => return self.mulhu(c.rd(), c.rs1(), c.rs2()),
+            0x02004033 => return self.div(c.rd(), c.rs1(), c.rs2()),
+            0x02005033 => return self.divu(c.rd(), c.rs1(), c.rs2()),
+            0x02006033 => return self.rem(c.rd(), c.rs1(), c.rs2()),
+            0x02007033 => return self.remu(c.rd(), c.rs1(), c.rs2()),
+            _ => {}
+        }
+        match code & 0x0000ffff {
+            0x9002 => return self.c_ebreak(),
+            _ => {}
+        }
+        match code & 0xf800707f {
+            0x1800202f => return self.sc_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x0800202f => return self.amoswap_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x0000202f => return self.amoadd_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x2000202f => return self.amoxor_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x6000202f => return self.amoand_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x4000202f => return self.amoor_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x8000202f => return self.amomin_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0xa000202f => return self.amomax_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0xc000202f => return self.amominu_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0xe000202f => return self.amomaxu_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            _ => {}
+        }
+        match code & 0x0000f07f {
+            0x8002 => return self.c_jr(c.rs1n0()),
+            0x9002 => return self.c_jalr(c.rs1n0()),
+            _ => {}
+        }
+        match code & 0x0000707f {
+            0x00000063 => return self.beq(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00001063 => return self.bne(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00004063 => return self.blt(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00005063 => return self.bge(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00006063 => return self.bltu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00007063 => return self.bgeu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00000067 => return self.jalr(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000013 => return self.addi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002013 => return self.slti(c.rd(), c.rs1(), c.iimmediate()),
+            0x00003013 => return self.sltiu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004013 => return self.xori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00006013 => return self.ori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00007013 => return self.andi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000003 => return self.lb(c.rd(), c.rs1(), c.iimmediate()),
+            0x00001003 => return self.lh(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002003 => return self.lw(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004003 => return self.lbu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00005003 => return self.lhu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000023 => return self.sb(c.rs1(), c.rs2(), c.simmediate()),
+            0x00001023 => return self.sh(c.rs1(), c.rs2(), c.simmediate()),
+            0x00002023 => return self.sw(c.rs1(), c.rs2(), c.simmediate()),
+            0x0000000f => return self.fence(c.fm(), c.rd(), c.rs1()),
+            _ => {}
+        }
+        match code & 0x0000ef83 {
+            0x0001 => return self.c_nop(c.c_nzimm6()),
+            0x6101 => return self.c_addi16sp(c.c_nzimm10()),
+            _ => {}
+        }
+        match code & 0x0000fc63 {
+            0x8c01 => return self.c_sub(c.rdrs1p(), c.rs2p()),
+            0x8c21 => return self.c_xor(c.rdrs1p(), c.rs2p()),
+            0x8c41 => return self.c_or(c.rdrs1p(), c.rs2p()),
+            0x8c61 => return self.c_and(c.rdrs1p(), c.rs2p()),
+            _ => {}
+        }
+        match code & 0x0000007f {
+            0x0000006f => return self.jal(c.rd(), c.jimmediate()),
+            0x00000037 => return self.lui(c.rd(), c.uimmediate()),
+            0x00000017 => return self.auipc(c.rd(), c.uimmediate()),
+            _ => {}
+        }
+        match code & 0x0000ec03 {
+            0x8801 => return self.c_andi(c.rdrs1p(), c.c_imm6()),
+            0x8001 => return self.c_srli(c.rdrs1p(), c.c_nzuimm6()),
+            0x8401 => return self.c_srai(c.rdrs1p(), c.c_nzuimm6()),
+            _ => {}
+        }
+        match code & 0x0000f003 {
+            0x8002 => return self.c_mv(c.rd(), c.rs2n0()),
+            0x9002 => return self.c_add(c.rdrs1(), c.rs2n0()),
+            _ => {}
+        }
+        match code & 0x0000e003 {
+            0x0000 => return self.c_addi4spn(c.rdp(), c.c_nzuimm10()),
+            0x4000 => return self.c_lw(c.rdp(), c.rs1p(), c.c_uimm7()),
+            0xc000 => return self.c_sw(c.rs1p(), c.rs2p(), c.c_uimm7()),
+            0x0001 => return self.c_addi(c.rdrs1n0(), c.c_nzimm6()),
+            0x4001 => return self.c_li(c.rd(), c.c_imm6()),
+            0x6001 => return self.c_lui(c.rdn2(), c.c_nzimm18()),
+            0xa001 => return self.c_j(c.c_imm12()),
+            0xc001 => return self.c_beqz(c.rs1p(), c.c_bimm9()),
+            0xe001 => return self.c_bnez(c.rs1p(), c.c_bimm9()),
+            0x4002 => return self.c_lwsp(c.rdn0(), c.c_uimm8sp()),
+            0xc002 => return self.c_swsp(c.c_rs2(), c.c_uimm8sp_s()),
+            0x2001 => return self.c_jal(c.c_imm12()),
+            0x0002 => return self.c_slli(c.rdrs1n0(), c.c_nzuimm6()),
+            _ => {}
+        }
+        self.illegal(code)
+    }
+}
+
+// End of auto-generated code.
+
+// This code was generated by `make_dispatcher.py -mafc`. Do not edit.
+
+/// A dispatcher for RV32IMAFC instructions.
+pub trait DispatchRv32imafc {
+    type Item;
+
+    /// Decodes the input word to an RV32IMAFC instruction and dispatches it to a handler.
+    fn dispatch(&mut self, code: u32) -> <Self as HandleRv32i>::Item
+    where
+        Self: HandleRv32i + HandleRv32m + HandleRv32a + HandleRv32f + HandleRv32c;
+}
+
+impl<T, U> DispatchRv32imafc for T
+where
+    T: HandleRv32i
+        + HandleRv32i<Item = U>
+        + HandleRv32m
+        + HandleRv32m<Item = U>
+        + HandleRv32a
+        + HandleRv32a<Item = U>
+        + HandleRv32f
+        + HandleRv32f<Item = U>
+        + HandleRv32c
+        + HandleRv32c<Item = U>,
+{
+    type Item = U;
+
+    fn dispatch(&mut self, code: u32) -> Self::Item {
+        #![allow(clippy::single_match)]
+
+        let c = ToBits(code);
+
+        match code {
+            0x00000073 => return self.ecall(),
+            0x00100073 => return self.ebreak(),
+            _ => {}
+        }
+        match code & 0xfff0707f {
+            0xe0000053 => return self.fmv_x_w(c.rd(), c.rs1()),
+            0xe0001053 => return self.fclass_s(c.rd(), c.rs1()),
+            0xf0000053 => return self.fmv_w_x(c.rd(), c.rs1()),
+            _ => {}
+        }
+        match code & 0xf9f0707f {
+            0x1000202f => return self.lr_w(c.rd(), c.rs1(), c.aqrl()),
+            _ => {}
+        }
+        match code & 0xfff0007f {
+            0x58000053 => return self.fsqrt_s(c.rd(), c.rs1(), c.rm()),
+            0xc0000053 => return self.fcvt_w_s(c.rd(), c.rs1(), c.rm()),
+            0xc0100053 => return self.fcvt_wu_s(c.rd(), c.rs1(), c.rm()),
+            0xd0000053 => return self.fcvt_s_w(c.rd(), c.rs1(), c.rm()),
+            0xd0100053 => return self.fcvt_s_wu(c.rd(), c.rs1(), c.rm()),
+            _ => {}
+        }
+        match code & 0xfe00707f {
+            0x00000033 => return self.add(c.rd(), c.rs1(), c.rs2()),
+            0x40000033 => return self.sub(c.rd(), c.rs1(), c.rs2()),
+            0x00001033 => return self.sll(c.rd(), c.rs1(), c.rs2()),
+            0x00002033 => return self.slt(c.rd(), c.rs1(), c.rs2()),
+            0x00003033 => return self.sltu(c.rd(), c.rs1(), c.rs2()),
+            0x00004033 => return self.xor(c.rd(), c.rs1(), c.rs2()),
+            0x00005033 => return self.srl(c.rd(), c.rs1(), c.rs2()),
+            0x40005033 => return self.sra(c.rd(), c.rs1(), c.rs2()),
+            0x00006033 => return self.or(c.rd(), c.rs1(), c.rs2()),
+            0x00007033 => return self.and(c.rd(), c.rs1(), c.rs2()),
+            0x00001013 => return self.slli(c.rd(), c.rs1(), c.shamtw()),
+            0x00005013 => return self.srli(c.rd(), c.rs1(), c.shamtw()),
+            0x40005013 => return self.srai(c.rd(), c.rs1(), c.shamtw()),
+            0x02000033 => return self.mul(c.rd(), c.rs1(), c.rs2()),
+            0x02001033 => return self.mulh(c.rd(), c.rs1(), c.rs2()),
+            0x02002033 => return self.mulhsu(c.rd(), c.rs1(), c.rs2()),
+            0x02003033 => return self.mulhu(c.rd(), c.rs1(), c.rs2()),
+            0x02004033 => return self.div(c.rd(), c.rs1(), c.rs2()),
+            0x02005033 => return self.divu(c.rd(), c.rs1(), c.rs2()),
+            0x02006033 => return self.rem(c.rd(), c.rs1(), c.rs2()),
+            0x02007033 => return self.remu(c.rd(), c.rs1(), c.rs2()),
+            _ => {}
+        }
+        match code & 0xfe00707f {
+            0x20000053 => return self.fsgnj_s(c.rd(), c.rs1(), c.rs2()),
+            0x20001053 => return self.fsgnjn_s(c.rd(), c.rs1(), c.rs2()),
+            0x20002053 => return self.fsgnjx_s(c.rd(), c.rs1(), c.rs2()),
+            0x28000053 => return self.fmin_s(c.rd(), c.rs1(), c.rs2()),
+            0x28001053 => return self.fmax_s(c.rd(), c.rs1(), c.rs2()),
+            0xa0000053 => return self.fle_s(c.rd(), c.rs1(), c.rs2()),
+            0xa0001053 => return self.flt_s(c.rd(), c.rs1(), c.rs2()),
+            0xa0002053 => return self.feq_s(c.rd(), c.rs1(), c.rs2()),
+            _ => {}
+        }
+        match code & 0x0000ffff {
+            0x9002 => return self.c_ebreak(),
+            _ => {}
+        }
+        match code & 0xf800707f {
+            0x1800202f => return self.sc_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x0800202f => return self.amoswap_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x0000202f => return self.amoadd_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x2000202f => return self.amoxor_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x6000202f => return self.amoand_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x4000202f => return self.amoor_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x8000202f => return self.amomin_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0xa000202f => return self.amomax_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0xc000202f => return self.amominu_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0xe000202f => return self.amomaxu_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            _ => {}
+        }
+        match code & 0xfe00007f {
+            0x00000053 => return self.fadd_s(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x08000053 => return self.fsub_s(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x10000053 => return self.fmul_s(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x18000053 => return self.fdiv_s(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            _ => {}
+        }
+        match code & 0x0000f07f {
+            0x8002 => return self.c_jr(c.rs1n0()),
+            0x9002 => return self.c_jalr(c.rs1n0()),
+            _ => {}
+        }
+        match code & 0x0000707f {
+            0x00000063 => return self.beq(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00001063 => return self.bne(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00004063 => return self.blt(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00005063 => return self.bge(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00006063 => return self.bltu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00007063 => return self.bgeu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00000067 => return self.jalr(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000013 => return self.addi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002013 => return self.slti(c.rd(), c.rs1(), c.iimmediate()),
+            0x00003013 => return self.sltiu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004013 => return self.xori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00006013 => return self.ori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00007013 => return self.andi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000003 => return self.lb(c.rd(), c.rs1(), c.iimmediate()),
+            0x00001003 => return self.lh(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002003 => return self.lw(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004003 => return self.lbu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00005003 => return self.lhu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000023 => return self.sb(c.rs1(), c.rs2(), c.simmediate()),
+            0x00001023 => return self.sh(c.rs1(), c.rs2(), c.simmediate()),
+            0x00002023 => return self.sw(c.rs1(), c.rs2(), c.simmediate()),
+            0x0000000f => return self.fence(c.fm(), c.rd(), c.rs1()),
+            0x00002007 => return self.flw(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002027 => return self.fsw(c.rs1(), c.rs2(), c.simmediate()),
+            _ => {}
+        }
+        match code & 0x0000ef83 {
+            0x0001 => return self.c_nop(c.c_nzimm6()),
+            0x6101 => return self.c_addi16sp(c.c_nzimm10()),
+            _ => {}
+        }
+        match code & 0x0000fc63 {
+            0x8c01 => return self.c_sub(c.rdrs1p(), c.rs2p()),
+            0x8c21 => return self.c_xor(c.rdrs1p(), c.rs2p()),
+            0x8c41 => return self.c_or(c.rdrs1p(), c.rs2p()),
+            0x8c61 => return self.c_and(c.rdrs1p(), c.rs2p()),
+            _ => {}
+        }
+        match code & 0x0600007f {
+            0x00000043 => return self.fmadd_s(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x00000047 => return self.fmsub_s(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x0000004b => return self.fnmsub_s(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x0000004f => return self.fnmadd_s(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            _ => {}
+        }
+        match code & 0x0000007f {
+            0x0000006f => return self.jal(c.rd(), c.jimmediate()),
+            0x00000037 => return self.lui(c.rd(), c.uimmediate()),
+            0x00000017 => return self.auipc(c.rd(), c.uimmediate()),
+            _ => {}
+        }
+        match code & 0x0000ec03 {
+            0x8801 => return self.c_andi(c.rdrs1p(), c.c_imm6()),
+            0x8001 => return self.c_srli(c.rdrs1p(), c.c_nzuimm6()),
+            0x8401 => return self.c_srai(c.rdrs1p(), c.c_nzuimm6()),
+            _ => {}
+        }
+        match code & 0x0000f003 {
+            0x8002 => return self.c_mv(c.rd(), c.rs2n0()),
+            0x9002 => return self.c_add(c.rdrs1(), c.rs2n0()),
+            _ => {}
+        }
+        match code & 0x0000e003 {
+            0x0000 => return self.c_addi4spn(c.rdp(), c.c_nzuimm10()),
+            0x4000 => return self.c_lw(c.rdp(), c.rs1p(), c.c_uimm7()),
+            0xc000 => return self.c_sw(c.rs1p(), c.rs2p(), c.c_uimm7()),
+            0x0001 => return self.c_addi(c.rdrs1n0(), c.c_nzimm6()),
+            0x4001 => return self.c_li(c.rd(), c.c_imm6()),
+            0x6001 => return self.c_lui(c.rdn2(), c.c_nzimm18()),
+            0xa001 => return self.c_j(c.c_imm12()),
+            0xc001 => return self.c_beqz(c.rs1p(), c.c_bimm9()),
+            0xe001 => return self.c_bnez(c.rs1p(), c.c_bimm9()),
+            0x4002 => return self.c_lwsp(c.rdn0(), c.c_uimm8sp()),
+            0xc002 => return self.c_swsp(c.c_rs2(), c.c_uimm8sp_s()),
+            0x2001 => return self.c_jal(c.c_imm12()),
+            0x0002 => return self.c_slli(c.rdrs1n0(), c.c_nzuimm6()),
+            0x6000 => return self.c_flw(c.rdp(), c.rs1p(), c.c_uimm7()),
+            0xe000 => return self.c_fsw(c.rs1p(), c.rs2p(), c.c_uimm7()),
+            0x6002 => return self.c_flwsp(c.rd(), c.c_uimm8sp()),
+            0xe002 => return self.c_fswsp(c.c_rs2(), c.c_uimm8sp_s()),
+            _ => {}
+        }
+        self.illegal(code)
+    }
+}
+
+// End of auto-generated code.
+
+// This code was generated by `make_dispatcher.py -maczp`. Do not edit.
+
+/// A dispatcher for RV32IMAC_Zicsr_Priv instructions.
+pub trait DispatchRv32imacZicsrPriv {
+    type Item;
+
+    /// Decodes the input word to an RV32IMAC_Zicsr_Priv instruction and dispatches it to a handler.
+    fn dispatch(&mut self, code: u32) -> <Self as HandleRv32i>::Item
+    where
+        Self:
+            HandleRv32i + HandleRv32m + HandleRv32a + HandleRv32c + HandleZicsr + HandlePrivileged;
+}
+
+impl<T, U> DispatchRv32imacZicsrPriv for T
+where
+    T: HandleRv32i
+        + HandleRv32i<Item = U>
+        + HandleRv32m
+        + HandleRv32m<Item = U>
+        + HandleRv32a
+        + HandleRv32a<Item = U>
+        + HandleRv32c
+        + HandleRv32c<Item = U>
+        + HandleZicsr
+        + HandleZicsr<Item = U>
+        + HandlePrivileged
+        + HandlePrivileged<Item = U>,
+{
+    type Item = U;
+
+    fn dispatch(&mut self, code: u32) -> Self::Item {
+        #![allow(clippy::single_match)]
+
+        let c = ToBits(code);
+
+        match code {
+            0x00000073 => return self.ecall(),
+            0x00100073 => return self.ebreak(),
+            0x10200073 => return self.sret(),
+            0x30200073 => return self.mret(),
+            0x10500073 => return self.wfi(),
+            _ => {}
+        }
+        match code & 0xfe007fff {
+            0x12000073 => return self.sfence_vma(c.rs1(), c.rs2()),
+            _ => {}
+        }
+        match code & 0xf9f0707f {
+            0x1000202f => return self.lr_w(c.rd(), c.rs1(), c.aqrl()),
+            _ => {}
+        }
+        match code & 0xfe00707f {
+            0x00000033 => return self.add(c.rd(), c.rs1(), c.rs2()),
+            0x40000033 => return self.sub(c.rd(), c.rs1(), c.rs2()),
+            0x00001033 => return self.sll(c.rd(), c.rs1(), c.rs2()),
+            0x00002033 => return self.slt(c.rd(), c.rs1(), c.rs2()),
+            0x00003033 => return self.sltu(c.rd(), c.rs1(), c.rs2()),
+            0x00004033 => return self.xor(c.rd(), c.rs1(), c.rs2()),
+            0x00005033 => return self.srl(c.rd(), c.rs1(), c.rs2()),
+            0x40005033 => return self.sra(c.rd(), c.rs1(), c.rs2()),
+            0x00006033 => return self.or(c.rd(), c.rs1(), c.rs2()),
+            0x00007033 => return self.and(c.rd(), c.rs1(), c.rs2()),
+            0x00001013 => return self.slli(c.rd(), c.rs1(), c.shamtw()),
+            0x00005013 => return self.srli(c.rd(), c.rs1(), c.shamtw()),
+            0x40005013 => return self.srai(c.rd(), c.rs1(), c.shamtw()),
+            0x02000033 => return self.mul(c.rd(), c.rs1(), c.rs2()),
+            0x02001033 => return self.mulh(c.rd(), c.rs1(), c.rs2()),
+            0x02002033 => return self.mulhsu(c.rd(), c.rs1(), c.rs2()),
+            0x02003033 => return self.mulhu(c.rd(), c.rs1(), c.rs2()),
+            0x02004033 => return self.div(c.rd(), c.rs1(), c.rs2()),
+            0x02005033 => return self.divu(c.rd(), c.rs1(), c.rs2()),
+            0x02006033 => return self.rem(c.rd(), c.rs1(), c.rs2()),
+            0x02007033 => return self.remu(c.rd(), c.rs1(), c.rs2()),
+            _ => {}
+        }
+        match code & 0x0000ffff {
+            0x9002 => return self.c_ebreak(),
+            _ => {}
+        }
+        match code & 0xf800707f {
+            0x1800202f => return self.sc_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x0800202f => return self.amoswap_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x0000202f => return self.amoadd_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x2000202f => return self.amoxor_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x6000202f => return self.amoand_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x4000202f => return self.amoor_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x8000202f => return self.amomin_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0xa000202f => return self.amomax_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0xc000202f => return self.amominu_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0xe000202f => return self.amomaxu_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            _ => {}
+        }
+        match code & 0x0000f07f {
+            0x8002 => return self.c_jr(c.rs1n0()),
+            0x9002 => return self.c_jalr(c.rs1n0()),
+            _ => {}
+        }
+        match code & 0x0000707f {
+            0x00000063 => return self.beq(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00001063 => return self.bne(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00004063 => return self.blt(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00005063 => return self.bge(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00006063 => return self.bltu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00007063 => return self.bgeu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00000067 => return self.jalr(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000013 => return self.addi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002013 => return self.slti(c.rd(), c.rs1(), c.iimmediate()),
+            0x00003013 => return self.sltiu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004013 => return self.xori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00006013 => return self.ori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00007013 => return self.andi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000003 => return self.lb(c.rd(), c.rs1(), c.iimmediate()),
+            0x00001003 => return self.lh(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002003 => return self.lw(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004003 => return self.lbu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00005003 => return self.lhu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000023 => return self.sb(c.rs1(), c.rs2(), c.simmediate()),
+            0x00001023 => return self.sh(c.rs1(), c.rs2(), c.simmediate()),
+            0x00002023 => return self.sw(c.rs1(), c.rs2(), c.simmediate()),
+            0x0000000f => return self.fence(c.fm(), c.rd(), c.rs1()),
+            0x00001073 => return self.csrrw(c.rd(), c.rs1(), c.csr()),
+            0x00002073 => return self.csrrs(c.rd(), c.rs1(), c.csr()),
+            0x00003073 => return self.csrrc(c.rd(), c.rs1(), c.csr()),
+            0x00005073 => return self.csrrwi(c.rd(), c.zimm(), c.csr()),
+            0x00006073 => return self.csrrsi(c.rd(), c.zimm(), c.csr()),
+            0x00007073 => return self.csrrci(c.rd(), c.zimm(), c.csr()),
+            _ => {}
+        }
+        match code & 0x0000ef83 {
+            0x0001 => return self.c_nop(c.c_nzimm6()),
+            0x6101 => return self.c_addi16sp(c.c_nzimm10()),
+            _ => {}
+        }
+        match code & 0x0000fc63 {
+            0x8c01 => return self.c_sub(c.rdrs1p(), c.rs2p()),
+            0x8c21 => return self.c_xor(c.rdrs1p(), c.rs2p()),
+            0x8c41 => return self.c_or(c.rdrs1p(), c.rs2p()),
+            0x8c61 => return self.c_and(c.rdrs1p(), c.rs2p()),
+            _ => {}
+        }
+        match code & 0x0000007f {
+            0x0000006f => return self.jal(c.rd(), c.jimmediate()),
+            0x00000037 => return self.lui(c.rd(), c.uimmediate()),
+            0x00000017 => return self.auipc(c.rd(), c.uimmediate()),
+            _ => {}
+        }
+        match code & 0x0000ec03 {
+            0x8801 => return self.c_andi(c.rdrs1p(), c.c_imm6()),
+            0x8001 => return self.c_srli(c.rdrs1p(), c.c_nzuimm6()),
+            0x8401 => return self.c_srai(c.rdrs1p(), c.c_nzuimm6()),
+            _ => {}
+        }
+        match code & 0x0000f003 {
+            0x8002 => return self.c_mv(c.rd(), c.rs2n0()),
+            0x9002 => return self.c_add(c.rdrs1(), c.rs2n0()),
+            _ => {}
+        }
+        match code & 0x0000e003 {
+            0x0000 => return self.c_addi4spn(c.rdp(), c.c_nzuimm10()),
+            0x4000 => return self.c_lw(c.rdp(), c.rs1p(), c.c_uimm7()),
+            0xc000 => return self.c_sw(c.rs1p(), c.rs2p(), c.c_uimm7()),
+            0x0001 => return self.c_addi(c.rdrs1n0(), c.c_nzimm6()),
+            0x4001 => return self.c_li(c.rd(), c.c_imm6()),
+            0x6001 => return self.c_lui(c.rdn2(), c.c_nzimm18()),
+            0xa001 => return self.c_j(c.c_imm12()),
+            0xc001 => return self.c_beqz(c.rs1p(), c.c_bimm9()),
+            0xe001 => return self.c_bnez(c.rs1p(), c.c_bimm9()),
+            0x4002 => return self.c_lwsp(c.rdn0(), c.c_uimm8sp()),
+            0xc002 => return self.c_swsp(c.c_rs2(), c.c_uimm8sp_s()),
+            0x2001 => return self.c_jal(c.c_imm12()),
+            0x0002 => return self.c_slli(c.rdrs1n0(), c.c_nzuimm6()),
+            _ => {}
+        }
+        self.illegal(code)
+    }
+}
+
+// End of auto-generated code.
+
+// This code was generated by `make_dispatcher.py -mfdc`. Do not edit.
+
+/// A dispatcher for RV32IMFDC instructions.
+pub trait DispatchRv32imfdc {
+    type Item;
+
+    /// Decodes the input word to an RV32IMFDC instruction and dispatches it to a handler.
+    fn dispatch(&mut self, code: u32) -> <Self as HandleRv32i>::Item
+    where
+        Self: HandleRv32i + HandleRv32m + HandleRv32f + HandleRv32d + HandleRv32c;
+}
+
+impl<T, U> DispatchRv32imfdc for T
+where
+    T: HandleRv32i
+        + HandleRv32i<Item = U>
+        + HandleRv32m
+        + HandleRv32m<Item = U>
+        + HandleRv32f
+        + HandleRv32f<Item = U>
+        + HandleRv32d
+        + HandleRv32d<Item = U>
+        + HandleRv32c
+        + HandleRv32c<Item = U>,
+{
+    type Item = U;
+
+    fn dispatch(&mut self, code: u32) -> Self::Item {
+        #![allow(clippy::single_match)]
+
+        let c = ToBits(code);
+
+        match code {
+            0x00000073 => return self.ecall(),
+            0x00100073 => return self.ebreak(),
+            _ => {}
+        }
+        match code & 0xfff0707f {
+            0xe0000053 => return self.fmv_x_w(c.rd(), c.rs1()),
+            0xe0001053 => return self.fclass_s(c.rd(), c.rs1()),
+            0xf0000053 => return self.fmv_w_x(c.rd(), c.rs1()),
+            0xe2001053 => return self.fclass_d(c.rd(), c.rs1()),
+            _ => {}
+        }
+        match code & 0xfff0007f {
+            0x58000053 => return self.fsqrt_s(c.rd(), c.rs1(), c.rm()),
+            0xc0000053 => return self.fcvt_w_s(c.rd(), c.rs1(), c.rm()),
+            0xc0100053 => return self.fcvt_wu_s(c.rd(), c.rs1(), c.rm()),
+            0xd0000053 => return self.fcvt_s_w(c.rd(), c.rs1(), c.rm()),
+            0xd0100053 => return self.fcvt_s_wu(c.rd(), c.rs1(), c.rm()),
+            0x40100053 => return self.fcvt_s_d(c.rd(), c.rs1(), c.rm()),
+            0x42000053 => return self.fcvt_d_s(c.rd(), c.rs1(), c.rm()),
+            0x5a000053 => return self.fsqrt_d(c.rd(), c.rs1(), c.rm()),
+            0xc2000053 => return self.fcvt_w_d(c.rd(), c.rs1(), c.rm()),
+            0xc2100053 => return self.fcvt_wu_d(c.rd(), c.rs1(), c.rm()),
+            0xd2000053 => return self.fcvt_d_w(c.rd(), c.rs1(), c.rm()),
+            0xd2100053 => return self.fcvt_d_wu(c.rd(), c.rs1(), c.rm()),
+            _ => {}
+        }
+        match code & 0xfe00707f {
+            0x00000033 => return self.add(c.rd(), c.rs1(), c.rs2()),
+            0x40000033 => return self.sub(c.rd(), c.rs1(), c.rs2()),
+            0x00001033 => return self.sll(c.rd(), c.rs1(), c.rs2()),
+            0x00002033 => return self.slt(c.rd(), c.rs1(), c.rs2()),
+            0x00003033 => return self.sltu(c.rd(), c.rs1(), c.rs2()),
+            0x00004033 => return self.xor(c.rd(), c.rs1(), c.rs2()),
+            0x00005033 => return self.srl(c.rd(), c.rs1(), c.rs2()),
+            0x40005033 => return self.sra(c.rd(), c.rs1(), c.rs2()),
+            0x00006033 => return self.or(c.rd(), c.rs1(), c.rs2()),
+            0x00007033 => return self.and(c.rd(), c.rs1(), c.rs2()),
+            0x00001013 => return self.slli(c.rd(), c.rs1(), c.shamtw()),
+            0x00005013 => return self.srli(c.rd(), c.rs1(), c.shamtw()),
+            0x40005013 => return self.srai(c.rd(), c.rs1(), c.shamtw()),
+            0x02000033 => return self.mul(c.rd(), c.rs1(), c.rs2()),
+            0x02001033 => return self.mulh(c.rd(), c.rs1(), c.rs2()),
+            0x02002033 => return self.mulhsu(c.rd(), c.rs1(), c.rs2()),
+            0x02003033 => return self.mulhu(c.rd(), c.rs1(), c.rs2()),
+            0x02004033 => return self.div(c.rd(), c.rs1(), c.rs2()),
+            0x02005033 => return self.divu(c.rd(), c.rs1(), c.rs2()),
+            0x02006033 => return self.rem(c.rd(), c.rs1(), c.rs2()),
+            0x02007033 => return self.remu(c.rd(), c.rs1(), c.rs2()),
+            _ => {}
+        }
+        match code & 0xfe00707f {
+            0x20000053 => return self.fsgnj_s(c.rd(), c.rs1(), c.rs2()),
+            0x20001053 => return self.fsgnjn_s(c.rd(), c.rs1(), c.rs2()),
+            0x20002053 => return self.fsgnjx_s(c.rd(), c.rs1(), c.rs2()),
+            0x28000053 => return self.fmin_s(c.rd(), c.rs1(), c.rs2()),
+            0x28001053 => return self.fmax_s(c.rd(), c.rs1(), c.rs2()),
+            0xa0000053 => return self.fle_s(c.rd(), c.rs1(), c.rs2()),
+            0xa0001053 => return self.flt_s(c.rd(), c.rs1(), c.rs2()),
+            0xa0002053 => return self.feq_s(c.rd(), c.rs1(), c.rs2()),
+            0x22000053 => return self.fsgnj_d(c.rd(), c.rs1(), c.rs2()),
+            0x22001053 => return self.fsgnjn_d(c.rd(), c.rs1(), c.rs2()),
+            0x22002053 => return self.fsgnjx_d(c.rd(), c.rs1(), c.rs2()),
+            0x2a000053 => return self.fmin_d(c.rd(), c.rs1(), c.rs2()),
+            0x2a001053 => return self.fmax_d(c.rd(), c.rs1(), c.rs2()),
+            0xa2000053 => return self.fle_d(c.rd(), c.rs1(), c.rs2()),
+            0xa2001053 => return self.flt_d(c.rd(), c.rs1(), c.rs2()),
+            0xa2002053 => return self.feq_d(c.rd(), c.rs1(), c.rs2()),
+            _ => {}
+        }
+        match code & 0x0000ffff {
+            0x9002 => return self.c_ebreak(),
+            _ => {}
+        }
+        match code & 0xfe00007f {
+            0x00000053 => return self.fadd_s(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x08000053 => return self.fsub_s(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x10000053 => return self.fmul_s(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x18000053 => return self.fdiv_s(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x02000053 => return self.fadd_d(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x0a000053 => return self.fsub_d(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x12000053 => return self.fmul_d(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x1a000053 => return self.fdiv_d(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            _ => {}
+        }
+        match code & 0x0000f07f {
+            0x8002 => return self.c_jr(c.rs1n0()),
+            0x9002 => return self.c_jalr(c.rs1n0()),
+            _ => {}
+        }
+        match code & 0x0000707f {
+            0x00000063 => return self.beq(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00001063 => return self.bne(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00004063 => return self.blt(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00005063 => return self.bge(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00006063 => return self.bltu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00007063 => return self.bgeu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00000067 => return self.jalr(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000013 => return self.addi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002013 => return self.slti(c.rd(), c.rs1(), c.iimmediate()),
+            0x00003013 => return self.sltiu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004013 => return self.xori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00006013 => return self.ori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00007013 => return self.andi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000003 => return self.lb(c.rd(), c.rs1(), c.iimmediate()),
+            0x00001003 => return self.lh(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002003 => return self.lw(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004003 => return self.lbu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00005003 => return self.lhu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000023 => return self.sb(c.rs1(), c.rs2(), c.simmediate()),
+            0x00001023 => return self.sh(c.rs1(), c.rs2(), c.simmediate()),
+            0x00002023 => return self.sw(c.rs1(), c.rs2(), c.simmediate()),
+            0x0000000f => return self.fence(c.fm(), c.rd(), c.rs1()),
+            0x00002007 => return self.flw(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002027 => return self.fsw(c.rs1(), c.rs2(), c.simmediate()),
+            0x00003007 => return self.fld(c.rd(), c.rs1(), c.iimmediate()),
+            0x00003027 => return self.fsd(c.rs1(), c.rs2(), c.simmediate()),
+            _ => {}
+        }
+        match code & 0x0000ef83 {
+            0x0001 => return self.c_nop(c.c_nzimm6()),
+            0x6101 => return self.c_addi16sp(c.c_nzimm10()),
+            _ => {}
+        }
+        match code & 0x0000fc63 {
+            0x8c01 => return self.c_sub(c.rdrs1p(), c.rs2p()),
+            0x8c21 => return self.c_xor(c.rdrs1p(), c.rs2p()),
+            0x8c41 => return self.c_or(c.rdrs1p(), c.rs2p()),
+            0x8c61 => return self.c_and(c.rdrs1p(), c.rs2p()),
+            _ => {}
+        }
+        match code & 0x0600007f {
+            0x00000043 => return self.fmadd_s(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x00000047 => return self.fmsub_s(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x0000004b => return self.fnmsub_s(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x0000004f => return self.fnmadd_s(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x02000043 => return self.fmadd_d(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x02000047 => return self.fmsub_d(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x0200004b => return self.fnmsub_d(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x0200004f => return self.fnmadd_d(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            _ => {}
+        }
+        match code & 0x0000007f {
+            0x0000006f => return self.jal(c.rd(), c.jimmediate()),
+            0x00000037 => return self.lui(c.rd(), c.uimmediate()),
+            0x00000017 => return self.auipc(c.rd(), c.uimmediate()),
+            _ => {}
+        }
+        match code & 0x0000ec03 {
+            0x8801 => return self.c_andi(c.rdrs1p(), c.c_imm6()),
+            0x8001 => return self.c_srli(c.rdrs1p(), c.c_nzuimm6()),
+            0x8401 => return self.c_srai(c.rdrs1p(), c.c_nzuimm6()),
+            _ => {}
+        }
+        match code & 0x0000f003 {
+            0x8002 => return self.c_mv(c.rd(), c.rs2n0()),
+            0x9002 => return self.c_add(c.rdrs1(), c.rs2n0()),
+            _ => {}
+        }
+        match code & 0x0000e003 {
+            0x0000 => return self.c_addi4spn(c.rdp(), c.c_nzuimm10()),
+            0x4000 => return self.c_lw(c.rdp(), c.rs1p(), c.c_uimm7()),
+            0xc000 => return self.c_sw(c.rs1p(), c.rs2p(), c.c_uimm7()),
+            0x0001 => return self.c_addi(c.rdrs1n0(), c.c_nzimm6()),
+            0x4001 => return self.c_li(c.rd(), c.c_imm6()),
+            0x6001 => return self.c_lui(c.rdn2(), c.c_nzimm18()),
+            0xa001 => return self.c_j(c.c_imm12()),
+            0xc001 => return self.c_beqz(c.rs1p(), c.c_bimm9()),
+            0xe001 => return self.c_bnez(c.rs1p(), c.c_bimm9()),
+            0x4002 => return self.c_lwsp(c.rdn0(), c.c_uimm8sp()),
+            0xc002 => return self.c_swsp(c.c_rs2(), c.c_uimm8sp_s()),
+            0x2001 => return self.c_jal(c.c_imm12()),
+            0x0002 => return self.c_slli(c.rdrs1n0(), c.c_nzuimm6()),
+            0x6000 => return self.c_flw(c.rdp(), c.rs1p(), c.c_uimm7()),
+            0xe000 => return self.c_fsw(c.rs1p(), c.rs2p(), c.c_uimm7()),
+            0x6002 => return self.c_flwsp(c.rd(), c.c_uimm8sp()),
+            0xe002 => return self.c_fswsp(c.c_rs2(), c.c_uimm8sp_s()),
+            0x2000 => return self.c_fld(c.rdp(), c.rs1p(), c.c_uimm8()),
+            0xa000 => return self.c_fsd(c.rs1p(), c.rs2p(), c.c_uimm8()),
+            0x2002 => return self.c_fldsp(c.rd(), c.c_uimm9sp()),
+            0xa002 => return self.c_fsdsp(c.c_rs2(), c.c_uimm9sp_s()),
+            _ => {}
+        }
+        self.illegal(code)
+    }
+}
+
+// End of auto-generated code.
+
+// This code was generated by `make_dispatcher.py -gc`. Do not edit.
+
+/// A dispatcher for RV32GC instructions.
+pub trait DispatchRv32gc {
+    type Item;
+
+    /// Decodes the input word to an RV32GC instruction and dispatches it to a handler.
+    fn dispatch(&mut self, code: u32) -> <Self as HandleRv32i>::Item
+    where
+        Self: HandleRv32i
+            + HandleRv32m
+            + HandleRv32a
+            + HandleRv32f
+            + HandleRv32d
+            + HandleRv32c
+            + HandleZicsr
+            + HandleZifencei;
+}
+
+impl<T, U> DispatchRv32gc for T
+where
+    T: HandleRv32i
+        + HandleRv32i<Item = U>
+        + HandleRv32m
+        + HandleRv32m<Item = U>
+        + HandleRv32a
+        + HandleRv32a<Item = U>
+        + HandleRv32f
+        + HandleRv32f<Item = U>
+        + HandleRv32d
+        + HandleRv32d<Item = U>
+        + HandleRv32c
+        + HandleRv32c<Item = U>
+        + HandleZicsr
+        + HandleZicsr<Item = U>
+        + HandleZifencei
+        + HandleZifencei<Item = U>,
+{
+    type Item = U;
+
+    fn dispatch(&mut self, code: u32) -> Self::Item {
+        #![allow(clippy::single_match)]
+
+        let c = ToBits(code);
+
+        match code {
+            0x00000073 => return self.ecall(),
+            0x00100073 => return self.ebreak(),
+            _ => {}
+        }
+        match code & 0xfff0707f {
+            0xe0000053 => return self.fmv_x_w(c.rd(), c.rs1()),
+            0xe0001053 => return self.fclass_s(c.rd(), c.rs1()),
+            0xf0000053 => return self.fmv_w_x(c.rd(), c.rs1()),
+            0xe2001053 => return self.fclass_d(c.rd(), c.rs1()),
+            _ => {}
+        }
+        match code & 0xf9f0707f {
+            0x1000202f => return self.lr_w(c.rd(), c.rs1(), c.aqrl()),
+            _ => {}
+        }
+        match code & 0xfff0007f {
+            0x58000053 => return self.fsqrt_s(c.rd(), c.rs1(), c.rm()),
+            0xc0000053 => return self.fcvt_w_s(c.rd(), c.rs1(), c.rm()),
+            0xc0100053 => return self.fcvt_wu_s(c.rd(), c.rs1(), c.rm()),
+            0xd0000053 => return self.fcvt_s_w(c.rd(), c.rs1(), c.rm()),
+            0xd0100053 => return self.fcvt_s_wu(c.rd(), c.rs1(), c.rm()),
+            0x40100053 => return self.fcvt_s_d(c.rd(), c.rs1(), c.rm()),
+            0x42000053 => return self.fcvt_d_s(c.rd(), c.rs1(), c.rm()),
+            0x5a000053 => return self.fsqrt_d(c.rd(), c.rs1(), c.rm()),
+            0xc2000053 => return self.fcvt_w_d(c.rd(), c.rs1(), c.rm()),
+            0xc2100053 => return self.fcvt_wu_d(c.rd(), c.rs1(), c.rm()),
+            0xd2000053 => return self.fcvt_d_w(c.rd(), c.rs1(), c.rm()),
+            0xd2100053 => return self.fcvt_d_wu(c.rd(), c.rs1(), c.rm()),
+            _ => {}
+        }
+        match code & 0xfe00707f {
+            0x00000033 => return self.add(c.rd(), c.rs1(), c.rs2()),
+            0x40000033 => return self.sub(c.rd(), c.rs1(), c.rs2()),
+            0x00001033 => return self.sll(c.rd(), c.rs1(), c.rs2()),
+            0x00002033 => return self.slt(c.rd(), c.rs1(), c.rs2()),
+            0x00003033 => return self.sltu(c.rd(), c.rs1(), c.rs2()),
+            0x00004033 => return self.xor(c.rd(), c.rs1(), c.rs2()),
+            0x00005033 => return self.srl(c.rd(), c.rs1(), c.rs2()),
+            0x40005033 => return self.sra(c.rd(), c.rs1(), c.rs2()),
+            0x00006033 => return self.or(c.rd(), c.rs1(), c.rs2()),
+            0x00007033 => return self.and(c.rd(), c.rs1(), c.rs2()),
+            0x00001013 => return self.slli(c.rd(), c.rs1(), c.shamtw()),
+            0x00005013 => return self.srli(c.rd(), c.rs1(), c.shamtw()),
+            0x40005013 => return self.srai(c.rd(), c.rs1(), c.shamtw()),
+            0x02000033 => return self.mul(c.rd(), c.rs1(), c.rs2()),
+            0x02001033 => return self.mulh(c.rd(), c.rs1(), c.rs2()),
+            0x02002033 => return self.mulhsu(c.rd(), c.rs1(), c.rs2()),
+            0x02003033 => return self.mulhu(c.rd(), c.rs1(), c.rs2()),
+            0x02004033 => return self.div(c.rd(), c.rs1(), c.rs2()),
+            0x02005033 => return self.divu(c.rd(), c.rs1(), c.rs2()),
+            0x02006033 => return self.rem(c.rd(), c.rs1(), c.rs2()),
+            0x02007033 => return self.remu(c.rd(), c.rs1(), c.rs2()),
+            _ => {}
+        }
+        match code & 0xfe00707f {
+            0x20000053 => return self.fsgnj_s(c.rd(), c.rs1(), c.rs2()),
+            0x20001053 => return self.fsgnjn_s(c.rd(), c.rs1(), c.rs2()),
+            0x20002053 => return self.fsgnjx_s(c.rd(), c.rs1(), c.rs2()),
+            0x28000053 => return self.fmin_s(c.rd(), c.rs1(), c.rs2()),
+            0x28001053 => return self.fmax_s(c.rd(), c.rs1(), c.rs2()),
+            0xa0000053 => return self.fle_s(c.rd(), c.rs1(), c.rs2()),
+            0xa0001053 => return self.flt_s(c.rd(), c.rs1(), c.rs2()),
+            0xa0002053 => return self.feq_s(c.rd(), c.rs1(), c.rs2()),
+            0x22000053 => return self.fsgnj_d(c.rd(), c.rs1(), c.rs2()),
+            0x22001053 => return self.fsgnjn_d(c.rd(), c.rs1(), c.rs2()),
+            0x22002053 => return self.fsgnjx_d(c.rd(), c.rs1(), c.rs2()),
+            0x2a000053 => return self.fmin_d(c.rd(), c.rs1(), c.rs2()),
+            0x2a001053 => return self.fmax_d(c.rd(), c.rs1(), c.rs2()),
+            0xa2000053 => return self.fle_d(c.rd(), c.rs1(), c.rs2()),
+            0xa2001053 => return self.flt_d(c.rd(), c.rs1(), c.rs2()),
+            0xa2002053 => return self.feq_d(c.rd(), c.rs1(), c.rs2()),
+            _ => {}
+        }
+        match code & 0x0000ffff {
+            0x9002 => return self.c_ebreak(),
+            _ => {}
+        }
+        match code & 0xf800707f {
+            0x1800202f => return self.sc_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x0800202f => return self.amoswap_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x0000202f => return self.amoadd_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x2000202f => return self.amoxor_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x6000202f => return self.amoand_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x4000202f => return self.amoor_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0x8000202f => return self.amomin_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0xa000202f => return self.amomax_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0xc000202f => return self.amominu_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            0xe000202f => return self.amomaxu_w(c.rd(), c.rs1(), c.rs2(), c.aqrl()),
+            _ => {}
+        }
+        match code & 0xfe00007f {
+            0x00000053 => return self.fadd_s(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x08000053 => return self.fsub_s(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x10000053 => return self.fmul_s(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x18000053 => return self.fdiv_s(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x02000053 => return self.fadd_d(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x0a000053 => return self.fsub_d(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x12000053 => return self.fmul_d(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            0x1a000053 => return self.fdiv_d(c.rd(), c.rs1(), c.rs2(), c.rm()),
+            _ => {}
+        }
+        match code & 0x0000f07f {
+            0x8002 => return self.c_jr(c.rs1n0()),
+            0x9002 => return self.c_jalr(c.rs1n0()),
+            _ => {}
+        }
+        match code & 0x0000707f {
+            0x00000063 => return self.beq(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00001063 => return self.bne(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00004063 => return self.blt(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00005063 => return self.bge(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00006063 => return self.bltu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00007063 => return self.bgeu(c.rs1(), c.rs2(), c.bimmediate()),
+            0x00000067 => return self.jalr(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000013 => return self.addi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002013 => return self.slti(c.rd(), c.rs1(), c.iimmediate()),
+            0x00003013 => return self.sltiu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004013 => return self.xori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00006013 => return self.ori(c.rd(), c.rs1(), c.iimmediate()),
+            0x00007013 => return self.andi(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000003 => return self.lb(c.rd(), c.rs1(), c.iimmediate()),
+            0x00001003 => return self.lh(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002003 => return self.lw(c.rd(), c.rs1(), c.iimmediate()),
+            0x00004003 => return self.lbu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00005003 => return self.lhu(c.rd(), c.rs1(), c.iimmediate()),
+            0x00000023 => return self.sb(c.rs1(), c.rs2(), c.simmediate()),
+            0x00001023 => return self.sh(c.rs1(), c.rs2(), c.simmediate()),
+            0x00002023 => return self.sw(c.rs1(), c.rs2(), c.simmediate()),
+            0x0000000f => return self.fence(c.fm(), c.rd(), c.rs1()),
+            0x00002007 => return self.flw(c.rd(), c.rs1(), c.iimmediate()),
+            0x00002027 => return self.fsw(c.rs1(), c.rs2(), c.simmediate()),
+            0x00003007 => return self.fld(c.rd(), c.rs1(), c.iimmediate()),
+            0x00003027 => return self.fsd(c.rs1(), c.rs2(), c.simmediate()),
+            0x00001073 => return self.csrrw(c.rd(), c.rs1(), c.csr()),
+            0x00002073 => return self.csrrs(c.rd(), c.rs1(), c.csr()),
+            0x00003073 => return self.csrrc(c.rd(), c.rs1(), c.csr()),
+            0x00005073 => return self.csrrwi(c.rd(), c.zimm(), c.csr()),
+            0x00006073 => return self.csrrsi(c.rd(), c.zimm(), c.csr()),
+            0x00007073 => return self.csrrci(c.rd(), c.zimm(), c.csr()),
+            0x0000100f => return self.fence_i(c.rd(), c.rs1(), c.iimmediate()),
+            _ => {}
+        }
+        match code & 0x0000ef83 {
+            0x0001 => return self.c_nop(c.c_nzimm6()),
+            0x6101 => return self.c_addi16sp(c.c_nzimm10()),
+            _ => {}
+        }
+        match code & 0x0000fc63 {
+            0x8c01 => return self.c_sub(c.rdrs1p(), c.rs2p()),
+            0x8c21 => return self.c_xor(c.rdrs1p(), c.rs2p()),
+            0x8c41 => return self.c_or(c.rdrs1p(), c.rs2p()),
+            0x8c61 => return self.c_and(c.rdrs1p(), c.rs2p()),
+            _ => {}
+        }
+        match code & 0x0600007f {
+            0x00000043 => return self.fmadd_s(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x00000047 => return self.fmsub_s(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x0000004b => return self.fnmsub_s(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x0000004f => return self.fnmadd_s(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x02000043 => return self.fmadd_d(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x02000047 => return self.fmsub_d(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x0200004b => return self.fnmsub_d(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            0x0200004f => return self.fnmadd_d(c.rd(), c.rs1(), c.rs2(), c.rs3(), c.rm()),
+            _ => {}
+        }
+        match code & 0x0000007f {
+            0x0000006f => return self.jal(c.rd(), c.jimmediate()),
+            0x00000037 => return self.lui(c.rd(), c.uimmediate()),
+            0x00000017 => return self.auipc(c.rd(), c.uimmediate()),
+            _ => {}
+        }
+        match code & 0x0000ec03 {
+            0x8801 => return self.c_andi(c.rdrs1p(), c.c_imm6()),
+            0x8001 => return self.c_srli(c.rdrs1p(), c.c_nzuimm6()),
+            0x8401 => return self.c_srai(c.rdrs1p(), c.c_nzuimm6()),
+            _ => {}
+        }
+        match code & 0x0000f003 {
+            0x8002 => return self.c_mv(c.rd(), c.rs2n0()),
+            0x9002 => return self.c_add(c.rdrs1(), c.rs2n0()),
+            _ => {}
+        }
+        match code & 0x0000e003 {
+            0x0000 => return self.c_addi4spn(c.rdp(), c.c_nzuimm10()),
+            0x4000 => return self.c_lw(c.rdp(), c.rs1p(), c.c_uimm7()),
+            0xc000 => return self.c_sw(c.rs1p(), c.rs2p(), c.c_uimm7()),
+            0x0001 => return self.c_addi(c.rdrs1n0(), c.c_nzimm6()),
+            0x4001 => return self.c_li(c.rd(), c.c_imm6()),
+            0x6001 => return self.c_lui(c.rdn2(), c.c_nzimm18()),
+            0xa001 => return self.c_j(c.c_imm12()),
+            0xc001 => return self.c_beqz(c.rs1p(), c.c_bimm9()),
+            0xe001 => return self.c_bnez(c.rs1p(), c.c_bimm9()),
+            0x4002 => return self.c_lwsp(c.rdn0(), c.c_uimm8sp()),
+            0xc002 => return self.c_swsp(c.c_rs2(), c.c_uimm8sp_s()),
+            0x2001 => return self.c_jal(c.c_imm12()),
+            0x0002 => return self.c_slli(c.rdrs1n0(), c.c_nzuimm6()),
+            0x6000 => return self.c_flw(c.rdp(), c.rs1p(), c.c_uimm7()),
+            0xe000 => return self.c_fsw(c.rs1p(), c.rs2p(), c.c_uimm7()),
+            0x6002 => return self.c_flwsp(c.rd(), c.c_uimm8sp()),
+            0xe002 => return self.c_fswsp(c.c_rs2(), c.c_uimm8sp_s()),
+            0x2000 => return self.c_fld(c.rdp(), c.rs1p(), c.c_uimm8()),
+            0xa000 => return self.c_fsd(c.rs1p(), c.rs2p(), c.c_uimm8()),
+            0x2002 => return self.c_fldsp(c.rd(), c.c_uimm9sp()),
+            0xa002 => return self.c_fsdsp(c.c_rs2(), c.c_uimm9sp_s()),
             _ => {}
         }
         self.illegal(code)
@@ -769,7 +2606,10 @@ where
 
 #[cfg(test)]
 mod test {
-    use super::{HandleRv32c, HandleRv32f, HandleRv32i, HandleRv32m};
+    use super::{
+        HandlePrivileged, HandleRv32a, HandleRv32c, HandleRv32d, HandleRv32f, HandleRv32i,
+        HandleRv32m, HandleZicsr, HandleZifencei,
+    };
     use crate::reg::Reg;
 
     // Base RV32I - generated by parse.py in riscv-opcodes.
@@ -872,6 +2712,30 @@ mod test {
     const MATCH_REMU: u32 = 0x2007033;
     const MASK_REMU: u32 = 0xfe00707f;
 
+    // 'A' extension - generated by parse.py in riscv-opcodes.
+    const MATCH_AMOADD_W: u32 = 0x202f;
+    const MASK_AMOADD_W: u32 = 0xf800707f;
+    const MATCH_AMOAND_W: u32 = 0x6000202f;
+    const MASK_AMOAND_W: u32 = 0xf800707f;
+    const MATCH_AMOMAX_W: u32 = 0xa000202f;
+    const MASK_AMOMAX_W: u32 = 0xf800707f;
+    const MATCH_AMOMAXU_W: u32 = 0xe000202f;
+    const MASK_AMOMAXU_W: u32 = 0xf800707f;
+    const MATCH_AMOMIN_W: u32 = 0x8000202f;
+    const MASK_AMOMIN_W: u32 = 0xf800707f;
+    const MATCH_AMOMINU_W: u32 = 0xc000202f;
+    const MASK_AMOMINU_W: u32 = 0xf800707f;
+    const MATCH_AMOOR_W: u32 = 0x4000202f;
+    const MASK_AMOOR_W: u32 = 0xf800707f;
+    const MATCH_AMOSWAP_W: u32 = 0x800202f;
+    const MASK_AMOSWAP_W: u32 = 0xf800707f;
+    const MATCH_AMOXOR_W: u32 = 0x2000202f;
+    const MASK_AMOXOR_W: u32 = 0xf800707f;
+    const MATCH_LR_W: u32 = 0x1000202f;
+    const MASK_LR_W: u32 = 0xf9f0707f;
+    const MATCH_SC_W: u32 = 0x1800202f;
+    const MASK_SC_W: u32 = 0xf800707f;
+
     // 'C' extension - generated by parse.py in riscv-opcodes.
     const MATCH_C_ADD: u32 = 0x9002;
     const MASK_C_ADD: u32 = 0xf003;
@@ -982,6 +2846,106 @@ mod test {
     const MATCH_FSW: u32 = 0x2027;
     const MASK_FSW: u32 = 0x707f;
 
+    // 'D' extension - generated by parse.py in riscv-opcodes.
+    const MATCH_FADD_D: u32 = 0x2000053;
+    const MASK_FADD_D: u32 = 0xfe00007f;
+    const MATCH_FCLASS_D: u32 = 0xe2001053;
+    const MASK_FCLASS_D: u32 = 0xfff0707f;
+    const MATCH_FCVT_D_S: u32 = 0x42000053;
+    const MASK_FCVT_D_S: u32 = 0xfff0007f;
+    const MATCH_FCVT_D_W: u32 = 0xd2000053;
+    const MASK_FCVT_D_W: u32 = 0xfff0007f;
+    const MATCH_FCVT_D_WU: u32 = 0xd2100053;
+    const MASK_FCVT_D_WU: u32 = 0xfff0007f;
+    const MATCH_FCVT_S_D: u32 = 0x40100053;
+    const MASK_FCVT_S_D: u32 = 0xfff0007f;
+    const MATCH_FCVT_WU_D: u32 = 0xc2100053;
+    const MASK_FCVT_WU_D: u32 = 0xfff0007f;
+    const MATCH_FCVT_W_D: u32 = 0xc2000053;
+    const MASK_FCVT_W_D: u32 = 0xfff0007f;
+    const MATCH_FDIV_D: u32 = 0x1a000053;
+    const MASK_FDIV_D: u32 = 0xfe00007f;
+    const MATCH_FEQ_D: u32 = 0xa2002053;
+    const MASK_FEQ_D: u32 = 0xfe00707f;
+    const MATCH_FLD: u32 = 0x3007;
+    const MASK_FLD: u32 = 0x707f;
+    const MATCH_FLE_D: u32 = 0xa2000053;
+    const MASK_FLE_D: u32 = 0xfe00707f;
+    const MATCH_FLT_D: u32 = 0xa2001053;
+    const MASK_FLT_D: u32 = 0xfe00707f;
+    const MATCH_FMADD_D: u32 = 0x2000043;
+    const MASK_FMADD_D: u32 = 0x600007f;
+    const MATCH_FMAX_D: u32 = 0x2a001053;
+    const MASK_FMAX_D: u32 = 0xfe00707f;
+    const MATCH_FMIN_D: u32 = 0x2a000053;
+    const MASK_FMIN_D: u32 = 0xfe00707f;
+    const MATCH_FMSUB_D: u32 = 0x2000047;
+    const MASK_FMSUB_D: u32 = 0x600007f;
+    const MATCH_FMUL_D: u32 = 0x12000053;
+    const MASK_FMUL_D: u32 = 0xfe00007f;
+    const MATCH_FNMADD_D: u32 = 0x200004f;
+    const MASK_FNMADD_D: u32 = 0x600007f;
+    const MATCH_FNMSUB_D: u32 = 0x200004b;
+    const MASK_FNMSUB_D: u32 = 0x600007f;
+    const MATCH_FSD: u32 = 0x3027;
+    const MASK_FSD: u32 = 0x707f;
+    const MATCH_FSGNJN_D: u32 = 0x22001053;
+    const MASK_FSGNJN_D: u32 = 0xfe00707f;
+    const MATCH_FSGNJX_D: u32 = 0x22002053;
+    const MASK_FSGNJX_D: u32 = 0xfe00707f;
+    const MATCH_FSGNJ_D: u32 = 0x22000053;
+    const MASK_FSGNJ_D: u32 = 0xfe00707f;
+    const MATCH_FSQRT_D: u32 = 0x5a000053;
+    const MASK_FSQRT_D: u32 = 0xfff0007f;
+    const MATCH_FSUB_D: u32 = 0xa000053;
+    const MASK_FSUB_D: u32 = 0xfe00007f;
+
+    // 'C' extension floating point loads and stores - generated by parse.py in riscv-opcodes.
+    const MATCH_C_FLW: u32 = 0x6000;
+    const MASK_C_FLW: u32 = 0xe003;
+    const MATCH_C_FLWSP: u32 = 0x6002;
+    const MASK_C_FLWSP: u32 = 0xe003;
+    const MATCH_C_FSW: u32 = 0xe000;
+    const MASK_C_FSW: u32 = 0xe003;
+    const MATCH_C_FSWSP: u32 = 0xe002;
+    const MASK_C_FSWSP: u32 = 0xe003;
+    const MATCH_C_FLD: u32 = 0x2000;
+    const MASK_C_FLD: u32 = 0xe003;
+    const MATCH_C_FLDSP: u32 = 0x2002;
+    const MASK_C_FLDSP: u32 = 0xe003;
+    const MATCH_C_FSD: u32 = 0xa000;
+    const MASK_C_FSD: u32 = 0xe003;
+    const MATCH_C_FSDSP: u32 = 0xa002;
+    const MASK_C_FSDSP: u32 = 0xe003;
+
+    // 'Zicsr' extension - generated by parse.py in riscv-opcodes.
+    const MATCH_CSRRC: u32 = 0x3073;
+    const MASK_CSRRC: u32 = 0x707f;
+    const MATCH_CSRRCI: u32 = 0x7073;
+    const MASK_CSRRCI: u32 = 0x707f;
+    const MATCH_CSRRS: u32 = 0x2073;
+    const MASK_CSRRS: u32 = 0x707f;
+    const MATCH_CSRRSI: u32 = 0x6073;
+    const MASK_CSRRSI: u32 = 0x707f;
+    const MATCH_CSRRW: u32 = 0x1073;
+    const MASK_CSRRW: u32 = 0x707f;
+    const MATCH_CSRRWI: u32 = 0x5073;
+    const MASK_CSRRWI: u32 = 0x707f;
+
+    // 'Zifencei' extension - generated by parse.py in riscv-opcodes.
+    const MATCH_FENCE_I: u32 = 0x100f;
+    const MASK_FENCE_I: u32 = 0x707f;
+
+    // Privileged instructions - generated by parse.py in riscv-opcodes.
+    const MATCH_MRET: u32 = 0x30200073;
+    const MASK_MRET: u32 = 0xffffffff;
+    const MATCH_SRET: u32 = 0x10200073;
+    const MASK_SRET: u32 = 0xffffffff;
+    const MATCH_WFI: u32 = 0x10500073;
+    const MASK_WFI: u32 = 0xffffffff;
+    const MATCH_SFENCE_VMA: u32 = 0x12000073;
+    const MASK_SFENCE_VMA: u32 = 0xfe007fff;
+
     #[inline]
     fn enc(pattern: u32, mask: u32) -> u32 {
         !mask | pattern
@@ -1255,6 +3219,81 @@ mod test {
         assert_eq!("remu", c.dispatch(enc(MATCH_REMU, MASK_REMU)));
     }
 
+    impl HandleRv32a for Checker {
+        type Item = &'static str;
+
+        fn lr_w(&mut self, _rd: Reg, _rs1: Reg, _aqrl: u32) -> Self::Item {
+            "lr.w"
+        }
+
+        fn sc_w(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _aqrl: u32) -> Self::Item {
+            "sc.w"
+        }
+
+        fn amoswap_w(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _aqrl: u32) -> Self::Item {
+            "amoswap.w"
+        }
+
+        fn amoadd_w(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _aqrl: u32) -> Self::Item {
+            "amoadd.w"
+        }
+
+        fn amoxor_w(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _aqrl: u32) -> Self::Item {
+            "amoxor.w"
+        }
+
+        fn amoand_w(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _aqrl: u32) -> Self::Item {
+            "amoand.w"
+        }
+
+        fn amoor_w(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _aqrl: u32) -> Self::Item {
+            "amoor.w"
+        }
+
+        fn amomin_w(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _aqrl: u32) -> Self::Item {
+            "amomin.w"
+        }
+
+        fn amomax_w(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _aqrl: u32) -> Self::Item {
+            "amomax.w"
+        }
+
+        fn amominu_w(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _aqrl: u32) -> Self::Item {
+            "amominu.w"
+        }
+
+        fn amomaxu_w(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _aqrl: u32) -> Self::Item {
+            "amomaxu.w"
+        }
+    }
+
+    #[test]
+    fn dispatch_rv32ima() {
+        use super::DispatchRv32ima;
+
+        let mut c = Checker {};
+        assert_eq!("amoadd.w", c.dispatch(enc(MATCH_AMOADD_W, MASK_AMOADD_W)));
+        assert_eq!("amoand.w", c.dispatch(enc(MATCH_AMOAND_W, MASK_AMOAND_W)));
+        assert_eq!("amomax.w", c.dispatch(enc(MATCH_AMOMAX_W, MASK_AMOMAX_W)));
+        assert_eq!(
+            "amomaxu.w",
+            c.dispatch(enc(MATCH_AMOMAXU_W, MASK_AMOMAXU_W))
+        );
+        assert_eq!("amomin.w", c.dispatch(enc(MATCH_AMOMIN_W, MASK_AMOMIN_W)));
+        assert_eq!(
+            "amominu.w",
+            c.dispatch(enc(MATCH_AMOMINU_W, MASK_AMOMINU_W))
+        );
+        assert_eq!("amoor.w", c.dispatch(enc(MATCH_AMOOR_W, MASK_AMOOR_W)));
+        assert_eq!(
+            "amoswap.w",
+            c.dispatch(enc(MATCH_AMOSWAP_W, MASK_AMOSWAP_W))
+        );
+        assert_eq!("amoxor.w", c.dispatch(enc(MATCH_AMOXOR_W, MASK_AMOXOR_W)));
+        assert_eq!("lr.w", c.dispatch(enc(MATCH_LR_W, MASK_LR_W)));
+        assert_eq!("sc.w", c.dispatch(enc(MATCH_SC_W, MASK_SC_W)));
+    }
+
     impl HandleRv32c for Checker {
         type Item = &'static str;
 
@@ -1513,6 +3552,22 @@ mod test {
         fn feq_s(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg) -> Self::Item {
             "feq.s"
         }
+
+        fn c_flw(&mut self, _rdp: Reg, _rs1p: Reg, _imm: u32) -> Self::Item {
+            "c.flw"
+        }
+
+        fn c_fsw(&mut self, _rs1p: Reg, _rs2p: Reg, _imm: u32) -> Self::Item {
+            "c.fsw"
+        }
+
+        fn c_flwsp(&mut self, _rd: Reg, _imm: u32) -> Self::Item {
+            "c.flwsp"
+        }
+
+        fn c_fswsp(&mut self, _rs2: Reg, _imm: u32) -> Self::Item {
+            "c.fswsp"
+        }
     }
 
     #[test]
@@ -1553,4 +3608,279 @@ mod test {
         assert_eq!("fsub.s", c.dispatch(enc(MATCH_FSUB_S, MASK_FSUB_S)));
         assert_eq!("fsw", c.dispatch(enc(MATCH_FSW, MASK_FSW)));
     }
+
+    impl HandleRv32d for Checker {
+        type Item = &'static str;
+
+        fn fadd_d(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _rm: u32) -> Self::Item {
+            "fadd.d"
+        }
+
+        fn fsub_d(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _rm: u32) -> Self::Item {
+            "fsub.d"
+        }
+
+        fn fmul_d(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _rm: u32) -> Self::Item {
+            "fmul.d"
+        }
+
+        fn fdiv_d(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _rm: u32) -> Self::Item {
+            "fdiv.d"
+        }
+
+        fn fsgnj_d(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg) -> Self::Item {
+            "fsgnj.d"
+        }
+
+        fn fsgnjn_d(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg) -> Self::Item {
+            "fsgnjn.d"
+        }
+
+        fn fsgnjx_d(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg) -> Self::Item {
+            "fsgnjx.d"
+        }
+
+        fn fmin_d(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg) -> Self::Item {
+            "fmin.d"
+        }
+
+        fn fmax_d(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg) -> Self::Item {
+            "fmax.d"
+        }
+
+        fn fcvt_s_d(&mut self, _rd: Reg, _rs1: Reg, _rm: u32) -> Self::Item {
+            "fcvt.s.d"
+        }
+
+        fn fcvt_d_s(&mut self, _rd: Reg, _rs1: Reg, _rm: u32) -> Self::Item {
+            "fcvt.d.s"
+        }
+
+        fn fsqrt_d(&mut self, _rd: Reg, _rs1: Reg, _rm: u32) -> Self::Item {
+            "fsqrt.d"
+        }
+
+        fn fle_d(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg) -> Self::Item {
+            "fle.d"
+        }
+
+        fn flt_d(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg) -> Self::Item {
+            "flt.d"
+        }
+
+        fn feq_d(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg) -> Self::Item {
+            "feq.d"
+        }
+
+        fn fcvt_w_d(&mut self, _rd: Reg, _rs1: Reg, _rm: u32) -> Self::Item {
+            "fcvt.w.d"
+        }
+
+        fn fcvt_wu_d(&mut self, _rd: Reg, _rs1: Reg, _rm: u32) -> Self::Item {
+            "fcvt.wu.d"
+        }
+
+        fn fclass_d(&mut self, _rd: Reg, _rs1: Reg) -> Self::Item {
+            "fclass.d"
+        }
+
+        fn fcvt_d_w(&mut self, _rd: Reg, _rs1: Reg, _rm: u32) -> Self::Item {
+            "fcvt.d.w"
+        }
+
+        fn fcvt_d_wu(&mut self, _rd: Reg, _rs1: Reg, _rm: u32) -> Self::Item {
+            "fcvt.d.wu"
+        }
+
+        fn fld(&mut self, _rd: Reg, _rs1: Reg, _iimm: u32) -> Self::Item {
+            "fld"
+        }
+
+        fn fsd(&mut self, _rs1: Reg, _rs2: Reg, _simm: u32) -> Self::Item {
+            "fsd"
+        }
+
+        fn fmadd_d(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _rs3: Reg, _rm: u32) -> Self::Item {
+            "fmadd.d"
+        }
+
+        fn fmsub_d(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _rs3: Reg, _rm: u32) -> Self::Item {
+            "fmsub.d"
+        }
+
+        fn fnmsub_d(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _rs3: Reg, _rm: u32) -> Self::Item {
+            "fnmsub.d"
+        }
+
+        fn fnmadd_d(&mut self, _rd: Reg, _rs1: Reg, _rs2: Reg, _rs3: Reg, _rm: u32) -> Self::Item {
+            "fnmadd.d"
+        }
+
+        fn c_fld(&mut self, _rdp: Reg, _rs1p: Reg, _imm: u32) -> Self::Item {
+            "c.fld"
+        }
+
+        fn c_fsd(&mut self, _rs1p: Reg, _rs2p: Reg, _imm: u32) -> Self::Item {
+            "c.fsd"
+        }
+
+        fn c_fldsp(&mut self, _rd: Reg, _imm: u32) -> Self::Item {
+            "c.fldsp"
+        }
+
+        fn c_fsdsp(&mut self, _rs2: Reg, _imm: u32) -> Self::Item {
+            "c.fsdsp"
+        }
+    }
+
+    #[test]
+    fn dispatch_rv32imfdc() {
+        use super::DispatchRv32imfdc;
+
+        let mut c = Checker {};
+        assert_eq!("fadd.d", c.dispatch(enc(MATCH_FADD_D, MASK_FADD_D)));
+        assert_eq!("fclass.d", c.dispatch(enc(MATCH_FCLASS_D, MASK_FCLASS_D)));
+        assert_eq!("fcvt.d.s", c.dispatch(enc(MATCH_FCVT_D_S, MASK_FCVT_D_S)));
+        assert_eq!("fcvt.d.w", c.dispatch(enc(MATCH_FCVT_D_W, MASK_FCVT_D_W)));
+        assert_eq!(
+            "fcvt.d.wu",
+            c.dispatch(enc(MATCH_FCVT_D_WU, MASK_FCVT_D_WU))
+        );
+        assert_eq!("fcvt.s.d", c.dispatch(enc(MATCH_FCVT_S_D, MASK_FCVT_S_D)));
+        assert_eq!(
+            "fcvt.wu.d",
+            c.dispatch(enc(MATCH_FCVT_WU_D, MASK_FCVT_WU_D))
+        );
+        assert_eq!("fcvt.w.d", c.dispatch(enc(MATCH_FCVT_W_D, MASK_FCVT_W_D)));
+        assert_eq!("fdiv.d", c.dispatch(enc(MATCH_FDIV_D, MASK_FDIV_D)));
+        assert_eq!("feq.d", c.dispatch(enc(MATCH_FEQ_D, MASK_FEQ_D)));
+        assert_eq!("fld", c.dispatch(enc(MATCH_FLD, MASK_FLD)));
+        assert_eq!("fle.d", c.dispatch(enc(MATCH_FLE_D, MASK_FLE_D)));
+        assert_eq!("flt.d", c.dispatch(enc(MATCH_FLT_D, MASK_FLT_D)));
+        assert_eq!("fmadd.d", c.dispatch(enc(MATCH_FMADD_D, MASK_FMADD_D)));
+        assert_eq!("fmax.d", c.dispatch(enc(MATCH_FMAX_D, MASK_FMAX_D)));
+        assert_eq!("fmin.d", c.dispatch(enc(MATCH_FMIN_D, MASK_FMIN_D)));
+        assert_eq!("fmsub.d", c.dispatch(enc(MATCH_FMSUB_D, MASK_FMSUB_D)));
+        assert_eq!("fmul.d", c.dispatch(enc(MATCH_FMUL_D, MASK_FMUL_D)));
+        assert_eq!("fnmadd.d", c.dispatch(enc(MATCH_FNMADD_D, MASK_FNMADD_D)));
+        assert_eq!("fnmsub.d", c.dispatch(enc(MATCH_FNMSUB_D, MASK_FNMSUB_D)));
+        assert_eq!("fsd", c.dispatch(enc(MATCH_FSD, MASK_FSD)));
+        assert_eq!("fsgnjn.d", c.dispatch(enc(MATCH_FSGNJN_D, MASK_FSGNJN_D)));
+        assert_eq!("fsgnjx.d", c.dispatch(enc(MATCH_FSGNJX_D, MASK_FSGNJX_D)));
+        assert_eq!("fsgnj.d", c.dispatch(enc(MATCH_FSGNJ_D, MASK_FSGNJ_D)));
+        assert_eq!("fsqrt.d", c.dispatch(enc(MATCH_FSQRT_D, MASK_FSQRT_D)));
+        assert_eq!("fsub.d", c.dispatch(enc(MATCH_FSUB_D, MASK_FSUB_D)));
+        assert_eq!("c.flw", c.dispatch(enc(MATCH_C_FLW, MASK_C_FLW)));
+        assert_eq!("c.flwsp", c.dispatch(enc(MATCH_C_FLWSP, MASK_C_FLWSP)));
+        assert_eq!("c.fsw", c.dispatch(enc(MATCH_C_FSW, MASK_C_FSW)));
+        assert_eq!("c.fswsp", c.dispatch(enc(MATCH_C_FSWSP, MASK_C_FSWSP)));
+        assert_eq!("c.fld", c.dispatch(enc(MATCH_C_FLD, MASK_C_FLD)));
+        assert_eq!("c.fldsp", c.dispatch(enc(MATCH_C_FLDSP, MASK_C_FLDSP)));
+        assert_eq!("c.fsd", c.dispatch(enc(MATCH_C_FSD, MASK_C_FSD)));
+        assert_eq!("c.fsdsp", c.dispatch(enc(MATCH_C_FSDSP, MASK_C_FSDSP)));
+        assert_eq!("fadd.s", c.dispatch(enc(MATCH_FADD_S, MASK_FADD_S)));
+        assert_eq!("c.lw", c.dispatch(enc(MATCH_C_LW, MASK_C_LW)));
+    }
+
+    impl HandleZifencei for Checker {
+        type Item = &'static str;
+
+        fn fence_i(&mut self, _rd: Reg, _rs1: Reg, _iimm: u32) -> Self::Item {
+            "fence.i"
+        }
+    }
+
+    #[test]
+    fn dispatch_rv32gc() {
+        use super::DispatchRv32gc;
+
+        let mut c = Checker {};
+        assert_eq!("mul", c.dispatch(enc(MATCH_MUL, MASK_MUL)));
+        assert_eq!("amoadd.w", c.dispatch(enc(MATCH_AMOADD_W, MASK_AMOADD_W)));
+        assert_eq!("fadd.s", c.dispatch(enc(MATCH_FADD_S, MASK_FADD_S)));
+        assert_eq!("fadd.d", c.dispatch(enc(MATCH_FADD_D, MASK_FADD_D)));
+        assert_eq!("c.fld", c.dispatch(enc(MATCH_C_FLD, MASK_C_FLD)));
+        assert_eq!("csrrw", c.dispatch(enc(MATCH_CSRRW, MASK_CSRRW)));
+        assert_eq!("fence.i", c.dispatch(enc(MATCH_FENCE_I, MASK_FENCE_I)));
+        assert_eq!("fence", c.dispatch(enc(MATCH_FENCE, MASK_FENCE)));
+    }
+
+    impl HandleZicsr for Checker {
+        type Item = &'static str;
+
+        fn csrrw(&mut self, _rd: Reg, _rs1: Reg, _csr: u32) -> Self::Item {
+            "csrrw"
+        }
+
+        fn csrrs(&mut self, _rd: Reg, _rs1: Reg, _csr: u32) -> Self::Item {
+            "csrrs"
+        }
+
+        fn csrrc(&mut self, _rd: Reg, _rs1: Reg, _csr: u32) -> Self::Item {
+            "csrrc"
+        }
+
+        fn csrrwi(&mut self, _rd: Reg, _zimm: u32, _csr: u32) -> Self::Item {
+            "csrrwi"
+        }
+
+        fn csrrsi(&mut self, _rd: Reg, _zimm: u32, _csr: u32) -> Self::Item {
+            "csrrsi"
+        }
+
+        fn csrrci(&mut self, _rd: Reg, _zimm: u32, _csr: u32) -> Self::Item {
+            "csrrci"
+        }
+    }
+
+    #[test]
+    fn dispatch_rv32i_zicsr() {
+        use super::DispatchRv32iZicsr;
+
+        let mut c = Checker {};
+        assert_eq!("csrrc", c.dispatch(enc(MATCH_CSRRC, MASK_CSRRC)));
+        assert_eq!("csrrci", c.dispatch(enc(MATCH_CSRRCI, MASK_CSRRCI)));
+        assert_eq!("csrrs", c.dispatch(enc(MATCH_CSRRS, MASK_CSRRS)));
+        assert_eq!("csrrsi", c.dispatch(enc(MATCH_CSRRSI, MASK_CSRRSI)));
+        assert_eq!("csrrw", c.dispatch(enc(MATCH_CSRRW, MASK_CSRRW)));
+        assert_eq!("csrrwi", c.dispatch(enc(MATCH_CSRRWI, MASK_CSRRWI)));
+        assert_eq!("ecall", c.dispatch(enc(MATCH_ECALL, MASK_ECALL)));
+        assert_eq!("ebreak", c.dispatch(enc(MATCH_EBREAK, MASK_EBREAK)));
+    }
+
+    impl HandlePrivileged for Checker {
+        type Item = &'static str;
+
+        fn sret(&mut self) -> Self::Item {
+            "sret"
+        }
+
+        fn mret(&mut self) -> Self::Item {
+            "mret"
+        }
+
+        fn wfi(&mut self) -> Self::Item {
+            "wfi"
+        }
+
+        fn sfence_vma(&mut self, _rs1: Reg, _rs2: Reg) -> Self::Item {
+            "sfence.vma"
+        }
+    }
+
+    #[test]
+    fn dispatch_rv32i_zicsr_priv() {
+        use super::DispatchRv32iZicsrPriv;
+
+        let mut c = Checker {};
+        assert_eq!("csrrw", c.dispatch(enc(MATCH_CSRRW, MASK_CSRRW)));
+        assert_eq!("ecall", c.dispatch(enc(MATCH_ECALL, MASK_ECALL)));
+        assert_eq!("mret", c.dispatch(enc(MATCH_MRET, MASK_MRET)));
+        assert_eq!("sret", c.dispatch(enc(MATCH_SRET, MASK_SRET)));
+        assert_eq!("wfi", c.dispatch(enc(MATCH_WFI, MASK_WFI)));
+        assert_eq!(
+            "sfence.vma",
+            c.dispatch(enc(MATCH_SFENCE_VMA, MASK_SFENCE_VMA))
+        );
+    }
 }
@@ -0,0 +1,585 @@
+//! Loads 32-bit RISC-V ELF executables into memory.
+//!
+//! An ELF file is loaded by copying each of its loadable segments into memory with [`Load`], zero-filling any part of a
+//! segment that isn't present in the file, e.g., `.bss`. The entry point is written to the CPU with
+//! [`Fetch::set_next_pc`] so that the next call to [`Fetch::fetch`] fetches the first instruction.
+
+use std::fmt::Display;
+
+use crate::{
+    cpu::Fetch,
+    memory::{Address, Load},
+};
+
+// Identification.
+const ELF_MAGIC: &[u8; 4] = b"\x7fELF";
+const ELFCLASS32: u8 = 1;
+const ELFDATA2LSB: u8 = 1;
+
+// Object file types.
+const ET_EXEC: u16 = 2;
+
+// Machine types.
+const EM_RISCV: u16 = 243;
+
+// Segment types.
+const PT_LOAD: u32 = 1;
+
+// Section types.
+const SHT_SYMTAB: u32 = 2;
+
+// Symbol types.
+const STT_NOTYPE: u8 = 0;
+const STT_OBJECT: u8 = 1;
+const STT_FUNC: u8 = 2;
+
+// Sizes of the headers and table entries that we use.
+const EHDR_SIZE: usize = 52;
+const PHDR_SIZE: usize = 32;
+const SHDR_SIZE: usize = 40;
+const SYM_SIZE: usize = 16;
+
+// The most zeros that are written to memory at a time when zero-filling a segment.
+const ZERO_CHUNK: usize = 0x1000;
+
+// RISC-V specific bits of e_flags.
+const EF_RISCV_RVC: u32 = 0x0001;
+const EF_RISCV_FLOAT_ABI: u32 = 0x0006;
+const EF_RISCV_RVE: u32 = 0x0008;
+const EF_RISCV_TSO: u32 = 0x0010;
+
+/// The reasons that an ELF file can't be loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElfError {
+    /// The file isn't an ELF file.
+    NotElf,
+    /// The file isn't a 32-bit ELF file.
+    WrongClass(u8),
+    /// The file isn't little-endian.
+    WrongEndianness(u8),
+    /// The file isn't an executable.
+    NotExecutable(u16),
+    /// The file isn't for RISC-V.
+    WrongMachine(u16),
+    /// The file's program headers aren't the size of 32-bit ELF program headers.
+    WrongPhdrSize(u16),
+    /// The file ends before the given offset.
+    Truncated(usize),
+    /// A segment couldn't be written to memory at the given address.
+    LoadFailed(Address),
+}
+
+impl Display for ElfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ElfError::NotElf => write!(f, "not an ELF file"),
+            ElfError::WrongClass(class) => write!(f, "not a 32-bit ELF file (class {})", class),
+            ElfError::WrongEndianness(data) => {
+                write!(f, "not a little-endian ELF file (data {})", data)
+            }
+            ElfError::NotExecutable(e_type) => write!(f, "not an executable (type {})", e_type),
+            ElfError::WrongMachine(machine) => {
+                write!(f, "not a RISC-V ELF file (machine {})", machine)
+            }
+            ElfError::WrongPhdrSize(size) => {
+                write!(f, "unexpected program header size (size {})", size)
+            }
+            ElfError::Truncated(offset) => write!(f, "truncated at offset 0x{:x}", offset),
+            ElfError::LoadFailed(address) => {
+                write!(f, "failed to load memory at 0x{:08x}", address)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ElfError {}
+
+/// The floating point ABI claimed by an ELF file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatAbi {
+    Soft,
+    Single,
+    Double,
+    Quad,
+}
+
+/// The RISC-V specific flags from the ELF header, which describe the ISA that the file was built for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IsaFlags(pub u32);
+
+impl IsaFlags {
+    /// Returns true if the file may contain compressed instructions.
+    pub fn has_compressed(&self) -> bool {
+        self.0 & EF_RISCV_RVC != 0
+    }
+
+    /// Returns the floating point ABI that the file uses to pass arguments.
+    pub fn float_abi(&self) -> FloatAbi {
+        match (self.0 & EF_RISCV_FLOAT_ABI) >> 1 {
+            0 => FloatAbi::Soft,
+            1 => FloatAbi::Single,
+            2 => FloatAbi::Double,
+            _ => FloatAbi::Quad,
+        }
+    }
+
+    /// Returns true if the file targets the RV32E base ISA.
+    pub fn is_rve(&self) -> bool {
+        self.0 & EF_RISCV_RVE != 0
+    }
+
+    /// Returns true if the file requires the RVTSO memory consistency model.
+    pub fn is_tso(&self) -> bool {
+        self.0 & EF_RISCV_TSO != 0
+    }
+}
+
+/// The kind of thing that a symbol refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    /// A function or other executable code.
+    Func,
+    /// A data object, such as a variable or an array.
+    Object,
+    /// A symbol with no type, such as a label in assembly language.
+    NoType,
+}
+
+/// A named address from an ELF file's symbol table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub address: Address,
+    pub size: u32,
+    pub kind: SymbolKind,
+}
+
+/// The symbols from an ELF file, ordered by address.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SymbolTable {
+    symbols: Vec<Symbol>,
+}
+
+impl SymbolTable {
+    /// Creates a symbol table from a list of symbols in any order.
+    pub fn new(mut symbols: Vec<Symbol>) -> Self {
+        symbols.sort_by(|a, b| a.address.cmp(&b.address).then_with(|| a.name.cmp(&b.name)));
+        Self { symbols }
+    }
+
+    /// Returns the symbol with the given name.
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|symbol| symbol.name == name)
+    }
+
+    /// Returns the nearest symbol at or below the given address, along with the address's offset from it.
+    pub fn lookup(&self, address: Address) -> Option<(&Symbol, u32)> {
+        let index = self
+            .symbols
+            .partition_point(|symbol| symbol.address <= address);
+        let symbol = self.symbols[..index].last()?;
+        Some((symbol, address - symbol.address))
+    }
+
+    /// Returns an iterator over the symbols, in address order.
+    pub fn iter(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.iter()
+    }
+
+    /// Returns the number of symbols.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Returns true if there are no symbols.
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+/// The result of loading an ELF file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElfInfo {
+    /// The entry point.
+    pub entry: Address,
+    /// The ISA flags from the ELF header.
+    pub flags: IsaFlags,
+    /// The symbols from the symbol table, if there is one.
+    pub symbols: SymbolTable,
+}
+
+/// Loads an ELF executable into the given CPU's memory and sets its entry point.
+pub fn load<T>(cpu: &mut T, bytes: &[u8]) -> Result<ElfInfo, ElfError>
+where
+    T: Load + Fetch,
+{
+    let elf = Elf::parse(bytes)?;
+    let symbols = elf.symbols()?;
+    elf.load_segments(cpu)?;
+    cpu.set_next_pc(elf.entry);
+    Ok(ElfInfo {
+        entry: elf.entry,
+        flags: IsaFlags(elf.flags),
+        symbols,
+    })
+}
+
+/// A validated ELF header and the file that it came from.
+struct Elf<'a> {
+    bytes: &'a [u8],
+    entry: Address,
+    flags: u32,
+    phoff: usize,
+    phnum: usize,
+    shoff: usize,
+    shnum: usize,
+}
+
+impl<'a> Elf<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self, ElfError> {
+        if bytes.len() < 16 || &bytes[0..4] != ELF_MAGIC {
+            return Err(ElfError::NotElf);
+        }
+        if bytes[4] != ELFCLASS32 {
+            return Err(ElfError::WrongClass(bytes[4]));
+        }
+        if bytes[5] != ELFDATA2LSB {
+            return Err(ElfError::WrongEndianness(bytes[5]));
+        }
+        let elf = Self {
+            bytes,
+            entry: 0,
+            flags: 0,
+            phoff: 0,
+            phnum: 0,
+            shoff: 0,
+            shnum: 0,
+        };
+        elf.slice(0, EHDR_SIZE)?;
+        let e_type = elf.u16_at(16)?;
+        if e_type != ET_EXEC {
+            return Err(ElfError::NotExecutable(e_type));
+        }
+        let machine = elf.u16_at(18)?;
+        if machine != EM_RISCV {
+            return Err(ElfError::WrongMachine(machine));
+        }
+        let phentsize = elf.u16_at(42)?;
+        if phentsize as usize != PHDR_SIZE {
+            return Err(ElfError::WrongPhdrSize(phentsize));
+        }
+        Ok(Self {
+            entry: elf.u32_at(24)?,
+            phoff: elf.u32_at(28)? as usize,
+            shoff: elf.u32_at(32)? as usize,
+            flags: elf.u32_at(36)?,
+            phnum: elf.u16_at(44)? as usize,
+            shnum: elf.u16_at(48)? as usize,
+            ..elf
+        })
+    }
+
+    fn slice(&self, offset: usize, len: usize) -> Result<&'a [u8], ElfError> {
+        let end = offset.checked_add(len).ok_or(ElfError::Truncated(offset))?;
+        self.bytes
+            .get(offset..end)
+            .ok_or(ElfError::Truncated(offset))
+    }
+
+    fn u16_at(&self, offset: usize) -> Result<u16, ElfError> {
+        let bytes = self.slice(offset, 2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32_at(&self, offset: usize) -> Result<u32, ElfError> {
+        let bytes = self.slice(offset, 4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn load_segments<T: Load>(&self, cpu: &mut T) -> Result<(), ElfError> {
+        for i in 0..self.phnum {
+            let phdr = self.phoff + i * PHDR_SIZE;
+            if self.u32_at(phdr)? != PT_LOAD {
+                continue;
+            }
+            let offset = self.u32_at(phdr + 4)? as usize;
+            let paddr = self.u32_at(phdr + 12)?;
+            let filesz = self.u32_at(phdr + 16)?;
+            let memsz = self.u32_at(phdr + 20)?;
+
+            // Copy the part of the segment that's in the file, then zero-fill the rest.
+            let data = self.slice(offset, filesz as usize)?;
+            cpu.write_bytes(paddr, data)
+                .map_err(|_| ElfError::LoadFailed(paddr))?;
+
+            // The rest is zero-filled a chunk at a time, so that a hostile file can't make us allocate memsz bytes.
+            let zeros = [0; ZERO_CHUNK];
+            let mut bss = paddr.wrapping_add(filesz);
+            let mut remaining = memsz.saturating_sub(filesz) as usize;
+            while remaining > 0 {
+                let len = remaining.min(ZERO_CHUNK);
+                cpu.write_bytes(bss, &zeros[..len])
+                    .map_err(|_| ElfError::LoadFailed(bss))?;
+                bss = bss.wrapping_add(len as u32);
+                remaining -= len;
+            }
+        }
+        Ok(())
+    }
+
+    fn symbols(&self) -> Result<SymbolTable, ElfError> {
+        let mut symbols = Vec::new();
+        for i in 0..self.shnum {
+            let shdr = self.shoff + i * SHDR_SIZE;
+            if self.u32_at(shdr + 4)? != SHT_SYMTAB {
+                continue;
+            }
+            let offset = self.u32_at(shdr + 16)? as usize;
+            let size = self.u32_at(shdr + 20)? as usize;
+            let link = self.u32_at(shdr + 24)? as usize;
+
+            // The linked section holds the names of the symbols.
+            let strtab = self.shoff + link * SHDR_SIZE;
+            let strtab_offset = self.u32_at(strtab + 16)? as usize;
+            let strtab_size = self.u32_at(strtab + 20)? as usize;
+            let names = self.slice(strtab_offset, strtab_size)?;
+
+            for sym in (offset..offset + size).step_by(SYM_SIZE) {
+                let name = self.u32_at(sym)? as usize;
+                let kind = match self.slice(sym + 12, 1)?[0] & 0xf {
+                    STT_NOTYPE => SymbolKind::NoType,
+                    STT_OBJECT => SymbolKind::Object,
+                    STT_FUNC => SymbolKind::Func,
+                    _ => continue,
+                };
+                let shndx = self.u16_at(sym + 14)?;
+                let name = match names.get(name..) {
+                    Some(name) => name.split(|&b| b == 0).next().unwrap_or_default(),
+                    None => continue,
+                };
+                // Skip the null symbol, undefined symbols, and the local labels that assemblers generate.
+                if name.is_empty()
+                    || shndx == 0
+                    || name.starts_with(b".L")
+                    || name.starts_with(b"$")
+                {
+                    continue;
+                }
+                symbols.push(Symbol {
+                    name: String::from_utf8_lossy(name).into_owned(),
+                    address: self.u32_at(sym + 4)?,
+                    size: self.u32_at(sym + 8)?,
+                    kind,
+                });
+            }
+        }
+        Ok(SymbolTable::new(symbols))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load, ElfError, FloatAbi, SymbolKind};
+    use crate::{backends::memory::basic::BasicMem, platforms::basic::Rv32iCpu, Fetch, Memory};
+
+    // Builds a minimal ELF executable with one loadable segment of `code` followed by `bss` bytes of zeros, and a
+    // symbol table with the given (name, value, info) entries.
+    fn make_elf(
+        entry: u32,
+        vaddr: u32,
+        code: &[u8],
+        bss: u32,
+        symbols: &[(&str, u32, u8)],
+    ) -> Vec<u8> {
+        let mut strtab = vec![0u8];
+        let mut symtab = vec![0u8; 16];
+        for (name, value, info) in symbols {
+            let name_offset = strtab.len() as u32;
+            strtab.extend_from_slice(name.as_bytes());
+            strtab.push(0);
+            symtab.extend_from_slice(&name_offset.to_le_bytes());
+            symtab.extend_from_slice(&value.to_le_bytes());
+            symtab.extend_from_slice(&0u32.to_le_bytes());
+            symtab.push(*info);
+            symtab.push(0);
+            symtab.extend_from_slice(&1u16.to_le_bytes());
+        }
+
+        let code_offset = 52 + 32;
+        let symtab_offset = code_offset + code.len();
+        let strtab_offset = symtab_offset + symtab.len();
+        let shoff = strtab_offset + strtab.len();
+
+        let mut elf = Vec::new();
+        elf.extend_from_slice(b"\x7fELF\x01\x01\x01\x00");
+        elf.extend_from_slice(&[0; 8]);
+        elf.extend_from_slice(&2u16.to_le_bytes()); // e_type
+        elf.extend_from_slice(&243u16.to_le_bytes()); // e_machine
+        elf.extend_from_slice(&1u32.to_le_bytes()); // e_version
+        elf.extend_from_slice(&entry.to_le_bytes()); // e_entry
+        elf.extend_from_slice(&52u32.to_le_bytes()); // e_phoff
+        elf.extend_from_slice(&(shoff as u32).to_le_bytes()); // e_shoff
+        elf.extend_from_slice(&0x5u32.to_le_bytes()); // e_flags: RVC, double float ABI
+        elf.extend_from_slice(&52u16.to_le_bytes()); // e_ehsize
+        elf.extend_from_slice(&32u16.to_le_bytes()); // e_phentsize
+        elf.extend_from_slice(&1u16.to_le_bytes()); // e_phnum
+        elf.extend_from_slice(&40u16.to_le_bytes()); // e_shentsize
+        elf.extend_from_slice(&3u16.to_le_bytes()); // e_shnum
+        elf.extend_from_slice(&0u16.to_le_bytes()); // e_shstrndx
+
+        // The program header.
+        for word in [
+            1,
+            code_offset as u32,
+            vaddr,
+            vaddr,
+            code.len() as u32,
+            code.len() as u32 + bss,
+            7,
+            4,
+        ] {
+            elf.extend_from_slice(&word.to_le_bytes());
+        }
+
+        elf.extend_from_slice(code);
+        elf.extend_from_slice(&symtab);
+        elf.extend_from_slice(&strtab);
+
+        // The section headers: null, .symtab and .strtab.
+        let sections: [[u32; 10]; 3] = [
+            [0; 10],
+            [
+                0,
+                2,
+                0,
+                0,
+                symtab_offset as u32,
+                symtab.len() as u32,
+                2,
+                1,
+                4,
+                16,
+            ],
+            [
+                0,
+                3,
+                0,
+                0,
+                strtab_offset as u32,
+                strtab.len() as u32,
+                0,
+                0,
+                1,
+                0,
+            ],
+        ];
+        for section in sections {
+            for word in section {
+                elf.extend_from_slice(&word.to_le_bytes());
+            }
+        }
+        elf
+    }
+
+    #[test]
+    fn test_load_copies_segments_and_sets_the_entry_point() {
+        let code = [0x13, 0x00, 0x00, 0x00, 0x73, 0x00, 0x10, 0x00]; // nop; ebreak
+        let elf = make_elf(0x4004, 0x4000, &code, 8, &[]);
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+        cpu.write32(0x4008, 0xffff_ffff).unwrap();
+        cpu.write32(0x400c, 0xffff_ffff).unwrap();
+
+        let info = load(&mut cpu, &elf).unwrap();
+
+        assert_eq!(0x4004, info.entry);
+        assert_eq!(Ok(0x0000_0013), cpu.read32(0x4000));
+        assert_eq!(Ok(0x0010_0073), cpu.read32(0x4004));
+        assert_eq!(Ok(0), cpu.read32(0x4008)); // .bss is zero-filled.
+        assert_eq!(Ok(0), cpu.read32(0x400c));
+        assert_eq!(Ok(0x0010_0073), cpu.fetch());
+        assert_eq!(0x4004, cpu.pc());
+    }
+
+    #[test]
+    fn test_large_bss_is_zero_filled_in_chunks() {
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+        cpu.write32(0x5ffc, 0xffff_ffff).unwrap();
+        cpu.write32(0x6004, 0xffff_ffff).unwrap();
+        let elf = make_elf(0x4000, 0x4000, &[0; 4], 0x2000, &[]);
+        load(&mut cpu, &elf).unwrap();
+        assert_eq!(Ok(0), cpu.read32(0x5ffc));
+        assert_eq!(Ok(0xffff_ffff), cpu.read32(0x6004));
+
+        // A huge .bss fails at the first chunk that doesn't fit in memory, rather than allocating all of it up front.
+        let elf = make_elf(0x4000, 0x4000, &[0; 4], 0xffff_0000, &[]);
+        assert_eq!(Err(ElfError::LoadFailed(0x7004)), load(&mut cpu, &elf));
+    }
+
+    #[test]
+    fn test_load_reports_isa_flags_and_symbols() {
+        let symbols = [
+            ("main", 0x4004, 0x12),
+            ("_start", 0x4000, 0x12),
+            ("counter", 0x4008, 0x11),
+            (".L0", 0x4004, 0x00),
+        ];
+        let elf = make_elf(0x4000, 0x4000, &[0; 8], 4, &symbols);
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+
+        let info = load(&mut cpu, &elf).unwrap();
+
+        assert!(info.flags.has_compressed());
+        assert_eq!(FloatAbi::Double, info.flags.float_abi());
+        assert!(!info.flags.is_rve());
+        let names: Vec<_> = info.symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(vec!["_start", "main", "counter"], names);
+        assert_eq!(
+            Some(SymbolKind::Object),
+            info.symbols.get("counter").map(|s| s.kind)
+        );
+        let (symbol, offset) = info.symbols.lookup(0x4006).unwrap();
+        assert_eq!(("main", 2), (symbol.name.as_str(), offset));
+        assert!(info.symbols.lookup(0x3fff).is_none());
+    }
+
+    #[test]
+    fn test_load_rejects_files_that_are_not_riscv_elf32_executables() {
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+        let elf = make_elf(0x4000, 0x4000, &[0; 4], 0, &[]);
+
+        assert_eq!(Err(ElfError::NotElf), load(&mut cpu, b"\x13\x00\x00\x00"));
+
+        let mut wrong_class = elf.clone();
+        wrong_class[4] = 2;
+        assert_eq!(Err(ElfError::WrongClass(2)), load(&mut cpu, &wrong_class));
+
+        let mut wrong_endianness = elf.clone();
+        wrong_endianness[5] = 2;
+        assert_eq!(
+            Err(ElfError::WrongEndianness(2)),
+            load(&mut cpu, &wrong_endianness)
+        );
+
+        let mut wrong_machine = elf.clone();
+        wrong_machine[18] = 0x3e;
+        assert_eq!(
+            Err(ElfError::WrongMachine(0x3e)),
+            load(&mut cpu, &wrong_machine)
+        );
+
+        assert_eq!(Err(ElfError::Truncated(0)), load(&mut cpu, &elf[..40]));
+
+        let mut wrong_phdr_size = elf.clone();
+        wrong_phdr_size[42] = 56;
+        assert_eq!(
+            Err(ElfError::WrongPhdrSize(56)),
+            load(&mut cpu, &wrong_phdr_size)
+        );
+
+        let outside_memory = make_elf(0x10000, 0x10000, &[0; 4], 0, &[]);
+        assert_eq!(
+            Err(ElfError::LoadFailed(0x10000)),
+            load(&mut cpu, &outside_memory)
+        );
+    }
+}
@@ -0,0 +1,412 @@
+//! Running guests until something interesting happens.
+//!
+//! The [`run`] function fetches and dispatches instructions until the CPU traps or until it has executed a given
+//! number of instructions, then returns a [`StopReason`] that says why it stopped. An [`Executor`] does the same, but
+//! it can also stop at breakpoints and watchpoints, and it can handle environment calls on the guest's behalf.
+//!
+//! Both are generic over the dispatcher, which is supplied as a closure so that the caller can choose the ISA.
+//!
+//! While the CPU is waiting for an interrupt after a `wfi`, it doesn't execute anything, but memory is still ticked so
+//! that devices can wake it. An [`Executor`] can also skip straight to the next device event instead, which makes
+//! guests that spend most of their time idle much faster to run. See [`Executor::set_fast_forward`].
+//!
+//! ```
+//! use arviss::executor::{run, StopReason};
+//! use arviss::platforms::basic::*;
+//! use arviss::DispatchRv32i;
+//!
+//! let mut cpu = Rv32iCpu::<BasicMem>::new();
+//! cpu.write_bytes(0, &0x0000006fu32.to_le_bytes()).unwrap(); // j 0
+//! assert_eq!(StopReason::Limit, run(&mut cpu, 1000, |cpu, ins| cpu.dispatch(ins)));
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    cpu::Fetch,
+    memory::{Address, Memory, MemoryResult},
+    trap::{Trap, TrapCause},
+};
+
+/// Why execution stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// The instruction limit was reached.
+    Limit,
+    /// The CPU trapped. The program counter is the address of the instruction that caused the trap.
+    Trap(TrapCause),
+    /// Execution reached a breakpoint at the given address. The instruction there hasn't been executed yet.
+    Breakpoint(Address),
+    /// The last instruction changed the memory that's watched by the watchpoint at the given address.
+    Watchpoint(Address),
+    /// An environment call hook asked to stop. The program counter is the address of the `ecall`.
+    Ecall,
+}
+
+/// What an environment call hook wants to happen after it has handled an `ecall`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EcallAction {
+    /// Resume execution at the instruction after the `ecall`.
+    Continue,
+    /// Stop with [`StopReason::Ecall`].
+    Stop,
+}
+
+/// The encoding of `ecall`.
+const ECALL: u32 = 0x00000073;
+
+/// An environment call hook.
+type EcallHook<T> = Box<dyn FnMut(&mut T) -> EcallAction>;
+
+/// Executes instructions with breakpoints, watchpoints, and an optional environment call hook.
+///
+/// Watchpoints are implemented in software, i.e., the executor checks the watched memory after every instruction, so
+/// they only detect writes that change its contents. The memory is checked with [`Memory::peek8`] so that watching it
+/// doesn't disturb the guest. That means that memory-mapped devices can't be watched, because reading their registers
+/// can have side effects, e.g., consuming a byte of input.
+pub struct Executor<T> {
+    breakpoints: BTreeSet<Address>,
+    watchpoints: BTreeMap<Address, Vec<u8>>,
+    ecall_hook: Option<EcallHook<T>>,
+    executed: u64,
+    fast_forward: bool,
+}
+
+impl<T> Default for Executor<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Executor<T> {
+    /// Creates an executor with no breakpoints, watchpoints or hooks.
+    pub fn new() -> Self {
+        Self {
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeMap::new(),
+            ecall_hook: None,
+            executed: 0,
+            fast_forward: false,
+        }
+    }
+
+    /// Returns the total number of instructions that this executor has executed.
+    pub fn executed(&self) -> u64 {
+        self.executed
+    }
+
+    /// Adds a breakpoint at the given address.
+    pub fn add_breakpoint(&mut self, address: Address) {
+        self.breakpoints.insert(address);
+    }
+
+    /// Removes the breakpoint at the given address.
+    pub fn remove_breakpoint(&mut self, address: Address) {
+        self.breakpoints.remove(&address);
+    }
+
+    /// Watches `len` bytes of memory starting at the given address, stopping whenever their contents change. Returns
+    /// an error if the memory can't be read without side effects, e.g., because it's a device's registers.
+    pub fn add_watchpoint(&mut self, cpu: &T, address: Address, len: u32) -> MemoryResult<()>
+    where
+        T: Memory,
+    {
+        let contents = peek_bytes(cpu, address, len)?;
+        self.watchpoints.insert(address, contents);
+        Ok(())
+    }
+
+    /// Removes the watchpoint at the given address.
+    pub fn remove_watchpoint(&mut self, address: Address) {
+        self.watchpoints.remove(&address);
+    }
+
+    /// Sets a hook that handles environment calls, e.g., to implement system calls for the guest. Without a hook, an
+    /// `ecall` stops execution with [`StopReason::Trap`], or enters the guest's trap handler on CPUs that vector traps.
+    ///
+    /// The hook runs in place of the `ecall`, so the CPU never sees it and doesn't trap.
+    pub fn on_ecall(&mut self, hook: impl FnMut(&mut T) -> EcallAction + 'static) {
+        self.ecall_hook = Some(Box::new(hook));
+    }
+
+    /// Sets whether time skips ahead while the CPU is waiting for an interrupt. When it does, each step spent waiting
+    /// advances memory straight to its next event with [`Memory::advance`], e.g., to the time in a CLINT's `mtimecmp`,
+    /// rather than ticking it once.
+    pub fn set_fast_forward(&mut self, fast_forward: bool) {
+        self.fast_forward = fast_forward;
+    }
+
+    /// Executes up to `limit` instructions, using `dispatch` to execute each one. Returns the reason for stopping.
+    ///
+    /// Breakpoints are checked before each instruction except the first, so execution can resume from a breakpoint by
+    /// calling `run` again. Instruction fetches that fail are reported as [`TrapCause::InstructionAccessFault`].
+    ///
+    /// Pending interrupts are taken between instructions with [`Trap::take_interrupt`], and memory is ticked with
+    /// [`Memory::tick`] after every instruction so that devices keep time with the guest. Each step that the CPU spends
+    /// waiting for an interrupt counts towards the limit, but not towards the number of instructions executed.
+    pub fn run<F>(&mut self, cpu: &mut T, limit: u64, mut dispatch: F) -> StopReason
+    where
+        T: Fetch + Trap + Memory,
+        F: FnMut(&mut T, u32),
+    {
+        if let Some(cause) = cpu.trap_cause() {
+            return StopReason::Trap(cause);
+        }
+        for count in 0..limit {
+            // Take any pending interrupt, so that the guest's trap handler is the next thing to run.
+            cpu.take_interrupt();
+
+            // Let time pass if the CPU is waiting for an interrupt.
+            if cpu.is_waiting() {
+                match cpu.next_event() {
+                    Some(ticks) if self.fast_forward && ticks > 0 => cpu.advance(ticks),
+                    _ => cpu.tick(),
+                }
+                continue;
+            }
+
+            // Stop at breakpoints, unless we're resuming from one.
+            let pc = cpu.transfer();
+            if count > 0 && self.breakpoints.contains(&pc) {
+                return StopReason::Breakpoint(pc);
+            }
+
+            // Fetch and execute the next instruction. An ecall goes to the hook instead of the CPU, if there is one, so
+            // that CPUs that vector traps don't enter the guest's trap handler.
+            let mut action = EcallAction::Continue;
+            match (cpu.fetch(), &mut self.ecall_hook) {
+                (Ok(ECALL), Some(hook)) => action = hook(cpu),
+                (Ok(ins), _) => dispatch(cpu, ins),
+                (Err(address), _) => cpu.handle_trap(TrapCause::InstructionAccessFault(address)),
+            }
+            cpu.tick();
+            self.executed += 1;
+
+            // Stop if the instruction trapped or if the hook asked to stop.
+            if let Some(cause) = cpu.trap_cause() {
+                return StopReason::Trap(cause);
+            }
+            if action == EcallAction::Stop {
+                return StopReason::Ecall;
+            }
+
+            // Stop if the instruction changed any watched memory.
+            for (&address, contents) in self.watchpoints.iter_mut() {
+                if let Ok(current) = peek_bytes(cpu, address, contents.len() as u32) {
+                    if current != *contents {
+                        *contents = current;
+                        return StopReason::Watchpoint(address);
+                    }
+                }
+            }
+        }
+        StopReason::Limit
+    }
+}
+
+/// Executes up to `limit` instructions, using `dispatch` to execute each one. Returns the reason for stopping.
+///
+/// Instruction fetches that fail are reported as [`TrapCause::InstructionAccessFault`].
+pub fn run<T, F>(cpu: &mut T, limit: u64, dispatch: F) -> StopReason
+where
+    T: Fetch + Trap + Memory,
+    F: FnMut(&mut T, u32),
+{
+    Executor::new().run(cpu, limit, dispatch)
+}
+
+fn peek_bytes<T: Memory>(cpu: &T, address: Address, len: u32) -> MemoryResult<Vec<u8>> {
+    (0..len)
+        .map(|i| cpu.peek8(address.wrapping_add(i)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::{run, EcallAction, Executor, StopReason};
+    use crate::test_support::{program, timer_cpu};
+    use crate::{
+        backends::{cpus::rv32izicsr::Rv32iZicsrCpu, memory::bus::Bus},
+        cpu::Csrs,
+        csr,
+        platforms::basic::*,
+        reg::Reg,
+        DispatchRv32i, DispatchRv32iZicsrPriv,
+    };
+
+    #[test]
+    fn test_run_stops_when_the_limit_is_reached_or_the_cpu_traps() {
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+        program(&mut cpu, 0, &[0x00150513, 0x00150513, 0x00100073]); // addi a0, a0, 1 (x2); ebreak
+        assert_eq!(
+            StopReason::Limit,
+            run(&mut cpu, 1, |cpu, ins| cpu.dispatch(ins))
+        );
+        assert_eq!(1, cpu.rx(Reg::new(10)));
+        assert_eq!(
+            StopReason::Trap(TrapCause::Breakpoint(8)),
+            run(&mut cpu, 10, |cpu, ins| cpu.dispatch(ins))
+        );
+        assert_eq!(2, cpu.rx(Reg::new(10)));
+        assert_eq!(8, cpu.pc());
+    }
+
+    #[test]
+    fn test_fetch_errors_are_instruction_access_faults() {
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+        cpu.set_next_pc(0x10000);
+        assert_eq!(
+            StopReason::Trap(TrapCause::InstructionAccessFault(0x10000)),
+            run(&mut cpu, 10, |cpu, ins| cpu.dispatch(ins))
+        );
+        assert_eq!(
+            Some(TrapCause::InstructionAccessFault(0x10000)),
+            cpu.trap_cause()
+        );
+        assert_eq!(0x10000, cpu.pc());
+    }
+
+    #[test]
+    fn test_execution_stops_at_breakpoints_and_watchpoints() {
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+        #[rustfmt::skip]
+        program(&mut cpu, 0, &[
+            0x000045b7, // 0x00: lui  a1, 0x4
+            0x00150513, // 0x04: addi a0, a0, 1
+            0x00a5a223, // 0x08: sw   a0, 4(a1)
+            0xff5ff06f, // 0x0c: j    0x00
+        ]);
+        let mut executor = Executor::new();
+        executor.add_breakpoint(0x08);
+        executor.add_watchpoint(&cpu, 0x4004, 4).unwrap();
+        let mut run =
+            |cpu: &mut Rv32iCpu<BasicMem>| executor.run(cpu, 100, |cpu, ins| cpu.dispatch(ins));
+        assert_eq!(StopReason::Breakpoint(0x08), run(&mut cpu));
+        assert_eq!(StopReason::Watchpoint(0x4004), run(&mut cpu));
+        assert_eq!(0x08, cpu.pc());
+        assert_eq!(StopReason::Breakpoint(0x08), run(&mut cpu));
+        assert_eq!(StopReason::Watchpoint(0x4004), run(&mut cpu));
+        assert_eq!(Ok(2), cpu.read32(0x4004));
+    }
+
+    #[test]
+    fn test_watchpoints_cannot_cover_devices() {
+        use crate::backends::devices::tty::Console;
+
+        let (sender, input) = std::sync::mpsc::channel();
+        sender.send(b'x').unwrap();
+        let console = Console::new(std::io::sink()).with_input(input);
+        let cpu = Rv32iCpu::with_mem(BasicMem::new().with_console(console));
+        let mut executor = Executor::new();
+        assert_eq!(Err(0x8000), executor.add_watchpoint(&cpu, 0x7ffc, 8));
+        assert_eq!(Ok(b'x'), cpu.read8(0x8001)); // Adding the watchpoint didn't consume the input.
+    }
+
+    #[test]
+    fn test_ecall_hooks_can_continue_or_stop() {
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+        #[rustfmt::skip]
+        program(&mut cpu, 0, &[
+            0x00000073, // 0x00: ecall
+            0x00000073, // 0x04: ecall
+            0x00000073, // 0x08: ecall
+        ]);
+        let calls = Rc::new(Cell::new(0));
+        let mut executor = Executor::new();
+        let counter = Rc::clone(&calls);
+        executor.on_ecall(move |_: &mut Rv32iCpu<BasicMem>| {
+            counter.set(counter.get() + 1);
+            if counter.get() < 2 {
+                EcallAction::Continue
+            } else {
+                EcallAction::Stop
+            }
+        });
+        assert_eq!(
+            StopReason::Ecall,
+            executor.run(&mut cpu, 10, |cpu, ins| cpu.dispatch(ins))
+        );
+        assert_eq!(2, calls.get());
+        assert_eq!(4, cpu.pc());
+        assert!(!cpu.is_trapped());
+        assert_eq!(2, executor.executed());
+    }
+
+    #[test]
+    fn test_ecall_hooks_run_instead_of_the_trap_handler_on_trap_vectoring_cpus() {
+        #[rustfmt::skip]
+        let mut cpu = timer_cpu(u32::MAX, &[
+            0x00000073, // 0x14: ecall
+            0x00100613, // 0x18: li     a2, 1
+            0x00100073, // 0x1c: ebreak
+        ]);
+        let mut executor = Executor::new();
+        executor.on_ecall(|cpu: &mut Rv32iZicsrCpu<Bus>| {
+            cpu.wx(Reg::new(13), 42);
+            EcallAction::Stop
+        });
+        assert_eq!(
+            StopReason::Ecall,
+            executor.run(&mut cpu, 100, DispatchRv32iZicsrPriv::dispatch)
+        );
+        assert_eq!(0x14, cpu.pc());
+        assert_eq!(42, cpu.rx(Reg::new(13)));
+        assert_eq!(Ok(0), cpu.rcsr(csr::MEPC));
+        assert_eq!(Ok(0), cpu.rcsr(csr::MCAUSE));
+
+        // Resuming continues after the ecall rather than in the trap handler.
+        assert_eq!(
+            StopReason::Trap(TrapCause::Breakpoint(0x1c)),
+            executor.run(&mut cpu, 100, DispatchRv32iZicsrPriv::dispatch)
+        );
+        assert_eq!(1, cpu.rx(Reg::new(12)));
+        assert_eq!(0, cpu.rx(Reg::new(10))); // The handler didn't run.
+    }
+
+    #[test]
+    fn test_interrupts_are_taken_between_instructions() {
+        let mut cpu = timer_cpu(20, &[0x0000006f]); // 0x14: j 0x14
+        let mut executor = Executor::new();
+        assert_eq!(
+            StopReason::Trap(TrapCause::Breakpoint(0x108)),
+            executor.run(&mut cpu, 100, DispatchRv32iZicsrPriv::dispatch)
+        );
+        assert_eq!(23, executor.executed());
+        assert_eq!(0x8000_0007, cpu.rx(Reg::new(10))); // mcause
+        assert_eq!(0x14, cpu.rx(Reg::new(11))); // mepc
+    }
+
+    #[test]
+    fn test_waiting_for_an_interrupt_can_fast_forward_to_the_next_event() {
+        #[rustfmt::skip]
+        let code = [
+            0x10500073, // 0x14: wfi
+            0xffdff06f, // 0x18: j 0x14
+        ];
+
+        // Without fast-forwarding, the CPU waits one tick at a time.
+        let mut cpu = timer_cpu(1_000_000, &code);
+        let mut executor = Executor::new();
+        assert_eq!(
+            StopReason::Limit,
+            executor.run(&mut cpu, 1000, DispatchRv32iZicsrPriv::dispatch)
+        );
+        assert_eq!(6, executor.executed());
+        assert!(cpu.is_waiting());
+
+        // With fast-forwarding, it skips straight to mtimecmp.
+        let mut cpu = timer_cpu(1_000_000, &code);
+        let mut executor = Executor::new();
+        executor.set_fast_forward(true);
+        assert_eq!(
+            StopReason::Trap(TrapCause::Breakpoint(0x108)),
+            executor.run(&mut cpu, 1000, DispatchRv32iZicsrPriv::dispatch)
+        );
+        assert_eq!(9, executor.executed());
+        assert_eq!(0x8000_0007, cpu.rx(Reg::new(10))); // mcause
+        assert_eq!(0x18, cpu.rx(Reg::new(11))); // mepc
+        assert_eq!(Ok(1_000_000 + 3), cpu.read32(0x0200_bff8)); // mtime
+    }
+}
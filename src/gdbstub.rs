@@ -0,0 +1,881 @@
+//! A stub that lets GDB debug guest programs using the GDB Remote Serial Protocol.
+//!
+//! The stub talks to GDB over a [`Connection`], which can be a TCP socket or a [`Pipe`]. It supports reading and
+//! writing registers and memory, software breakpoints, single-stepping, continuing, and interrupting a running guest
+//! with Ctrl-C.
+//!
+//! To debug a guest, serve it from a TCP socket, then attach to it with `target remote`.
+//!
+//! ```no_run
+//! # use std::error::Error;
+//! #
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! use std::net::TcpListener;
+//!
+//! use arviss::gdbstub::GdbStub;
+//! use arviss::platforms::basic::*;
+//! use arviss::DispatchRv32ic;
+//!
+//! let cpu = Rv32iCpu::<BasicMem>::new();
+//! let listener = TcpListener::bind("127.0.0.1:1234")?;
+//! let (mut stream, _) = listener.accept()?;
+//! let mut stub = GdbStub::new(cpu);
+//! stub.serve(&mut stream, |cpu, ins| cpu.dispatch(ins))?;
+//! #
+//! #     Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    io::{self, ErrorKind, Read, Write},
+    net::TcpStream,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use crate::{
+    cpu::{FRegisters, Fetch, XRegisters},
+    disassembler::{ABI_NAMES, FABI_NAMES},
+    memory::{Address, Memory},
+    reg::Reg,
+    trap::{Trap, TrapCause},
+};
+
+// Signals reported to GDB when the guest stops.
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+const SIGBUS: u8 = 7;
+const SIGSEGV: u8 = 11;
+
+// The byte that GDB sends to interrupt a running guest.
+const INTERRUPT: u8 = 0x03;
+
+// The encodings of ebreak and c.ebreak, which are used for software breakpoints.
+const EBREAK: u32 = 0x0010_0073;
+const C_EBREAK: u16 = 0x9002;
+
+// GDB's register numbers.
+const PC_REGNUM: usize = 32;
+const FIRST_FP_REGNUM: usize = 33;
+
+// How many instructions to execute between checks for an interrupt from GDB.
+const POLL_INTERVAL: u32 = 1024;
+
+// The largest packet that the stub accepts or sends, excluding its framing.
+const PACKET_SIZE: usize = 0x1000;
+
+/// A bidirectional byte stream to GDB.
+pub trait Connection {
+    /// Reads a byte, waiting until one is available.
+    fn read_byte(&mut self) -> io::Result<u8>;
+
+    /// Reads a byte if one is available without waiting.
+    fn poll_byte(&mut self) -> io::Result<Option<u8>>;
+
+    /// Writes all of the given bytes.
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()>;
+}
+
+impl Connection for TcpStream {
+    fn read_byte(&mut self) -> io::Result<u8> {
+        let mut byte = [0];
+        self.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn poll_byte(&mut self) -> io::Result<Option<u8>> {
+        self.set_nonblocking(true)?;
+        let mut byte = [0];
+        let result = match self.read(&mut byte) {
+            Ok(0) => Err(ErrorKind::UnexpectedEof.into()),
+            Ok(_) => Ok(Some(byte[0])),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e),
+        };
+        self.set_nonblocking(false)?;
+        result
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.write_all(bytes)?;
+        self.flush()
+    }
+}
+
+/// A connection made from a reader and a writer, such as stdin and stdout, for use with GDB's `target remote | command`.
+///
+/// The reader is read on a separate thread so that the stub can check for an interrupt without waiting.
+pub struct Pipe<W>
+where
+    W: Write,
+{
+    bytes: Receiver<u8>,
+    writer: W,
+}
+
+impl<W> Pipe<W>
+where
+    W: Write,
+{
+    /// Creates a connection that reads from `reader` and writes to `writer`.
+    pub fn new<R>(mut reader: R, writer: W) -> Self
+    where
+        R: Read + Send + 'static,
+    {
+        let (sender, bytes) = mpsc::channel();
+        thread::spawn(move || {
+            let mut byte = [0];
+            while let Ok(1) = reader.read(&mut byte) {
+                if sender.send(byte[0]).is_err() {
+                    break;
+                }
+            }
+        });
+        Self { bytes, writer }
+    }
+
+    /// Consumes the connection, returning its writer.
+    pub fn into_writer(self) -> W {
+        self.writer
+    }
+}
+
+impl<W> Connection for Pipe<W>
+where
+    W: Write,
+{
+    fn read_byte(&mut self) -> io::Result<u8> {
+        self.bytes
+            .recv()
+            .map_err(|_| ErrorKind::UnexpectedEof.into())
+    }
+
+    fn poll_byte(&mut self) -> io::Result<Option<u8>> {
+        match self.bytes.try_recv() {
+            Ok(byte) => Ok(Some(byte)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(ErrorKind::UnexpectedEof.into()),
+        }
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.writer.write_all(bytes)?;
+        self.writer.flush()
+    }
+}
+
+/// Accessors for the floating point registers of CPUs that have them.
+struct FpAccess<T> {
+    read: fn(&T, Reg) -> f32,
+    write: fn(&mut T, Reg, f32),
+}
+
+/// A breakpoint, along with the instruction that it replaced, if it was written to memory.
+enum Breakpoint {
+    Inserted(u32, usize),
+    Watched,
+}
+
+/// What the stub should do after handling a packet.
+enum Action {
+    Reply(String),
+    Resume { step: bool },
+    Stop,
+}
+
+/// A GDB stub that debugs a CPU.
+///
+/// Software breakpoints are inserted by replacing an instruction with `ebreak` or `c.ebreak`. If the memory can't be
+/// written, e.g., because it's ROM, the stub checks for the breakpoint before executing each instruction instead.
+///
+/// The stub stops the guest whenever it traps and reports the trap to GDB as a signal. If the CPU vectors traps to the
+/// guest's trap handler (see [`Trap::vectors_traps`]) then the program counter is left at the handler, so continuing
+/// lets the guest handle the trap. Otherwise, it's left at the instruction that caused the trap.
+pub struct GdbStub<T>
+where
+    T: Fetch + XRegisters + Memory + Trap,
+{
+    cpu: T,
+    fp: Option<FpAccess<T>>,
+    breakpoints: BTreeMap<Address, Breakpoint>,
+    no_ack: bool,
+    signal: u8,
+}
+
+impl<T> GdbStub<T>
+where
+    T: Fetch + XRegisters + Memory + Trap,
+{
+    /// Creates a stub for a CPU with integer registers.
+    pub fn new(mut cpu: T) -> Self {
+        // The program counter always holds the address of the next instruction while the guest is stopped.
+        cpu.transfer();
+        Self {
+            cpu,
+            fp: None,
+            breakpoints: BTreeMap::new(),
+            no_ack: false,
+            signal: SIGTRAP,
+        }
+    }
+
+    /// Creates a stub for a CPU with integer and single-precision floating point registers.
+    pub fn with_fregisters(cpu: T) -> Self
+    where
+        T: FRegisters,
+    {
+        Self {
+            fp: Some(FpAccess {
+                read: |cpu, reg| cpu.rf(reg),
+                write: |cpu, reg, val| cpu.wf(reg, val),
+            }),
+            ..Self::new(cpu)
+        }
+    }
+
+    /// Returns the CPU.
+    pub fn cpu(&self) -> &T {
+        &self.cpu
+    }
+
+    /// Returns the CPU.
+    pub fn cpu_mut(&mut self) -> &mut T {
+        &mut self.cpu
+    }
+
+    /// Consumes the stub, returning the CPU.
+    pub fn into_inner(self) -> T {
+        self.cpu
+    }
+
+    /// Serves GDB over the given connection, using `dispatch` to execute instructions. Returns when GDB detaches or
+    /// kills the guest.
+    pub fn serve<C, F>(&mut self, conn: &mut C, mut dispatch: F) -> io::Result<()>
+    where
+        C: Connection,
+        F: FnMut(&mut T, u32),
+    {
+        loop {
+            let packet = match self.read_packet(conn)? {
+                Some(packet) => packet,
+                None => continue,
+            };
+            match self.handle_packet(&packet) {
+                Action::Reply(reply) => self.write_packet(conn, &reply)?,
+                Action::Resume { step } => {
+                    self.signal = self.resume(conn, step, &mut dispatch)?;
+                    let reply = format!("S{:02x}", self.signal);
+                    self.write_packet(conn, &reply)?;
+                }
+                Action::Stop => {
+                    self.write_packet(conn, "OK")?;
+                    self.remove_all_breakpoints();
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    // Reads a packet, returning its contents, or `None` if it was corrupt or wasn't a packet.
+    fn read_packet<C: Connection>(&mut self, conn: &mut C) -> io::Result<Option<String>> {
+        // Skip acknowledgements and interrupts that arrive while the guest is stopped.
+        while conn.read_byte()? != b'$' {}
+        let mut data = Vec::new();
+        let mut checksum: u8 = 0;
+        loop {
+            let byte = conn.read_byte()?;
+            if byte == b'#' {
+                break;
+            }
+            checksum = checksum.wrapping_add(byte);
+            data.push(byte);
+        }
+        let expected = [conn.read_byte()?, conn.read_byte()?];
+        let expected = std::str::from_utf8(&expected)
+            .ok()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        if self.no_ack {
+            return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+        }
+        if expected == Some(checksum) {
+            conn.write_bytes(b"+")?;
+            Ok(Some(String::from_utf8_lossy(&data).into_owned()))
+        } else {
+            conn.write_bytes(b"-")?;
+            Ok(None)
+        }
+    }
+
+    fn write_packet<C: Connection>(&mut self, conn: &mut C, data: &str) -> io::Result<()> {
+        let checksum = data.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+        conn.write_bytes(format!("${}#{:02x}", data, checksum).as_bytes())?;
+        if !self.no_ack {
+            // Wait for the acknowledgement.
+            while !matches!(conn.read_byte()?, b'+' | b'-') {}
+        }
+        Ok(())
+    }
+
+    fn handle_packet(&mut self, packet: &str) -> Action {
+        let reply = match packet.as_bytes().first() {
+            Some(b'?') => format!("S{:02x}", self.signal),
+            Some(b'g') => self.read_registers(),
+            Some(b'G') => self.write_registers(&packet[1..]),
+            Some(b'p') => self.read_register(&packet[1..]),
+            Some(b'P') => self.write_register(&packet[1..]),
+            Some(b'm') => self.read_memory(&packet[1..]),
+            Some(b'M') => self.write_memory(&packet[1..]),
+            Some(b'Z') => self.insert_breakpoint(&packet[1..]),
+            Some(b'z') => self.remove_breakpoint(&packet[1..]),
+            Some(b'c') | Some(b's') => {
+                if let Some(address) = parse_hex(&packet[1..]) {
+                    self.set_pc(address);
+                }
+                return Action::Resume {
+                    step: packet.starts_with('s'),
+                };
+            }
+            Some(b'D') | Some(b'k') => return Action::Stop,
+            Some(b'H') | Some(b'T') => "OK".to_string(),
+            _ => self.handle_query(packet),
+        };
+        Action::Reply(reply)
+    }
+
+    fn handle_query(&mut self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            format!("PacketSize={PACKET_SIZE:x};qXfer:features:read+;QStartNoAckMode+")
+        } else if packet == "QStartNoAckMode" {
+            self.no_ack = true;
+            "OK".to_string()
+        } else if let Some(args) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            self.read_target_xml(args)
+        } else {
+            match packet {
+                "qAttached" => "1",
+                "qC" => "QC1",
+                "qfThreadInfo" => "m1",
+                "qsThreadInfo" => "l",
+                _ => "",
+            }
+            .to_string()
+        }
+    }
+
+    fn read_target_xml(&self, args: &str) -> String {
+        let (offset, length) = match parse_pair(args, ',') {
+            Some(pair) => pair,
+            None => return "E01".to_string(),
+        };
+        let xml = self.target_xml();
+        let start = (offset as usize).min(xml.len());
+        let end = start.saturating_add(length as usize).min(xml.len());
+        let more = if end < xml.len() { 'm' } else { 'l' };
+        format!("{}{}", more, &xml[start..end])
+    }
+
+    fn target_xml(&self) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\"?>\
+            <!DOCTYPE target SYSTEM \"gdb-target.dtd\">\
+            <target version=\"1.0\">\
+            <architecture>riscv:rv32</architecture>\
+            <feature name=\"org.gnu.gdb.riscv.cpu\">",
+        );
+        for (regnum, name) in ABI_NAMES.iter().enumerate() {
+            let kind = match *name {
+                "ra" => "code_ptr",
+                "sp" | "gp" | "tp" => "data_ptr",
+                _ => "int",
+            };
+            xml += &format!(
+                "<reg name=\"{name}\" bitsize=\"32\" type=\"{kind}\" regnum=\"{regnum}\"/>"
+            );
+        }
+        xml +=
+            &format!("<reg name=\"pc\" bitsize=\"32\" type=\"code_ptr\" regnum=\"{PC_REGNUM}\"/>");
+        xml += "</feature>";
+        if self.fp.is_some() {
+            xml += "<feature name=\"org.gnu.gdb.riscv.fpu\">";
+            for (index, name) in FABI_NAMES.iter().enumerate() {
+                let regnum = FIRST_FP_REGNUM + index;
+                xml += &format!("<reg name=\"{name}\" bitsize=\"32\" type=\"ieee_single\" regnum=\"{regnum}\"/>");
+            }
+            xml += "</feature>";
+        }
+        xml += "</target>";
+        xml
+    }
+
+    fn register_count(&self) -> usize {
+        if self.fp.is_some() {
+            FIRST_FP_REGNUM + 32
+        } else {
+            FIRST_FP_REGNUM
+        }
+    }
+
+    fn register(&self, regnum: usize) -> Option<u32> {
+        match (regnum, &self.fp) {
+            (0..=31, _) => Some(self.cpu.rx(Reg::new(regnum as u32))),
+            (PC_REGNUM, _) => Some(self.cpu.pc()),
+            (FIRST_FP_REGNUM..=64, Some(fp)) => {
+                let reg = Reg::new((regnum - FIRST_FP_REGNUM) as u32);
+                Some((fp.read)(&self.cpu, reg).to_bits())
+            }
+            _ => None,
+        }
+    }
+
+    fn set_register(&mut self, regnum: usize, value: u32) -> bool {
+        match (regnum, &self.fp) {
+            (0..=31, _) => self.cpu.wx(Reg::new(regnum as u32), value),
+            (PC_REGNUM, _) => self.set_pc(value),
+            (FIRST_FP_REGNUM..=64, Some(fp)) => {
+                let reg = Reg::new((regnum - FIRST_FP_REGNUM) as u32);
+                (fp.write)(&mut self.cpu, reg, f32::from_bits(value));
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn set_pc(&mut self, address: Address) {
+        self.cpu.set_next_pc(address);
+        self.cpu.transfer();
+    }
+
+    fn read_registers(&self) -> String {
+        (0..self.register_count())
+            .filter_map(|regnum| self.register(regnum))
+            .map(|value| to_hex(&value.to_le_bytes()))
+            .collect()
+    }
+
+    fn write_registers(&mut self, args: &str) -> String {
+        let values = match from_hex(args) {
+            Some(bytes) => bytes,
+            None => return "E01".to_string(),
+        };
+        for (regnum, value) in values.chunks_exact(4).enumerate() {
+            let value = u32::from_le_bytes([value[0], value[1], value[2], value[3]]);
+            self.set_register(regnum, value);
+        }
+        "OK".to_string()
+    }
+
+    fn read_register(&self, args: &str) -> String {
+        match parse_hex(args).and_then(|regnum| self.register(regnum as usize)) {
+            Some(value) => to_hex(&value.to_le_bytes()),
+            None => "E01".to_string(),
+        }
+    }
+
+    fn write_register(&mut self, args: &str) -> String {
+        let (regnum, value) = match args.split_once('=') {
+            Some((regnum, value)) => (parse_hex(regnum), from_hex(value)),
+            None => return "E01".to_string(),
+        };
+        match (regnum, value.as_deref()) {
+            (Some(regnum), Some(&[b0, b1, b2, b3])) => {
+                if self.set_register(regnum as usize, u32::from_le_bytes([b0, b1, b2, b3])) {
+                    "OK".to_string()
+                } else {
+                    "E01".to_string()
+                }
+            }
+            _ => "E01".to_string(),
+        }
+    }
+
+    // Reads a byte of memory, showing the original contents where a breakpoint has been inserted.
+    fn read_byte(&self, address: Address) -> Option<u8> {
+        let original =
+            self.breakpoints
+                .range(..=address)
+                .next_back()
+                .and_then(|(&start, breakpoint)| match breakpoint {
+                    Breakpoint::Inserted(ins, len) if address - start < *len as u32 => {
+                        Some(ins.to_le_bytes()[(address - start) as usize])
+                    }
+                    _ => None,
+                });
+        original.or_else(|| self.cpu.read8(address).ok())
+    }
+
+    fn read_memory(&self, args: &str) -> String {
+        let (address, length) = match parse_pair(args, ',') {
+            Some(pair) => pair,
+            None => return "E01".to_string(),
+        };
+        // Send no more than fits in a packet. GDB asks again for the rest.
+        let length = length.min((PACKET_SIZE / 2) as u32);
+        let bytes: Option<Vec<u8>> = (0..length)
+            .map(|i| self.read_byte(address.wrapping_add(i)))
+            .collect();
+        match bytes {
+            Some(bytes) => to_hex(&bytes),
+            None => "E14".to_string(),
+        }
+    }
+
+    fn write_memory(&mut self, args: &str) -> String {
+        let (range, data) = match args.split_once(':') {
+            Some(split) => split,
+            None => return "E01".to_string(),
+        };
+        let (address, data) = match (parse_pair(range, ','), from_hex(data)) {
+            (Some((address, _)), Some(data)) => (address, data),
+            _ => return "E01".to_string(),
+        };
+        for (i, byte) in data.into_iter().enumerate() {
+            if self
+                .cpu
+                .write8(address.wrapping_add(i as u32), byte)
+                .is_err()
+            {
+                return "E14".to_string();
+            }
+        }
+        "OK".to_string()
+    }
+
+    fn insert_breakpoint(&mut self, args: &str) -> String {
+        let (address, kind) = match args
+            .strip_prefix("0,")
+            .and_then(|args| parse_pair(args, ','))
+        {
+            Some(pair) => pair,
+            None => return String::new(),
+        };
+        if self.breakpoints.contains_key(&address) {
+            return "OK".to_string();
+        }
+        let inserted = match kind {
+            2 => self.cpu.read16(address).ok().and_then(|ins| {
+                self.cpu.write16(address, C_EBREAK).ok()?;
+                Some(Breakpoint::Inserted(u32::from(ins), 2))
+            }),
+            _ => self.cpu.read32(address).ok().and_then(|ins| {
+                self.cpu.write32(address, EBREAK).ok()?;
+                Some(Breakpoint::Inserted(ins, 4))
+            }),
+        };
+        self.breakpoints
+            .insert(address, inserted.unwrap_or(Breakpoint::Watched));
+        "OK".to_string()
+    }
+
+    fn remove_breakpoint(&mut self, args: &str) -> String {
+        let address = match args
+            .strip_prefix("0,")
+            .and_then(|args| parse_pair(args, ','))
+        {
+            Some((address, _)) => address,
+            None => return String::new(),
+        };
+        self.remove(address);
+        "OK".to_string()
+    }
+
+    fn remove(&mut self, address: Address) {
+        match self.breakpoints.remove(&address) {
+            Some(Breakpoint::Inserted(ins, 2)) => {
+                let _ = self.cpu.write16(address, ins as u16);
+            }
+            Some(Breakpoint::Inserted(ins, _)) => {
+                let _ = self.cpu.write32(address, ins);
+            }
+            _ => {}
+        }
+    }
+
+    fn remove_all_breakpoints(&mut self) {
+        let addresses: Vec<Address> = self.breakpoints.keys().copied().collect();
+        for address in addresses {
+            self.remove(address);
+        }
+    }
+
+    // Runs the guest until it stops, returning the signal to report to GDB.
+    fn resume<C, F>(&mut self, conn: &mut C, step: bool, dispatch: &mut F) -> io::Result<u8>
+    where
+        C: Connection,
+        F: FnMut(&mut T, u32),
+    {
+        let mut count: u32 = 0;
+        loop {
+            // Stop at breakpoints without executing them, so that the guest never sees GDB's own ebreaks. Breakpoints
+            // that couldn't be written to memory are skipped if we're resuming from one.
+            let pc = self.cpu.pc();
+            match self.breakpoints.get(&pc) {
+                Some(Breakpoint::Inserted(..)) => return Ok(SIGTRAP),
+                Some(Breakpoint::Watched) if count > 0 => return Ok(SIGTRAP),
+                _ => {}
+            }
+
+            // Execute the next instruction.
+            match self.cpu.fetch() {
+                Ok(ins) => dispatch(&mut self.cpu, ins),
+                Err(address) => self
+                    .cpu
+                    .handle_trap(TrapCause::InstructionAccessFault(address)),
+            }
+            self.cpu.tick();
+
+            // Stop if it trapped. If the CPU vectored to the guest's trap handler then stop there, so that continuing
+            // runs the handler. Otherwise, stop at the instruction that caused the trap.
+            if let Some(cause) = self.cpu.trap_cause() {
+                self.cpu.clear_trap();
+                if self.cpu.vectors_traps() {
+                    self.cpu.transfer();
+                } else {
+                    self.set_pc(pc);
+                }
+                return Ok(signal_for(cause));
+            }
+            self.cpu.take_interrupt();
+            self.cpu.transfer();
+
+            count = count.wrapping_add(1);
+            if step {
+                return Ok(SIGTRAP);
+            }
+            if count.is_multiple_of(POLL_INTERVAL) && conn.poll_byte()? == Some(INTERRUPT) {
+                return Ok(SIGINT);
+            }
+        }
+    }
+}
+
+/// Returns the signal that GDB expects for a trap.
+fn signal_for(cause: TrapCause) -> u8 {
+    match cause {
+        TrapCause::IllegalInstruction(_) => SIGILL,
+        TrapCause::InstructionAddressMisaligned(_)
+        | TrapCause::LoadAddressMisaligned(_)
+        | TrapCause::StoreAddressMisaligned(_) => SIGBUS,
+        TrapCause::InstructionAccessFault(_)
+        | TrapCause::LoadAccessFault(_)
+        | TrapCause::StoreAccessFault(_)
+        | TrapCause::InstructionPageFault(_)
+        | TrapCause::LoadPageFault(_)
+        | TrapCause::StorePageFault(_) => SIGSEGV,
+        cause if cause.is_interrupt() => SIGINT,
+        _ => SIGTRAP,
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn parse_hex(hex: &str) -> Option<u32> {
+    u32::from_str_radix(hex, 16).ok()
+}
+
+fn parse_pair(args: &str, separator: char) -> Option<(u32, u32)> {
+    let (first, second) = args.split_once(separator)?;
+    Some((parse_hex(first)?, parse_hex(second)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::{GdbStub, Pipe};
+    use crate::{
+        backends::memory::basic::BasicMem,
+        platforms::{basic::Rv32iCpu, basic_float::Rv32ifCpu},
+        test_support::program,
+        Fetch,
+    };
+
+    // Wraps each command in a packet and acknowledges each reply.
+    fn session(commands: &[&str]) -> Vec<u8> {
+        let mut input = Vec::new();
+        for command in commands {
+            if *command == "\x03" {
+                // GDB interrupts the guest before acknowledging the stop reply.
+                input.insert(input.len() - 1, 0x03);
+                continue;
+            }
+            let checksum = command.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+            input.extend_from_slice(format!("${}#{:02x}+", command, checksum).as_bytes());
+        }
+        input
+    }
+
+    // Returns the contents of the packets that the stub sent.
+    fn replies(output: &[u8]) -> Vec<String> {
+        let output = String::from_utf8_lossy(output);
+        output
+            .split('$')
+            .skip(1)
+            .map(|packet| packet.split('#').next().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_registers_and_memory_can_be_read_and_written() {
+        use crate::DispatchRv32ic;
+
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+        cpu.set_next_pc(0x100);
+        let mut stub = GdbStub::new(cpu);
+        let input = session(&[
+            "?",
+            "pa",
+            "Pa=78563412",
+            "pa",
+            "p20",
+            "M4000,2:abcd",
+            "m4000,4",
+            "m10000,1",
+            "D",
+        ]);
+        let mut pipe = Pipe::new(Cursor::new(input), Vec::new());
+        stub.serve(&mut pipe, |cpu, ins| cpu.dispatch(ins)).unwrap();
+        assert_eq!(
+            vec!["S05", "00000000", "OK", "78563412", "00010000", "OK", "abcd0000", "E14", "OK"],
+            replies(&pipe.into_writer())
+        );
+    }
+
+    #[test]
+    fn test_breakpoints_stop_the_guest_and_step_executes_one_instruction() {
+        use crate::DispatchRv32ic;
+
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+        #[rustfmt::skip]
+        program(&mut cpu, 0x4000, &[
+            0x00100513, // 0x4000: li a0, 1
+            0x00150513, // 0x4004: addi a0, a0, 1
+            0xffdff06f, // 0x4008: j 0x4004
+        ]);
+        cpu.set_next_pc(0x4000);
+        let mut stub = GdbStub::new(cpu);
+        let input = session(&[
+            "Z0,4004,4",
+            "m4004,4",
+            "c",
+            "p20",
+            "pa",
+            "z0,4004,4",
+            "s",
+            "p20",
+            "pa",
+            "D",
+        ]);
+        let mut pipe = Pipe::new(Cursor::new(input), Vec::new());
+        stub.serve(&mut pipe, |cpu, ins| cpu.dispatch(ins)).unwrap();
+        assert_eq!(
+            vec![
+                "OK", "13051500", "S05", "04400000", "01000000", "OK", "S05", "08400000",
+                "02000000", "OK"
+            ],
+            replies(&pipe.into_writer())
+        );
+    }
+
+    #[test]
+    fn test_breakpoints_work_in_rom() {
+        use crate::DispatchRv32ic;
+
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+        program(&mut cpu, 0, &[0x00100513, 0x00150513, 0xffdff06f]);
+        let mut stub = GdbStub::new(cpu);
+        let input = session(&["Z0,4,4", "c", "c", "pa", "D"]);
+        let mut pipe = Pipe::new(Cursor::new(input), Vec::new());
+        stub.serve(&mut pipe, |cpu, ins| cpu.dispatch(ins)).unwrap();
+        assert_eq!(
+            vec!["OK", "S05", "S05", "02000000", "OK"],
+            replies(&pipe.into_writer())
+        );
+    }
+
+    #[test]
+    fn test_a_running_guest_can_be_interrupted() {
+        use crate::DispatchRv32ic;
+
+        let mut cpu = Rv32iCpu::<BasicMem>::new();
+        program(&mut cpu, 0, &[0x0000006f]); // j 0
+        let mut stub = GdbStub::new(cpu);
+        let input = session(&["c", "\x03", "D"]);
+        let mut pipe = Pipe::new(Cursor::new(input), Vec::new());
+        stub.serve(&mut pipe, |cpu, ins| cpu.dispatch(ins)).unwrap();
+        assert_eq!(vec!["S02", "OK"], replies(&pipe.into_writer()));
+    }
+
+    #[test]
+    fn test_target_xml_describes_the_floating_point_registers() {
+        use crate::DispatchRv32imfc;
+
+        let cpu = Rv32ifCpu::<BasicMem>::new();
+        let mut stub = GdbStub::with_fregisters(cpu);
+        let input = session(&["qXfer:features:read:target.xml:0,fff", "p21", "D"]);
+        let mut pipe = Pipe::new(Cursor::new(input), Vec::new());
+        stub.serve(&mut pipe, |cpu, ins| cpu.dispatch(ins)).unwrap();
+        let replies = replies(&pipe.into_writer());
+        assert!(replies[0].starts_with("l<?xml"));
+        assert!(replies[0]
+            .contains("<reg name=\"pc\" bitsize=\"32\" type=\"code_ptr\" regnum=\"32\"/>"));
+        assert!(replies[0]
+            .contains("<reg name=\"ft0\" bitsize=\"32\" type=\"ieee_single\" regnum=\"33\"/>"));
+        assert_eq!("00000000", replies[1]);
+    }
+
+    #[test]
+    fn test_trap_vectoring_cpus_stop_at_the_trap_handler() {
+        use crate::{backends::cpus::rv32izicsr::Rv32iZicsrCpu, csr, Csrs, DispatchRv32iZicsrPriv};
+
+        let mut cpu = Rv32iZicsrCpu::<BasicMem>::new();
+        #[rustfmt::skip]
+        program(&mut cpu, 0x4000, &[
+            0x00000000, // 0x4000: illegal
+        ]);
+        #[rustfmt::skip]
+        program(&mut cpu, 0x4100, &[
+            0x00100513, // 0x4100: li a0, 1
+            0x0000006f, // 0x4104: j 0x4104
+        ]);
+        cpu.wcsr(csr::MTVEC, 0x4100).unwrap();
+        cpu.set_next_pc(0x4000);
+        let mut stub = GdbStub::new(cpu);
+        let input = session(&["Z0,4104,4", "c", "p20", "c", "p20", "pa", "D"]);
+        let mut pipe = Pipe::new(Cursor::new(input), Vec::new());
+        stub.serve(&mut pipe, |cpu, ins| cpu.dispatch(ins)).unwrap();
+        assert_eq!(
+            vec!["OK", "S04", "00410000", "S05", "04410000", "01000000", "OK"],
+            replies(&pipe.into_writer())
+        );
+
+        // The guest took the illegal instruction trap, but not the breakpoint.
+        assert_eq!(Ok(2), stub.cpu().rcsr(csr::MCAUSE));
+        assert_eq!(Ok(0x4000), stub.cpu().rcsr(csr::MEPC));
+    }
+
+    #[test]
+    fn test_memory_reads_are_limited_to_the_packet_size() {
+        use crate::DispatchRv32ic;
+
+        let mut stub = GdbStub::new(Rv32iCpu::<BasicMem>::new());
+        let input = session(&["m0,2000", "D"]);
+        let mut pipe = Pipe::new(Cursor::new(input), Vec::new());
+        stub.serve(&mut pipe, |cpu, ins| cpu.dispatch(ins)).unwrap();
+        let replies = replies(&pipe.into_writer());
+        assert_eq!(0x1000, replies[0].len());
+    }
+}
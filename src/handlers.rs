@@ -1,9 +1,10 @@
 //! Instruction handlers.
 
 use crate::{
-    cpu::{FRegisters, Fetch, XRegisters},
-    memory::Memory,
+    cpu::{Csrs, DRegisters, FRegisters, Fetch, MisalignedAccess, Reservation, XRegisters},
+    memory::{Address, Memory},
     reg::Reg,
+    softfloat::{SignOp, DYN, F32, F64, RMM},
     trap::{Trap, TrapCause},
 };
 
@@ -73,6 +74,98 @@ pub trait HandleRv32i {
     fn ebreak(&mut self) -> Self::Item;
 }
 
+/// Sets `next_pc` to the target of a jump or a taken branch, or raises an instruction-address-misaligned exception if
+/// the CPU's alignment policy doesn't allow it. Returns true if the target was valid.
+fn jump<T>(cpu: &mut T, target: Address) -> bool
+where
+    T: Fetch + Trap,
+{
+    if !cpu.alignment().instructions.is_aligned(target) {
+        cpu.handle_trap(TrapCause::InstructionAddressMisaligned(target));
+        return false;
+    }
+    cpu.set_next_pc(target);
+    true
+}
+
+/// Loads a zero-extended byte, half word or word, depending on `size`. Misaligned loads trap or are emulated according
+/// to the CPU's alignment policy.
+fn load<T>(cpu: &T, address: Address, size: u32) -> Result<u32, TrapCause>
+where
+    T: Fetch + Memory,
+{
+    let result = if address & (size - 1) == 0 {
+        match size {
+            1 => cpu.read8(address).map(u32::from),
+            2 => cpu.read16(address).map(u32::from),
+            _ => cpu.read32(address),
+        }
+    } else if cpu.alignment().data == MisalignedAccess::Emulate {
+        (0..size).rev().try_fold(0, |word, i| {
+            cpu.read8(address.wrapping_add(i))
+                .map(|byte| (word << 8) | u32::from(byte))
+        })
+    } else {
+        return Err(TrapCause::LoadAddressMisaligned(address));
+    };
+    result.map_err(TrapCause::LoadAccessFault)
+}
+
+/// Stores the low byte, half word or word of `value`, depending on `size`. Misaligned stores trap or are emulated
+/// according to the CPU's alignment policy.
+fn store<T>(cpu: &mut T, address: Address, size: u32, value: u32) -> Result<(), TrapCause>
+where
+    T: Fetch + Memory,
+{
+    let result = if address & (size - 1) == 0 {
+        match size {
+            1 => cpu.write8(address, value as u8),
+            2 => cpu.write16(address, value as u16),
+            _ => cpu.write32(address, value),
+        }
+    } else if cpu.alignment().data == MisalignedAccess::Emulate {
+        (0..size).try_for_each(|i| cpu.write8(address.wrapping_add(i), (value >> (8 * i)) as u8))
+    } else {
+        return Err(TrapCause::StoreAddressMisaligned(address));
+    };
+    result.map_err(TrapCause::StoreAccessFault)
+}
+
+/// Loads a double word as a single access, so that a fault can't leave a register half loaded. Misaligned loads trap
+/// or are emulated according to the CPU's alignment policy.
+fn load64<T>(cpu: &T, address: Address) -> Result<u64, TrapCause>
+where
+    T: Fetch + Memory,
+{
+    let result = if address & 7 == 0 {
+        cpu.read64(address)
+    } else if cpu.alignment().data == MisalignedAccess::Emulate {
+        (0..8).rev().try_fold(0, |double_word, i| {
+            cpu.read8(address.wrapping_add(i))
+                .map(|byte| (double_word << 8) | u64::from(byte))
+        })
+    } else {
+        return Err(TrapCause::LoadAddressMisaligned(address));
+    };
+    result.map_err(TrapCause::LoadAccessFault)
+}
+
+/// Stores a double word as a single access, so that a fault can't leave memory half written. Misaligned stores trap or
+/// are emulated according to the CPU's alignment policy.
+fn store64<T>(cpu: &mut T, address: Address, value: u64) -> Result<(), TrapCause>
+where
+    T: Fetch + Memory,
+{
+    let result = if address & 7 == 0 {
+        cpu.write64(address, value)
+    } else if cpu.alignment().data == MisalignedAccess::Emulate {
+        (0..8).try_for_each(|i| cpu.write8(address.wrapping_add(i), (value >> (8 * i)) as u8))
+    } else {
+        return Err(TrapCause::StoreAddressMisaligned(address));
+    };
+    result.map_err(TrapCause::StoreAccessFault)
+}
+
 impl<T> HandleRv32i for T
 where
     T: Fetch + Trap + XRegisters + Memory,
@@ -90,14 +183,14 @@ where
     fn beq(&mut self, rs1: Reg, rs2: Reg, bimm: u32) {
         // pc <- pc + ((rs1 == rs2) ? imm_b : 4)
         if self.rx(rs1) == self.rx(rs2) {
-            self.set_next_pc(self.pc().wrapping_add(bimm));
+            jump(self, self.pc().wrapping_add(bimm));
         }
     }
 
     fn bne(&mut self, rs1: Reg, rs2: Reg, bimm: u32) {
         // pc <- pc + ((rs1 != rs2) ? imm_b : 4)
         if self.rx(rs1) != self.rx(rs2) {
-            self.set_next_pc(self.pc().wrapping_add(bimm));
+            jump(self, self.pc().wrapping_add(bimm));
         }
     }
 
@@ -105,7 +198,7 @@ where
         // Signed.
         // pc <- pc + ((rs1 < rs2) ? imm_b : 4)
         if (self.rx(rs1) as i32) < (self.rx(rs2) as i32) {
-            self.set_next_pc(self.pc().wrapping_add(bimm));
+            jump(self, self.pc().wrapping_add(bimm));
         }
     }
 
@@ -113,7 +206,7 @@ where
         // Signed.
         // pc <- pc + ((rs1 >= rs2) ? imm_b : 4)
         if (self.rx(rs1) as i32) >= (self.rx(rs2) as i32) {
-            self.set_next_pc(self.pc().wrapping_add(bimm));
+            jump(self, self.pc().wrapping_add(bimm));
         }
     }
 
@@ -121,7 +214,7 @@ where
         // Unsigned.
         // pc <- pc + ((rs1 < rs2) ? imm_b : 4)
         if self.rx(rs1) < self.rx(rs2) {
-            self.set_next_pc(self.pc().wrapping_add(bimm));
+            jump(self, self.pc().wrapping_add(bimm));
         }
     }
 
@@ -129,7 +222,7 @@ where
         // Unsigned.
         // pc <- pc + ((rs1 >= rs2) ? imm_b : 4)
         if self.rx(rs1) >= self.rx(rs2) {
-            self.set_next_pc(self.pc().wrapping_add(bimm));
+            jump(self, self.pc().wrapping_add(bimm));
         }
     }
 
@@ -137,59 +230,59 @@ where
 
     fn lb(&mut self, rd: Reg, rs1: Reg, iimm: u32) {
         // rd <- sx(m8(rs1 + imm_i)), pc += 4
-        match self.read8(self.rx(rs1).wrapping_add(iimm)) {
+        match load(self, self.rx(rs1).wrapping_add(iimm), 1) {
             Ok(byte) => {
                 self.wx(rd, (((byte as i8) as i16) as i32) as u32); // TODO: this should be a function.
             }
-            Err(address) => {
-                self.handle_trap(TrapCause::LoadAccessFault(address));
+            Err(cause) => {
+                self.handle_trap(cause);
             }
         }
     }
 
     fn lh(&mut self, rd: Reg, rs1: Reg, iimm: u32) {
         // rd <- sx(m16(rs1 + imm_i)), pc += 4
-        match self.read16(self.rx(rs1).wrapping_add(iimm)) {
+        match load(self, self.rx(rs1).wrapping_add(iimm), 2) {
             Ok(half_word) => {
                 self.wx(rd, ((half_word as i16) as i32) as u32); // TODO: this should be a function.
             }
-            Err(address) => {
-                self.handle_trap(TrapCause::LoadAccessFault(address));
+            Err(cause) => {
+                self.handle_trap(cause);
             }
         }
     }
 
     fn lw(&mut self, rd: Reg, rs1: Reg, iimm: u32) {
         // rd <- sx(m32(rs1 + imm_i)), pc += 4
-        match self.read32(self.rx(rs1).wrapping_add(iimm)) {
+        match load(self, self.rx(rs1).wrapping_add(iimm), 4) {
             Ok(word) => {
                 self.wx(rd, word);
             }
-            Err(address) => {
-                self.handle_trap(TrapCause::LoadAccessFault(address));
+            Err(cause) => {
+                self.handle_trap(cause);
             }
         }
     }
 
     fn lbu(&mut self, rd: Reg, rs1: Reg, iimm: u32) {
         // rd <- zx(m8(rs1 + imm_i)), pc += 4
-        match self.read8(self.rx(rs1).wrapping_add(iimm)) {
+        match load(self, self.rx(rs1).wrapping_add(iimm), 1) {
             Ok(byte) => {
-                self.wx(rd, byte as u32);
+                self.wx(rd, byte);
             }
-            Err(address) => {
-                self.handle_trap(TrapCause::LoadAccessFault(address));
+            Err(cause) => {
+                self.handle_trap(cause);
             }
         }
     }
 
     fn lhu(&mut self, rd: Reg, rs1: Reg, iimm: u32) {
         // rd <- zx(m16(rs1 + imm_i)), pc += 4
-        match self.read16(self.rx(rs1).wrapping_add(iimm)) {
+        match load(self, self.rx(rs1).wrapping_add(iimm), 2) {
             Ok(half_word) => {
-                self.wx(rd, half_word as u32);
+                self.wx(rd, half_word);
             }
-            Err(address) => self.handle_trap(TrapCause::LoadAccessFault(address)),
+            Err(cause) => self.handle_trap(cause),
         }
     }
 
@@ -229,36 +322,32 @@ where
 
     fn jalr(&mut self, rd: Reg, rs1: Reg, iimm: u32) {
         // rd <- pc + 4, pc <- (rs1 + imm_i) & ~1
-        let rs1_before = self.rx(rs1); // Because rd and rs1 might be the same register.
-        self.wx(rd, self.pc().wrapping_add(4));
-        self.set_next_pc(rs1_before.wrapping_add(iimm) & !1);
+        let target = self.rx(rs1).wrapping_add(iimm) & !1; // Before writing rd, because rd and rs1 might be the same.
+        if jump(self, target) {
+            self.wx(rd, self.pc().wrapping_add(4));
+        }
     }
 
     // S-type instructions.
 
     fn sb(&mut self, rs1: Reg, rs2: Reg, simm: u32) {
         // m8(rs1 + imm_s) <- rs2[7:0], pc += 4
-        if let Err(address) =
-            self.write8(self.rx(rs1).wrapping_add(simm), (self.rx(rs2) & 0xff) as u8)
-        {
-            self.handle_trap(TrapCause::StoreAccessFault(address))
+        if let Err(cause) = store(self, self.rx(rs1).wrapping_add(simm), 1, self.rx(rs2)) {
+            self.handle_trap(cause)
         }
     }
 
     fn sh(&mut self, rs1: Reg, rs2: Reg, simm: u32) {
         // m16(rs1 + imm_s) <- rs2[15:0], pc += 4
-        if let Err(address) = self.write16(
-            self.rx(rs1).wrapping_add(simm),
-            (self.rx(rs2) & 0xffff) as u16,
-        ) {
-            self.handle_trap(TrapCause::StoreAccessFault(address))
+        if let Err(cause) = store(self, self.rx(rs1).wrapping_add(simm), 2, self.rx(rs2)) {
+            self.handle_trap(cause)
         }
     }
 
     fn sw(&mut self, rs1: Reg, rs2: Reg, simm: u32) {
         // m32(rs1 + imm_s) <- rs2[31:0], pc += 4
-        if let Err(address) = self.write32(self.rx(rs1).wrapping_add(simm), self.rx(rs2)) {
-            self.handle_trap(TrapCause::StoreAccessFault(address))
+        if let Err(cause) = store(self, self.rx(rs1).wrapping_add(simm), 4, self.rx(rs2)) {
+            self.handle_trap(cause)
         }
     }
 
@@ -278,8 +367,9 @@ where
 
     fn jal(&mut self, rd: Reg, jimm: u32) {
         // rd <- pc + 4, pc <- pc + imm_j
-        self.wx(rd, self.pc().wrapping_add(4));
-        self.set_next_pc(self.pc().wrapping_add(jimm));
+        if jump(self, self.pc().wrapping_add(jimm)) {
+            self.wx(rd, self.pc().wrapping_add(4));
+        }
     }
 
     // Arithmetic instructions.
@@ -366,7 +456,7 @@ where
     }
 
     fn ebreak(&mut self) {
-        self.handle_ebreak()
+        self.handle_ebreak(self.pc())
     }
 }
 
@@ -481,6 +571,142 @@ where
     }
 }
 
+/// An **instruction handler** for instructions from the 'A' extension for atomic instructions.
+pub trait HandleRv32a {
+    type Item;
+
+    // Load-reserved / store-conditional instructions.
+    fn lr_w(&mut self, rd: Reg, rs1: Reg, aqrl: u32) -> Self::Item;
+    fn sc_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item;
+
+    // Atomic memory operations.
+    fn amoswap_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item;
+    fn amoadd_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item;
+    fn amoxor_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item;
+    fn amoand_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item;
+    fn amoor_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item;
+    fn amomin_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item;
+    fn amomax_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item;
+    fn amominu_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item;
+    fn amomaxu_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item;
+}
+
+/// Performs an atomic read-modify-write of the word at rs1, writing `op(old, rs2)` to memory and `old` to rd.
+///
+/// There's only one hart so the read and the write are atomic by construction, which is also why the ordering bits are
+/// ignored. AMOs report both misaligned addresses and access faults as store / AMO exceptions.
+fn amo<T>(cpu: &mut T, rd: Reg, rs1: Reg, rs2: Reg, op: impl FnOnce(u32, u32) -> u32)
+where
+    T: Trap + XRegisters + Memory,
+{
+    let address = cpu.rx(rs1);
+    if address & 3 != 0 {
+        return cpu.handle_trap(TrapCause::StoreAddressMisaligned(address));
+    }
+    let old = match cpu.read32(address) {
+        Ok(word) => word,
+        Err(address) => return cpu.handle_trap(TrapCause::StoreAccessFault(address)),
+    };
+    if let Err(address) = cpu.write32(address, op(old, cpu.rx(rs2))) {
+        return cpu.handle_trap(TrapCause::StoreAccessFault(address));
+    }
+    cpu.wx(rd, old);
+}
+
+impl<T> HandleRv32a for T
+where
+    T: Fetch + Trap + XRegisters + Memory + Reservation,
+{
+    type Item = ();
+
+    // Load-reserved / store-conditional instructions.
+
+    fn lr_w(&mut self, rd: Reg, rs1: Reg, _aqrl: u32) -> Self::Item {
+        // rd <- m32(rs1), reserve(rs1)
+        let address = self.rx(rs1);
+        if address & 3 != 0 {
+            return self.handle_trap(TrapCause::LoadAddressMisaligned(address));
+        }
+        match self.read32(address) {
+            Ok(word) => {
+                self.reserve(address);
+                self.wx(rd, word);
+            }
+            Err(address) => self.handle_trap(TrapCause::LoadAccessFault(address)),
+        }
+    }
+
+    fn sc_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, _aqrl: u32) -> Self::Item {
+        // if reserved(rs1) { m32(rs1) <- rs2, rd <- 0 } else { rd <- 1 }
+        let address = self.rx(rs1);
+        if address & 3 != 0 {
+            return self.handle_trap(TrapCause::StoreAddressMisaligned(address));
+        }
+        if !self.take_reservation(address) {
+            return self.wx(rd, 1);
+        }
+        match self.write32(address, self.rx(rs2)) {
+            Ok(()) => self.wx(rd, 0),
+            Err(address) => self.handle_trap(TrapCause::StoreAccessFault(address)),
+        }
+    }
+
+    // Atomic memory operations.
+
+    fn amoswap_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, _aqrl: u32) -> Self::Item {
+        // rd <- m32(rs1), m32(rs1) <- rs2
+        amo(self, rd, rs1, rs2, |_, src| src);
+    }
+
+    fn amoadd_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, _aqrl: u32) -> Self::Item {
+        // rd <- m32(rs1), m32(rs1) <- m32(rs1) + rs2
+        amo(self, rd, rs1, rs2, |old, src| old.wrapping_add(src));
+    }
+
+    fn amoxor_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, _aqrl: u32) -> Self::Item {
+        // rd <- m32(rs1), m32(rs1) <- m32(rs1) ^ rs2
+        amo(self, rd, rs1, rs2, |old, src| old ^ src);
+    }
+
+    fn amoand_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, _aqrl: u32) -> Self::Item {
+        // rd <- m32(rs1), m32(rs1) <- m32(rs1) & rs2
+        amo(self, rd, rs1, rs2, |old, src| old & src);
+    }
+
+    fn amoor_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, _aqrl: u32) -> Self::Item {
+        // rd <- m32(rs1), m32(rs1) <- m32(rs1) | rs2
+        amo(self, rd, rs1, rs2, |old, src| old | src);
+    }
+
+    fn amomin_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, _aqrl: u32) -> Self::Item {
+        // Signed.
+        // rd <- m32(rs1), m32(rs1) <- min(m32(rs1), rs2)
+        amo(self, rd, rs1, rs2, |old, src| {
+            (old as i32).min(src as i32) as u32
+        });
+    }
+
+    fn amomax_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, _aqrl: u32) -> Self::Item {
+        // Signed.
+        // rd <- m32(rs1), m32(rs1) <- max(m32(rs1), rs2)
+        amo(self, rd, rs1, rs2, |old, src| {
+            (old as i32).max(src as i32) as u32
+        });
+    }
+
+    fn amominu_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, _aqrl: u32) -> Self::Item {
+        // Unsigned.
+        // rd <- m32(rs1), m32(rs1) <- min(m32(rs1), rs2)
+        amo(self, rd, rs1, rs2, |old, src| old.min(src));
+    }
+
+    fn amomaxu_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, _aqrl: u32) -> Self::Item {
+        // Unsigned.
+        // rd <- m32(rs1), m32(rs1) <- max(m32(rs1), rs2)
+        amo(self, rd, rs1, rs2, |old, src| old.max(src));
+    }
+}
+
 /// An **instruction handler** for instructions from the 'C' extension for compressed instructions.
 pub trait HandleRv32c {
     type Item;
@@ -700,8 +926,143 @@ pub trait HandleRv32f {
     fn flt_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item;
     fn fsgnjx_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item;
     fn feq_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item;
+
+    // Compressed instructions, dispatched only when the 'C' extension is also enabled.
+    fn c_flw(&mut self, rdp: Reg, rs1p: Reg, imm: u32) -> Self::Item;
+    fn c_fsw(&mut self, rs1p: Reg, rs2p: Reg, imm: u32) -> Self::Item;
+    fn c_flwsp(&mut self, rd: Reg, imm: u32) -> Self::Item;
+    fn c_fswsp(&mut self, rs2: Reg, imm: u32) -> Self::Item;
+}
+
+/// Returns the bits of an instruction with the OP-FP major opcode, for reporting illegal instructions.
+fn encode_op_fp(funct7: u32, rs2: u32, rs1: Reg, rm: u32, rd: Reg) -> u32 {
+    let rs1 = usize::from(rs1) as u32;
+    let rd = usize::from(rd) as u32;
+    (funct7 << 25) | (rs2 << 20) | (rs1 << 15) | (rm << 12) | (rd << 7) | 0x53
+}
+
+/// Returns the bits of a fused multiply / add instruction, for reporting illegal instructions.
+fn encode_fma(opcode: u32, fmt: u32, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> u32 {
+    let rs3 = usize::from(rs3) as u32;
+    let rs2 = usize::from(rs2) as u32;
+    let rs1 = usize::from(rs1) as u32;
+    let rd = usize::from(rd) as u32;
+    (rs3 << 27) | (fmt << 25) | (rs2 << 20) | (rs1 << 15) | (rm << 12) | (rd << 7) | opcode
+}
+
+/// Returns the bits of a floating point load, for reporting illegal instructions.
+fn encode_fp_load(width: u32, rd: Reg, rs1: Reg, iimm: u32) -> u32 {
+    let rs1 = usize::from(rs1) as u32;
+    let rd = usize::from(rd) as u32;
+    ((iimm & 0xfff) << 20) | (rs1 << 15) | (width << 12) | (rd << 7) | 0x07
+}
+
+/// Returns the bits of a floating point store, for reporting illegal instructions.
+fn encode_fp_store(width: u32, rs1: Reg, rs2: Reg, simm: u32) -> u32 {
+    let rs2 = usize::from(rs2) as u32;
+    let rs1 = usize::from(rs1) as u32;
+    (((simm >> 5) & 0x7f) << 25)
+        | (rs2 << 20)
+        | (rs1 << 15)
+        | (width << 12)
+        | ((simm & 0x1f) << 7)
+        | 0x27
+}
+
+/// Returns the bits of a compressed floating point load or store with a register base, i.e., `c.flw`, `c.fsw`, `c.fld`
+/// or `c.fsd`, for reporting illegal instructions.
+fn encode_c_fp_mem(funct3: u32, rdrs2p: Reg, rs1p: Reg, imm: u32) -> u32 {
+    let rdrs2p = usize::from(rdrs2p) as u32 & 0b111;
+    let rs1p = usize::from(rs1p) as u32 & 0b111;
+    let imm_6_5 = if funct3 & 0b010 != 0 {
+        // Words: imm[2|6].
+        (((imm >> 2) & 0b1) << 6) | (((imm >> 6) & 0b1) << 5)
+    } else {
+        // Double words: imm[7:6].
+        ((imm >> 6) & 0b11) << 5
+    };
+    (funct3 << 13) | (((imm >> 3) & 0b111) << 10) | (rs1p << 7) | imm_6_5 | (rdrs2p << 2)
+}
+
+/// Returns the bits of a compressed floating point load relative to the stack pointer, i.e., `c.flwsp` or `c.fldsp`,
+/// for reporting illegal instructions.
+fn encode_c_fp_load_sp(funct3: u32, rd: Reg, imm: u32) -> u32 {
+    let rd = usize::from(rd) as u32;
+    let imm_6_2 = if funct3 & 0b010 != 0 {
+        // Words: imm[4:2|7:6].
+        (((imm >> 2) & 0b111) << 4) | (((imm >> 6) & 0b11) << 2)
+    } else {
+        // Double words: imm[4:3|8:6].
+        (((imm >> 3) & 0b11) << 5) | (((imm >> 6) & 0b111) << 2)
+    };
+    (funct3 << 13) | (((imm >> 5) & 0b1) << 12) | (rd << 7) | imm_6_2 | 0b10
+}
+
+/// Returns the bits of a compressed floating point store relative to the stack pointer, i.e., `c.fswsp` or `c.fsdsp`,
+/// for reporting illegal instructions.
+fn encode_c_fp_store_sp(funct3: u32, rs2: Reg, imm: u32) -> u32 {
+    let rs2 = usize::from(rs2) as u32;
+    let imm_12_7 = if funct3 & 0b010 != 0 {
+        // Words: imm[5:2|7:6].
+        (((imm >> 2) & 0b1111) << 9) | (((imm >> 6) & 0b11) << 7)
+    } else {
+        // Double words: imm[5:3|8:6].
+        (((imm >> 3) & 0b111) << 10) | (((imm >> 6) & 0b111) << 7)
+    };
+    (funct3 << 13) | imm_12_7 | (rs2 << 2) | 0b10
+}
+
+/// Traps with an illegal instruction if floating point instructions are disabled, returning true if it did so. The trap
+/// reports `ins`, the bits of the instruction being executed.
+fn trap_if_fp_disabled<T>(cpu: &mut T, ins: u32) -> bool
+where
+    T: Trap + FRegisters,
+{
+    if cpu.is_fp_enabled() {
+        return false;
+    }
+    cpu.handle_trap(TrapCause::IllegalInstruction(ins));
+    true
+}
+
+/// Performs a floating point operation with the rounding mode from the instruction, or from `frm` if the instruction
+/// uses the dynamic rounding mode, then accrues the exception flags that the operation returns. Reserved rounding
+/// modes are illegal.
+fn with_rounding<T>(cpu: &mut T, ins: u32, rm: u32, op: impl FnOnce(&mut T, u32) -> u32)
+where
+    T: Trap + FRegisters,
+{
+    let rm = if rm == DYN { cpu.frm() } else { rm };
+    if rm > RMM {
+        return cpu.handle_trap(TrapCause::IllegalInstruction(ins));
+    }
+    let flags = op(cpu, rm);
+    cpu.accrue_fflags(flags);
+}
+
+#[inline]
+fn rf_operand<T: FRegisters>(cpu: &T, reg: Reg) -> u64 {
+    u64::from(cpu.rf(reg).to_bits())
+}
+
+#[inline]
+fn wf_bits<T: FRegisters>(cpu: &mut T, reg: Reg, bits: u64) {
+    cpu.wf(reg, f32::from_bits(bits as u32));
 }
 
+#[inline]
+fn rfd_bits<T: DRegisters>(cpu: &T, reg: Reg) -> u64 {
+    cpu.rfd(reg).to_bits()
+}
+
+#[inline]
+fn wfd_bits<T: DRegisters>(cpu: &mut T, reg: Reg, bits: u64) {
+    cpu.wfd(reg, f64::from_bits(bits));
+}
+
+const F32_SIGN: u64 = 0x8000_0000;
+const F64_SIGN: u64 = 0x8000_0000_0000_0000;
+
 impl<T> HandleRv32f for T
 where
     T: Fetch + Trap + XRegisters + FRegisters + Memory,
@@ -711,13 +1072,17 @@ where
     // I-type instructions.
 
     fn flw(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        let ins = encode_fp_load(0b010, rd, rs1, iimm);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- f32(rs1 + imm_i)
-        match self.read32(self.rx(rs1).wrapping_add(iimm)) {
+        match load(self, self.rx(rs1).wrapping_add(iimm), 4) {
             Ok(word) => {
                 self.wf(rd, f32::from_bits(word));
             }
-            Err(address) => {
-                self.handle_trap(TrapCause::LoadAccessFault(address));
+            Err(cause) => {
+                self.handle_trap(cause);
             }
         }
     }
@@ -725,211 +1090,973 @@ where
     // S-type instructions.
 
     fn fsw(&mut self, rs1: Reg, rs2: Reg, simm: u32) -> Self::Item {
+        let ins = encode_fp_store(0b010, rs1, rs2, simm);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // f32(rs1 + imm_s) = rs2
-        let data = f32::to_bits(self.rf(rs2));
-        if let Err(address) = self.write32(self.rx(rs1).wrapping_add(simm), data) {
-            self.handle_trap(TrapCause::StoreAccessFault(address));
+        let data = self.rf_bits(rs2);
+        if let Err(cause) = store(self, self.rx(rs1).wrapping_add(simm), 4, data) {
+            self.handle_trap(cause);
         }
     }
 
     // Instructions with rd rs1 rm operands.
 
-    fn fsqrt_s(&mut self, rd: Reg, rs1: Reg, _rm: u32) -> Self::Item {
+    fn fsqrt_s(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b0101100, 0, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- sqrt(rs1)
-        let f = self.rf(rs1);
-        self.wf(rd, f32::sqrt(f));
-        // TODO: handle rounding modes.
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F32.sqrt(rf_operand(cpu, rs1), rm);
+            wf_bits(cpu, rd, result);
+            flags
+        });
     }
 
-    fn fcvt_w_s(&mut self, rd: Reg, rs1: Reg, _rm: u32) -> Self::Item {
+    fn fcvt_w_s(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b1100000, 0, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- int32_t(rs1)
-        let i = self.rf(rs1) as i32;
-        self.wx(rd, i as u32);
-        // TODO: handle rounding modes.
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F32.float_to_int(rf_operand(cpu, rs1), true, rm);
+            cpu.wx(rd, result);
+            flags
+        });
     }
 
-    fn fcvt_wu_s(&mut self, rd: Reg, rs1: Reg, _rm: u32) -> Self::Item {
+    fn fcvt_wu_s(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b1100000, 1, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- uint32_t(rs1)
-        let i = self.rf(rs1) as u32;
-        self.wx(rd, i);
-        // TODO: handle rounding modes.
-    }
-
-    fn fcvt_s_w(&mut self, rd: Reg, rs1: Reg, _rm: u32) -> Self::Item {
-        // rd <- float(int32_t((rs1))
-        let i = self.rx(rs1) as i32;
-        self.wf(rd, i as f32);
-        // TODO: handle rounding modes.
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F32.float_to_int(rf_operand(cpu, rs1), false, rm);
+            cpu.wx(rd, result);
+            flags
+        });
     }
 
-    fn fcvt_s_wu(&mut self, rd: Reg, rs1: Reg, _rm: u32) -> Self::Item {
+    fn fcvt_s_w(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b1101000, 0, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- float(int32_t(rs1))
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F32.int_to_float(cpu.rx(rs1), true, rm);
+            wf_bits(cpu, rd, result);
+            flags
+        });
+    }
+
+    fn fcvt_s_wu(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b1101000, 1, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- float(rs1)
-        self.wf(rd, self.rx(rs1) as f32);
-        // TODO: handle rounding modes.
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F32.int_to_float(cpu.rx(rs1), false, rm);
+            wf_bits(cpu, rd, result);
+            flags
+        });
     }
 
     // Arithmetic instructions.
 
-    fn fadd_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, _rm: u32) -> Self::Item {
+    fn fadd_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b0000000, usize::from(rs2) as u32, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- rs1 + rs2
-        self.wf(rd, self.rf(rs1) + self.rf(rs2));
-        // TODO: handle rounding modes.
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F32.add(rf_operand(cpu, rs1), rf_operand(cpu, rs2), rm);
+            wf_bits(cpu, rd, result);
+            flags
+        });
     }
 
-    fn fsub_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, _rm: u32) -> Self::Item {
+    fn fsub_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b0000100, usize::from(rs2) as u32, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- rs1 - rs2
-        self.wf(rd, self.rf(rs1) - self.rf(rs2));
-        // TODO: handle rounding modes.
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F32.sub(rf_operand(cpu, rs1), rf_operand(cpu, rs2), rm);
+            wf_bits(cpu, rd, result);
+            flags
+        });
     }
 
-    fn fmul_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, _rm: u32) -> Self::Item {
+    fn fmul_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b0001000, usize::from(rs2) as u32, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- rs1 * rs2
-        self.wf(rd, self.rf(rs1) * self.rf(rs2));
-        // TODO: handle rounding modes.
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F32.mul(rf_operand(cpu, rs1), rf_operand(cpu, rs2), rm);
+            wf_bits(cpu, rd, result);
+            flags
+        });
     }
 
-    fn fdiv_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, _rm: u32) -> Self::Item {
+    fn fdiv_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b0001100, usize::from(rs2) as u32, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- rs1 / rs2
-        self.wf(rd, self.rf(rs1) / self.rf(rs2));
-        // TODO: handle rounding modes.
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F32.div(rf_operand(cpu, rs1), rf_operand(cpu, rs2), rm);
+            wf_bits(cpu, rd, result);
+            flags
+        });
     }
 
     // Fused multiply / add instructions.
 
-    fn fmadd_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, _rm: u32) -> Self::Item {
+    fn fmadd_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        let ins = encode_fma(0x43, 0, rd, rs1, rs2, rs3, rm);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- (rs1 * rs2) + rs3
-        self.wf(rd, (self.rf(rs1) * self.rf(rs2)) + self.rf(rs3));
-        // TODO: handle rounding modes.
-    }
-
-    fn fmsub_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, _rm: u32) -> Self::Item {
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F32.mul_add(
+                rf_operand(cpu, rs1),
+                rf_operand(cpu, rs2),
+                rf_operand(cpu, rs3),
+                rm,
+            );
+            wf_bits(cpu, rd, result);
+            flags
+        });
+    }
+
+    fn fmsub_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        let ins = encode_fma(0x47, 0, rd, rs1, rs2, rs3, rm);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- (rs1 * rs2) - rs3
-        self.wf(rd, (self.rf(rs1) * self.rf(rs2)) - self.rf(rs3));
-        // TODO: handle rounding modes.
-    }
-
-    fn fnmsub_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, _rm: u32) -> Self::Item {
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F32.mul_add(
+                rf_operand(cpu, rs1),
+                rf_operand(cpu, rs2),
+                rf_operand(cpu, rs3) ^ F32_SIGN,
+                rm,
+            );
+            wf_bits(cpu, rd, result);
+            flags
+        });
+    }
+
+    fn fnmsub_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        let ins = encode_fma(0x4b, 0, rd, rs1, rs2, rs3, rm);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- -(rs1 * rs2) + rs3
-        self.wf(rd, -(self.rf(rs1) * self.rf(rs2)) + self.rf(rs3));
-        // TODO: handle rounding modes.
-    }
-
-    fn fnmadd_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, _rm: u32) -> Self::Item {
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F32.mul_add(
+                rf_operand(cpu, rs1) ^ F32_SIGN,
+                rf_operand(cpu, rs2),
+                rf_operand(cpu, rs3),
+                rm,
+            );
+            wf_bits(cpu, rd, result);
+            flags
+        });
+    }
+
+    fn fnmadd_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        let ins = encode_fma(0x4f, 0, rd, rs1, rs2, rs3, rm);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- -(rs1 * rs2) - rs3
-        self.wf(rd, -(self.rf(rs1) * self.rf(rs2)) - self.rf(rs3));
-        // TODO: handle rounding modes.
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F32.mul_add(
+                rf_operand(cpu, rs1) ^ F32_SIGN,
+                rf_operand(cpu, rs2),
+                rf_operand(cpu, rs3) ^ F32_SIGN,
+                rm,
+            );
+            wf_bits(cpu, rd, result);
+            flags
+        });
     }
 
     // Instructions with rd rs1 operands.
 
     fn fmv_x_w(&mut self, rd: Reg, rs1: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b1110000, 0, rs1, 0b000, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // bits(rd) <- bits(rs1)
-        self.wx(rd, f32::to_bits(self.rf(rs1)));
+        self.wx(rd, self.rf_bits(rs1));
     }
 
     fn fmv_w_x(&mut self, rd: Reg, rs1: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b1111000, 0, rs1, 0b000, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // bits(rd) <- bits(rs1)
         self.wf(rd, f32::from_bits(self.rx(rs1)));
     }
 
     fn fclass_s(&mut self, rd: Reg, rs1: Reg) -> Self::Item {
-        let v = self.rf(rs1);
-        let bits = f32::to_bits(v);
-        let result: u32 = if v == f32::NEG_INFINITY {
-            1 << 0
-        } else if v == f32::INFINITY {
-            1 << 7
-        } else if bits == 0x80000000 {
-            // Negative zero.
-            1 << 3
-        } else if v == 0.0 {
-            1 << 4
-        } else if (bits & 0x7f800000) == 0 {
-            // The exponent is zero.
-            if (bits & 0x80000000) != 0 {
-                // Negative subnormal number.
-                1 << 2
-            } else {
-                // Postive subnormal number.
-                1 << 5
-            }
-        } else if (bits & 0x7f800000) == 0x7f800000 && (bits & 0x00400000) != 0 {
-            // Quiet NaN.
-            1 << 9
-        } else if (bits & 0x7f800000) == 0x7f800000 && (bits & 0x003fffff) != 0 {
-            // Signalling NaN.
-            1 << 8
-        } else if v < 0.0 {
-            1 << 1
-        } else if v > 0.0 {
-            1 << 6
-        } else {
-            0
-        };
-        self.wx(rd, result);
+        let ins = encode_op_fp(0b1110000, 0, rs1, 0b001, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- class(rs1)
+        self.wx(rd, F32.classify(rf_operand(self, rs1)));
     }
 
     // Instructions with rd rs1 rs2 operands.
 
     fn fsgnj_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b0010000, usize::from(rs2) as u32, rs1, 0b000, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- abs(rs1) * sgn(rs2)
-        let freg_rs1 = self.rf(rs1);
-        let freg_rs2 = self.rf(rs2);
-        self.wf(rd, freg_rs1.abs() * if freg_rs2 < 0.0 { -1.0 } else { 1.0 });
+        let result = F32.sign_inject(rf_operand(self, rs1), rf_operand(self, rs2), SignOp::Copy);
+        wf_bits(self, rd, result);
     }
 
     fn fmin_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b0010100, usize::from(rs2) as u32, rs1, 0b000, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- min(rs1, rs2)
-        let freg_rs1 = self.rf(rs1);
-        let freg_rs2 = self.rf(rs2);
-        self.wf(rd, freg_rs1.min(freg_rs2));
+        let (result, flags) = F32.min(rf_operand(self, rs1), rf_operand(self, rs2));
+        wf_bits(self, rd, result);
+        self.accrue_fflags(flags);
     }
 
     fn fle_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b1010000, usize::from(rs2) as u32, rs1, 0b000, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- (rs1 <= rs2) ? 1 : 0;
-        let freg_rs1 = self.rf(rs1);
-        let freg_rs2 = self.rf(rs2);
-        self.wx(rd, if freg_rs1 < freg_rs2 { 1 } else { 0 });
+        let (result, flags) = F32.le(rf_operand(self, rs1), rf_operand(self, rs2));
+        self.wx(rd, u32::from(result));
+        self.accrue_fflags(flags);
     }
 
     fn fsgnjn_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b0010000, usize::from(rs2) as u32, rs1, 0b001, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- abs(rs1) * -sgn(rs2)
-        let freg_rs1 = self.rf(rs1);
-        let freg_rs2 = self.rf(rs2);
-        self.wf(rd, freg_rs1.abs() * if freg_rs2 < 0.0 { 1.0 } else { -1.0 });
+        let result = F32.sign_inject(rf_operand(self, rs1), rf_operand(self, rs2), SignOp::Negate);
+        wf_bits(self, rd, result);
     }
 
     fn fmax_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b0010100, usize::from(rs2) as u32, rs1, 0b001, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- max(rs1, rs2)
-        let freg_rs1 = self.rf(rs1);
-        let freg_rs2 = self.rf(rs2);
-        self.wf(rd, freg_rs1.max(freg_rs2));
+        let (result, flags) = F32.max(rf_operand(self, rs1), rf_operand(self, rs2));
+        wf_bits(self, rd, result);
+        self.accrue_fflags(flags);
     }
 
     fn flt_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b1010000, usize::from(rs2) as u32, rs1, 0b001, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- (rs1 < rs2) ? 1 : 0;
-        let freg_rs1 = self.rf(rs1);
-        let freg_rs2 = self.rf(rs2);
-        self.wx(rd, if freg_rs1 <= freg_rs2 { 1 } else { 0 });
+        let (result, flags) = F32.lt(rf_operand(self, rs1), rf_operand(self, rs2));
+        self.wx(rd, u32::from(result));
+        self.accrue_fflags(flags);
     }
 
     fn fsgnjx_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b0010000, usize::from(rs2) as u32, rs1, 0b010, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- abs(rs1) * (sgn(rs1) == sgn(rs2)) ? 1 : -1
-        let freg_rs1 = self.rf(rs1);
-        let freg_rs2 = self.rf(rs2);
-        // The sign bit is the XOR of the sign bits of rs1 and rs2.
-        let m = if (freg_rs1 < 0.0 && freg_rs2 >= 0.0) || (freg_rs1 >= 0.0 && freg_rs2 < 0.0) {
-            -1.0
-        } else {
-            1.0
-        };
-        self.wf(rd, freg_rs1.abs() * m);
+        let result = F32.sign_inject(rf_operand(self, rs1), rf_operand(self, rs2), SignOp::Xor);
+        wf_bits(self, rd, result);
     }
 
     fn feq_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b1010000, usize::from(rs2) as u32, rs1, 0b010, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
         // rd <- (rs1 == rs2) ? 1 : 0;
-        let freg_rs1 = self.rf(rs1);
-        let freg_rs2 = self.rf(rs2);
-        self.wx(rd, if freg_rs1 == freg_rs2 { 1 } else { 0 });
+        let (result, flags) = F32.eq(rf_operand(self, rs1), rf_operand(self, rs2));
+        self.wx(rd, u32::from(result));
+        self.accrue_fflags(flags);
+    }
+
+    // Compressed instructions.
+
+    fn c_flw(&mut self, rdp: Reg, rs1p: Reg, imm: u32) -> Self::Item {
+        if trap_if_fp_disabled(self, encode_c_fp_mem(0b011, rdp, rs1p, imm)) {
+            return;
+        }
+        // flw rdp, offset[6:2](rs1p)
+        self.flw(rdp, rs1p, imm);
+    }
+
+    fn c_fsw(&mut self, rs1p: Reg, rs2p: Reg, imm: u32) -> Self::Item {
+        if trap_if_fp_disabled(self, encode_c_fp_mem(0b111, rs2p, rs1p, imm)) {
+            return;
+        }
+        // fsw rs2p, offset[6:2](rs1p)
+        self.fsw(rs1p, rs2p, imm);
+    }
+
+    fn c_flwsp(&mut self, rd: Reg, imm: u32) -> Self::Item {
+        if trap_if_fp_disabled(self, encode_c_fp_load_sp(0b011, rd, imm)) {
+            return;
+        }
+        // flw rd, offset[7:2](x2)
+        self.flw(rd, Reg::SP, imm);
+    }
+
+    fn c_fswsp(&mut self, rs2: Reg, imm: u32) -> Self::Item {
+        if trap_if_fp_disabled(self, encode_c_fp_store_sp(0b111, rs2, imm)) {
+            return;
+        }
+        // fsw rs2, offset[7:2](x2)
+        self.fsw(Reg::SP, rs2, imm);
+    }
+}
+
+/// An **instruction handler** for instructions from the 'D' extension for double-precision floating point.
+pub trait HandleRv32d {
+    type Item;
+
+    // I-type instructions.
+    fn fld(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item;
+
+    // S-type instructions.
+    fn fsd(&mut self, rs1: Reg, rs2: Reg, simm: u32) -> Self::Item;
+
+    // Instructions with rd rs1 rm operands.
+    fn fsqrt_d(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item;
+    fn fcvt_s_d(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item;
+    fn fcvt_d_s(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item;
+    fn fcvt_w_d(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item;
+    fn fcvt_wu_d(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item;
+    fn fcvt_d_w(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item;
+    fn fcvt_d_wu(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item;
+
+    // Arithmetic instructions.
+    fn fadd_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item;
+    fn fsub_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item;
+    fn fmul_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item;
+    fn fdiv_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item;
+
+    // Fused multiply / add instructions.
+    fn fmadd_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item;
+    fn fmsub_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item;
+    fn fnmsub_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item;
+    fn fnmadd_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item;
+
+    // Instructions with rd rs1 operands.
+    fn fclass_d(&mut self, rd: Reg, rs1: Reg) -> Self::Item;
+
+    // Instructions with rd rs1 rs2 operands.
+    fn fsgnj_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item;
+    fn fsgnjn_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item;
+    fn fsgnjx_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item;
+    fn fmin_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item;
+    fn fmax_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item;
+    fn fle_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item;
+    fn flt_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item;
+    fn feq_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item;
+
+    // Compressed instructions, dispatched only when the 'C' extension is also enabled.
+    fn c_fld(&mut self, rdp: Reg, rs1p: Reg, imm: u32) -> Self::Item;
+    fn c_fsd(&mut self, rs1p: Reg, rs2p: Reg, imm: u32) -> Self::Item;
+    fn c_fldsp(&mut self, rd: Reg, imm: u32) -> Self::Item;
+    fn c_fsdsp(&mut self, rs2: Reg, imm: u32) -> Self::Item;
+}
+
+impl<T> HandleRv32d for T
+where
+    T: Fetch + Trap + XRegisters + FRegisters + DRegisters + Memory,
+{
+    type Item = ();
+
+    // I-type instructions.
+
+    fn fld(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        let ins = encode_fp_load(0b011, rd, rs1, iimm);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- f64(rs1 + imm_i)
+        match load64(self, self.rx(rs1).wrapping_add(iimm)) {
+            Ok(double_word) => {
+                self.wfd(rd, f64::from_bits(double_word));
+            }
+            Err(cause) => {
+                self.handle_trap(cause);
+            }
+        }
+    }
+
+    // S-type instructions.
+
+    fn fsd(&mut self, rs1: Reg, rs2: Reg, simm: u32) -> Self::Item {
+        let ins = encode_fp_store(0b011, rs1, rs2, simm);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // f64(rs1 + imm_s) = rs2
+        let data = f64::to_bits(self.rfd(rs2));
+        if let Err(cause) = store64(self, self.rx(rs1).wrapping_add(simm), data) {
+            self.handle_trap(cause);
+        }
+    }
+
+    // Instructions with rd rs1 rm operands.
+
+    fn fsqrt_d(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b0101101, 0, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- sqrt(rs1)
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F64.sqrt(rfd_bits(cpu, rs1), rm);
+            wfd_bits(cpu, rd, result);
+            flags
+        });
+    }
+
+    fn fcvt_s_d(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b0100000, 1, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- float(rs1)
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F64.convert(rfd_bits(cpu, rs1), F32, rm);
+            wf_bits(cpu, rd, result);
+            flags
+        });
+    }
+
+    fn fcvt_d_s(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b0100001, 0, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- double(rs1)
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F32.convert(rf_operand(cpu, rs1), F64, rm);
+            wfd_bits(cpu, rd, result);
+            flags
+        });
+    }
+
+    fn fcvt_w_d(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b1100001, 0, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- int32_t(rs1)
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F64.float_to_int(rfd_bits(cpu, rs1), true, rm);
+            cpu.wx(rd, result);
+            flags
+        });
+    }
+
+    fn fcvt_wu_d(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b1100001, 1, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- uint32_t(rs1)
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F64.float_to_int(rfd_bits(cpu, rs1), false, rm);
+            cpu.wx(rd, result);
+            flags
+        });
+    }
+
+    fn fcvt_d_w(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b1101001, 0, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- double(int32_t(rs1))
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F64.int_to_float(cpu.rx(rs1), true, rm);
+            wfd_bits(cpu, rd, result);
+            flags
+        });
+    }
+
+    fn fcvt_d_wu(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b1101001, 1, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- double(rs1)
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F64.int_to_float(cpu.rx(rs1), false, rm);
+            wfd_bits(cpu, rd, result);
+            flags
+        });
+    }
+
+    // Arithmetic instructions.
+
+    fn fadd_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b0000001, usize::from(rs2) as u32, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- rs1 + rs2
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F64.add(rfd_bits(cpu, rs1), rfd_bits(cpu, rs2), rm);
+            wfd_bits(cpu, rd, result);
+            flags
+        });
+    }
+
+    fn fsub_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b0000101, usize::from(rs2) as u32, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- rs1 - rs2
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F64.sub(rfd_bits(cpu, rs1), rfd_bits(cpu, rs2), rm);
+            wfd_bits(cpu, rd, result);
+            flags
+        });
+    }
+
+    fn fmul_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b0001001, usize::from(rs2) as u32, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- rs1 * rs2
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F64.mul(rfd_bits(cpu, rs1), rfd_bits(cpu, rs2), rm);
+            wfd_bits(cpu, rd, result);
+            flags
+        });
+    }
+
+    fn fdiv_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        let ins = encode_op_fp(0b0001101, usize::from(rs2) as u32, rs1, rm, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- rs1 / rs2
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F64.div(rfd_bits(cpu, rs1), rfd_bits(cpu, rs2), rm);
+            wfd_bits(cpu, rd, result);
+            flags
+        });
+    }
+
+    // Fused multiply / add instructions.
+
+    fn fmadd_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        let ins = encode_fma(0x43, 1, rd, rs1, rs2, rs3, rm);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- (rs1 * rs2) + rs3
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F64.mul_add(
+                rfd_bits(cpu, rs1),
+                rfd_bits(cpu, rs2),
+                rfd_bits(cpu, rs3),
+                rm,
+            );
+            wfd_bits(cpu, rd, result);
+            flags
+        });
+    }
+
+    fn fmsub_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        let ins = encode_fma(0x47, 1, rd, rs1, rs2, rs3, rm);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- (rs1 * rs2) - rs3
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F64.mul_add(
+                rfd_bits(cpu, rs1),
+                rfd_bits(cpu, rs2),
+                rfd_bits(cpu, rs3) ^ F64_SIGN,
+                rm,
+            );
+            wfd_bits(cpu, rd, result);
+            flags
+        });
+    }
+
+    fn fnmsub_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        let ins = encode_fma(0x4b, 1, rd, rs1, rs2, rs3, rm);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- -(rs1 * rs2) + rs3
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F64.mul_add(
+                rfd_bits(cpu, rs1) ^ F64_SIGN,
+                rfd_bits(cpu, rs2),
+                rfd_bits(cpu, rs3),
+                rm,
+            );
+            wfd_bits(cpu, rd, result);
+            flags
+        });
+    }
+
+    fn fnmadd_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        let ins = encode_fma(0x4f, 1, rd, rs1, rs2, rs3, rm);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- -(rs1 * rs2) - rs3
+        with_rounding(self, ins, rm, |cpu, rm| {
+            let (result, flags) = F64.mul_add(
+                rfd_bits(cpu, rs1) ^ F64_SIGN,
+                rfd_bits(cpu, rs2),
+                rfd_bits(cpu, rs3) ^ F64_SIGN,
+                rm,
+            );
+            wfd_bits(cpu, rd, result);
+            flags
+        });
+    }
+
+    // Instructions with rd rs1 operands.
+
+    fn fclass_d(&mut self, rd: Reg, rs1: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b1110001, 0, rs1, 0b001, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- class(rs1)
+        self.wx(rd, F64.classify(rfd_bits(self, rs1)));
+    }
+
+    // Instructions with rd rs1 rs2 operands.
+
+    fn fsgnj_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b0010001, usize::from(rs2) as u32, rs1, 0b000, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- abs(rs1) * sgn(rs2)
+        let result = F64.sign_inject(rfd_bits(self, rs1), rfd_bits(self, rs2), SignOp::Copy);
+        wfd_bits(self, rd, result);
+    }
+
+    fn fsgnjn_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b0010001, usize::from(rs2) as u32, rs1, 0b001, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- abs(rs1) * -sgn(rs2)
+        let result = F64.sign_inject(rfd_bits(self, rs1), rfd_bits(self, rs2), SignOp::Negate);
+        wfd_bits(self, rd, result);
+    }
+
+    fn fsgnjx_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b0010001, usize::from(rs2) as u32, rs1, 0b010, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- abs(rs1) * (sgn(rs1) == sgn(rs2)) ? 1 : -1
+        let result = F64.sign_inject(rfd_bits(self, rs1), rfd_bits(self, rs2), SignOp::Xor);
+        wfd_bits(self, rd, result);
+    }
+
+    fn fmin_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b0010101, usize::from(rs2) as u32, rs1, 0b000, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- min(rs1, rs2)
+        let (result, flags) = F64.min(rfd_bits(self, rs1), rfd_bits(self, rs2));
+        wfd_bits(self, rd, result);
+        self.accrue_fflags(flags);
+    }
+
+    fn fmax_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b0010101, usize::from(rs2) as u32, rs1, 0b001, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- max(rs1, rs2)
+        let (result, flags) = F64.max(rfd_bits(self, rs1), rfd_bits(self, rs2));
+        wfd_bits(self, rd, result);
+        self.accrue_fflags(flags);
+    }
+
+    fn fle_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b1010001, usize::from(rs2) as u32, rs1, 0b000, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- (rs1 <= rs2) ? 1 : 0;
+        let (result, flags) = F64.le(rfd_bits(self, rs1), rfd_bits(self, rs2));
+        self.wx(rd, u32::from(result));
+        self.accrue_fflags(flags);
+    }
+
+    fn flt_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b1010001, usize::from(rs2) as u32, rs1, 0b001, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- (rs1 < rs2) ? 1 : 0;
+        let (result, flags) = F64.lt(rfd_bits(self, rs1), rfd_bits(self, rs2));
+        self.wx(rd, u32::from(result));
+        self.accrue_fflags(flags);
+    }
+
+    fn feq_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        let ins = encode_op_fp(0b1010001, usize::from(rs2) as u32, rs1, 0b010, rd);
+        if trap_if_fp_disabled(self, ins) {
+            return;
+        }
+        // rd <- (rs1 == rs2) ? 1 : 0;
+        let (result, flags) = F64.eq(rfd_bits(self, rs1), rfd_bits(self, rs2));
+        self.wx(rd, u32::from(result));
+        self.accrue_fflags(flags);
+    }
+
+    // Compressed instructions.
+
+    fn c_fld(&mut self, rdp: Reg, rs1p: Reg, imm: u32) -> Self::Item {
+        if trap_if_fp_disabled(self, encode_c_fp_mem(0b001, rdp, rs1p, imm)) {
+            return;
+        }
+        // fld rdp, offset[7:3](rs1p)
+        self.fld(rdp, rs1p, imm);
+    }
+
+    fn c_fsd(&mut self, rs1p: Reg, rs2p: Reg, imm: u32) -> Self::Item {
+        if trap_if_fp_disabled(self, encode_c_fp_mem(0b101, rs2p, rs1p, imm)) {
+            return;
+        }
+        // fsd rs2p, offset[7:3](rs1p)
+        self.fsd(rs1p, rs2p, imm);
+    }
+
+    fn c_fldsp(&mut self, rd: Reg, imm: u32) -> Self::Item {
+        if trap_if_fp_disabled(self, encode_c_fp_load_sp(0b001, rd, imm)) {
+            return;
+        }
+        // fld rd, offset[8:3](x2)
+        self.fld(rd, Reg::SP, imm);
+    }
+
+    fn c_fsdsp(&mut self, rs2: Reg, imm: u32) -> Self::Item {
+        if trap_if_fp_disabled(self, encode_c_fp_store_sp(0b101, rs2, imm)) {
+            return;
+        }
+        // fsd rs2, offset[8:3](x2)
+        self.fsd(Reg::SP, rs2, imm);
+    }
+}
+
+/// An **instruction handler** for instructions from the 'Zicsr' extension for control and status registers.
+pub trait HandleZicsr {
+    type Item;
+
+    // Instructions with rd rs1 csr operands.
+    fn csrrw(&mut self, rd: Reg, rs1: Reg, csr: u32) -> Self::Item;
+    fn csrrs(&mut self, rd: Reg, rs1: Reg, csr: u32) -> Self::Item;
+    fn csrrc(&mut self, rd: Reg, rs1: Reg, csr: u32) -> Self::Item;
+
+    // Instructions with rd zimm csr operands.
+    fn csrrwi(&mut self, rd: Reg, zimm: u32, csr: u32) -> Self::Item;
+    fn csrrsi(&mut self, rd: Reg, zimm: u32, csr: u32) -> Self::Item;
+    fn csrrci(&mut self, rd: Reg, zimm: u32, csr: u32) -> Self::Item;
+}
+
+/// The operation performed by a Zicsr instruction on the value in a CSR.
+enum CsrOp {
+    Write,
+    Set,
+    Clear,
+}
+
+/// Performs the read-modify-write common to all Zicsr instructions, trapping with `ins` if the CSR can't be accessed.
+///
+/// The CSR is only read if the instruction isn't a write to x0, and it is only written if `write` is set, so that
+/// neither happens with side-effects that the instruction doesn't call for.
+fn access_csr<T>(cpu: &mut T, ins: u32, rd: Reg, csr: u32, op: CsrOp, operand: u32, write: bool)
+where
+    T: Trap + XRegisters + Csrs,
+{
+    let read = !matches!(op, CsrOp::Write) || usize::from(rd) != 0;
+    let old = if read {
+        match cpu.rcsr(csr) {
+            Ok(value) => value,
+            Err(_) => return cpu.handle_trap(TrapCause::IllegalInstruction(ins)),
+        }
+    } else {
+        0
+    };
+    if write {
+        let new = match op {
+            CsrOp::Write => operand,
+            CsrOp::Set => old | operand,
+            CsrOp::Clear => old & !operand,
+        };
+        if cpu.wcsr(csr, new).is_err() {
+            return cpu.handle_trap(TrapCause::IllegalInstruction(ins));
+        }
+    }
+    cpu.wx(rd, old);
+}
+
+/// Reassembles a Zicsr instruction from its operands so that it can be reported as an illegal instruction.
+fn encode_zicsr(funct3: u32, rd: Reg, rs1: u32, csr: u32) -> u32 {
+    let rd = usize::from(rd) as u32;
+    (csr << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | 0x73
+}
+
+impl<T> HandleZicsr for T
+where
+    T: Fetch + Trap + XRegisters + Csrs,
+{
+    type Item = ();
+
+    // Instructions with rd rs1 csr operands.
+
+    fn csrrw(&mut self, rd: Reg, rs1: Reg, csr: u32) -> Self::Item {
+        // t <- csr, csr <- rs1, rd <- t
+        let ins = encode_zicsr(0b001, rd, usize::from(rs1) as u32, csr);
+        access_csr(self, ins, rd, csr, CsrOp::Write, self.rx(rs1), true);
+    }
+
+    fn csrrs(&mut self, rd: Reg, rs1: Reg, csr: u32) -> Self::Item {
+        // t <- csr, csr <- t | rs1, rd <- t
+        let rs1_index = usize::from(rs1) as u32;
+        let ins = encode_zicsr(0b010, rd, rs1_index, csr);
+        access_csr(self, ins, rd, csr, CsrOp::Set, self.rx(rs1), rs1_index != 0);
+    }
+
+    fn csrrc(&mut self, rd: Reg, rs1: Reg, csr: u32) -> Self::Item {
+        // t <- csr, csr <- t & ~rs1, rd <- t
+        let rs1_index = usize::from(rs1) as u32;
+        let ins = encode_zicsr(0b011, rd, rs1_index, csr);
+        access_csr(
+            self,
+            ins,
+            rd,
+            csr,
+            CsrOp::Clear,
+            self.rx(rs1),
+            rs1_index != 0,
+        );
+    }
+
+    // Instructions with rd zimm csr operands.
+
+    fn csrrwi(&mut self, rd: Reg, zimm: u32, csr: u32) -> Self::Item {
+        // rd <- csr, csr <- zimm
+        let ins = encode_zicsr(0b101, rd, zimm, csr);
+        access_csr(self, ins, rd, csr, CsrOp::Write, zimm, true);
+    }
+
+    fn csrrsi(&mut self, rd: Reg, zimm: u32, csr: u32) -> Self::Item {
+        // t <- csr, csr <- t | zimm, rd <- t
+        let ins = encode_zicsr(0b110, rd, zimm, csr);
+        access_csr(self, ins, rd, csr, CsrOp::Set, zimm, zimm != 0);
+    }
+
+    fn csrrci(&mut self, rd: Reg, zimm: u32, csr: u32) -> Self::Item {
+        // t <- csr, csr <- t & ~zimm, rd <- t
+        let ins = encode_zicsr(0b111, rd, zimm, csr);
+        access_csr(self, ins, rd, csr, CsrOp::Clear, zimm, zimm != 0);
+    }
+}
+
+/// An **instruction handler** for instructions from the 'Zifencei' extension for instruction-fetch fences.
+pub trait HandleZifencei {
+    type Item;
+
+    // Instruction-fetch fence.
+    fn fence_i(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item;
+}
+
+impl<T> HandleZifencei for T
+where
+    T: Fetch,
+{
+    type Item = ();
+
+    fn fence_i(&mut self, _rd: Reg, _rs1: Reg, _iimm: u32) {}
+}
+
+/// An **instruction handler** for privileged instructions that return from traps, wait for interrupts, or manage
+/// virtual memory.
+pub trait HandlePrivileged {
+    type Item;
+
+    // Trap-return instructions.
+    fn sret(&mut self) -> Self::Item;
+    fn mret(&mut self) -> Self::Item;
+
+    // Interrupt-management instructions.
+    fn wfi(&mut self) -> Self::Item;
+
+    // Supervisor memory-management instructions.
+    fn sfence_vma(&mut self, rs1: Reg, rs2: Reg) -> Self::Item;
+}
+
+impl<T> HandlePrivileged for T
+where
+    T: Trap,
+{
+    type Item = ();
+
+    fn sret(&mut self) -> Self::Item {
+        self.handle_sret()
+    }
+
+    fn mret(&mut self) -> Self::Item {
+        self.handle_mret()
+    }
+
+    fn wfi(&mut self) -> Self::Item {
+        self.handle_wfi()
+    }
+
+    fn sfence_vma(&mut self, rs1: Reg, rs2: Reg) -> Self::Item {
+        self.handle_sfence_vma(rs1, rs2)
     }
 }
@@ -0,0 +1,1825 @@
+//! A cache of pre-decoded instructions.
+//!
+//! Dispatching an instruction means running it through a cascade of `match` expressions to find its handler. Guests
+//! spend most of their time in loops, so a [`CachedCpu`] decodes each instruction once, keeps the result in a cache
+//! that's keyed by the program counter, and executes from the cache thereafter.
+//!
+//! ```
+//! use arviss::icache::CachedCpu;
+//! use arviss::platforms::basic::*;
+//! use arviss::DispatchRv32ic;
+//!
+//! let mut cpu = CachedCpu::new(Rv32iCpu::<BasicMem>::new(), |decoder, ins| decoder.dispatch(ins));
+//! cpu.write_bytes(0, &0x00100073u32.to_le_bytes()).unwrap(); // ebreak
+//! while !cpu.is_trapped() {
+//!     cpu.step();
+//! }
+//! assert_eq!(Some(TrapCause::Breakpoint(0)), cpu.trap_cause());
+//! ```
+
+use std::marker::PhantomData;
+
+use crate::{
+    cpu::{
+        Alignment, CsrResult, Csrs, DRegisters, FRegisters, Fetch, Privilege, Reservation,
+        XRegisters,
+    },
+    csr,
+    handlers::*,
+    memory::{Address, Load, Memory, MemoryResult},
+    reg::Reg,
+    trap::{Trap, TrapCause, TrapRecord},
+};
+
+// The number of entries in the cache. Instructions are at least 2 bytes long, so this covers 16K of code.
+const CACHE_SIZE: usize = 8192;
+
+/// The operands of a decoded instruction.
+///
+/// Instructions that have an immediate keep it in `imm`, whatever its encoding, as do instructions that take a rounding
+/// mode, a CSR number, fence bits or the ordering bits of an atomic memory operation. The CSR instructions that take a
+/// 5-bit immediate keep it in `rs1`, which is where it's encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Operands {
+    pub rd: Reg,
+    pub rs1: Reg,
+    pub rs2: Reg,
+    pub rs3: Reg,
+    pub imm: u32,
+}
+
+impl Operands {
+    /// Creates operands from all of their fields.
+    pub fn new(rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, imm: u32) -> Self {
+        Self {
+            rd,
+            rs1,
+            rs2,
+            rs3,
+            imm,
+        }
+    }
+
+    /// Creates operands for an R-type instruction.
+    pub fn r(rd: Reg, rs1: Reg, rs2: Reg) -> Self {
+        Self::new(rd, rs1, rs2, Reg::ZERO, 0)
+    }
+
+    /// Creates operands for an I-type instruction.
+    pub fn i(rd: Reg, rs1: Reg, imm: u32) -> Self {
+        Self::new(rd, rs1, Reg::ZERO, Reg::ZERO, imm)
+    }
+
+    /// Creates operands for an S-type or B-type instruction.
+    pub fn s(rs1: Reg, rs2: Reg, imm: u32) -> Self {
+        Self::new(Reg::ZERO, rs1, rs2, Reg::ZERO, imm)
+    }
+
+    /// Creates operands for a U-type or J-type instruction.
+    pub fn u(rd: Reg, imm: u32) -> Self {
+        Self::new(rd, Reg::ZERO, Reg::ZERO, Reg::ZERO, imm)
+    }
+}
+
+impl Default for Operands {
+    fn default() -> Self {
+        Self::u(Reg::ZERO, 0)
+    }
+}
+
+/// A decoded instruction, i.e., its handler along with its operands.
+pub struct Decoded<T> {
+    handler: fn(&mut T, &Operands),
+    operands: Operands,
+}
+
+impl<T> Clone for Decoded<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Decoded<T> {}
+
+impl<T> Decoded<T> {
+    /// Creates a decoded instruction that invokes `handler` with the given operands.
+    pub fn new(handler: fn(&mut T, &Operands), operands: Operands) -> Self {
+        Self { handler, operands }
+    }
+
+    /// Returns the operands.
+    pub fn operands(&self) -> &Operands {
+        &self.operands
+    }
+
+    /// Executes the instruction on the given CPU.
+    #[inline]
+    pub fn execute(&self, cpu: &mut T) {
+        (self.handler)(cpu, &self.operands)
+    }
+}
+
+/// An **instruction handler** that decodes instructions for a CPU of type `T` rather than executing them.
+///
+/// Dispatching an instruction to a `Decoder` returns a [`Decoded`] instruction that can be executed later.
+pub struct Decoder<T> {
+    _cpu: PhantomData<fn(&mut T)>,
+}
+
+impl<T> Default for Decoder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Decoder<T> {
+    pub fn new() -> Self {
+        Self { _cpu: PhantomData }
+    }
+}
+
+/// A function that decodes an instruction for a [`CachedCpu`], typically by dispatching it to a [`Decoder`].
+pub type DecodeFn<T> = fn(&mut Decoder<T>, u32) -> Decoded<T>;
+
+/// A cache entry.
+struct Entry<T> {
+    tag: u64,            // The address of the instruction, or `EMPTY` if the entry is unused.
+    len: u32,            // The length of the instruction in bytes.
+    decoded: Decoded<T>, // The decoded instruction.
+}
+
+// The tag of an unused entry. It's outside of the address space, so it never matches a program counter.
+const EMPTY: u64 = u64::MAX;
+
+impl<T> Entry<T> {
+    fn empty() -> Self {
+        Self {
+            tag: EMPTY,
+            len: 0,
+            decoded: Decoded::new(|_, _| {}, Operands::default()),
+        }
+    }
+}
+
+impl<T> Clone for Entry<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Entry<T> {}
+
+/// A CPU that executes instructions from a cache of pre-decoded instructions.
+///
+/// The cache is direct-mapped and keyed by the program counter. Writes to memory made through the `CachedCpu`, including
+/// the guest's own stores, invalidate any cached instructions that they overlap, so self-modifying code works without
+/// the guest needing to execute `fence.i`. Writes made directly to the wrapped CPU bypass the cache, so call
+/// [`CachedCpu::flush`] after making them.
+///
+/// Cached instructions were fetched through the address space that was current when they were cached, so the cache is
+/// also flushed by `sfence.vma`, by writes to `satp`, and whenever a trap, `mret` or `sret` changes the privilege level.
+pub struct CachedCpu<T> {
+    cpu: T,
+    decode: DecodeFn<CachedCpu<T>>,
+    entries: Box<[Entry<CachedCpu<T>>]>,
+    code_range: (Address, Address),
+}
+
+impl<T> CachedCpu<T> {
+    /// Wraps a CPU with an instruction cache that uses `decode` to decode instructions.
+    pub fn new(cpu: T, decode: DecodeFn<Self>) -> Self {
+        Self {
+            cpu,
+            decode,
+            entries: vec![Entry::empty(); CACHE_SIZE].into_boxed_slice(),
+            code_range: (Address::MAX, 0),
+        }
+    }
+
+    /// Returns the wrapped CPU.
+    pub fn cpu(&self) -> &T {
+        &self.cpu
+    }
+
+    /// Returns the wrapped CPU. Call [`CachedCpu::flush`] after using it to modify code.
+    pub fn cpu_mut(&mut self) -> &mut T {
+        &mut self.cpu
+    }
+
+    /// Consumes the cache, returning the wrapped CPU.
+    pub fn into_inner(self) -> T {
+        self.cpu
+    }
+
+    /// Invalidates every instruction in the cache.
+    pub fn flush(&mut self) {
+        self.entries.fill(Entry::empty());
+        self.code_range = (Address::MAX, 0);
+    }
+
+    /// Fetches the next instruction and executes it, decoding it first if it isn't in the cache. If the instruction
+    /// can't be fetched then the CPU takes an instruction access fault.
+    ///
+    /// Like [`Executor::run`](crate::executor::Executor::run), this takes any pending interrupt before the instruction
+    /// and ticks memory after it. If the CPU is waiting for an interrupt then it ticks memory without executing
+    /// anything.
+    #[inline]
+    pub fn step(&mut self)
+    where
+        T: Fetch + Trap + Memory,
+    {
+        self.take_interrupt();
+        if self.cpu.is_waiting() {
+            self.cpu.tick();
+            return;
+        }
+        let pc = self.cpu.transfer();
+        let index = (pc >> 1) as usize % CACHE_SIZE;
+        let entry = &self.entries[index];
+        let (len, decoded) = if entry.tag == u64::from(pc) {
+            (entry.len, entry.decoded)
+        } else {
+            let ins = match self.cpu.fetch32(pc) {
+                Ok(ins) => ins,
+                Err(address) => {
+                    self.handle_trap(TrapCause::InstructionAccessFault(address));
+                    self.cpu.tick();
+                    return;
+                }
+            };
+            let (ins, len) = if (ins & 0b11) == 0b11 {
+                (ins, 4)
+            } else {
+                (ins & 0xffff, 2)
+            };
+            let decoded = (self.decode)(&mut Decoder::new(), ins);
+            let (lo, hi) = self.code_range;
+            self.code_range = (lo.min(pc), hi.max(pc.saturating_add(len)));
+            self.entries[index] = Entry {
+                tag: u64::from(pc),
+                len,
+                decoded,
+            };
+            (len, decoded)
+        };
+        self.cpu.set_next_pc(pc.wrapping_add(len));
+        decoded.execute(self);
+        self.cpu.tick();
+    }
+
+    // Runs `f` on the wrapped CPU, then flushes the cache if it changed the privilege level, because fetches are
+    // translated and checked differently at each level.
+    fn flush_on_privilege_change(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Trap,
+    {
+        let privilege = self.cpu.privilege();
+        f(&mut self.cpu);
+        if self.cpu.privilege() != privilege {
+            self.flush();
+        }
+    }
+
+    // Invalidates any cached instructions that overlap the given range of addresses.
+    fn invalidate(&mut self, address: Address, len: u32) {
+        // Most writes are to data, so skip them quickly if they can't overlap anything that's been cached.
+        let (lo, hi) = self.code_range;
+        if address >= hi || address.saturating_add(len) <= lo {
+            return;
+        }
+        if len as usize >= CACHE_SIZE * 2 {
+            return self.flush();
+        }
+        // Instructions are up to 4 bytes long, so one that starts up to 3 bytes before the range may overlap it.
+        let start = address.wrapping_sub(3) & !1;
+        for i in 0..=(len + 3) / 2 {
+            let pc = start.wrapping_add(i * 2);
+            let entry = &mut self.entries[(pc >> 1) as usize % CACHE_SIZE];
+            if entry.tag == u64::from(pc) {
+                *entry = Entry::empty();
+            }
+        }
+    }
+}
+
+impl<T> Fetch for CachedCpu<T>
+where
+    T: Fetch,
+{
+    fn pc(&self) -> Address {
+        self.cpu.pc()
+    }
+
+    fn transfer(&mut self) -> Address {
+        self.cpu.transfer()
+    }
+
+    fn set_next_pc(&mut self, address: Address) {
+        self.cpu.set_next_pc(address)
+    }
+
+    fn fetch32(&self, address: Address) -> MemoryResult<u32> {
+        self.cpu.fetch32(address)
+    }
+
+    fn alignment(&self) -> Alignment {
+        self.cpu.alignment()
+    }
+}
+
+impl<T> Memory for CachedCpu<T>
+where
+    T: Memory,
+{
+    fn read8(&self, address: Address) -> MemoryResult<u8> {
+        self.cpu.read8(address)
+    }
+
+    fn read16(&self, address: Address) -> MemoryResult<u16> {
+        self.cpu.read16(address)
+    }
+
+    fn read32(&self, address: Address) -> MemoryResult<u32> {
+        self.cpu.read32(address)
+    }
+
+    fn write8(&mut self, address: Address, value: u8) -> MemoryResult<()> {
+        self.cpu.write8(address, value)?;
+        self.invalidate(address, 1);
+        Ok(())
+    }
+
+    fn write16(&mut self, address: Address, value: u16) -> MemoryResult<()> {
+        self.cpu.write16(address, value)?;
+        self.invalidate(address, 2);
+        Ok(())
+    }
+
+    fn write32(&mut self, address: Address, value: u32) -> MemoryResult<()> {
+        self.cpu.write32(address, value)?;
+        self.invalidate(address, 4);
+        Ok(())
+    }
+
+    fn read64(&self, address: Address) -> MemoryResult<u64> {
+        self.cpu.read64(address)
+    }
+
+    fn write64(&mut self, address: Address, value: u64) -> MemoryResult<()> {
+        self.cpu.write64(address, value)?;
+        self.invalidate(address, 8);
+        Ok(())
+    }
+
+    fn peek8(&self, address: Address) -> MemoryResult<u8> {
+        self.cpu.peek8(address)
+    }
+
+    fn tick(&mut self) {
+        self.cpu.tick()
+    }
+
+    fn next_event(&self) -> Option<u64> {
+        self.cpu.next_event()
+    }
+
+    fn advance(&mut self, ticks: u64) {
+        self.cpu.advance(ticks)
+    }
+}
+
+impl<T> Load for CachedCpu<T>
+where
+    T: Load,
+{
+    fn write_bytes(&mut self, start: Address, bytes: &[u8]) -> MemoryResult<()> {
+        self.cpu.write_bytes(start, bytes)?;
+        self.invalidate(start, bytes.len().min(u32::MAX as usize) as u32);
+        Ok(())
+    }
+}
+
+impl<T> XRegisters for CachedCpu<T>
+where
+    T: XRegisters,
+{
+    fn rx(&self, reg: Reg) -> u32 {
+        self.cpu.rx(reg)
+    }
+
+    fn wx(&mut self, reg: Reg, val: u32) {
+        self.cpu.wx(reg, val)
+    }
+}
+
+impl<T> FRegisters for CachedCpu<T>
+where
+    T: FRegisters,
+{
+    fn rf(&self, reg: Reg) -> f32 {
+        self.cpu.rf(reg)
+    }
+
+    fn rf_bits(&self, reg: Reg) -> u32 {
+        self.cpu.rf_bits(reg)
+    }
+
+    fn wf(&mut self, reg: Reg, val: f32) {
+        self.cpu.wf(reg, val)
+    }
+
+    fn frm(&self) -> u32 {
+        self.cpu.frm()
+    }
+
+    fn accrue_fflags(&mut self, flags: u32) {
+        self.cpu.accrue_fflags(flags)
+    }
+
+    fn is_fp_enabled(&self) -> bool {
+        self.cpu.is_fp_enabled()
+    }
+}
+
+impl<T> DRegisters for CachedCpu<T>
+where
+    T: DRegisters,
+{
+    fn rfd(&self, reg: Reg) -> f64 {
+        self.cpu.rfd(reg)
+    }
+
+    fn wfd(&mut self, reg: Reg, val: f64) {
+        self.cpu.wfd(reg, val)
+    }
+}
+
+impl<T> Csrs for CachedCpu<T>
+where
+    T: Csrs,
+{
+    fn rcsr(&self, csr: u32) -> CsrResult<u32> {
+        self.cpu.rcsr(csr)
+    }
+
+    fn wcsr(&mut self, csr: u32, val: u32) -> CsrResult<()> {
+        self.cpu.wcsr(csr, val)?;
+        if csr == csr::SATP {
+            // Fetches are translated through the new address space from now on.
+            self.flush();
+        }
+        Ok(())
+    }
+}
+
+impl<T> Reservation for CachedCpu<T>
+where
+    T: Reservation,
+{
+    fn reserve(&mut self, address: Address) {
+        self.cpu.reserve(address)
+    }
+
+    fn take_reservation(&mut self, address: Address) -> bool {
+        self.cpu.take_reservation(address)
+    }
+}
+
+impl<T> Trap for CachedCpu<T>
+where
+    T: Trap,
+{
+    fn trap(&self) -> Option<TrapRecord> {
+        self.cpu.trap()
+    }
+
+    fn clear_trap(&mut self) {
+        self.cpu.clear_trap()
+    }
+
+    fn handle_trap(&mut self, cause: TrapCause) {
+        self.flush_on_privilege_change(|cpu| cpu.handle_trap(cause))
+    }
+
+    fn privilege(&self) -> Privilege {
+        self.cpu.privilege()
+    }
+
+    fn vectors_traps(&self) -> bool {
+        self.cpu.vectors_traps()
+    }
+
+    fn handle_ecall(&mut self) {
+        self.flush_on_privilege_change(|cpu| cpu.handle_ecall())
+    }
+
+    fn handle_ebreak(&mut self, address: Address) {
+        self.flush_on_privilege_change(|cpu| cpu.handle_ebreak(address))
+    }
+
+    fn pending_interrupt(&self) -> Option<TrapCause> {
+        self.cpu.pending_interrupt()
+    }
+
+    fn handle_mret(&mut self) {
+        self.flush_on_privilege_change(|cpu| cpu.handle_mret())
+    }
+
+    fn handle_sret(&mut self) {
+        self.flush_on_privilege_change(|cpu| cpu.handle_sret())
+    }
+
+    fn handle_wfi(&mut self) {
+        self.cpu.handle_wfi()
+    }
+
+    fn handle_sfence_vma(&mut self, rs1: Reg, rs2: Reg) {
+        self.cpu.handle_sfence_vma(rs1, rs2);
+        self.flush();
+    }
+
+    fn is_waiting(&self) -> bool {
+        self.cpu.is_waiting()
+    }
+}
+
+impl<T> HandleRv32i for Decoder<T>
+where
+    T: HandleRv32i<Item = ()>,
+{
+    type Item = Decoded<T>;
+
+    fn illegal(&mut self, ins: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.illegal(o.imm), Operands::u(Reg::ZERO, ins))
+    }
+
+    fn beq(&mut self, rs1: Reg, rs2: Reg, bimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.beq(o.rs1, o.rs2, o.imm),
+            Operands::s(rs1, rs2, bimm),
+        )
+    }
+
+    fn bne(&mut self, rs1: Reg, rs2: Reg, bimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.bne(o.rs1, o.rs2, o.imm),
+            Operands::s(rs1, rs2, bimm),
+        )
+    }
+
+    fn blt(&mut self, rs1: Reg, rs2: Reg, bimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.blt(o.rs1, o.rs2, o.imm),
+            Operands::s(rs1, rs2, bimm),
+        )
+    }
+
+    fn bge(&mut self, rs1: Reg, rs2: Reg, bimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.bge(o.rs1, o.rs2, o.imm),
+            Operands::s(rs1, rs2, bimm),
+        )
+    }
+
+    fn bltu(&mut self, rs1: Reg, rs2: Reg, bimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.bltu(o.rs1, o.rs2, o.imm),
+            Operands::s(rs1, rs2, bimm),
+        )
+    }
+
+    fn bgeu(&mut self, rs1: Reg, rs2: Reg, bimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.bgeu(o.rs1, o.rs2, o.imm),
+            Operands::s(rs1, rs2, bimm),
+        )
+    }
+
+    fn lb(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.lb(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, iimm),
+        )
+    }
+
+    fn lh(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.lh(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, iimm),
+        )
+    }
+
+    fn lw(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.lw(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, iimm),
+        )
+    }
+
+    fn lbu(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.lbu(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, iimm),
+        )
+    }
+
+    fn lhu(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.lhu(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, iimm),
+        )
+    }
+
+    fn addi(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.addi(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, iimm),
+        )
+    }
+
+    fn slti(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.slti(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, iimm),
+        )
+    }
+
+    fn sltiu(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.sltiu(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, iimm),
+        )
+    }
+
+    fn xori(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.xori(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, iimm),
+        )
+    }
+
+    fn ori(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.ori(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, iimm),
+        )
+    }
+
+    fn andi(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.andi(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, iimm),
+        )
+    }
+
+    fn jalr(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.jalr(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, iimm),
+        )
+    }
+
+    fn sb(&mut self, rs1: Reg, rs2: Reg, simm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.sb(o.rs1, o.rs2, o.imm),
+            Operands::s(rs1, rs2, simm),
+        )
+    }
+
+    fn sh(&mut self, rs1: Reg, rs2: Reg, simm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.sh(o.rs1, o.rs2, o.imm),
+            Operands::s(rs1, rs2, simm),
+        )
+    }
+
+    fn sw(&mut self, rs1: Reg, rs2: Reg, simm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.sw(o.rs1, o.rs2, o.imm),
+            Operands::s(rs1, rs2, simm),
+        )
+    }
+
+    fn auipc(&mut self, rd: Reg, uimm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.auipc(o.rd, o.imm), Operands::u(rd, uimm))
+    }
+
+    fn lui(&mut self, rd: Reg, uimm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.lui(o.rd, o.imm), Operands::u(rd, uimm))
+    }
+
+    fn jal(&mut self, rd: Reg, jimm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.jal(o.rd, o.imm), Operands::u(rd, jimm))
+    }
+
+    fn add(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.add(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn sub(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.sub(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn sll(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.sll(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn slt(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.slt(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn sltu(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.sltu(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn xor(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.xor(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn srl(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.srl(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn sra(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.sra(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn or(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.or(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn and(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.and(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn slli(&mut self, rd: Reg, rs1: Reg, shamt: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.slli(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, shamt),
+        )
+    }
+
+    fn srli(&mut self, rd: Reg, rs1: Reg, shamt: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.srli(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, shamt),
+        )
+    }
+
+    fn srai(&mut self, rd: Reg, rs1: Reg, shamt: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.srai(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, shamt),
+        )
+    }
+
+    fn fence(&mut self, fm: u32, rd: Reg, rs1: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fence(o.imm, o.rd, o.rs1),
+            Operands::i(rd, rs1, fm),
+        )
+    }
+
+    fn ecall(&mut self) -> Self::Item {
+        Decoded::new(|cpu, _| cpu.ecall(), Default::default())
+    }
+
+    fn ebreak(&mut self) -> Self::Item {
+        Decoded::new(|cpu, _| cpu.ebreak(), Default::default())
+    }
+}
+
+impl<T> HandleRv32m for Decoder<T>
+where
+    T: HandleRv32m<Item = ()>,
+{
+    type Item = Decoded<T>;
+
+    fn mul(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.mul(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn mulh(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.mulh(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn mulhsu(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.mulhsu(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn mulhu(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.mulhu(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn div(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.div(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn divu(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.divu(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn rem(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.rem(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn remu(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.remu(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+}
+
+impl<T> HandleRv32a for Decoder<T>
+where
+    T: HandleRv32a<Item = ()>,
+{
+    type Item = Decoded<T>;
+
+    fn lr_w(&mut self, rd: Reg, rs1: Reg, aqrl: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.lr_w(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, aqrl),
+        )
+    }
+
+    fn sc_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.sc_w(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, aqrl),
+        )
+    }
+
+    fn amoswap_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.amoswap_w(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, aqrl),
+        )
+    }
+
+    fn amoadd_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.amoadd_w(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, aqrl),
+        )
+    }
+
+    fn amoxor_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.amoxor_w(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, aqrl),
+        )
+    }
+
+    fn amoand_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.amoand_w(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, aqrl),
+        )
+    }
+
+    fn amoor_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.amoor_w(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, aqrl),
+        )
+    }
+
+    fn amomin_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.amomin_w(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, aqrl),
+        )
+    }
+
+    fn amomax_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.amomax_w(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, aqrl),
+        )
+    }
+
+    fn amominu_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.amominu_w(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, aqrl),
+        )
+    }
+
+    fn amomaxu_w(&mut self, rd: Reg, rs1: Reg, rs2: Reg, aqrl: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.amomaxu_w(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, aqrl),
+        )
+    }
+}
+
+impl<T> HandleRv32c for Decoder<T>
+where
+    T: HandleRv32c<Item = ()>,
+{
+    type Item = Decoded<T>;
+
+    fn c_addi4spn(&mut self, rdp: Reg, imm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.c_addi4spn(o.rd, o.imm), Operands::u(rdp, imm))
+    }
+
+    fn c_lw(&mut self, rdp: Reg, rs1p: Reg, imm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_lw(o.rd, o.rs1, o.imm),
+            Operands::i(rdp, rs1p, imm),
+        )
+    }
+
+    fn c_sw(&mut self, rs1p: Reg, rs2p: Reg, imm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_sw(o.rs1, o.rs2, o.imm),
+            Operands::s(rs1p, rs2p, imm),
+        )
+    }
+
+    fn c_sub(&mut self, rdrs1p: Reg, rs2p: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_sub(o.rd, o.rs2),
+            Operands::r(rdrs1p, Reg::ZERO, rs2p),
+        )
+    }
+
+    fn c_xor(&mut self, rdrs1p: Reg, rs2p: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_xor(o.rd, o.rs2),
+            Operands::r(rdrs1p, Reg::ZERO, rs2p),
+        )
+    }
+
+    fn c_or(&mut self, rdrs1p: Reg, rs2p: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_or(o.rd, o.rs2),
+            Operands::r(rdrs1p, Reg::ZERO, rs2p),
+        )
+    }
+
+    fn c_and(&mut self, rdrs1p: Reg, rs2p: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_and(o.rd, o.rs2),
+            Operands::r(rdrs1p, Reg::ZERO, rs2p),
+        )
+    }
+
+    fn c_nop(&mut self, imm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.c_nop(o.imm), Operands::u(Reg::ZERO, imm))
+    }
+
+    fn c_addi16sp(&mut self, imm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.c_addi16sp(o.imm), Operands::u(Reg::ZERO, imm))
+    }
+
+    fn c_andi(&mut self, rsrs1p: Reg, imm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.c_andi(o.rd, o.imm), Operands::u(rsrs1p, imm))
+    }
+
+    fn c_addi(&mut self, rdrs1n0: Reg, imm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.c_addi(o.rd, o.imm), Operands::u(rdrs1n0, imm))
+    }
+
+    fn c_li(&mut self, rd: Reg, imm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.c_li(o.rd, o.imm), Operands::u(rd, imm))
+    }
+
+    fn c_lui(&mut self, rdn2: Reg, imm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.c_lui(o.rd, o.imm), Operands::u(rdn2, imm))
+    }
+
+    fn c_j(&mut self, imm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.c_j(o.imm), Operands::u(Reg::ZERO, imm))
+    }
+
+    fn c_beqz(&mut self, rs1p: Reg, imm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_beqz(o.rs1, o.imm),
+            Operands::s(rs1p, Reg::ZERO, imm),
+        )
+    }
+
+    fn c_bnez(&mut self, rs1p: Reg, imm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_bnez(o.rs1, o.imm),
+            Operands::s(rs1p, Reg::ZERO, imm),
+        )
+    }
+
+    fn c_jr(&mut self, rs1n0: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_jr(o.rs1),
+            Operands::r(Reg::ZERO, rs1n0, Reg::ZERO),
+        )
+    }
+
+    fn c_jalr(&mut self, rs1n0: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_jalr(o.rs1),
+            Operands::r(Reg::ZERO, rs1n0, Reg::ZERO),
+        )
+    }
+
+    fn c_ebreak(&mut self) -> Self::Item {
+        Decoded::new(|cpu, _| cpu.c_ebreak(), Default::default())
+    }
+
+    fn c_mv(&mut self, rd: Reg, rs2n0: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_mv(o.rd, o.rs2),
+            Operands::r(rd, Reg::ZERO, rs2n0),
+        )
+    }
+
+    fn c_add(&mut self, rdrs1: Reg, rs2n0: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_add(o.rd, o.rs2),
+            Operands::r(rdrs1, Reg::ZERO, rs2n0),
+        )
+    }
+
+    fn c_lwsp(&mut self, rdn0: Reg, imm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.c_lwsp(o.rd, o.imm), Operands::u(rdn0, imm))
+    }
+
+    fn c_swsp(&mut self, rs2: Reg, imm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_swsp(o.rs2, o.imm),
+            Operands::s(Reg::ZERO, rs2, imm),
+        )
+    }
+
+    fn c_jal(&mut self, imm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.c_jal(o.imm), Operands::u(Reg::ZERO, imm))
+    }
+
+    fn c_srli(&mut self, rdrs1p: Reg, imm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.c_srli(o.rd, o.imm), Operands::u(rdrs1p, imm))
+    }
+
+    fn c_srai(&mut self, rdrs1p: Reg, imm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.c_srai(o.rd, o.imm), Operands::u(rdrs1p, imm))
+    }
+
+    fn c_slli(&mut self, rdrs1n0: Reg, imm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.c_slli(o.rd, o.imm), Operands::u(rdrs1n0, imm))
+    }
+}
+
+impl<T> HandleRv32f for Decoder<T>
+where
+    T: HandleRv32f<Item = ()>,
+{
+    type Item = Decoded<T>;
+
+    fn flw(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.flw(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, iimm),
+        )
+    }
+
+    fn fsw(&mut self, rs1: Reg, rs2: Reg, simm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fsw(o.rs1, o.rs2, o.imm),
+            Operands::s(rs1, rs2, simm),
+        )
+    }
+
+    fn fsqrt_s(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fsqrt_s(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, rm),
+        )
+    }
+
+    fn fcvt_w_s(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fcvt_w_s(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, rm),
+        )
+    }
+
+    fn fcvt_wu_s(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fcvt_wu_s(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, rm),
+        )
+    }
+
+    fn fcvt_s_w(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fcvt_s_w(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, rm),
+        )
+    }
+
+    fn fcvt_s_wu(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fcvt_s_wu(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, rm),
+        )
+    }
+
+    fn fadd_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fadd_s(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, rm),
+        )
+    }
+
+    fn fsub_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fsub_s(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, rm),
+        )
+    }
+
+    fn fmul_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fmul_s(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, rm),
+        )
+    }
+
+    fn fdiv_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fdiv_s(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, rm),
+        )
+    }
+
+    fn fmadd_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fmadd_s(o.rd, o.rs1, o.rs2, o.rs3, o.imm),
+            Operands::new(rd, rs1, rs2, rs3, rm),
+        )
+    }
+
+    fn fmsub_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fmsub_s(o.rd, o.rs1, o.rs2, o.rs3, o.imm),
+            Operands::new(rd, rs1, rs2, rs3, rm),
+        )
+    }
+
+    fn fnmsub_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fnmsub_s(o.rd, o.rs1, o.rs2, o.rs3, o.imm),
+            Operands::new(rd, rs1, rs2, rs3, rm),
+        )
+    }
+
+    fn fnmadd_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fnmadd_s(o.rd, o.rs1, o.rs2, o.rs3, o.imm),
+            Operands::new(rd, rs1, rs2, rs3, rm),
+        )
+    }
+
+    fn fmv_x_w(&mut self, rd: Reg, rs1: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fmv_x_w(o.rd, o.rs1),
+            Operands::r(rd, rs1, Reg::ZERO),
+        )
+    }
+
+    fn fmv_w_x(&mut self, rd: Reg, rs1: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fmv_w_x(o.rd, o.rs1),
+            Operands::r(rd, rs1, Reg::ZERO),
+        )
+    }
+
+    fn fclass_s(&mut self, rd: Reg, rs1: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fclass_s(o.rd, o.rs1),
+            Operands::r(rd, rs1, Reg::ZERO),
+        )
+    }
+
+    fn fsgnj_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fsgnj_s(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn fmin_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fmin_s(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn fle_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fle_s(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn fsgnjn_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fsgnjn_s(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn fmax_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fmax_s(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn flt_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.flt_s(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn fsgnjx_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fsgnjx_s(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn feq_s(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.feq_s(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn c_flw(&mut self, rdp: Reg, rs1p: Reg, imm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_flw(o.rd, o.rs1, o.imm),
+            Operands::i(rdp, rs1p, imm),
+        )
+    }
+
+    fn c_fsw(&mut self, rs1p: Reg, rs2p: Reg, imm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_fsw(o.rs1, o.rs2, o.imm),
+            Operands::s(rs1p, rs2p, imm),
+        )
+    }
+
+    fn c_flwsp(&mut self, rd: Reg, imm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.c_flwsp(o.rd, o.imm), Operands::u(rd, imm))
+    }
+
+    fn c_fswsp(&mut self, rs2: Reg, imm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_fswsp(o.rs2, o.imm),
+            Operands::s(Reg::ZERO, rs2, imm),
+        )
+    }
+}
+
+impl<T> HandleRv32d for Decoder<T>
+where
+    T: HandleRv32d<Item = ()>,
+{
+    type Item = Decoded<T>;
+
+    fn fld(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fld(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, iimm),
+        )
+    }
+
+    fn fsd(&mut self, rs1: Reg, rs2: Reg, simm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fsd(o.rs1, o.rs2, o.imm),
+            Operands::s(rs1, rs2, simm),
+        )
+    }
+
+    fn fsqrt_d(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fsqrt_d(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, rm),
+        )
+    }
+
+    fn fcvt_s_d(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fcvt_s_d(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, rm),
+        )
+    }
+
+    fn fcvt_d_s(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fcvt_d_s(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, rm),
+        )
+    }
+
+    fn fcvt_w_d(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fcvt_w_d(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, rm),
+        )
+    }
+
+    fn fcvt_wu_d(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fcvt_wu_d(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, rm),
+        )
+    }
+
+    fn fcvt_d_w(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fcvt_d_w(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, rm),
+        )
+    }
+
+    fn fcvt_d_wu(&mut self, rd: Reg, rs1: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fcvt_d_wu(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, rm),
+        )
+    }
+
+    fn fadd_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fadd_d(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, rm),
+        )
+    }
+
+    fn fsub_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fsub_d(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, rm),
+        )
+    }
+
+    fn fmul_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fmul_d(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, rm),
+        )
+    }
+
+    fn fdiv_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fdiv_d(o.rd, o.rs1, o.rs2, o.imm),
+            Operands::new(rd, rs1, rs2, Reg::ZERO, rm),
+        )
+    }
+
+    fn fmadd_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fmadd_d(o.rd, o.rs1, o.rs2, o.rs3, o.imm),
+            Operands::new(rd, rs1, rs2, rs3, rm),
+        )
+    }
+
+    fn fmsub_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fmsub_d(o.rd, o.rs1, o.rs2, o.rs3, o.imm),
+            Operands::new(rd, rs1, rs2, rs3, rm),
+        )
+    }
+
+    fn fnmsub_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fnmsub_d(o.rd, o.rs1, o.rs2, o.rs3, o.imm),
+            Operands::new(rd, rs1, rs2, rs3, rm),
+        )
+    }
+
+    fn fnmadd_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg, rs3: Reg, rm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fnmadd_d(o.rd, o.rs1, o.rs2, o.rs3, o.imm),
+            Operands::new(rd, rs1, rs2, rs3, rm),
+        )
+    }
+
+    fn fclass_d(&mut self, rd: Reg, rs1: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fclass_d(o.rd, o.rs1),
+            Operands::r(rd, rs1, Reg::ZERO),
+        )
+    }
+
+    fn fsgnj_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fsgnj_d(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn fsgnjn_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fsgnjn_d(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn fsgnjx_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fsgnjx_d(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn fmin_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fmin_d(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn fmax_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fmax_d(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn fle_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fle_d(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn flt_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.flt_d(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn feq_d(&mut self, rd: Reg, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.feq_d(o.rd, o.rs1, o.rs2),
+            Operands::r(rd, rs1, rs2),
+        )
+    }
+
+    fn c_fld(&mut self, rdp: Reg, rs1p: Reg, imm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_fld(o.rd, o.rs1, o.imm),
+            Operands::i(rdp, rs1p, imm),
+        )
+    }
+
+    fn c_fsd(&mut self, rs1p: Reg, rs2p: Reg, imm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_fsd(o.rs1, o.rs2, o.imm),
+            Operands::s(rs1p, rs2p, imm),
+        )
+    }
+
+    fn c_fldsp(&mut self, rd: Reg, imm: u32) -> Self::Item {
+        Decoded::new(|cpu, o| cpu.c_fldsp(o.rd, o.imm), Operands::u(rd, imm))
+    }
+
+    fn c_fsdsp(&mut self, rs2: Reg, imm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.c_fsdsp(o.rs2, o.imm),
+            Operands::s(Reg::ZERO, rs2, imm),
+        )
+    }
+}
+
+impl<T> HandleZicsr for Decoder<T>
+where
+    T: HandleZicsr<Item = ()>,
+{
+    type Item = Decoded<T>;
+
+    fn csrrw(&mut self, rd: Reg, rs1: Reg, csr: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.csrrw(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, csr),
+        )
+    }
+
+    fn csrrs(&mut self, rd: Reg, rs1: Reg, csr: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.csrrs(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, csr),
+        )
+    }
+
+    fn csrrc(&mut self, rd: Reg, rs1: Reg, csr: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.csrrc(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, csr),
+        )
+    }
+
+    fn csrrwi(&mut self, rd: Reg, zimm: u32, csr: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.csrrwi(o.rd, o.rs1.into(), o.imm),
+            Operands::i(rd, Reg::new(zimm), csr),
+        )
+    }
+
+    fn csrrsi(&mut self, rd: Reg, zimm: u32, csr: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.csrrsi(o.rd, o.rs1.into(), o.imm),
+            Operands::i(rd, Reg::new(zimm), csr),
+        )
+    }
+
+    fn csrrci(&mut self, rd: Reg, zimm: u32, csr: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.csrrci(o.rd, o.rs1.into(), o.imm),
+            Operands::i(rd, Reg::new(zimm), csr),
+        )
+    }
+}
+
+impl<T> HandleZifencei for Decoder<T>
+where
+    T: HandleZifencei<Item = ()>,
+{
+    type Item = Decoded<T>;
+
+    fn fence_i(&mut self, rd: Reg, rs1: Reg, iimm: u32) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.fence_i(o.rd, o.rs1, o.imm),
+            Operands::i(rd, rs1, iimm),
+        )
+    }
+}
+
+impl<T> HandlePrivileged for Decoder<T>
+where
+    T: HandlePrivileged<Item = ()>,
+{
+    type Item = Decoded<T>;
+
+    fn sret(&mut self) -> Self::Item {
+        Decoded::new(|cpu, _| cpu.sret(), Default::default())
+    }
+
+    fn mret(&mut self) -> Self::Item {
+        Decoded::new(|cpu, _| cpu.mret(), Default::default())
+    }
+
+    fn wfi(&mut self) -> Self::Item {
+        Decoded::new(|cpu, _| cpu.wfi(), Default::default())
+    }
+
+    fn sfence_vma(&mut self, rs1: Reg, rs2: Reg) -> Self::Item {
+        Decoded::new(
+            |cpu, o| cpu.sfence_vma(o.rs1, o.rs2),
+            Operands::r(Reg::ZERO, rs1, rs2),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CachedCpu;
+    use crate::{platforms::basic::*, reg::Reg, test_support::program};
+
+    fn run(cpu: &mut CachedCpu<Rv32iCpu<BasicMem>>) {
+        while !cpu.is_trapped() {
+            cpu.step();
+        }
+    }
+
+    #[test]
+    fn test_cached_instructions_give_the_same_results_as_dispatched_instructions() {
+        use crate::DispatchRv32ic;
+
+        #[rustfmt::skip]
+        let code = [
+            0x00a00593, // li   a1, 10
+            0x00000513, // li   a0, 0
+            0x00b50533, // add  a0, a0, a1
+            0xfff58593, // addi a1, a1, -1
+            0xfe059ce3, // bnez a1, -8
+            0x00100073, // ebreak
+        ];
+
+        let mut dispatched = Rv32iCpu::<BasicMem>::new();
+        program(&mut dispatched, 0, &code);
+        while !dispatched.is_trapped() {
+            let ins = dispatched.fetch().unwrap();
+            dispatched.dispatch(ins);
+        }
+
+        let mut cached = CachedCpu::new(Rv32iCpu::<BasicMem>::new(), |decoder, ins| {
+            decoder.dispatch(ins)
+        });
+        program(&mut cached, 0, &code);
+        run(&mut cached);
+
+        assert_eq!(55, cached.rx(Reg::new(10)));
+        assert_eq!(dispatched.rx(Reg::new(10)), cached.rx(Reg::new(10)));
+        assert_eq!(dispatched.pc(), cached.pc());
+    }
+
+    #[test]
+    fn test_writing_to_a_cached_instruction_invalidates_it() {
+        use crate::DispatchRv32ic;
+
+        let mut cpu = CachedCpu::new(Rv32iCpu::<BasicMem>::new(), |decoder, ins| {
+            decoder.dispatch(ins)
+        });
+        #[rustfmt::skip]
+        program(&mut cpu, 0x4000, &[
+            0x00150513, // 0x4000: addi a0, a0, 1
+            0x00100073, // 0x4004: ebreak
+        ]);
+        cpu.set_next_pc(0x4000);
+        run(&mut cpu);
+        assert_eq!(1, cpu.rx(Reg::new(10)));
+
+        // Overwrite the addi with c.addi a0, 2 followed by c.nop, as if the guest had stored it.
+        cpu.clear_trap();
+        cpu.write32(0x4000, 0x0001_0509).unwrap();
+        cpu.set_next_pc(0x4000);
+        run(&mut cpu);
+        assert_eq!(3, cpu.rx(Reg::new(10)));
+        assert_eq!(0x4004, cpu.pc());
+    }
+
+    #[test]
+    fn test_illegal_instructions_are_reported_with_their_bits() {
+        use crate::DispatchRv32i;
+
+        let mut cpu = CachedCpu::new(Rv32iCpu::<BasicMem>::new(), |decoder, ins| {
+            decoder.dispatch(ins)
+        });
+        program(&mut cpu, 0, &[0x0000_0509]); // c.addi a0, 2, which isn't RV32I.
+        run(&mut cpu);
+        assert_eq!(
+            Some(TrapCause::IllegalInstruction(0x0509)),
+            cpu.trap_cause()
+        );
+    }
+
+    #[test]
+    fn test_changing_address_space_or_privilege_level_flushes_the_cache() {
+        use crate::{backends::cpus::rv32ipriv::Rv32iPrivCpu, csr, Csrs, DispatchRv32i};
+
+        let mut cpu = CachedCpu::new(Rv32iPrivCpu::<BasicMem>::new(), |decoder, ins| {
+            decoder.dispatch(ins)
+        });
+        program(&mut cpu, 0x1000, &[0x00000801]); // VA 0x0000_0000 -> the table at 0x2000.
+        program(&mut cpu, 0x200c, &[0x0000144b]); // VA 0x3000 -> PA 0x5000, R X A.
+        program(&mut cpu, 0x3000, &[0x00100513, 0x0000006f]); // addi a0, zero, 1; j .
+        program(&mut cpu, 0x5000, &[0x00200513, 0x0000006f]); // addi a0, zero, 2; j .
+        let run_at = |cpu: &mut CachedCpu<Rv32iPrivCpu<BasicMem>>, pc| {
+            cpu.set_next_pc(pc);
+            cpu.step();
+            cpu.rx(Reg::new(10))
+        };
+
+        // Drop to S-mode, with M-mode traps going to 0x3000.
+        cpu.wcsr(csr::MTVEC, 0x3000).unwrap();
+        cpu.wcsr(csr::MSTATUS, 1 << 11).unwrap();
+        cpu.handle_mret();
+        assert_eq!(1, run_at(&mut cpu, 0x3000));
+        cpu.wcsr(csr::SATP, 0x8000_0001).unwrap();
+        assert_eq!(2, run_at(&mut cpu, 0x3000));
+
+        // Remap VA 0x3000 -> PA 0x3000 behind the cache's back, then tell it with sfence.vma.
+        cpu.cpu_mut()
+            .write_bytes(0x200c, &0x00000c4bu32.to_le_bytes())
+            .unwrap();
+        cpu.handle_sfence_vma(Reg::ZERO, Reg::ZERO);
+        assert_eq!(1, run_at(&mut cpu, 0x3000));
+
+        // Map it back, then trap to M-mode, which doesn't translate addresses.
+        cpu.cpu_mut()
+            .write_bytes(0x200c, &0x0000144bu32.to_le_bytes())
+            .unwrap();
+        cpu.handle_sfence_vma(Reg::ZERO, Reg::ZERO);
+        assert_eq!(2, run_at(&mut cpu, 0x3000));
+        cpu.handle_trap(TrapCause::Breakpoint(0x3000));
+        cpu.clear_trap();
+        assert_eq!(1, run_at(&mut cpu, 0x3000));
+    }
+
+    #[test]
+    fn test_interrupts_are_taken_between_instructions_and_memory_is_ticked() {
+        use crate::{test_support::timer_cpu, DispatchRv32iZicsrPriv};
+
+        let code = [0x0000006f]; // 0x14: j 0x14
+        let mut cpu = CachedCpu::new(timer_cpu(20, &code), |decoder, ins| {
+            DispatchRv32iZicsrPriv::dispatch(decoder, ins)
+        });
+        let mut executed = 0;
+        while !cpu.is_trapped() {
+            let waiting = cpu.is_waiting();
+            cpu.step();
+            executed += u64::from(!waiting);
+        }
+        assert_eq!(Some(TrapCause::Breakpoint(0x108)), cpu.trap_cause());
+        assert_eq!(23, executed);
+        assert_eq!(0x8000_0007, cpu.rx(Reg::new(10))); // mcause
+        assert_eq!(0x14, cpu.rx(Reg::new(11))); // mepc
+
+        // A CPU that's waiting for an interrupt executes nothing, but memory keeps time.
+        #[rustfmt::skip]
+        let code = [
+            0x10500073, // 0x14: wfi
+            0xffdff06f, // 0x18: j 0x14
+        ];
+        let mut cpu = CachedCpu::new(timer_cpu(100, &code), |decoder, ins| {
+            DispatchRv32iZicsrPriv::dispatch(decoder, ins)
+        });
+        let mut executed = 0;
+        while !cpu.is_trapped() {
+            let waiting = cpu.is_waiting();
+            cpu.step();
+            executed += u64::from(!waiting);
+        }
+        assert_eq!(Some(TrapCause::Breakpoint(0x108)), cpu.trap_cause());
+        assert_eq!(9, executed);
+        assert_eq!(0x8000_0007, cpu.rx(Reg::new(10))); // mcause
+        assert_eq!(0x18, cpu.rx(Reg::new(11))); // mepc
+        assert_eq!(Ok(103), cpu.read32(0x0200_bff8)); // mtime
+    }
+
+    #[test]
+    fn test_fetch_errors_are_instruction_access_faults() {
+        use crate::{backends::cpus::rv32izicsr::Rv32iZicsrCpu, csr, Csrs, DispatchRv32iZicsrPriv};
+
+        let mut cpu = CachedCpu::new(Rv32iZicsrCpu::<BasicMem>::new(), |decoder, ins| {
+            DispatchRv32iZicsrPriv::dispatch(decoder, ins)
+        });
+        cpu.wcsr(csr::MTVEC, 0x100).unwrap();
+        cpu.set_next_pc(0x9000);
+        cpu.step();
+        assert_eq!(
+            Some(TrapCause::InstructionAccessFault(0x9000)),
+            cpu.trap_cause()
+        );
+        assert_eq!(Ok(1), cpu.rcsr(csr::MCAUSE));
+        assert_eq!(Ok(0x9000), cpu.rcsr(csr::MEPC));
+        assert_eq!(0x100, cpu.transfer());
+    }
+}
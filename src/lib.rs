@@ -9,8 +9,12 @@
 //!
 //! - the RV32I base integer instruction set
 //! - the 'M' standard extension for integer multiplication and division
+//! - the 'A' standard extension for atomic instructions
 //! - the 'F' standard extension for single-precision floating point
+//! - the 'D' standard extension for double-precision floating point
 //! - the 'C' standard extension for compressed instructions
+//! - the 'Zicsr' standard extension for control and status registers
+//! - the 'Zifencei' standard extension for instruction-fetch fences
 //!
 //!
 //! ## Examples
@@ -19,7 +23,7 @@
 //! To do this, it loads the data from an image and uses it to populate a [`platforms::basic::BasicCpu`]'s memory. It
 //! then executes instructions, dispatching them with [`DispatchRv32i`] which is implemented for [`HandleRv32i`].
 //!
-//! It does this until the CPU hits a trap, which it will do when it reaches an `ebreak`.
+//! It does this with [`executor::run`] until the CPU hits a trap, which it will do when it reaches an `ebreak`.
 //!
 //! If you run this example it should output "Hello, world from Rust!" multiple times.
 //!
@@ -32,6 +36,7 @@
 //!
 //! use arviss::DispatchRv32i;
 //!
+//! use arviss::executor::{run, StopReason};
 //! use arviss::platforms::basic::*;
 //!
 //! // Load an RV32I image into a buffer.
@@ -45,10 +50,8 @@
 //!     .expect("Failed to initialize memory.");
 //!
 //! // Execute the image.
-//! while !cpu.is_trapped() {
-//!     let instruction = cpu.fetch().unwrap();
-//!     cpu.dispatch(instruction);
-//! }
+//! let reason = run(&mut cpu, u64::MAX, |cpu, ins| cpu.dispatch(ins));
+//! assert!(matches!(reason, StopReason::Trap(TrapCause::Breakpoint(_))));
 //! #
 //! #     Ok(())
 //! # }
@@ -71,7 +74,7 @@
 //!
 //! // Disassemble the image, one instruction at a time, taking into account that compact
 //! // instructions are only 2 bytes.
-//! let mut disassembler = Disassembler {};
+//! let mut disassembler = Disassembler::new();
 //! let mut index: usize = 0;
 //! let image = buffer.as_slice();
 //! println!("addr     instr    code");
@@ -80,6 +83,7 @@
 //!         let word = u32::from_le_bytes(*slice);
 //!         let is_compact = (word & 3) != 3;
 //!         let word = if is_compact { word & 0xffff } else { word };
+//!         disassembler.set_pc(index as u32);
 //!         let result = disassembler.dispatch(word);
 //!         if is_compact {
 //!             // Compact instructions are 2 bytes each.
@@ -95,16 +99,27 @@
 //! ```
 
 pub mod backends;
+pub mod blocks;
+pub mod csr;
 pub mod disassembler;
+pub mod elf;
+pub mod executor;
+pub mod gdbstub;
+pub mod icache;
+pub mod mmu;
 
 mod cpu;
 mod dispatcher;
 mod handlers;
 mod memory;
 mod reg;
+mod softfloat;
 mod tobits;
 mod trap;
 
+#[cfg(test)]
+mod test_support;
+
 #[doc(inline)]
 pub use cpu::*;
 
@@ -145,4 +160,54 @@ pub mod platforms {
 
         pub type BasicCpu = Rv32iCpu<BasicMem>;
     }
+
+    /// A platform that uses an RV32IFCPU with basic memory.
+    pub mod basic_float {
+        use super::backends;
+
+        #[doc(inline)]
+        pub use backends::cpus::rv32if::*;
+
+        #[doc(inline)]
+        pub use backends::memory::basic::*;
+
+        pub type BasicFloatCpu = Rv32ifCpu<BasicMem>;
+    }
+
+    /// A platform that uses an RV32IFDCPU with basic memory.
+    pub mod basic_double {
+        use super::backends;
+
+        #[doc(inline)]
+        pub use backends::cpus::rv32ifd::*;
+
+        #[doc(inline)]
+        pub use backends::memory::basic::*;
+
+        pub type BasicDoubleCpu = Rv32ifdCpu<BasicMem>;
+    }
+
+    /// A platform that uses an RV32ICPU with memory laid out like QEMU's `virt` machine and Spike.
+    ///
+    /// ```
+    /// use arviss::platforms::virt::*;
+    ///
+    /// let cpu = VirtCpu::new();
+    /// assert_eq!(Ok(0), cpu.read32(0x8000_0000));
+    ///
+    /// // With less RAM.
+    /// let cpu = VirtCpu::with_mem(VirtMem::from(FlatMem::new(Layout::QEMU_VIRT.with_ram_size(0x1000))));
+    /// assert_eq!(Err(0x8000_1000), cpu.read32(0x8000_1000));
+    /// ```
+    pub mod virt {
+        use super::backends;
+
+        #[doc(inline)]
+        pub use backends::cpus::rv32i::*;
+
+        #[doc(inline)]
+        pub use backends::memory::flat::*;
+
+        pub type VirtCpu = Rv32iCpu<VirtMem>;
+    }
 }
@@ -25,6 +25,44 @@ pub trait Memory {
 
     /// Writes a 32-bit word to memory.
     fn write32(&mut self, address: Address, word: u32) -> MemoryResult<()>;
+
+    /// Reads a 64-bit double word from memory. By default, this reads two words, low word first.
+    fn read64(&self, address: Address) -> MemoryResult<u64> {
+        let lo = self.read32(address)?;
+        let hi = self.read32(address.wrapping_add(4))?;
+        Ok((u64::from(hi) << 32) | u64::from(lo))
+    }
+
+    /// Writes a 64-bit double word to memory. By default, this writes two words, low word first, so memory where the
+    /// second write can fail after the first has succeeded should override it to check both words before writing
+    /// either of them.
+    fn write64(&mut self, address: Address, double_word: u64) -> MemoryResult<()> {
+        self.write32(address, double_word as u32)?;
+        self.write32(address.wrapping_add(4), (double_word >> 32) as u32)
+    }
+
+    /// Reads a byte without any side effects, e.g., for a watchpoint. Memory returns an error for addresses that it
+    /// can't read without side effects, such as device registers whose reads consume input, so by default, nothing can
+    /// be peeked.
+    fn peek8(&self, address: Address) -> MemoryResult<u8> {
+        Err(address)
+    }
+
+    /// Advances any devices in memory by one step. Execution loops call this once per instruction so that devices such
+    /// as timers can keep time with the guest.
+    fn tick(&mut self) {}
+
+    /// Returns the number of ticks until a device in memory next does something of its own accord, e.g., until a timer
+    /// expires, or `None` if nothing is scheduled.
+    fn next_event(&self) -> Option<u64> {
+        None
+    }
+
+    /// Advances any devices in memory by the given number of ticks at once, e.g., to skip ahead to the next event while
+    /// the CPU is idle. By default, this ticks once, which suits memory whose devices don't keep time.
+    fn advance(&mut self, _ticks: u64) {
+        self.tick()
+    }
 }
 
 /// Loads data into memory.
@@ -0,0 +1,459 @@
+//! Sv32 virtual memory.
+//!
+//! [`Sv32`] translates virtual addresses to physical addresses by walking the two-level page tables that `satp` points
+//! to, and remembers its translations in a small software TLB until they're flushed by `sfence.vma`. It sits between a
+//! CPU and its [`Memory`], so that it works with any memory back end, but it doesn't hold any CPU state of its own. The
+//! CPU describes the state that translation depends on with a [`Context`] for each access.
+//!
+//! The walker doesn't update the accessed (A) and dirty (D) bits of a page table entry. Instead, as the privileged
+//! architecture allows, an access to a page whose A bit is clear, or a store to a page whose D bit is clear, raises a
+//! page fault so that the operating system can set them.
+
+use std::cell::Cell;
+
+use crate::{
+    cpu::Privilege,
+    memory::{Address, Memory, MemoryResult},
+    trap::TrapCause,
+};
+
+/// The bit of `satp` that selects Sv32 translation rather than bare, untranslated, addressing.
+pub const SATP_MODE_SV32: u32 = 1 << 31;
+
+// The fields of satp.
+const SATP_ASID_SHIFT: u32 = 22;
+const SATP_ASID_MASK: u32 = 0x1ff;
+const SATP_PPN_MASK: u32 = 0x003f_ffff;
+
+// The bits of a page table entry.
+const PTE_V: u32 = 1 << 0;
+const PTE_R: u32 = 1 << 1;
+const PTE_W: u32 = 1 << 2;
+const PTE_X: u32 = 1 << 3;
+const PTE_U: u32 = 1 << 4;
+const PTE_G: u32 = 1 << 5;
+const PTE_A: u32 = 1 << 6;
+const PTE_D: u32 = 1 << 7;
+const PTE_PPN_SHIFT: u32 = 10;
+
+const PAGE_SHIFT: u32 = 12;
+const PAGE_OFFSET_MASK: u32 = (1 << PAGE_SHIFT) - 1;
+
+// The number of entries in the TLB. It's direct-mapped, so this must be a power of two.
+const TLB_ENTRIES: usize = 64;
+
+/// The kind of memory access that's being translated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// An instruction fetch.
+    Fetch,
+    /// A load.
+    Load,
+    /// A store, or an atomic memory operation.
+    Store,
+}
+
+/// The state of the CPU that determines how an access is translated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Context {
+    /// The value of `satp`.
+    pub satp: u32,
+    /// The privilege level that the access is made at. For loads and stores, this takes `mstatus.MPRV` into account.
+    pub privilege: Privilege,
+    /// True if `mstatus.SUM` is set, permitting S-mode loads and stores to user pages.
+    pub sum: bool,
+    /// True if `mstatus.MXR` is set, making executable pages readable.
+    pub mxr: bool,
+}
+
+impl Context {
+    fn is_translated(&self) -> bool {
+        self.privilege != Privilege::Machine && self.satp & SATP_MODE_SV32 != 0
+    }
+
+    fn asid(&self) -> u32 {
+        (self.satp >> SATP_ASID_SHIFT) & SATP_ASID_MASK
+    }
+
+    // Returns true if a leaf page table entry permits the access.
+    fn permits(&self, pte: u32, access: Access) -> bool {
+        let user_page = pte & PTE_U != 0;
+        let privileged = match self.privilege {
+            Privilege::User => user_page,
+            Privilege::Supervisor => !user_page || (self.sum && access != Access::Fetch),
+            Privilege::Machine => true,
+        };
+        let permitted = match access {
+            Access::Fetch => pte & PTE_X != 0,
+            Access::Load => pte & PTE_R != 0 || (self.mxr && pte & PTE_X != 0),
+            Access::Store => pte & PTE_W != 0 && pte & PTE_D != 0,
+        };
+        privileged && permitted && pte & PTE_A != 0
+    }
+}
+
+/// A cached translation for a 4 KiB page. Megapages are cached one 4 KiB page at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TlbEntry {
+    vpn: u32,       // The virtual page number.
+    asid: u32,      // The address space that the translation belongs to, unless it's global.
+    ppn: u32,       // The physical page number.
+    pte: u32,       // The leaf page table entry, for its permissions.
+    megapage: bool, // True if the translation came from a megapage.
+}
+
+impl TlbEntry {
+    fn is_global(&self) -> bool {
+        self.pte & PTE_G != 0
+    }
+}
+
+/// An Sv32 memory management unit with a page table walker and a TLB.
+///
+/// ```
+/// use arviss::backends::memory::basic::BasicMem;
+/// use arviss::mmu::*;
+/// use arviss::{Load, Privilege};
+///
+/// let mut mem = BasicMem::new();
+/// let root: u32 = (0x2 << 10) | 0x01; // The root table points to a second-level table at 0x2000.
+/// let leaf: u32 = (0x8 << 10) | 0x53; // VA 0x5000 maps to PA 0x8000 as a readable user page.
+/// mem.write_bytes(0x1000, &root.to_le_bytes()).unwrap();
+/// mem.write_bytes(0x2000 + 4 * 5, &leaf.to_le_bytes()).unwrap();
+///
+/// let mmu = Sv32::new();
+/// let context = Context {
+///     satp: SATP_MODE_SV32 | 0x1,
+///     privilege: Privilege::User,
+///     ..Default::default()
+/// };
+/// assert_eq!(Ok(0x8123), mmu.translate(&mem, &context, 0x5123, Access::Load));
+/// assert_eq!(Err(0x5123), mmu.translate(&mem, &context, 0x5123, Access::Store));
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub struct Sv32 {
+    tlb: Vec<Cell<Option<TlbEntry>>>,
+    fault: Cell<Option<Address>>, // The virtual address of the last page fault.
+}
+
+impl Default for Sv32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sv32 {
+    /// Creates an MMU with an empty TLB.
+    pub fn new() -> Self {
+        Self {
+            tlb: vec![Cell::new(None); TLB_ENTRIES],
+            fault: Cell::new(None),
+        }
+    }
+
+    /// Translates a virtual address to a physical address, walking the page tables in `mem` if the translation isn't
+    /// in the TLB. Addresses aren't translated in M-mode, or if `satp` selects bare addressing.
+    ///
+    /// On failure, this returns the virtual address. Page faults are remembered, so that the CPU can turn the access
+    /// fault that it would otherwise raise into a page fault with [`Sv32::page_fault`].
+    pub fn translate<M>(
+        &self,
+        mem: &M,
+        context: &Context,
+        address: Address,
+        access: Access,
+    ) -> MemoryResult<Address>
+    where
+        M: Memory,
+    {
+        self.fault.set(None);
+        if !context.is_translated() {
+            return Ok(address);
+        }
+
+        // Use the TLB if it permits the access. If it doesn't, walk the page tables in case they've been updated.
+        let vpn = address >> PAGE_SHIFT;
+        let entry = match self.lookup(vpn, context.asid()) {
+            Some(entry) if context.permits(entry.pte, access) => entry,
+            _ => {
+                let entry = self.walk(mem, context, address)?;
+                self.tlb[Self::index(vpn)].set(Some(entry));
+                entry
+            }
+        };
+        if !context.permits(entry.pte, access) {
+            return Err(self.raise(address));
+        }
+        Ok((entry.ppn << PAGE_SHIFT) | (address & PAGE_OFFSET_MASK))
+    }
+
+    /// Returns the page fault that corresponds to an access fault if the access fault was caused by the last
+    /// translation failing with a page fault. Otherwise, it returns the cause unchanged.
+    pub fn page_fault(&self, cause: TrapCause) -> TrapCause {
+        let fault = self.fault.take();
+        match cause {
+            TrapCause::InstructionAccessFault(a) if Some(a) == fault => {
+                TrapCause::InstructionPageFault(a)
+            }
+            TrapCause::LoadAccessFault(a) if Some(a) == fault => TrapCause::LoadPageFault(a),
+            TrapCause::StoreAccessFault(a) if Some(a) == fault => TrapCause::StorePageFault(a),
+            cause => cause,
+        }
+    }
+
+    /// Flushes translations from the TLB, as `sfence.vma` does. If there's an address then only translations for that
+    /// address are flushed, and if there's an address space then global translations aren't flushed.
+    pub fn flush(&self, address: Option<Address>, asid: Option<u32>) {
+        for slot in &self.tlb {
+            let Some(entry) = slot.get() else {
+                continue;
+            };
+            let address_matches = address.is_none_or(|address| {
+                let vpn = address >> PAGE_SHIFT;
+                if entry.megapage {
+                    entry.vpn >> 10 == vpn >> 10
+                } else {
+                    entry.vpn == vpn
+                }
+            });
+            let asid_matches =
+                asid.is_none_or(|asid| !entry.is_global() && entry.asid == asid & SATP_ASID_MASK);
+            if address_matches && asid_matches {
+                slot.set(None);
+            }
+        }
+    }
+
+    fn index(vpn: u32) -> usize {
+        vpn as usize & (TLB_ENTRIES - 1)
+    }
+
+    fn lookup(&self, vpn: u32, asid: u32) -> Option<TlbEntry> {
+        self.tlb[Self::index(vpn)]
+            .get()
+            .filter(|entry| entry.vpn == vpn && (entry.is_global() || entry.asid == asid))
+    }
+
+    // Remembers a page fault at the given address, and returns the address.
+    fn raise(&self, address: Address) -> Address {
+        self.fault.set(Some(address));
+        address
+    }
+
+    // Walks the page tables for the given virtual address, returning its leaf page table entry.
+    fn walk<M>(&self, mem: &M, context: &Context, address: Address) -> MemoryResult<TlbEntry>
+    where
+        M: Memory,
+    {
+        let vpn = [(address >> PAGE_SHIFT) & 0x3ff, address >> 22];
+        let mut table = u64::from(context.satp & SATP_PPN_MASK) << PAGE_SHIFT;
+        let mut global = 0;
+        for level in (0..2).rev() {
+            // Physical addresses are 34 bits, but our memory only has 32 of them.
+            let pte_address =
+                u32::try_from(table + 4 * u64::from(vpn[level])).map_err(|_| address)?;
+            let pte = mem.read32(pte_address).map_err(|_| address)?;
+            if pte & PTE_V == 0 || (pte & PTE_R == 0 && pte & PTE_W != 0) {
+                return Err(self.raise(address));
+            }
+            let ppn = pte >> PTE_PPN_SHIFT;
+            global |= pte & PTE_G;
+            if pte & (PTE_R | PTE_X) == 0 {
+                // It's a pointer to the next level of the page table.
+                table = u64::from(ppn) << PAGE_SHIFT;
+                continue;
+            }
+
+            // It's a leaf. Megapages must be aligned to 4 MiB.
+            let megapage = level == 1;
+            if megapage && ppn & 0x3ff != 0 {
+                return Err(self.raise(address));
+            }
+            let ppn = if megapage { ppn | vpn[0] } else { ppn };
+            if ppn >> (32 - PAGE_SHIFT) != 0 {
+                return Err(address);
+            }
+            return Ok(TlbEntry {
+                vpn: address >> PAGE_SHIFT,
+                asid: context.asid(),
+                ppn,
+                pte: pte | global,
+                megapage,
+            });
+        }
+
+        // A pointer at the last level.
+        Err(self.raise(address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Access, Context, Sv32, SATP_MODE_SV32};
+    use crate::{backends::memory::basic::BasicMem, Load, Privilege, TrapCause};
+
+    const ROOT: u32 = 0x1000;
+    const TABLE: u32 = 0x2000;
+
+    // Page table entry bits.
+    const V: u32 = 0x01;
+    const R: u32 = 0x02;
+    const W: u32 = 0x04;
+    const X: u32 = 0x08;
+    const U: u32 = 0x10;
+    const A: u32 = 0x40;
+    const D: u32 = 0x80;
+
+    fn poke(mem: &mut BasicMem, address: u32, word: u32) {
+        mem.write_bytes(address, &word.to_le_bytes()).unwrap();
+    }
+
+    fn pte(physical: u32, bits: u32) -> u32 {
+        ((physical >> 12) << 10) | bits
+    }
+
+    // Returns memory with a root table at ROOT whose first entry points to a second-level table at TABLE.
+    fn mem_with_tables() -> BasicMem {
+        let mut mem = BasicMem::new();
+        poke(&mut mem, ROOT, pte(TABLE, V));
+        mem
+    }
+
+    fn context(privilege: Privilege) -> Context {
+        Context {
+            satp: SATP_MODE_SV32 | (ROOT >> 12),
+            privilege,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_permissions_are_checked_and_page_faults_are_reported() {
+        let mut mem = mem_with_tables();
+        poke(&mut mem, TABLE + 4 * 4, pte(0x8000, V | R | X | A)); // 0x4000: S-mode code.
+        poke(&mut mem, TABLE + 4 * 5, pte(0x9000, V | R | W | U | A)); // 0x5000: user data, not dirty.
+        poke(&mut mem, TABLE + 4 * 6, pte(0xa000, V | X | U | A | D)); // 0x6000: user code.
+        let mmu = Sv32::new();
+
+        let supervisor = context(Privilege::Supervisor);
+        assert_eq!(
+            Ok(0x8004),
+            mmu.translate(&mem, &supervisor, 0x4004, Access::Fetch)
+        );
+        assert_eq!(
+            Err(0x4004),
+            mmu.translate(&mem, &supervisor, 0x4004, Access::Store)
+        );
+        assert_eq!(
+            TrapCause::StorePageFault(0x4004),
+            mmu.page_fault(TrapCause::StoreAccessFault(0x4004))
+        );
+
+        // S-mode can only access user pages with SUM, and can never execute them.
+        assert_eq!(
+            Err(0x5000),
+            mmu.translate(&mem, &supervisor, 0x5000, Access::Load)
+        );
+        let sum = Context {
+            sum: true,
+            ..supervisor
+        };
+        assert_eq!(Ok(0x9000), mmu.translate(&mem, &sum, 0x5000, Access::Load));
+        assert_eq!(
+            Err(0x6000),
+            mmu.translate(&mem, &sum, 0x6000, Access::Fetch)
+        );
+
+        // U-mode can't access supervisor pages, can only read executable pages with MXR, and can't store to clean pages.
+        let user = context(Privilege::User);
+        assert_eq!(
+            Err(0x4000),
+            mmu.translate(&mem, &user, 0x4000, Access::Fetch)
+        );
+        assert_eq!(
+            Err(0x6000),
+            mmu.translate(&mem, &user, 0x6000, Access::Load)
+        );
+        let mxr = Context { mxr: true, ..user };
+        assert_eq!(Ok(0xa000), mmu.translate(&mem, &mxr, 0x6000, Access::Load));
+        assert_eq!(
+            Err(0x5008),
+            mmu.translate(&mem, &user, 0x5008, Access::Store)
+        );
+
+        // Unmapped pages are page faults, but failing to read a page table entry is an access fault.
+        assert_eq!(
+            Err(0x7000),
+            mmu.translate(&mem, &user, 0x7000, Access::Load)
+        );
+        assert_eq!(
+            TrapCause::LoadPageFault(0x7000),
+            mmu.page_fault(TrapCause::LoadAccessFault(0x7000))
+        );
+        let broken = Context {
+            satp: SATP_MODE_SV32 | 0xfffff,
+            ..user
+        };
+        assert_eq!(
+            Err(0x7000),
+            mmu.translate(&mem, &broken, 0x7000, Access::Load)
+        );
+        assert_eq!(
+            TrapCause::LoadAccessFault(0x7000),
+            mmu.page_fault(TrapCause::LoadAccessFault(0x7000))
+        );
+
+        // Nothing is translated in M-mode.
+        let machine = context(Privilege::Machine);
+        assert_eq!(
+            Ok(0x7000),
+            mmu.translate(&mem, &machine, 0x7000, Access::Store)
+        );
+    }
+
+    #[test]
+    fn test_translations_are_cached_until_flushed() {
+        let mut mem = mem_with_tables();
+        poke(&mut mem, ROOT + 4, pte(0x0040_0000, V | R | W | A | D)); // A megapage at 0x0040_0000.
+        poke(&mut mem, TABLE + 4 * 5, pte(0x9000, V | R | A));
+        let mmu = Sv32::new();
+        let supervisor = context(Privilege::Supervisor);
+        assert_eq!(
+            Ok(0x9010),
+            mmu.translate(&mem, &supervisor, 0x5010, Access::Load)
+        );
+        assert_eq!(
+            Ok(0x0041_2345),
+            mmu.translate(&mem, &supervisor, 0x0041_2345, Access::Load)
+        );
+
+        // Changing the page tables doesn't change the translation until it's flushed.
+        poke(&mut mem, TABLE + 4 * 5, pte(0xa000, V | R | A));
+        poke(&mut mem, ROOT + 4, 0);
+        assert_eq!(
+            Ok(0x9010),
+            mmu.translate(&mem, &supervisor, 0x5010, Access::Load)
+        );
+        mmu.flush(Some(0x6000), None);
+        assert_eq!(
+            Ok(0x9010),
+            mmu.translate(&mem, &supervisor, 0x5010, Access::Load)
+        );
+        mmu.flush(Some(0x5000), None);
+        assert_eq!(
+            Ok(0xa010),
+            mmu.translate(&mem, &supervisor, 0x5010, Access::Load)
+        );
+
+        // Flushing any address in a megapage flushes all of it.
+        assert_eq!(
+            Ok(0x0041_2345),
+            mmu.translate(&mem, &supervisor, 0x0041_2345, Access::Load)
+        );
+        mmu.flush(Some(0x0070_0000), Some(0));
+        assert_eq!(
+            Err(0x0041_2345),
+            mmu.translate(&mem, &supervisor, 0x0041_2345, Access::Load)
+        );
+    }
+}
@@ -3,7 +3,7 @@
 use std::fmt::Display;
 
 /// A RISC-V register index.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Reg(u32);
 
 impl Reg {
@@ -60,6 +60,13 @@ impl From<u32> for Reg {
     }
 }
 
+impl From<Reg> for u32 {
+    #[inline]
+    fn from(r: Reg) -> Self {
+        r.0 % 32
+    }
+}
+
 impl From<Reg> for usize {
     #[inline]
     fn from(r: Reg) -> Self {
@@ -0,0 +1,712 @@
+//! A software implementation of the IEEE 754 binary floating point operations used by the 'F' and 'D' extensions.
+//!
+//! Values are passed around as raw bit patterns so that results, including NaNs and accrued exception flags, are bit
+//! exact and don't depend on the host's floating point unit. Every operation returns its result along with the
+//! exception flags that it raised.
+
+use std::cmp::Ordering;
+
+// Rounding modes.
+pub const RNE: u32 = 0b000; // Round to nearest, ties to even.
+pub const RTZ: u32 = 0b001; // Round towards zero.
+pub const RDN: u32 = 0b010; // Round down, towards -infinity.
+pub const RUP: u32 = 0b011; // Round up, towards +infinity.
+pub const RMM: u32 = 0b100; // Round to nearest, ties to max magnitude.
+pub const DYN: u32 = 0b111; // Use the dynamic rounding mode from frm.
+
+// Accrued exception flags.
+pub const NX: u32 = 0b00001; // Inexact.
+pub const UF: u32 = 0b00010; // Underflow.
+pub const OF: u32 = 0b00100; // Overflow.
+pub const DZ: u32 = 0b01000; // Divide by zero.
+pub const NV: u32 = 0b10000; // Invalid operation.
+
+/// A binary floating point format.
+#[derive(Clone, Copy)]
+pub struct Format {
+    exp_bits: u32,
+    frac_bits: u32,
+}
+
+/// The single-precision format used by the 'F' extension.
+pub const F32: Format = Format {
+    exp_bits: 8,
+    frac_bits: 23,
+};
+
+/// The double-precision format used by the 'D' extension.
+pub const F64: Format = Format {
+    exp_bits: 11,
+    frac_bits: 52,
+};
+
+/// A finite, non-zero value, `sig * 2^exp`.
+#[derive(Clone, Copy)]
+struct Finite {
+    sign: bool,
+    exp: i32,
+    sig: u128,
+}
+
+impl Finite {
+    /// Shifts the significand left so that its most significant bit is at bit `msb`.
+    fn normalized(self, msb: u32) -> Self {
+        let shift = self.sig.leading_zeros() - (127 - msb);
+        Finite {
+            sign: self.sign,
+            exp: self.exp - shift as i32,
+            sig: self.sig << shift,
+        }
+    }
+}
+
+/// An unpacked value.
+enum Value {
+    Nan,
+    Inf(bool),
+    Zero(bool),
+    Finite(Finite),
+}
+
+/// Shifts `sig` right by `shift` bits, rounding the result according to `rm`. The `sticky` flag indicates that there
+/// are further non-zero bits below the least significant bit of `sig`. Returns the rounded result and whether it is
+/// inexact.
+fn shift_right_round(sig: u128, shift: u32, sticky: bool, sign: bool, rm: u32) -> (u128, bool) {
+    let (kept, rem, half) = match shift {
+        0 => (sig, 0, None),
+        1..=128 => (
+            sig.checked_shr(shift).unwrap_or(0),
+            sig & (u128::MAX >> (128 - shift)),
+            Some(1u128 << (shift - 1)),
+        ),
+        _ => (0, sig, None),
+    };
+    let inexact = rem != 0 || sticky;
+    let above_half = half.is_some_and(|half| rem > half || (rem == half && sticky));
+    let exactly_half = half.is_some_and(|half| rem == half && !sticky);
+    let round_up = match rm {
+        RNE => above_half || (exactly_half && (kept & 1) == 1),
+        RMM => above_half || exactly_half,
+        RDN => inexact && sign,
+        RUP => inexact && !sign,
+        _ => false,
+    };
+    (kept + u128::from(round_up), inexact)
+}
+
+/// Returns the integer square root of `n`, rounded down.
+fn isqrt(n: u128) -> u128 {
+    let mut rem = n;
+    let mut root = 0u128;
+    let mut bit = 1u128 << 126;
+    while bit > n {
+        bit >>= 2;
+    }
+    while bit != 0 {
+        if rem >= root + bit {
+            rem -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    root
+}
+
+impl Format {
+    fn bias(self) -> i32 {
+        (1 << (self.exp_bits - 1)) - 1
+    }
+
+    fn max_exp(self) -> u64 {
+        (1 << self.exp_bits) - 1
+    }
+
+    fn sign_bit(self) -> u64 {
+        1 << (self.exp_bits + self.frac_bits)
+    }
+
+    fn frac_mask(self) -> u64 {
+        (1 << self.frac_bits) - 1
+    }
+
+    fn sign(self, sign: bool) -> u64 {
+        if sign {
+            self.sign_bit()
+        } else {
+            0
+        }
+    }
+
+    fn zero(self, sign: bool) -> u64 {
+        self.sign(sign)
+    }
+
+    fn inf(self, sign: bool) -> u64 {
+        self.sign(sign) | (self.max_exp() << self.frac_bits)
+    }
+
+    fn max_finite(self, sign: bool) -> u64 {
+        self.inf(sign) - 1
+    }
+
+    /// Returns the canonical NaN, the quiet NaN with a positive sign and no payload.
+    pub fn canonical_nan(self) -> u64 {
+        (self.max_exp() << self.frac_bits) | (1 << (self.frac_bits - 1))
+    }
+
+    fn is_nan(self, bits: u64) -> bool {
+        (bits & !self.sign_bit()) > self.inf(false)
+    }
+
+    fn is_signalling_nan(self, bits: u64) -> bool {
+        self.is_nan(bits) && (bits & (1 << (self.frac_bits - 1))) == 0
+    }
+
+    fn unpack(self, bits: u64) -> Value {
+        let sign = (bits & self.sign_bit()) != 0;
+        let exp = (bits >> self.frac_bits) & self.max_exp();
+        let frac = bits & self.frac_mask();
+        match (exp, frac) {
+            (0, 0) => Value::Zero(sign),
+            (0, _) => Value::Finite(Finite {
+                sign,
+                exp: 1 - self.bias() - self.frac_bits as i32,
+                sig: u128::from(frac),
+            }),
+            (exp, 0) if exp == self.max_exp() => Value::Inf(sign),
+            (exp, _) if exp == self.max_exp() => Value::Nan,
+            (exp, _) => Value::Finite(Finite {
+                sign,
+                exp: exp as i32 - self.bias() - self.frac_bits as i32,
+                sig: u128::from(frac | (1 << self.frac_bits)),
+            }),
+        }
+    }
+
+    /// Returns the canonical NaN, raising the invalid operation flag if any of the inputs is a signalling NaN.
+    fn propagate_nan(self, inputs: &[u64]) -> (u64, u32) {
+        let flags = if inputs.iter().any(|&bits| self.is_signalling_nan(bits)) {
+            NV
+        } else {
+            0
+        };
+        (self.canonical_nan(), flags)
+    }
+
+    /// Rounds `sig * 2^exp` to this format according to `rm`. The `sticky` flag indicates that the exact value has
+    /// further non-zero bits below the least significant bit of `sig`.
+    fn round_pack(self, sign: bool, exp: i32, sig: u128, sticky: bool, rm: u32) -> (u64, u32) {
+        if sig == 0 && !sticky {
+            return (self.zero(sign), 0);
+        }
+        let frac_bits = self.frac_bits as i32;
+        let emin = 1 - self.bias();
+        let msb = exp + (127 - sig.leading_zeros() as i32);
+
+        // Round to the precision of the format, taking into account that subnormal numbers have fewer bits.
+        let mut lsb = msb.max(emin) - frac_bits;
+        let (mut kept, inexact) = if lsb >= exp {
+            shift_right_round(sig, (lsb - exp) as u32, sticky, sign, rm)
+        } else {
+            shift_right_round(sig << (exp - lsb), 0, sticky, sign, rm)
+        };
+        if kept == 1 << (frac_bits + 1) {
+            kept >>= 1;
+            lsb += 1;
+        }
+
+        // Tininess is detected after rounding, i.e., as if the exponent range were unbounded.
+        let tiny = msb < emin && {
+            let shift = msb - frac_bits - exp;
+            let carried = shift > 0
+                && shift_right_round(sig, shift as u32, sticky, sign, rm).0 == 1 << (frac_bits + 1);
+            msb + i32::from(carried) < emin
+        };
+
+        let mut flags = 0;
+        if inexact {
+            flags |= NX;
+            if tiny {
+                flags |= UF;
+            }
+        }
+
+        if kept < 1 << frac_bits {
+            // Subnormal or zero.
+            return (self.sign(sign) | kept as u64, flags);
+        }
+        let biased_exp = (lsb + frac_bits + self.bias()) as u64;
+        if biased_exp >= self.max_exp() {
+            let result = match rm {
+                RTZ => self.max_finite(sign),
+                RDN if !sign => self.max_finite(sign),
+                RUP if sign => self.max_finite(sign),
+                _ => self.inf(sign),
+            };
+            return (result, OF | NX);
+        }
+        let frac = kept as u64 & self.frac_mask();
+        (
+            self.sign(sign) | (biased_exp << self.frac_bits) | frac,
+            flags,
+        )
+    }
+
+    /// Adds two finite values. The significands must have no more than 106 bits.
+    fn add_finite(self, x: Finite, y: Finite, rm: u32) -> (u64, u32) {
+        // Line up the most significant bits so that the smaller value is only ever shifted right.
+        let x = x.normalized(125);
+        let y = y.normalized(125);
+        let (big, small) = if x.exp >= y.exp { (x, y) } else { (y, x) };
+        let shift = (big.exp - small.exp) as u32;
+        let (small_sig, sticky) = if shift >= 128 {
+            (0, true)
+        } else {
+            (small.sig >> shift, (small.sig & !(u128::MAX << shift)) != 0)
+        };
+        if big.sign == small.sign {
+            return self.round_pack(big.sign, big.exp, big.sig + small_sig, sticky, rm);
+        }
+        match big.sig.cmp(&small_sig) {
+            Ordering::Equal if !sticky => (self.zero(rm == RDN), 0),
+            Ordering::Less => self.round_pack(small.sign, big.exp, small_sig - big.sig, false, rm),
+            _ => {
+                let sig = big.sig - small_sig - u128::from(sticky);
+                self.round_pack(big.sign, big.exp, sig, sticky, rm)
+            }
+        }
+    }
+
+    /// Returns `a + b`.
+    pub fn add(self, a: u64, b: u64, rm: u32) -> (u64, u32) {
+        match (self.unpack(a), self.unpack(b)) {
+            (Value::Nan, _) | (_, Value::Nan) => self.propagate_nan(&[a, b]),
+            (Value::Inf(sa), Value::Inf(sb)) if sa != sb => (self.canonical_nan(), NV),
+            (Value::Inf(sign), _) | (_, Value::Inf(sign)) => (self.inf(sign), 0),
+            (Value::Zero(sa), Value::Zero(sb)) => {
+                (self.zero(if sa == sb { sa } else { rm == RDN }), 0)
+            }
+            (Value::Zero(_), _) => (b, 0),
+            (_, Value::Zero(_)) => (a, 0),
+            (Value::Finite(x), Value::Finite(y)) => self.add_finite(x, y, rm),
+        }
+    }
+
+    /// Returns `a - b`.
+    pub fn sub(self, a: u64, b: u64, rm: u32) -> (u64, u32) {
+        self.add(a, b ^ self.sign_bit(), rm)
+    }
+
+    /// Returns `a * b`.
+    pub fn mul(self, a: u64, b: u64, rm: u32) -> (u64, u32) {
+        match (self.unpack(a), self.unpack(b)) {
+            (Value::Nan, _) | (_, Value::Nan) => self.propagate_nan(&[a, b]),
+            (Value::Inf(_), Value::Zero(_)) | (Value::Zero(_), Value::Inf(_)) => {
+                (self.canonical_nan(), NV)
+            }
+            (Value::Inf(sa), Value::Inf(sb))
+            | (Value::Inf(sa), Value::Finite(Finite { sign: sb, .. }))
+            | (Value::Finite(Finite { sign: sa, .. }), Value::Inf(sb)) => (self.inf(sa != sb), 0),
+            (Value::Zero(sa), Value::Zero(sb))
+            | (Value::Zero(sa), Value::Finite(Finite { sign: sb, .. }))
+            | (Value::Finite(Finite { sign: sa, .. }), Value::Zero(sb)) => (self.zero(sa != sb), 0),
+            (Value::Finite(x), Value::Finite(y)) => {
+                self.round_pack(x.sign != y.sign, x.exp + y.exp, x.sig * y.sig, false, rm)
+            }
+        }
+    }
+
+    /// Returns `a / b`.
+    pub fn div(self, a: u64, b: u64, rm: u32) -> (u64, u32) {
+        match (self.unpack(a), self.unpack(b)) {
+            (Value::Nan, _) | (_, Value::Nan) => self.propagate_nan(&[a, b]),
+            (Value::Inf(_), Value::Inf(_)) | (Value::Zero(_), Value::Zero(_)) => {
+                (self.canonical_nan(), NV)
+            }
+            (Value::Inf(sa), Value::Zero(sb))
+            | (Value::Inf(sa), Value::Finite(Finite { sign: sb, .. })) => (self.inf(sa != sb), 0),
+            (Value::Finite(Finite { sign: sa, .. }), Value::Zero(sb)) => (self.inf(sa != sb), DZ),
+            (Value::Zero(sa), Value::Inf(sb))
+            | (Value::Zero(sa), Value::Finite(Finite { sign: sb, .. }))
+            | (Value::Finite(Finite { sign: sa, .. }), Value::Inf(sb)) => (self.zero(sa != sb), 0),
+            (Value::Finite(x), Value::Finite(y)) => {
+                // Give the quotient at least 62 bits, which is plenty for rounding.
+                let x = x.normalized(125);
+                let y = y.normalized(63);
+                let quotient = x.sig / y.sig;
+                let sticky = x.sig % y.sig != 0;
+                self.round_pack(x.sign != y.sign, x.exp - y.exp, quotient, sticky, rm)
+            }
+        }
+    }
+
+    /// Returns the square root of `a`.
+    pub fn sqrt(self, a: u64, rm: u32) -> (u64, u32) {
+        match self.unpack(a) {
+            Value::Nan => self.propagate_nan(&[a]),
+            Value::Inf(false) => (a, 0),
+            Value::Zero(_) => (a, 0),
+            Value::Inf(true) | Value::Finite(Finite { sign: true, .. }) => {
+                (self.canonical_nan(), NV)
+            }
+            Value::Finite(x) => {
+                // Make the exponent even so that it can be halved.
+                let mut x = x.normalized(125);
+                if x.exp % 2 != 0 {
+                    x.sig >>= 1;
+                    x.exp += 1;
+                }
+                let root = isqrt(x.sig);
+                let sticky = root * root != x.sig;
+                self.round_pack(false, x.exp / 2, root, sticky, rm)
+            }
+        }
+    }
+
+    /// Returns `(a * b) + c` with a single rounding.
+    pub fn mul_add(self, a: u64, b: u64, c: u64, rm: u32) -> (u64, u32) {
+        let (va, vb, vc) = (self.unpack(a), self.unpack(b), self.unpack(c));
+        let product_sign = ((a ^ b) & self.sign_bit()) != 0;
+        match (va, vb, vc) {
+            (Value::Inf(_), Value::Zero(_), _) | (Value::Zero(_), Value::Inf(_), _) => {
+                (self.canonical_nan(), NV)
+            }
+            (Value::Nan, _, _) | (_, Value::Nan, _) | (_, _, Value::Nan) => {
+                self.propagate_nan(&[a, b, c])
+            }
+            (Value::Inf(_), _, Value::Inf(sc)) | (_, Value::Inf(_), Value::Inf(sc))
+                if sc != product_sign =>
+            {
+                (self.canonical_nan(), NV)
+            }
+            (Value::Inf(_), _, _) | (_, Value::Inf(_), _) => (self.inf(product_sign), 0),
+            (_, _, Value::Inf(_)) => (c, 0),
+            (Value::Zero(_), _, Value::Zero(sc)) | (_, Value::Zero(_), Value::Zero(sc)) => {
+                let sign = if sc == product_sign { sc } else { rm == RDN };
+                (self.zero(sign), 0)
+            }
+            (Value::Zero(_), _, _) | (_, Value::Zero(_), _) => (c, 0),
+            (Value::Finite(x), Value::Finite(y), vc) => {
+                let product = Finite {
+                    sign: product_sign,
+                    exp: x.exp + y.exp,
+                    sig: x.sig * y.sig,
+                };
+                match vc {
+                    Value::Finite(z) => self.add_finite(product, z, rm),
+                    _ => self.round_pack(product.sign, product.exp, product.sig, false, rm),
+                }
+            }
+        }
+    }
+
+    /// Returns a key that orders non-NaN values, treating -0 and +0 as equal.
+    fn order_key(self, bits: u64) -> i128 {
+        let magnitude = i128::from(bits & !self.sign_bit());
+        if (bits & self.sign_bit()) != 0 {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    /// Returns `a == b`. This is a quiet comparison, so only signalling NaNs are invalid.
+    pub fn eq(self, a: u64, b: u64) -> (bool, u32) {
+        if self.is_nan(a) || self.is_nan(b) {
+            let (_, flags) = self.propagate_nan(&[a, b]);
+            return (false, flags);
+        }
+        (self.order_key(a) == self.order_key(b), 0)
+    }
+
+    /// Returns `a < b`. This is a signalling comparison, so any NaN is invalid.
+    pub fn lt(self, a: u64, b: u64) -> (bool, u32) {
+        if self.is_nan(a) || self.is_nan(b) {
+            return (false, NV);
+        }
+        (self.order_key(a) < self.order_key(b), 0)
+    }
+
+    /// Returns `a <= b`. This is a signalling comparison, so any NaN is invalid.
+    pub fn le(self, a: u64, b: u64) -> (bool, u32) {
+        if self.is_nan(a) || self.is_nan(b) {
+            return (false, NV);
+        }
+        (self.order_key(a) <= self.order_key(b), 0)
+    }
+
+    fn min_max(self, a: u64, b: u64, want_max: bool) -> (u64, u32) {
+        let (_, flags) = self.propagate_nan(&[a, b]);
+        let result = match (self.is_nan(a), self.is_nan(b)) {
+            (true, true) => self.canonical_nan(),
+            (true, false) => b,
+            (false, true) => a,
+            (false, false) => match self.order_key(a).cmp(&self.order_key(b)) {
+                Ordering::Less => [a, b][usize::from(want_max)],
+                Ordering::Greater => [b, a][usize::from(want_max)],
+                // -0 is considered to be less than +0.
+                Ordering::Equal if ((a & self.sign_bit()) != 0) != want_max => a,
+                Ordering::Equal => b,
+            },
+        };
+        (result, flags)
+    }
+
+    /// Returns the smaller of `a` and `b`, preferring a number to a NaN and -0 to +0.
+    pub fn min(self, a: u64, b: u64) -> (u64, u32) {
+        self.min_max(a, b, false)
+    }
+
+    /// Returns the larger of `a` and `b`, preferring a number to a NaN and +0 to -0.
+    pub fn max(self, a: u64, b: u64) -> (u64, u32) {
+        self.min_max(a, b, true)
+    }
+
+    /// Converts `a` to a 32-bit integer, saturating if it is out of range.
+    pub fn float_to_int(self, a: u64, signed: bool, rm: u32) -> (u32, u32) {
+        let (min, max) = if signed {
+            (i128::from(i32::MIN), i128::from(i32::MAX))
+        } else {
+            (0, i128::from(u32::MAX))
+        };
+        let saturated = |sign: bool| (if sign { min } else { max }) as u32;
+        match self.unpack(a) {
+            Value::Nan => (saturated(false), NV),
+            Value::Inf(sign) => (saturated(sign), NV),
+            Value::Zero(_) => (0, 0),
+            Value::Finite(x) if x.exp >= 64 => (saturated(x.sign), NV),
+            Value::Finite(x) => {
+                let (magnitude, inexact) = if x.exp >= 0 {
+                    (x.sig << x.exp, false)
+                } else {
+                    shift_right_round(x.sig, (-x.exp) as u32, false, x.sign, rm)
+                };
+                let value = if x.sign {
+                    -(magnitude as i128)
+                } else {
+                    magnitude as i128
+                };
+                if value < min || value > max {
+                    (saturated(x.sign), NV)
+                } else {
+                    (value as u32, if inexact { NX } else { 0 })
+                }
+            }
+        }
+    }
+
+    /// Converts a 32-bit integer to this format.
+    pub fn int_to_float(self, value: u32, signed: bool, rm: u32) -> (u64, u32) {
+        let sign = signed && (value as i32) < 0;
+        let magnitude = if sign {
+            (value as i32).unsigned_abs()
+        } else {
+            value
+        };
+        self.round_pack(sign, 0, u128::from(magnitude), false, rm)
+    }
+
+    /// Converts `a` from this format to another format.
+    pub fn convert(self, a: u64, to: Format, rm: u32) -> (u64, u32) {
+        match self.unpack(a) {
+            Value::Nan => {
+                let (_, flags) = self.propagate_nan(&[a]);
+                (to.canonical_nan(), flags)
+            }
+            Value::Inf(sign) => (to.inf(sign), 0),
+            Value::Zero(sign) => (to.zero(sign), 0),
+            Value::Finite(x) => to.round_pack(x.sign, x.exp, x.sig, false, rm),
+        }
+    }
+
+    /// Classifies `a`, returning a mask with a single bit set as described for the `fclass` instructions.
+    pub fn classify(self, a: u64) -> u32 {
+        let negative = (a & self.sign_bit()) != 0;
+        let subnormal = (a & !self.sign_bit()) < (1 << self.frac_bits);
+        match self.unpack(a) {
+            Value::Inf(true) => 1 << 0,
+            Value::Finite(_) if negative && !subnormal => 1 << 1,
+            Value::Finite(_) if negative => 1 << 2,
+            Value::Zero(true) => 1 << 3,
+            Value::Zero(false) => 1 << 4,
+            Value::Finite(_) if subnormal => 1 << 5,
+            Value::Finite(_) => 1 << 6,
+            Value::Inf(false) => 1 << 7,
+            Value::Nan if self.is_signalling_nan(a) => 1 << 8,
+            Value::Nan => 1 << 9,
+        }
+    }
+
+    /// Returns `a` with its sign replaced by the sign of `b`, the sign of `b` negated, or the XOR of both signs.
+    pub fn sign_inject(self, a: u64, b: u64, op: SignOp) -> u64 {
+        let sign = match op {
+            SignOp::Copy => b,
+            SignOp::Negate => !b,
+            SignOp::Xor => a ^ b,
+        } & self.sign_bit();
+        (a & !self.sign_bit()) | sign
+    }
+}
+
+/// The sign injection operations.
+pub enum SignOp {
+    Copy,
+    Negate,
+    Xor,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(f: f32) -> u64 {
+        u64::from(f.to_bits())
+    }
+
+    fn d(f: f64) -> u64 {
+        f.to_bits()
+    }
+
+    #[test]
+    fn test_rounding_modes_are_honoured() {
+        // 1 + 2^-24 is exactly halfway between 1 and the next single-precision number.
+        let a = s(1.0);
+        let b = s(f32::EPSILON / 2.0);
+        assert_eq!((s(1.0), NX), F32.add(a, b, RNE));
+        assert_eq!((s(1.0), NX), F32.add(a, b, RTZ));
+        assert_eq!((s(1.0), NX), F32.add(a, b, RDN));
+        assert_eq!((s(1.0 + f32::EPSILON), NX), F32.add(a, b, RUP));
+        assert_eq!((s(1.0 + f32::EPSILON), NX), F32.add(a, b, RMM));
+
+        // Negative values round the other way.
+        let a = s(-1.0);
+        let b = s(-f32::EPSILON / 2.0);
+        assert_eq!((s(-1.0 - f32::EPSILON), NX), F32.add(a, b, RDN));
+        assert_eq!((s(-1.0), NX), F32.add(a, b, RUP));
+
+        // Exact results are not inexact.
+        assert_eq!((d(3.0), 0), F64.add(d(1.0), d(2.0), RNE));
+    }
+
+    #[test]
+    fn test_exact_zero_sums_are_negative_only_when_rounding_down() {
+        assert_eq!((s(0.0), 0), F32.sub(s(1.0), s(1.0), RNE));
+        assert_eq!((s(-0.0), 0), F32.sub(s(1.0), s(1.0), RDN));
+        assert_eq!((s(-0.0), 0), F32.add(s(-0.0), s(-0.0), RNE));
+    }
+
+    #[test]
+    fn test_exception_flags_are_raised() {
+        assert_eq!((s(f32::INFINITY), DZ), F32.div(s(1.0), s(0.0), RNE));
+        assert_eq!((F32.canonical_nan(), NV), F32.div(s(0.0), s(0.0), RNE));
+        assert_eq!((F32.canonical_nan(), NV), F32.sqrt(s(-1.0), RNE));
+        assert_eq!(
+            (s(f32::INFINITY), OF | NX),
+            F32.mul(s(f32::MAX), s(2.0), RNE)
+        );
+        assert_eq!((s(f32::MAX), OF | NX), F32.mul(s(f32::MAX), s(2.0), RTZ));
+        assert_eq!(
+            (s(f32::from_bits(1)), UF | NX),
+            F32.mul(s(f32::MIN_POSITIVE), s(f32::EPSILON / 2.0), RUP)
+        );
+        assert_eq!((s(1.0 / 3.0), NX), F32.div(s(1.0), s(3.0), RNE));
+    }
+
+    #[test]
+    fn test_nans_are_canonical() {
+        let signalling = 0x7f80_0001;
+        let quiet_with_payload = 0xffc0_1234;
+        assert_eq!((0x7fc0_0000, NV), F32.add(signalling, s(1.0), RNE));
+        assert_eq!((0x7fc0_0000, 0), F32.mul(quiet_with_payload, s(1.0), RNE));
+        assert_eq!(
+            (0x7ff8_0000_0000_0000, NV),
+            F32.convert(signalling, F64, RNE)
+        );
+        assert_eq!((s(1.0), NV), F32.min(signalling, s(1.0)));
+        assert_eq!(
+            (0x7fc0_0000, 0),
+            F32.max(quiet_with_payload, quiet_with_payload)
+        );
+    }
+
+    #[test]
+    fn test_conversions_to_integers_saturate() {
+        assert_eq!((i32::MAX as u32, NV), F32.float_to_int(s(3e9), true, RTZ));
+        assert_eq!((i32::MIN as u32, NV), F32.float_to_int(s(-3e9), true, RTZ));
+        assert_eq!(
+            (i32::MAX as u32, NV),
+            F32.float_to_int(0x7fc0_0000, true, RTZ)
+        );
+        assert_eq!((u32::MAX, NV), F32.float_to_int(0xffc0_0000, false, RTZ));
+        assert_eq!((0, NV), F32.float_to_int(s(-1.0), false, RTZ));
+        assert_eq!((0, NX), F32.float_to_int(s(-0.5), false, RTZ));
+        assert_eq!((-2i32 as u32, NX), F32.float_to_int(s(-1.5), true, RNE));
+        assert_eq!((-1i32 as u32, NX), F32.float_to_int(s(-1.5), true, RTZ));
+        assert_eq!((-2i32 as u32, NX), F32.float_to_int(s(-1.5), true, RDN));
+        assert_eq!((3, NX), F64.float_to_int(d(2.5), true, RMM));
+        assert_eq!((2, NX), F64.float_to_int(d(2.5), true, RNE));
+    }
+
+    #[test]
+    fn test_fused_multiply_add_rounds_once() {
+        // (1 + 2^-23) * (1 - 2^-23) - 1 = -2^-46, which is lost if the product is rounded first.
+        let a = s(1.0 + f32::EPSILON);
+        let b = s(1.0 - f32::EPSILON);
+        assert_eq!(
+            (s(-(f32::EPSILON * f32::EPSILON)), 0),
+            F32.mul_add(a, b, s(-1.0), RNE)
+        );
+        assert_eq!(
+            (F32.canonical_nan(), NV),
+            F32.mul_add(s(f32::INFINITY), s(0.0), 0x7fc0_0000, RNE)
+        );
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert_eq!((true, 0), F32.eq(s(0.0), s(-0.0)));
+        assert_eq!((false, 0), F32.eq(0x7fc0_0000, s(1.0)));
+        assert_eq!((false, NV), F32.eq(0x7f80_0001, s(1.0)));
+        assert_eq!((false, NV), F32.lt(0x7fc0_0000, s(1.0)));
+        assert_eq!((true, 0), F64.lt(d(-2.0), d(-1.0)));
+        assert_eq!((true, 0), F64.le(d(-0.0), d(0.0)));
+        assert_eq!((s(-0.0), 0), F32.min(s(0.0), s(-0.0)));
+        assert_eq!((s(0.0), 0), F32.max(s(-0.0), s(0.0)));
+    }
+
+    #[test]
+    fn test_results_match_the_host_when_rounding_to_nearest() {
+        let values: [f32; 12] = [
+            0.0,
+            -0.0,
+            1.0,
+            -1.5,
+            3.0,
+            0.1,
+            1e-40,
+            -3e-39,
+            1e30,
+            -7e37,
+            123456.78,
+            1.0 / 3.0,
+        ];
+        for &x in &values {
+            if x >= 0.0 {
+                assert_eq!(s(x.sqrt()), F32.sqrt(s(x), RNE).0, "sqrt({x})");
+            }
+            for &y in &values {
+                assert_eq!(s(x + y), F32.add(s(x), s(y), RNE).0, "{x} + {y}");
+                assert_eq!(s(x - y), F32.sub(s(x), s(y), RNE).0, "{x} - {y}");
+                assert_eq!(s(x * y), F32.mul(s(x), s(y), RNE).0, "{x} * {y}");
+                if y != 0.0 {
+                    assert_eq!(s(x / y), F32.div(s(x), s(y), RNE).0, "{x} / {y}");
+                }
+                let (x, y) = (f64::from(x) * 1e100, f64::from(y) * 1e-200);
+                assert_eq!(d(x * y), F64.mul(d(x), d(y), RNE).0, "{x} * {y}");
+                assert_eq!(d(x.mul_add(y, x)), F64.mul_add(d(x), d(y), d(x), RNE).0);
+            }
+        }
+    }
+}
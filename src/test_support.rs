@@ -0,0 +1,50 @@
+//! Helpers shared by the unit tests.
+
+use crate::{
+    backends::{
+        cpus::rv32izicsr::Rv32iZicsrCpu,
+        devices::clint::{Clint, CLINT_SIZE},
+        memory::bus::{Bus, InterruptLine, Region, Widths},
+    },
+    memory::{Load, Memory},
+    trap::TrapCause,
+};
+
+/// Loads a program of 32-bit instructions into memory at the given address.
+pub fn program(cpu: &mut impl Load, address: u32, code: &[u32]) {
+    for (i, ins) in code.iter().enumerate() {
+        cpu.write_bytes(address + 4 * i as u32, &ins.to_le_bytes())
+            .unwrap();
+    }
+}
+
+/// Returns a CPU with a CLINT that enables the timer interrupt, then runs `code` at 0x14. The trap handler at 0x100
+/// copies mcause to a0 and mepc to a1, then executes an ebreak at 0x108.
+pub fn timer_cpu(mtimecmp: u32, code: &[u32]) -> Rv32iZicsrCpu<Bus> {
+    let timer = InterruptLine::new();
+    let mut bus = Bus::new();
+    bus.map_ram(0, 0x1000).unwrap();
+    let region = Region::new(0x0200_0000, CLINT_SIZE).with_widths(Widths::WORD);
+    bus.map(region, Clint::new().with_timer_interrupt(timer.clone()))
+        .unwrap();
+    let mut cpu = Rv32iZicsrCpu::with_mem(bus);
+    cpu.connect_interrupt(TrapCause::MachineTimerInterrupt, timer);
+    #[rustfmt::skip]
+    program(&mut cpu, 0, &[
+        0x10000293, // 0x00: li    t0, 0x100
+        0x30529073, // 0x04: csrw  mtvec, t0
+        0x08000293, // 0x08: li    t0, 0x80
+        0x30429073, // 0x0c: csrw  mie, t0
+        0x30046073, // 0x10: csrsi mstatus, 8
+    ]);
+    program(&mut cpu, 0x14, code);
+    #[rustfmt::skip]
+    program(&mut cpu, 0x100, &[
+        0x34202573, // 0x100: csrr   a0, mcause
+        0x341025f3, // 0x104: csrr   a1, mepc
+        0x00100073, // 0x108: ebreak
+    ]);
+    cpu.write32(0x0200_4004, 0).unwrap(); // mtimecmp (high)
+    cpu.write32(0x0200_4000, mtimecmp).unwrap(); // mtimecmp
+    cpu
+}
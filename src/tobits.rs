@@ -140,6 +140,25 @@ impl ToBits {
         (self.0 >> 20) & 0x1f
     }
 
+    // RV32A
+
+    #[inline]
+    pub fn aqrl(&self) -> u32 {
+        self.bits(26, 25)
+    }
+
+    // Zicsr
+
+    #[inline]
+    pub fn csr(&self) -> u32 {
+        self.bits(31, 20)
+    }
+
+    #[inline]
+    pub fn zimm(&self) -> u32 {
+        (self.0 >> 15) & 0x1f // inst[19:15] -> zext(imm[4:0])
+    }
+
     // RV32C
 
     #[inline]
@@ -226,9 +245,9 @@ impl ToBits {
     #[inline]
     pub fn c_uimm7(&self) -> u32 {
         // Zero extended.
-        let a = ((self.0 >> 12) & 1) << 5; // offset[5]
-        let b = ((self.0 & 0b11100) >> 2) << 3; // offset[4:3]
-        let c = (self.0 & 0b00011) << 6; // offset[7:6]
+        let a = ((self.0 >> 10) & 0b111) << 3; // offset[5:3]
+        let b = ((self.0 >> 6) & 1) << 2; // offset[2]
+        let c = ((self.0 >> 5) & 1) << 6; // offset[6]
         a | b | c
     }
 
@@ -296,6 +315,14 @@ impl ToBits {
         sext(a | b | c | d | e, 8)
     }
 
+    #[inline]
+    pub fn c_uimm8(&self) -> u32 {
+        // Zero extended.
+        let a = ((self.0 >> 10) & 0b111) << 3; // offset[5:3]
+        let b = ((self.0 >> 5) & 0b11) << 6; // offset[7:6]
+        a | b
+    }
+
     #[inline]
     pub fn c_uimm8sp(&self) -> u32 {
         // Zero extended.
@@ -315,6 +342,25 @@ impl ToBits {
         a | b
     }
 
+    #[inline]
+    pub fn c_uimm9sp(&self) -> u32 {
+        // Zero extended.
+        let a = ((self.0 >> 12) & 1) << 5; // offset[5]
+        let imm = (self.0 >> 2) & 0x1f;
+        let b = ((imm & 0b11000) >> 3) << 3; // offset[4:3]
+        let c = (imm & 0b00111) << 6; // offset[8:6]
+        a | b | c
+    }
+
+    #[inline]
+    pub fn c_uimm9sp_s(&self) -> u32 {
+        // Zero extended.
+        let imm = (self.0 >> 7) & 0x3f;
+        let a = ((imm & 0b111000) >> 3) << 3; // offset[5:3]
+        let b = (imm & 0b000111) << 6; // offset[8:6]
+        a | b
+    }
+
     #[inline]
     pub fn c_nzuimm6(&self) -> u32 {
         // Zero extended.
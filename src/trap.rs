@@ -1,25 +1,30 @@
 //! Trap handling.
 
-use crate::memory::Address;
+use std::fmt::Display;
+
+use crate::{cpu::Privilege, memory::Address, reg::Reg};
 
 /// Denotes the cause of a trap.
+///
+/// Exceptions carry the value that the privileged architecture writes to `mtval` when they're taken, i.e., the faulting
+/// address, or the instruction bits for an illegal instruction. Environment calls and interrupts don't have one.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TrapCause {
     // Non-interrupt traps.
-    InstructionAddressMisaligned,
-    InstructionAccessFault,
+    InstructionAddressMisaligned(Address),
+    InstructionAccessFault(Address),
     IllegalInstruction(u32),
-    Breakpoint,
-    LoadAddressMisaligned,
+    Breakpoint(Address),
+    LoadAddressMisaligned(Address),
     LoadAccessFault(Address),
-    StoreAddressMisaligned,
+    StoreAddressMisaligned(Address),
     StoreAccessFault(Address),
     EnvironmentCallFromUMode,
     EnvironmentCallFromSMode,
     EnvironmentCallFromMMode,
-    InstructionPageFault,
-    LoadPageFault,
-    StorePageFault,
+    InstructionPageFault(Address),
+    LoadPageFault(Address),
+    StorePageFault(Address),
     // Interrupts.
     SupervisorSoftwareInterrupt,
     MachineSoftwareInterrupt,
@@ -29,9 +34,172 @@ pub enum TrapCause {
     MachineExternalInterrupt,
 }
 
+impl TrapCause {
+    /// Returns the environment call exception for an `ecall` at the given privilege level.
+    pub fn ecall_from(privilege: Privilege) -> TrapCause {
+        match privilege {
+            Privilege::User => TrapCause::EnvironmentCallFromUMode,
+            Privilege::Supervisor => TrapCause::EnvironmentCallFromSMode,
+            Privilege::Machine => TrapCause::EnvironmentCallFromMMode,
+        }
+    }
+
+    /// Returns true if the trap is an interrupt rather than an exception.
+    pub fn is_interrupt(&self) -> bool {
+        matches!(
+            self,
+            TrapCause::SupervisorSoftwareInterrupt
+                | TrapCause::MachineSoftwareInterrupt
+                | TrapCause::SupervisorTimerInterrupt
+                | TrapCause::MachineTimerInterrupt
+                | TrapCause::SupervisorExternalInterrupt
+                | TrapCause::MachineExternalInterrupt
+        )
+    }
+
+    /// Returns the exception or interrupt code that identifies the trap in `mcause`, without the interrupt bit.
+    pub fn code(&self) -> u32 {
+        match self {
+            TrapCause::InstructionAddressMisaligned(_) => 0,
+            TrapCause::InstructionAccessFault(_) => 1,
+            TrapCause::IllegalInstruction(_) => 2,
+            TrapCause::Breakpoint(_) => 3,
+            TrapCause::LoadAddressMisaligned(_) => 4,
+            TrapCause::LoadAccessFault(_) => 5,
+            TrapCause::StoreAddressMisaligned(_) => 6,
+            TrapCause::StoreAccessFault(_) => 7,
+            TrapCause::EnvironmentCallFromUMode => 8,
+            TrapCause::EnvironmentCallFromSMode => 9,
+            TrapCause::EnvironmentCallFromMMode => 11,
+            TrapCause::InstructionPageFault(_) => 12,
+            TrapCause::LoadPageFault(_) => 13,
+            TrapCause::StorePageFault(_) => 15,
+            TrapCause::SupervisorSoftwareInterrupt => 1,
+            TrapCause::MachineSoftwareInterrupt => 3,
+            TrapCause::SupervisorTimerInterrupt => 5,
+            TrapCause::MachineTimerInterrupt => 7,
+            TrapCause::SupervisorExternalInterrupt => 9,
+            TrapCause::MachineExternalInterrupt => 11,
+        }
+    }
+
+    /// Returns the value written to `mcause` when the trap is taken, i.e., its code with the interrupt bit.
+    pub fn mcause(&self) -> u32 {
+        if self.is_interrupt() {
+            0x8000_0000 | self.code()
+        } else {
+            self.code()
+        }
+    }
+
+    /// Returns the value written to `mtval` when the trap is taken, or zero if the trap doesn't have one.
+    pub fn tval(&self) -> u32 {
+        match *self {
+            TrapCause::InstructionAddressMisaligned(address)
+            | TrapCause::InstructionAccessFault(address)
+            | TrapCause::Breakpoint(address)
+            | TrapCause::LoadAddressMisaligned(address)
+            | TrapCause::LoadAccessFault(address)
+            | TrapCause::StoreAddressMisaligned(address)
+            | TrapCause::StoreAccessFault(address)
+            | TrapCause::InstructionPageFault(address)
+            | TrapCause::LoadPageFault(address)
+            | TrapCause::StorePageFault(address) => address,
+            TrapCause::IllegalInstruction(ins) => ins,
+            _ => 0,
+        }
+    }
+}
+
+impl Display for TrapCause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            TrapCause::InstructionAddressMisaligned(address) => {
+                write!(f, "misaligned instruction address 0x{:08x}", address)
+            }
+            TrapCause::InstructionAccessFault(address) => {
+                write!(f, "instruction access fault at 0x{:08x}", address)
+            }
+            TrapCause::IllegalInstruction(ins) => write!(f, "illegal instruction 0x{:08x}", ins),
+            TrapCause::Breakpoint(address) => write!(f, "breakpoint at 0x{:08x}", address),
+            TrapCause::LoadAddressMisaligned(address) => {
+                write!(f, "misaligned load address 0x{:08x}", address)
+            }
+            TrapCause::LoadAccessFault(address) => {
+                write!(f, "load access fault at 0x{:08x}", address)
+            }
+            TrapCause::StoreAddressMisaligned(address) => {
+                write!(f, "misaligned store address 0x{:08x}", address)
+            }
+            TrapCause::StoreAccessFault(address) => {
+                write!(f, "store access fault at 0x{:08x}", address)
+            }
+            TrapCause::EnvironmentCallFromUMode => f.write_str("environment call from U-mode"),
+            TrapCause::EnvironmentCallFromSMode => f.write_str("environment call from S-mode"),
+            TrapCause::EnvironmentCallFromMMode => f.write_str("environment call from M-mode"),
+            TrapCause::InstructionPageFault(address) => {
+                write!(f, "instruction page fault at 0x{:08x}", address)
+            }
+            TrapCause::LoadPageFault(address) => write!(f, "load page fault at 0x{:08x}", address),
+            TrapCause::StorePageFault(address) => {
+                write!(f, "store page fault at 0x{:08x}", address)
+            }
+            TrapCause::SupervisorSoftwareInterrupt => f.write_str("supervisor software interrupt"),
+            TrapCause::MachineSoftwareInterrupt => f.write_str("machine software interrupt"),
+            TrapCause::SupervisorTimerInterrupt => f.write_str("supervisor timer interrupt"),
+            TrapCause::MachineTimerInterrupt => f.write_str("machine timer interrupt"),
+            TrapCause::SupervisorExternalInterrupt => f.write_str("supervisor external interrupt"),
+            TrapCause::MachineExternalInterrupt => f.write_str("machine external interrupt"),
+        }
+    }
+}
+
+/// A trap that has been taken, with the address of the instruction that caused it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrapRecord {
+    /// The cause of the trap.
+    pub cause: TrapCause,
+    /// The address of the instruction that caused the trap, i.e., the value written to `mepc`. For an interrupt, this
+    /// is the address of the instruction that will be executed when the trap handler returns.
+    pub pc: Address,
+}
+
+impl TrapRecord {
+    /// Returns the value written to `mcause` when the trap is taken.
+    pub fn mcause(&self) -> u32 {
+        self.cause.mcause()
+    }
+
+    /// Returns the value written to `mtval` when the trap is taken.
+    pub fn tval(&self) -> u32 {
+        self.cause.tval()
+    }
+}
+
+impl Display for TrapRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (pc 0x{:08x})", self.cause, self.pc)
+    }
+}
+
+/// The encoding of `mret`.
+const MRET: u32 = 0x30200073;
+
+/// The encoding of `sret`.
+const SRET: u32 = 0x10200073;
+
+/// The encoding of `sfence.vma`, without its registers.
+const SFENCE_VMA: u32 = 0x12000073;
+
 /// A trait for dealing with traps.
 pub trait Trap {
-    fn trap_cause(&self) -> Option<TrapCause>;
+    /// Returns the most recent trap, or `None` if the CPU isn't trapped.
+    fn trap(&self) -> Option<TrapRecord>;
+
+    /// Returns the cause of the most recent trap, or `None` if the CPU isn't trapped.
+    fn trap_cause(&self) -> Option<TrapCause> {
+        self.trap().map(|trap| trap.cause)
+    }
 
     fn clear_trap(&mut self);
 
@@ -41,11 +209,103 @@ pub trait Trap {
         self.trap_cause().is_some()
     }
 
+    /// Returns the interrupt that the CPU would take if it were between instructions, i.e., the highest priority
+    /// interrupt that's pending and enabled, or `None` if there isn't one. CPUs that don't support interrupts never
+    /// have one.
+    fn pending_interrupt(&self) -> Option<TrapCause> {
+        None
+    }
+
+    /// Takes the pending interrupt, if there is one, so that the guest's trap handler runs next. Unlike an exception,
+    /// this doesn't leave the CPU trapped, because the guest is expected to handle it. Returns the interrupt.
+    fn take_interrupt(&mut self) -> Option<TrapCause> {
+        let cause = self.pending_interrupt()?;
+        self.handle_trap(cause);
+        self.clear_trap();
+        Some(cause)
+    }
+
+    /// Returns the privilege level that the CPU is running at. CPUs that only have M-mode are always at M-mode.
+    fn privilege(&self) -> Privilege {
+        Privilege::Machine
+    }
+
+    /// Returns true if [`Trap::handle_trap`] vectors to the guest's trap handler, so that the guest handles the trap
+    /// itself, or false if the CPU stays at the instruction that caused the trap, e.g., because it has no trap
+    /// handler to go to.
+    fn vectors_traps(&self) -> bool {
+        false
+    }
+
+    /// Handles an `ecall` by raising the environment call exception for the current privilege level.
     fn handle_ecall(&mut self) {
-        self.handle_trap(TrapCause::EnvironmentCallFromMMode)
+        self.handle_trap(TrapCause::ecall_from(self.privilege()))
+    }
+
+    /// Handles an `ebreak` at the given address.
+    fn handle_ebreak(&mut self, address: Address) {
+        self.handle_trap(TrapCause::Breakpoint(address))
+    }
+
+    /// Returns from a machine-mode trap handler. This is illegal unless the implementation supports M-mode traps.
+    fn handle_mret(&mut self) {
+        self.handle_trap(TrapCause::IllegalInstruction(MRET))
+    }
+
+    /// Returns from a supervisor-mode trap handler. This is illegal unless the implementation supports S-mode.
+    fn handle_sret(&mut self) {
+        self.handle_trap(TrapCause::IllegalInstruction(SRET))
+    }
+
+    /// Waits for an interrupt. By default, this does nothing, which the spec allows because `wfi` is only a hint.
+    fn handle_wfi(&mut self) {}
+
+    /// Orders the CPU's accesses to page tables, with `rs1` selecting an address and `rs2` selecting an address space,
+    /// or `x0` selecting all of them. This is illegal unless the implementation supports S-mode.
+    fn handle_sfence_vma(&mut self, rs1: Reg, rs2: Reg) {
+        let code = SFENCE_VMA | (u32::from(rs2) << 20) | (u32::from(rs1) << 15);
+        self.handle_trap(TrapCause::IllegalInstruction(code))
+    }
+
+    /// Returns true if the CPU is waiting for an interrupt after a `wfi`, i.e., if it shouldn't execute anything until
+    /// an interrupt becomes pending. CPUs that don't wait never are.
+    fn is_waiting(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TrapCause, TrapRecord};
+
+    #[test]
+    fn test_traps_map_to_mcause_and_mtval() {
+        let trap = TrapRecord {
+            cause: TrapCause::LoadAccessFault(0x10000),
+            pc: 0x100,
+        };
+        assert_eq!(5, trap.mcause());
+        assert_eq!(0x10000, trap.tval());
+        assert_eq!(0x8000_0007, TrapCause::MachineTimerInterrupt.mcause());
+        assert_eq!(0, TrapCause::MachineTimerInterrupt.tval());
+        assert_eq!(0, TrapCause::EnvironmentCallFromMMode.tval());
+        assert_eq!(0x00100073, TrapCause::IllegalInstruction(0x00100073).tval());
+        assert_eq!(0x104, TrapCause::Breakpoint(0x104).tval());
     }
 
-    fn handle_ebreak(&mut self) {
-        self.handle_trap(TrapCause::Breakpoint)
+    #[test]
+    fn test_traps_are_displayed_as_diagnostics() {
+        let trap = TrapRecord {
+            cause: TrapCause::IllegalInstruction(0xffffffff),
+            pc: 0x1234,
+        };
+        assert_eq!(
+            "illegal instruction 0xffffffff (pc 0x00001234)",
+            trap.to_string()
+        );
+        assert_eq!(
+            "environment call from M-mode",
+            TrapCause::EnvironmentCallFromMMode.to_string()
+        );
     }
 }